@@ -0,0 +1,778 @@
+use super::AsyncSmartDevice;
+use crate::automation::DeviceRef;
+use crate::device::Capability;
+use crate::error::{DeviceAccessError, ProtocolError, SmartHomeError};
+use crate::events::HouseEvent;
+use crate::report::{DeviceReport, HouseReport, Metric, MetricValue, RoomReport};
+use crate::room::{Room, validate_name};
+use crate::{Reporter, SharedHouse, SmartDevice, StructuredReport};
+use indexmap::IndexMap;
+use std::future::Future;
+use std::sync::{Arc, RwLock};
+use std::time::Duration;
+use tokio_util::sync::CancellationToken;
+
+type AsyncDevices = IndexMap<DeviceRef, Arc<dyn AsyncSmartDevice>>;
+
+/// Async façade over a [`SharedHouse`] with networked devices
+///
+/// Networked devices are registered with
+/// [`AsyncSmartHouse::add_remote_device`] and driven through the async
+/// traits; every other device lives in the shared house and is driven
+/// through the sync API. Each call to a networked device fails with
+/// [`ProtocolError::Timeout`] when the device does not answer in time, and
+/// with [`ProtocolError::Cancelled`] once the cancellation token is
+/// cancelled. Dropping a call's future also cancels it.
+///
+/// Device names are unique per room across both kinds of devices as long as
+/// devices are added and rooms removed through the façade: adding a device or
+/// removing a room directly on [`AsyncSmartHouse::house`] skips the networked
+/// devices.
+///
+/// Power budgets only cover the in-memory devices: a networked device is
+/// switched on without checking the budgets of its room and of the house, its
+/// draw is not counted against them, and it is never shed.
+///
+/// Clones share the house, the networked devices and the cancellation token.
+#[derive(Debug, Clone)]
+pub struct AsyncSmartHouse {
+    house: SharedHouse,
+    remote: Arc<RwLock<AsyncDevices>>,
+    timeout: Duration,
+    cancel: CancellationToken,
+}
+
+impl AsyncSmartHouse {
+    /// Default limit for a single call to a networked device
+    pub const DEFAULT_TIMEOUT: Duration = Duration::from_secs(5);
+
+    /// Creates the façade over a house
+    pub fn new(house: impl Into<SharedHouse>) -> Self {
+        Self {
+            house: house.into(),
+            remote: Arc::new(RwLock::new(IndexMap::new())),
+            timeout: Self::DEFAULT_TIMEOUT,
+            cancel: CancellationToken::new(),
+        }
+    }
+
+    /// Sets the limit for a single call to a networked device
+    pub fn with_timeout(mut self, timeout: Duration) -> Self {
+        self.timeout = timeout;
+        self
+    }
+
+    /// Uses the given token to cancel calls to networked devices, e.g. on shutdown
+    pub fn with_cancellation(mut self, token: CancellationToken) -> Self {
+        self.cancel = token;
+        self
+    }
+
+    /// Returns the limit for a single call to a networked device
+    pub fn timeout(&self) -> Duration {
+        self.timeout
+    }
+
+    /// Returns the token cancelling calls to networked devices
+    pub fn cancellation_token(&self) -> &CancellationToken {
+        &self.cancel
+    }
+
+    /// Returns the house holding the in-memory devices
+    ///
+    /// Use [`AsyncSmartHouse::add_device`] and [`AsyncSmartHouse::remove_room`]
+    /// rather than their counterparts on the house, which do not know about
+    /// the networked devices.
+    pub fn house(&self) -> &SharedHouse {
+        &self.house
+    }
+
+    /// Adds an in-memory device to a room of the house
+    ///
+    /// Fails if the room does not exist or the name is taken by another
+    /// device of the room, networked or not.
+    pub fn add_device(
+        &self,
+        room_name: &String,
+        device_name: String,
+        device: SmartDevice,
+    ) -> Result<(), SmartHomeError> {
+        {
+            // Held so a networked device of the same name cannot be added meanwhile
+            let remote = self.remote_devices();
+            if remote.contains_key(&DeviceRef::new(room_name.as_str(), device_name.as_str())) {
+                return Err(SmartHomeError::DuplicateDevice(
+                    device_name,
+                    room_name.clone(),
+                ));
+            }
+            self.house.with_room_mut(room_name, |room| {
+                room.add_device(device_name.clone(), device)
+            })??;
+        }
+
+        self.house.publish(HouseEvent::DeviceAdded {
+            room: room_name.clone(),
+            device: device_name,
+        });
+        Ok(())
+    }
+
+    /// Removes a room from the house with its networked devices
+    ///
+    /// Returns the room with its in-memory devices; the networked devices
+    /// are dropped.
+    pub fn remove_room(&self, name: &String) -> Result<Room, SmartHomeError> {
+        let room = self.house.remove_room(name)?;
+        // A networked device can no longer be added to the room at this point
+        self.remote
+            .write()
+            .unwrap_or_else(|e| e.into_inner())
+            .retain(|target, _| &target.room != name);
+        Ok(room)
+    }
+
+    fn remote_devices(&self) -> std::sync::RwLockReadGuard<'_, AsyncDevices> {
+        self.remote.read().unwrap_or_else(|e| e.into_inner())
+    }
+
+    fn remote_device(&self, room: &str, device: &str) -> Option<Arc<dyn AsyncSmartDevice>> {
+        self.remote_devices()
+            .get(&DeviceRef::new(room, device))
+            .cloned()
+    }
+
+    /// Returns the keys of the networked devices in a room
+    pub fn remote_device_names(&self, room_name: &str) -> Vec<String> {
+        self.remote_devices()
+            .keys()
+            .filter(|target| target.room == room_name)
+            .map(|target| target.device.clone())
+            .collect()
+    }
+
+    /// Adds a networked device to a room of the house
+    ///
+    /// Fails if the room does not exist or the name is taken by another
+    /// device of the room, networked or not.
+    pub fn add_remote_device<D>(
+        &self,
+        room_name: &String,
+        device_name: String,
+        device: D,
+    ) -> Result<(), SmartHomeError>
+    where
+        D: AsyncSmartDevice + 'static,
+    {
+        validate_name("device name", &device_name)?;
+        let target = DeviceRef::new(room_name.as_str(), device_name.as_str());
+        {
+            let mut remote = self.remote.write().unwrap_or_else(|e| e.into_inner());
+            let taken = self
+                .house
+                .with_room(room_name, |room| room.device(&device_name).is_some())?;
+            if taken || remote.contains_key(&target) {
+                return Err(SmartHomeError::DuplicateDevice(
+                    device_name,
+                    room_name.clone(),
+                ));
+            }
+            remote.insert(target, Arc::new(device));
+        }
+
+        self.house.publish(HouseEvent::DeviceAdded {
+            room: room_name.clone(),
+            device: device_name,
+        });
+        Ok(())
+    }
+
+    /// Removes a networked device from a room and returns it
+    ///
+    /// In-memory devices are removed through [`SharedHouse::remove_device`].
+    pub fn remove_remote_device(
+        &self,
+        room_name: &String,
+        device_name: &String,
+    ) -> Result<Arc<dyn AsyncSmartDevice>, SmartHomeError> {
+        let local = self
+            .house
+            .with_room(room_name, |room| room.device(device_name).is_some())?;
+        if local {
+            return Err(SmartHomeError::invalid_value(
+                "device",
+                format!("'{}' is not a networked device", device_name),
+            ));
+        }
+
+        let device = self
+            .remote
+            .write()
+            .unwrap_or_else(|e| e.into_inner())
+            .shift_remove(&DeviceRef::new(room_name.as_str(), device_name.as_str()))
+            .ok_or_else(|| {
+                DeviceAccessError::DeviceNotFound(device_name.clone(), room_name.clone())
+            })?;
+
+        self.house.publish(HouseEvent::DeviceRemoved {
+            room: room_name.clone(),
+            device: device_name.clone(),
+        });
+        Ok(device)
+    }
+
+    /// Runs a call to a networked device under the timeout and cancellation token
+    async fn call<T>(
+        &self,
+        request: impl Future<Output = Result<T, ProtocolError>>,
+    ) -> Result<T, SmartHomeError> {
+        tokio::select! {
+            _ = self.cancel.cancelled() => Err(ProtocolError::Cancelled.into()),
+            result = tokio::time::timeout(self.timeout, request) => match result {
+                Ok(result) => result.map_err(SmartHomeError::from),
+                Err(_) => Err(ProtocolError::Timeout(self.timeout).into()),
+            },
+        }
+    }
+
+    /// Turns on a device in a room of the house
+    ///
+    /// Publishes [`HouseEvent::PowerStateChanged`] if the device was off.
+    /// In-memory devices are turned on within the power budgets through
+    /// [`SharedHouse::turn_on_device`]; networked devices bypass them.
+    pub async fn turn_on_device(
+        &self,
+        room_name: &String,
+        device_name: &String,
+    ) -> Result<(), SmartHomeError> {
+        self.switch_device(room_name, device_name, true).await
+    }
+
+    /// Turns off a device in a room of the house
+    ///
+    /// Publishes [`HouseEvent::PowerStateChanged`] if the device was on.
+    pub async fn turn_off_device(
+        &self,
+        room_name: &String,
+        device_name: &String,
+    ) -> Result<(), SmartHomeError> {
+        self.switch_device(room_name, device_name, false).await
+    }
+
+    async fn switch_device(
+        &self,
+        room_name: &String,
+        device_name: &String,
+        on: bool,
+    ) -> Result<(), SmartHomeError> {
+        let Some(device) = self.remote_device(room_name, device_name) else {
+            return if on {
+                self.house.turn_on_device(room_name, device_name)
+            } else {
+                self.house.turn_off_device(room_name, device_name)
+            };
+        };
+        let power = device
+            .as_power_control()
+            .ok_or_else(|| unsupported(device_name, Capability::PowerControl))?;
+
+        let was_on = self.call(power.is_on()).await?;
+        if was_on != on {
+            if on {
+                self.call(power.turn_on()).await?;
+            } else {
+                self.call(power.turn_off()).await?;
+            }
+            self.house.publish(HouseEvent::PowerStateChanged {
+                room: room_name.clone(),
+                device: device_name.clone(),
+                is_on: on,
+            });
+        }
+        Ok(())
+    }
+
+    /// Checks whether a device is on
+    pub async fn is_on(
+        &self,
+        room_name: &String,
+        device_name: &String,
+    ) -> Result<bool, SmartHomeError> {
+        match self.remote_device(room_name, device_name) {
+            Some(device) => {
+                let power = device
+                    .as_power_control()
+                    .ok_or_else(|| unsupported(device_name, Capability::PowerControl))?;
+                self.call(power.is_on()).await
+            }
+            None => self.local(
+                room_name,
+                device_name,
+                Capability::PowerControl,
+                SmartDevice::is_on,
+            ),
+        }
+    }
+
+    /// Returns the temperature reading of a device
+    pub async fn temperature(
+        &self,
+        room_name: &String,
+        device_name: &String,
+    ) -> Result<f32, SmartHomeError> {
+        match self.remote_device(room_name, device_name) {
+            Some(device) => {
+                let sensor = device
+                    .as_temperature_sensor()
+                    .ok_or_else(|| unsupported(device_name, Capability::TemperatureSensor))?;
+                self.call(sensor.temperature()).await
+            }
+            None => self.local(
+                room_name,
+                device_name,
+                Capability::TemperatureSensor,
+                SmartDevice::temperature,
+            ),
+        }
+    }
+
+    /// Returns the power consumption of a device in watts
+    pub async fn power_consumption(
+        &self,
+        room_name: &String,
+        device_name: &String,
+    ) -> Result<f32, SmartHomeError> {
+        match self.remote_device(room_name, device_name) {
+            Some(device) => {
+                let meter = device
+                    .as_power_consumption()
+                    .ok_or_else(|| unsupported(device_name, Capability::PowerConsumption))?;
+                self.call(meter.power_consumption()).await
+            }
+            None => self.local(
+                room_name,
+                device_name,
+                Capability::PowerConsumption,
+                SmartDevice::power_consumption,
+            ),
+        }
+    }
+
+    /// Returns the text report of the house, networked devices included
+    ///
+    /// Networked devices are listed after the in-memory devices of their
+    /// room. A device that does not answer is reported as unreachable.
+    pub async fn report(&self) -> String {
+        let remote = self.remote_reports().await;
+        let mut report = format!("=== Smart House: {} ===\n", self.house.name());
+        for room_name in self.house.room_names() {
+            let Ok(room) = self.house.with_room(&room_name, Room::report) else {
+                continue;
+            };
+            report.push_str(&room);
+            for (_, device) in remote.iter().filter(|(target, _)| target.room == room_name) {
+                report.push_str(&remote_report_line(device));
+                report.push('\n');
+            }
+            report.push('\n');
+        }
+        report
+    }
+
+    /// Returns the structured report of the house, networked devices included
+    ///
+    /// Networked devices have the `remote` kind and are listed after the
+    /// in-memory devices of their room. A device that does not answer only
+    /// reports an `error` metric.
+    pub async fn structured_report(&self) -> HouseReport {
+        let remote = self.remote_reports().await;
+        let rooms = self
+            .house
+            .room_names()
+            .into_iter()
+            .filter_map(|room_name| {
+                let room = self
+                    .house
+                    .with_room(&room_name, Room::structured_report)
+                    .ok()?;
+                let mut devices = room.devices;
+                devices.extend(
+                    remote
+                        .iter()
+                        .filter(|(target, _)| target.room == room_name)
+                        .map(|(_, device)| device.clone()),
+                );
+                Some(RoomReport::new(room.name, devices))
+            })
+            .collect();
+        HouseReport::new(self.house.name().to_string(), rooms)
+    }
+
+    /// Queries every networked device for the reports
+    async fn remote_reports(&self) -> Vec<(DeviceRef, DeviceReport)> {
+        let devices: Vec<_> = self
+            .remote_devices()
+            .iter()
+            .map(|(target, device)| (target.clone(), Arc::clone(device)))
+            .collect();
+
+        let mut reports = Vec::with_capacity(devices.len());
+        for (target, device) in devices {
+            let metrics = match self.remote_metrics(device.as_ref()).await {
+                Ok(metrics) => metrics,
+                Err(e) => vec![Metric::text("error", &e.to_string())],
+            };
+            let report = DeviceReport {
+                name: target.device.clone(),
+                kind: "remote".to_string(),
+                metrics,
+            };
+            reports.push((target, report));
+        }
+        reports
+    }
+
+    /// Reads every capability of a networked device
+    async fn remote_metrics(
+        &self,
+        device: &dyn AsyncSmartDevice,
+    ) -> Result<Vec<Metric>, SmartHomeError> {
+        let mut metrics = Vec::new();
+        if let Some(power) = device.as_power_control() {
+            metrics.push(Metric::flag("is_on", self.call(power.is_on()).await?));
+        }
+        if let Some(meter) = device.as_power_consumption() {
+            let watts = self.call(meter.power_consumption()).await?;
+            metrics.push(Metric::number("power", watts, "W"));
+        }
+        if let Some(sensor) = device.as_temperature_sensor() {
+            let temperature = self.call(sensor.temperature()).await?;
+            // Sensors without a reading yet report NaN
+            if temperature.is_finite() {
+                metrics.push(Metric::number("temperature", temperature, "°C"));
+            }
+        }
+        Ok(metrics)
+    }
+
+    /// Reads a capability of an in-memory device
+    fn local<T>(
+        &self,
+        room_name: &String,
+        device_name: &String,
+        capability: Capability,
+        read: impl FnOnce(&SmartDevice) -> Option<T>,
+    ) -> Result<T, SmartHomeError> {
+        self.house
+            .with_device(room_name, device_name, |device| {
+                read(device).ok_or_else(|| device.unsupported(capability))
+            })?
+            .map_err(SmartHomeError::from)
+    }
+}
+
+/// Formats a networked device like the report lines of in-memory devices
+fn remote_report_line(device: &DeviceReport) -> String {
+    let mut line = format!("Device: {}", device.name);
+    for metric in &device.metrics {
+        let part = match (metric.name.as_str(), &metric.value) {
+            ("is_on", MetricValue::Bool(is_on)) => {
+                format!("Status: {}", if *is_on { "ON" } else { "OFF" })
+            }
+            ("power", MetricValue::Number(watts)) => format!("Power consumption: {}W", watts),
+            ("temperature", MetricValue::Number(temperature)) => {
+                format!("Temperature: {}°C", temperature)
+            }
+            ("error", error) => format!("Status: UNREACHABLE ({})", error),
+            _ => metric.to_string(),
+        };
+        line.push_str(", ");
+        line.push_str(&part);
+    }
+    line
+}
+
+fn unsupported(device_name: &str, capability: Capability) -> DeviceAccessError {
+    DeviceAccessError::UnsupportedCapability(device_name.to_string(), capability)
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::asynchronous::AsyncRemoteSocket;
+    use crate::network::ServerHandle;
+    use crate::{SmartHouse, SmartSocket, SmartThermometer, SocketServer, create_room};
+    use std::net::SocketAddr;
+    use tokio::net::TcpListener;
+
+    fn key(name: &str) -> String {
+        name.to_string()
+    }
+
+    fn create_house() -> SmartHouse {
+        let living_room = create_room!(
+            "Living Room",
+            ("Lamp", SmartSocket::new("Lamp".to_string(), true, 40.0)),
+            (
+                "Thermometer",
+                SmartThermometer::new("Thermometer".to_string(), 21.5)
+            )
+        );
+        SmartHouse::new(
+            "Test House".to_string(),
+            vec![("Living Room".to_string(), living_room)],
+        )
+    }
+
+    /// Returns the house with a networked "TV" and the server behind it
+    async fn create_async_house() -> (AsyncSmartHouse, ServerHandle) {
+        let socket = SmartSocket::new("Server Socket".to_string(), false, 120.0);
+        let server = SocketServer::bind("127.0.0.1:0", socket)
+            .unwrap()
+            .spawn()
+            .unwrap();
+        let tv = AsyncRemoteSocket::connect("TV".to_string(), server.local_addr())
+            .await
+            .unwrap();
+
+        let house = AsyncSmartHouse::new(create_house());
+        house
+            .add_remote_device(&key("Living Room"), key("TV"), tv)
+            .unwrap();
+        (house, server)
+    }
+
+    /// Accepts connections and never answers
+    async fn silent_server() -> SocketAddr {
+        let listener = TcpListener::bind("127.0.0.1:0").await.unwrap();
+        let addr = listener.local_addr().unwrap();
+        tokio::spawn(async move {
+            let mut connections = Vec::new();
+            while let Ok((stream, _)) = listener.accept().await {
+                connections.push(stream);
+            }
+        });
+        addr
+    }
+
+    #[tokio::test]
+    async fn test_local_and_remote_devices() {
+        struct TestCase {
+            name: &'static str,
+            device: &'static str,
+            capability: Capability,
+            expected: Result<f32, String>,
+        }
+
+        let test_cases = vec![
+            TestCase {
+                name: "Remote socket is off and draws no power",
+                device: "TV",
+                capability: Capability::PowerConsumption,
+                expected: Ok(0.0),
+            },
+            TestCase {
+                name: "Local power consumption",
+                device: "Lamp",
+                capability: Capability::PowerConsumption,
+                expected: Ok(40.0),
+            },
+            TestCase {
+                name: "Local temperature",
+                device: "Thermometer",
+                capability: Capability::TemperatureSensor,
+                expected: Ok(21.5),
+            },
+            TestCase {
+                name: "Remote socket has no temperature",
+                device: "TV",
+                capability: Capability::TemperatureSensor,
+                expected: Err("Device 'TV' does not support temperature sensor".to_string()),
+            },
+            TestCase {
+                name: "Local thermometer has no power consumption",
+                device: "Thermometer",
+                capability: Capability::PowerConsumption,
+                expected: Err("Device 'Thermometer' does not support power consumption".to_string()),
+            },
+            TestCase {
+                name: "Missing device",
+                device: "Radio",
+                capability: Capability::PowerConsumption,
+                expected: Err("Device 'Radio' not found in room 'Living Room'".to_string()),
+            },
+        ];
+
+        let (house, _server) = create_async_house().await;
+        let room = key("Living Room");
+        for tc in test_cases {
+            let device = key(tc.device);
+            let result = match tc.capability {
+                Capability::TemperatureSensor => house.temperature(&room, &device).await,
+                _ => house.power_consumption(&room, &device).await,
+            };
+            assert_eq!(
+                result.map_err(|e| e.to_string()),
+                tc.expected,
+                "Failed test: {}",
+                tc.name
+            );
+        }
+    }
+
+    #[tokio::test]
+    async fn test_switching_publishes_events() {
+        let (house, _server) = create_async_house().await;
+        let events = house.house().channel();
+        let room = key("Living Room");
+
+        house.turn_on_device(&room, &key("TV")).await.unwrap();
+        house.turn_on_device(&room, &key("TV")).await.unwrap();
+        house.turn_off_device(&room, &key("Lamp")).await.unwrap();
+
+        assert!(house.is_on(&room, &key("TV")).await.unwrap());
+        assert!(!house.is_on(&room, &key("Lamp")).await.unwrap());
+        assert_eq!(
+            events.try_iter().collect::<Vec<_>>(),
+            vec![
+                HouseEvent::PowerStateChanged {
+                    room: room.clone(),
+                    device: key("TV"),
+                    is_on: true,
+                },
+                HouseEvent::PowerStateChanged {
+                    room: room.clone(),
+                    device: key("Lamp"),
+                    is_on: false,
+                },
+            ]
+        );
+    }
+
+    #[tokio::test]
+    async fn test_remote_device_registration() {
+        let (house, _server) = create_async_house().await;
+        let room = key("Living Room");
+        let addr = silent_server().await;
+
+        let duplicate = AsyncRemoteSocket::connect(key("Lamp"), addr).await.unwrap();
+        assert!(matches!(
+            house.add_remote_device(&room, key("Lamp"), duplicate),
+            Err(SmartHomeError::DuplicateDevice(..))
+        ));
+        let missing_room = AsyncRemoteSocket::connect(key("Fan"), addr).await.unwrap();
+        assert!(matches!(
+            house.add_remote_device(&key("Attic"), key("Fan"), missing_room),
+            Err(SmartHomeError::Access(DeviceAccessError::RoomNotFound(_)))
+        ));
+        assert!(matches!(
+            house.remove_remote_device(&room, &key("Lamp")),
+            Err(SmartHomeError::InvalidValue(..))
+        ));
+
+        assert_eq!(house.remote_device_names("Living Room"), vec![key("TV")]);
+        let tv = house.remove_remote_device(&room, &key("TV")).unwrap();
+        assert_eq!(tv.name(), "TV");
+        assert!(house.remote_device_names("Living Room").is_empty());
+    }
+
+    #[tokio::test]
+    async fn test_names_reports_and_room_removal() {
+        let (house, _server) = create_async_house().await;
+        let room = key("Living Room");
+        let fan = || SmartDevice::Socket(SmartSocket::new(key("Fan"), false, 30.0));
+
+        assert!(matches!(
+            house.add_device(&room, key("TV"), fan()),
+            Err(SmartHomeError::DuplicateDevice(..))
+        ));
+
+        let local = house.house().report();
+        assert_eq!(
+            house.report().await,
+            format!(
+                "{}Device: TV, Status: OFF, Power consumption: 0W\n\n",
+                local.strip_suffix('\n').unwrap()
+            )
+        );
+        let report = house.structured_report().await;
+        assert_eq!(
+            report.rooms[0]
+                .devices
+                .iter()
+                .map(|device| (device.name.as_str(), device.kind.as_str()))
+                .collect::<Vec<_>>(),
+            vec![
+                ("Lamp", "socket"),
+                ("Thermometer", "thermometer"),
+                ("TV", "remote")
+            ]
+        );
+        assert_eq!(
+            report.rooms[0].devices[2].metrics,
+            vec![
+                Metric::flag("is_on", false),
+                Metric::number("power", 0.0, "W")
+            ]
+        );
+
+        // The networked TV goes with the room and does not come back with a new one
+        house.remove_room(&room).unwrap();
+        assert!(house.remote_device_names("Living Room").is_empty());
+        house
+            .house()
+            .add_room(room.clone(), Room::new_empty(room.clone()))
+            .unwrap();
+        house.add_device(&room, key("TV"), fan()).unwrap();
+        assert_eq!(house.house().room_names(), vec![room]);
+    }
+
+    #[tokio::test]
+    async fn test_timeout_and_cancellation() {
+        struct TestCase {
+            name: &'static str,
+            cancel: bool,
+            expected_message: &'static str,
+        }
+
+        let test_cases = vec![
+            TestCase {
+                name: "Silent device times out",
+                cancel: false,
+                expected_message: "No response within 50 ms",
+            },
+            TestCase {
+                name: "Cancelled token stops the call",
+                cancel: true,
+                expected_message: "Request cancelled",
+            },
+        ];
+
+        for tc in test_cases {
+            let token = CancellationToken::new();
+            let house = AsyncSmartHouse::new(create_house())
+                .with_timeout(Duration::from_millis(50))
+                .with_cancellation(token.clone());
+            let socket = AsyncRemoteSocket::connect(key("Heater"), silent_server().await)
+                .await
+                .unwrap();
+            house
+                .add_remote_device(&key("Living Room"), key("Heater"), socket)
+                .unwrap();
+            if tc.cancel {
+                token.cancel();
+            }
+
+            let error = house
+                .turn_on_device(&key("Living Room"), &key("Heater"))
+                .await
+                .unwrap_err();
+            assert_eq!(
+                error.to_string(),
+                tc.expected_message,
+                "Failed test: {}",
+                tc.name
+            );
+        }
+    }
+}
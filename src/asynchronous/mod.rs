@@ -0,0 +1,159 @@
+//! Async API for networked devices, enabled with the `async` feature
+//!
+//! The traits mirror [`PowerControl`](crate::device::device_trait::PowerControl),
+//! [`TemperatureSensor`](crate::device::device_trait::TemperatureSensor) and
+//! [`PowerConsumption`](crate::device::device_trait::PowerConsumption), but
+//! return futures and report transport failures instead of hiding them.
+//! In-memory devices keep using the sync API; [`AsyncSmartHouse`] combines
+//! both behind one async façade with timeouts and cancellation.
+//!
+//! Everything here needs a tokio runtime with the time and I/O drivers enabled.
+
+mod house;
+mod remote_socket;
+
+pub use house::AsyncSmartHouse;
+pub use remote_socket::AsyncRemoteSocket;
+
+use crate::UdpThermometer;
+use crate::device::SmartDeviceTrait;
+use crate::error::ProtocolError;
+use async_trait::async_trait;
+use std::fmt::Debug;
+
+/// Base trait for devices driven through async calls
+///
+/// Methods take `&self` so a device can be shared between tasks;
+/// implementors synchronise access internally.
+pub trait AsyncSmartDevice: Debug + Send + Sync {
+    /// Returns the name of the device
+    fn name(&self) -> &str;
+
+    /// Returns the device as a power-controllable device, if supported
+    fn as_power_control(&self) -> Option<&dyn AsyncPowerControl> {
+        None
+    }
+
+    /// Returns the device as a temperature sensor, if supported
+    fn as_temperature_sensor(&self) -> Option<&dyn AsyncTemperatureSensor> {
+        None
+    }
+
+    /// Returns the device as a power consumer, if supported
+    fn as_power_consumption(&self) -> Option<&dyn AsyncPowerConsumption> {
+        None
+    }
+}
+
+/// Async counterpart of [`PowerControl`](crate::device::device_trait::PowerControl)
+#[async_trait]
+pub trait AsyncPowerControl: AsyncSmartDevice {
+    /// Checks if the device is on
+    async fn is_on(&self) -> Result<bool, ProtocolError>;
+
+    /// Turns the device on
+    async fn turn_on(&self) -> Result<(), ProtocolError>;
+
+    /// Turns the device off
+    async fn turn_off(&self) -> Result<(), ProtocolError>;
+}
+
+/// Async counterpart of [`TemperatureSensor`](crate::device::device_trait::TemperatureSensor)
+#[async_trait]
+pub trait AsyncTemperatureSensor: AsyncSmartDevice {
+    /// Returns the current temperature reading
+    async fn temperature(&self) -> Result<f32, ProtocolError>;
+}
+
+/// Async counterpart of [`PowerConsumption`](crate::device::device_trait::PowerConsumption)
+#[async_trait]
+pub trait AsyncPowerConsumption: AsyncSmartDevice {
+    /// Returns the current power consumption in watts
+    async fn power_consumption(&self) -> Result<f32, ProtocolError>;
+}
+
+// Readings arrive on a background thread, so the async API just checks the latest one
+impl AsyncSmartDevice for UdpThermometer {
+    fn name(&self) -> &str {
+        SmartDeviceTrait::name(self)
+    }
+
+    fn as_temperature_sensor(&self) -> Option<&dyn AsyncTemperatureSensor> {
+        Some(self)
+    }
+}
+
+#[async_trait]
+impl AsyncTemperatureSensor for UdpThermometer {
+    async fn temperature(&self) -> Result<f32, ProtocolError> {
+        self.try_temperature()
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::network::ThermometerEmulator;
+    use crate::network::udp_thermometer::tests::refused_thermometer;
+    use std::time::{Duration, Instant};
+
+    fn bind_thermometer() -> UdpThermometer {
+        UdpThermometer::bind("Udp Thermometer".to_string(), "127.0.0.1:0").unwrap()
+    }
+
+    /// Sends a reading and waits until the thermometer has it
+    fn send_reading(thermometer: &UdpThermometer, temperature: f32) {
+        let emulator = ThermometerEmulator::new(thermometer.local_addr()).unwrap();
+        emulator.send(temperature).unwrap();
+        let deadline = Instant::now() + Duration::from_secs(2);
+        while thermometer.latest_reading().is_none() && Instant::now() < deadline {
+            std::thread::sleep(Duration::from_millis(5));
+        }
+    }
+
+    #[tokio::test]
+    async fn test_udp_thermometer_temperature() {
+        let thermometer = bind_thermometer();
+        send_reading(&thermometer, 21.5);
+
+        assert_eq!(
+            AsyncTemperatureSensor::temperature(&thermometer)
+                .await
+                .unwrap(),
+            21.5
+        );
+    }
+
+    #[tokio::test]
+    async fn test_udp_thermometer_without_reading() {
+        let thermometer = bind_thermometer();
+
+        assert!(matches!(
+            AsyncTemperatureSensor::temperature(&thermometer).await,
+            Err(ProtocolError::NoReading)
+        ));
+    }
+
+    #[tokio::test]
+    async fn test_udp_thermometer_with_socket_error() {
+        let (thermometer, _) = refused_thermometer();
+
+        assert!(matches!(
+            AsyncTemperatureSensor::temperature(&thermometer).await,
+            Err(ProtocolError::Io(e)) if e.kind() == std::io::ErrorKind::ConnectionRefused
+        ));
+    }
+
+    #[tokio::test]
+    async fn test_udp_thermometer_with_stale_reading() {
+        let stale_after = Duration::from_millis(30);
+        let thermometer = bind_thermometer().with_stale_after(stale_after);
+        send_reading(&thermometer, 21.5);
+        tokio::time::sleep(Duration::from_millis(60)).await;
+
+        assert!(matches!(
+            AsyncTemperatureSensor::temperature(&thermometer).await,
+            Err(ProtocolError::Timeout(timeout)) if timeout == stale_after
+        ));
+    }
+}
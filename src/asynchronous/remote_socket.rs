@@ -0,0 +1,251 @@
+use super::{AsyncPowerConsumption, AsyncPowerControl, AsyncSmartDevice};
+use crate::error::ProtocolError;
+use crate::network::{Command, Response};
+use async_trait::async_trait;
+use std::net::SocketAddr;
+use tokio::io::{AsyncReadExt, AsyncWriteExt};
+use tokio::net::{TcpStream, ToSocketAddrs};
+use tokio::sync::Mutex;
+
+/// Async client for a smart socket exposed by a [`SocketServer`](crate::SocketServer)
+///
+/// Requests are cancel-safe: if a request future is dropped before the
+/// response arrives, the connection is closed and the next request opens a
+/// new one, so a late response is never mistaken for the answer to another
+/// command. The same happens after a transport error.
+#[derive(Debug)]
+pub struct AsyncRemoteSocket {
+    name: String,
+    addr: SocketAddr,
+    /// `None` while a request is in flight or after the connection was dropped
+    stream: Mutex<Option<TcpStream>>,
+}
+
+impl AsyncRemoteSocket {
+    /// Connects to a socket server at the given address
+    pub async fn connect<A: ToSocketAddrs>(name: String, addr: A) -> Result<Self, ProtocolError> {
+        let stream = TcpStream::connect(addr).await?;
+        stream.set_nodelay(true)?;
+
+        Ok(Self {
+            name,
+            addr: stream.peer_addr()?,
+            stream: Mutex::new(Some(stream)),
+        })
+    }
+
+    /// Returns the address of the socket server
+    pub fn peer_addr(&self) -> SocketAddr {
+        self.addr
+    }
+
+    /// Sends a raw command and waits for the response
+    pub async fn send(&self, command: Command) -> Result<Response, ProtocolError> {
+        let mut slot = self.stream.lock().await;
+        let mut stream = match slot.take() {
+            Some(stream) => stream,
+            None => {
+                let stream = TcpStream::connect(self.addr).await?;
+                stream.set_nodelay(true)?;
+                stream
+            }
+        };
+
+        stream.write_all(&[command.to_byte()]).await?;
+        let response = read_response(&mut stream).await?;
+        *slot = Some(stream);
+
+        match response {
+            Response::Error(code) => Err(ProtocolError::Rejected(code)),
+            response => Ok(response),
+        }
+    }
+
+    /// Turns the remote socket on
+    pub async fn try_turn_on(&self) -> Result<(), ProtocolError> {
+        self.expect_ok(Command::TurnOn).await
+    }
+
+    /// Turns the remote socket off
+    pub async fn try_turn_off(&self) -> Result<(), ProtocolError> {
+        self.expect_ok(Command::TurnOff).await
+    }
+
+    /// Queries whether the remote socket is on
+    pub async fn try_is_on(&self) -> Result<bool, ProtocolError> {
+        match self.send(Command::IsOn).await? {
+            Response::State(on) => Ok(on),
+            other => Err(ProtocolError::UnexpectedResponse(other)),
+        }
+    }
+
+    /// Queries the current power consumption of the remote socket in watts
+    pub async fn try_power_consumption(&self) -> Result<f32, ProtocolError> {
+        match self.send(Command::PowerConsumption).await? {
+            Response::Power(watts) => Ok(watts),
+            other => Err(ProtocolError::UnexpectedResponse(other)),
+        }
+    }
+
+    async fn expect_ok(&self, command: Command) -> Result<(), ProtocolError> {
+        match self.send(command).await? {
+            Response::Ok => Ok(()),
+            other => Err(ProtocolError::UnexpectedResponse(other)),
+        }
+    }
+}
+
+async fn read_response(stream: &mut TcpStream) -> Result<Response, ProtocolError> {
+    let tag = stream.read_u8().await?;
+    let mut payload = [0u8; 4];
+    let payload = &mut payload[..Response::payload_len(tag)?];
+    stream.read_exact(payload).await?;
+    Ok(Response::decode(tag, payload))
+}
+
+impl AsyncSmartDevice for AsyncRemoteSocket {
+    fn name(&self) -> &str {
+        &self.name
+    }
+
+    fn as_power_control(&self) -> Option<&dyn AsyncPowerControl> {
+        Some(self)
+    }
+
+    fn as_power_consumption(&self) -> Option<&dyn AsyncPowerConsumption> {
+        Some(self)
+    }
+}
+
+#[async_trait]
+impl AsyncPowerControl for AsyncRemoteSocket {
+    async fn is_on(&self) -> Result<bool, ProtocolError> {
+        self.try_is_on().await
+    }
+
+    async fn turn_on(&self) -> Result<(), ProtocolError> {
+        self.try_turn_on().await
+    }
+
+    async fn turn_off(&self) -> Result<(), ProtocolError> {
+        self.try_turn_off().await
+    }
+}
+
+#[async_trait]
+impl AsyncPowerConsumption for AsyncRemoteSocket {
+    async fn power_consumption(&self) -> Result<f32, ProtocolError> {
+        self.try_power_consumption().await
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::{SmartSocket, SocketServer};
+    use std::time::Duration;
+
+    #[tokio::test]
+    async fn test_async_remote_socket() {
+        struct TestCase {
+            name: &'static str,
+            command: Command,
+            expected: Response,
+        }
+
+        let test_cases = vec![
+            TestCase {
+                name: "Socket starts off",
+                command: Command::IsOn,
+                expected: Response::State(false),
+            },
+            TestCase {
+                name: "Turn on",
+                command: Command::TurnOn,
+                expected: Response::Ok,
+            },
+            TestCase {
+                name: "State after turning on",
+                command: Command::IsOn,
+                expected: Response::State(true),
+            },
+            TestCase {
+                name: "Power consumption",
+                command: Command::PowerConsumption,
+                expected: Response::Power(120.0),
+            },
+        ];
+
+        let socket = SmartSocket::new("Server Socket".to_string(), false, 120.0);
+        let server = SocketServer::bind("127.0.0.1:0", socket)
+            .unwrap()
+            .spawn()
+            .unwrap();
+        let remote = AsyncRemoteSocket::connect("TV".to_string(), server.local_addr())
+            .await
+            .unwrap();
+
+        for tc in test_cases {
+            assert_eq!(
+                remote.send(tc.command).await.unwrap(),
+                tc.expected,
+                "Failed test: {}",
+                tc.name
+            );
+        }
+    }
+
+    #[tokio::test]
+    async fn test_cancelled_request_reconnects() {
+        let listener = tokio::net::TcpListener::bind("127.0.0.1:0").await.unwrap();
+        let addr = listener.local_addr().unwrap();
+
+        // The first connection never answers, later ones answer every command with "on"
+        let server = tokio::spawn(async move {
+            let (_silent, _) = listener.accept().await.unwrap();
+            loop {
+                let (mut stream, _) = listener.accept().await.unwrap();
+                tokio::spawn(async move {
+                    while stream.read_u8().await.is_ok() {
+                        let bytes = Response::State(true).to_bytes();
+                        if stream.write_all(&bytes).await.is_err() {
+                            break;
+                        }
+                    }
+                });
+            }
+        });
+
+        let remote = AsyncRemoteSocket::connect("Lamp".to_string(), addr)
+            .await
+            .unwrap();
+        let cancelled = tokio::time::timeout(Duration::from_millis(50), remote.try_is_on()).await;
+        assert!(cancelled.is_err());
+
+        assert!(remote.try_is_on().await.unwrap());
+        assert!(matches!(
+            remote.try_turn_on().await,
+            Err(ProtocolError::UnexpectedResponse(Response::State(true)))
+        ));
+        server.abort();
+    }
+
+    #[tokio::test]
+    async fn test_server_error_is_rejected() {
+        let listener = tokio::net::TcpListener::bind("127.0.0.1:0").await.unwrap();
+        let addr = listener.local_addr().unwrap();
+        tokio::spawn(async move {
+            let (mut stream, _) = listener.accept().await.unwrap();
+            let _ = stream.read_u8().await;
+            let _ = stream.write_all(&Response::Error(0x01).to_bytes()).await;
+        });
+
+        let remote = AsyncRemoteSocket::connect("Lamp".to_string(), addr)
+            .await
+            .unwrap();
+        assert!(matches!(
+            remote.try_turn_off().await,
+            Err(ProtocolError::Rejected(0x01))
+        ));
+    }
+}
@@ -0,0 +1,64 @@
+use crate::SmartHouse;
+use crate::error::SmartHomeError;
+use serde::{Deserialize, Serialize};
+use std::fmt;
+
+/// Address of a device in a house: room key and device key
+#[derive(Debug, Clone, PartialEq, Eq, Hash, Serialize, Deserialize)]
+pub struct DeviceRef {
+    pub room: String,
+    pub device: String,
+}
+
+impl DeviceRef {
+    /// Creates a reference to a device in a room
+    pub fn new(room: impl Into<String>, device: impl Into<String>) -> Self {
+        Self {
+            room: room.into(),
+            device: device.into(),
+        }
+    }
+}
+
+impl fmt::Display for DeviceRef {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        write!(f, "{}/{}", self.room, self.device)
+    }
+}
+
+/// Something done to a house when a rule fires
+#[derive(Debug, Clone, PartialEq, Serialize, Deserialize)]
+#[serde(tag = "type", rename_all = "snake_case")]
+pub enum Action {
+    TurnOn(DeviceRef),
+    TurnOff(DeviceRef),
+}
+
+impl Action {
+    /// Returns the device the action applies to
+    pub fn target(&self) -> &DeviceRef {
+        match self {
+            Action::TurnOn(target) | Action::TurnOff(target) => target,
+        }
+    }
+
+    /// Applies the action to the house
+    ///
+    /// Goes through the house API, so subscribers of the house
+    /// [`EventBus`](crate::EventBus) see the resulting changes.
+    pub fn execute(&self, house: &mut SmartHouse) -> Result<(), SmartHomeError> {
+        match self {
+            Action::TurnOn(target) => house.turn_on_device(&target.room, &target.device),
+            Action::TurnOff(target) => house.turn_off_device(&target.room, &target.device),
+        }
+    }
+}
+
+impl fmt::Display for Action {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        match self {
+            Action::TurnOn(target) => write!(f, "turn on {}", target),
+            Action::TurnOff(target) => write!(f, "turn off {}", target),
+        }
+    }
+}
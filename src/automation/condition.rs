@@ -0,0 +1,327 @@
+use super::action::DeviceRef;
+use crate::SmartHouse;
+use crate::device::Capability;
+use crate::error::SmartHomeError;
+use serde::{Deserialize, Serialize};
+use std::fmt;
+
+/// How a measured value is compared with a threshold
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Serialize, Deserialize)]
+#[serde(rename_all = "snake_case")]
+pub enum Comparison {
+    Above,
+    AtLeast,
+    Below,
+    AtMost,
+}
+
+impl Comparison {
+    /// Applies the comparison to a measured value and a threshold
+    pub fn holds(self, value: f32, threshold: f32) -> bool {
+        match self {
+            Comparison::Above => value > threshold,
+            Comparison::AtLeast => value >= threshold,
+            Comparison::Below => value < threshold,
+            Comparison::AtMost => value <= threshold,
+        }
+    }
+}
+
+impl fmt::Display for Comparison {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        let symbol = match self {
+            Comparison::Above => ">",
+            Comparison::AtLeast => ">=",
+            Comparison::Below => "<",
+            Comparison::AtMost => "<=",
+        };
+        f.write_str(symbol)
+    }
+}
+
+/// A device reading a condition can compare against a threshold
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Serialize, Deserialize)]
+#[serde(rename_all = "snake_case")]
+pub enum Quantity {
+    Temperature,
+    PowerConsumption,
+}
+
+impl Quantity {
+    fn capability(self) -> Capability {
+        match self {
+            Quantity::Temperature => Capability::TemperatureSensor,
+            Quantity::PowerConsumption => Capability::PowerConsumption,
+        }
+    }
+}
+
+/// A predicate over the state of a house
+///
+/// Conditions referring to a missing device, or to a reading the device
+/// does not provide, fail with an error instead of evaluating to `false`.
+#[derive(Debug, Clone, PartialEq, Serialize, Deserialize)]
+#[serde(tag = "type", rename_all = "snake_case")]
+pub enum Condition {
+    /// Compares a device reading with a threshold
+    Compare {
+        target: DeviceRef,
+        quantity: Quantity,
+        comparison: Comparison,
+        threshold: f32,
+    },
+    /// Checks the power state of a device
+    PowerState { target: DeviceRef, is_on: bool },
+    /// Holds when every inner condition holds; an empty list always holds
+    All { conditions: Vec<Condition> },
+    /// Holds when at least one inner condition holds
+    Any { conditions: Vec<Condition> },
+    /// Inverts the inner condition
+    Not { condition: Box<Condition> },
+}
+
+impl Condition {
+    /// Compares the temperature of a device with a threshold in °C
+    pub fn temperature(target: DeviceRef, comparison: Comparison, threshold: f32) -> Self {
+        Condition::Compare {
+            target,
+            quantity: Quantity::Temperature,
+            comparison,
+            threshold,
+        }
+    }
+
+    /// Compares the power consumption of a device with a threshold in watts
+    pub fn power_consumption(target: DeviceRef, comparison: Comparison, threshold: f32) -> Self {
+        Condition::Compare {
+            target,
+            quantity: Quantity::PowerConsumption,
+            comparison,
+            threshold,
+        }
+    }
+
+    /// Holds when the device is on
+    pub fn is_on(target: DeviceRef) -> Self {
+        Condition::PowerState {
+            target,
+            is_on: true,
+        }
+    }
+
+    /// Holds when the device is off
+    pub fn is_off(target: DeviceRef) -> Self {
+        Condition::PowerState {
+            target,
+            is_on: false,
+        }
+    }
+
+    /// Combines two conditions so that both must hold
+    pub fn and(self, other: Condition) -> Self {
+        match self {
+            Condition::All { mut conditions } => {
+                conditions.push(other);
+                Condition::All { conditions }
+            }
+            condition => Condition::All {
+                conditions: vec![condition, other],
+            },
+        }
+    }
+
+    /// Combines two conditions so that at least one must hold
+    pub fn or(self, other: Condition) -> Self {
+        match self {
+            Condition::Any { mut conditions } => {
+                conditions.push(other);
+                Condition::Any { conditions }
+            }
+            condition => Condition::Any {
+                conditions: vec![condition, other],
+            },
+        }
+    }
+
+    /// Evaluates the condition against the current state of the house
+    pub fn evaluate(&self, house: &SmartHouse) -> Result<bool, SmartHomeError> {
+        match self {
+            Condition::Compare {
+                target,
+                quantity,
+                comparison,
+                threshold,
+            } => {
+                let device = house.device(&target.room, &target.device)?;
+                let value = match quantity {
+                    Quantity::Temperature => device.temperature(),
+                    Quantity::PowerConsumption => device.power_consumption(),
+                }
+                .ok_or_else(|| device.unsupported(quantity.capability()))?;
+                Ok(comparison.holds(value, *threshold))
+            }
+            Condition::PowerState { target, is_on } => {
+                let device = house.device(&target.room, &target.device)?;
+                let state = device
+                    .is_on()
+                    .ok_or_else(|| device.unsupported(Capability::PowerControl))?;
+                Ok(state == *is_on)
+            }
+            Condition::All { conditions } => {
+                for condition in conditions {
+                    if !condition.evaluate(house)? {
+                        return Ok(false);
+                    }
+                }
+                Ok(true)
+            }
+            Condition::Any { conditions } => {
+                for condition in conditions {
+                    if condition.evaluate(house)? {
+                        return Ok(true);
+                    }
+                }
+                Ok(false)
+            }
+            Condition::Not { condition } => Ok(!condition.evaluate(house)?),
+        }
+    }
+}
+
+impl std::ops::Not for Condition {
+    type Output = Condition;
+
+    fn not(self) -> Condition {
+        Condition::Not {
+            condition: Box::new(self),
+        }
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::{SmartSocket, SmartThermometer, create_room};
+
+    fn create_house() -> SmartHouse {
+        let room = create_room!(
+            "Living Room",
+            (
+                "Thermometer",
+                SmartThermometer::new("Thermometer".to_string(), 27.0)
+            ),
+            (
+                "TV Socket",
+                SmartSocket::new("TV Socket".to_string(), true, 120.0)
+            ),
+            ("Lamp", SmartSocket::new("Lamp".to_string(), false, 40.0))
+        );
+        SmartHouse::new(
+            "Test House".to_string(),
+            vec![("Living Room".to_string(), room)],
+        )
+    }
+
+    fn device(name: &str) -> DeviceRef {
+        DeviceRef::new("Living Room", name)
+    }
+
+    #[test]
+    fn test_evaluate() {
+        struct TestCase {
+            name: &'static str,
+            condition: Condition,
+            expected: Result<bool, &'static str>,
+        }
+
+        let test_cases = vec![
+            TestCase {
+                name: "Temperature above threshold",
+                condition: Condition::temperature(device("Thermometer"), Comparison::Above, 26.0),
+                expected: Ok(true),
+            },
+            TestCase {
+                name: "Temperature at most threshold",
+                condition: Condition::temperature(device("Thermometer"), Comparison::AtMost, 26.0),
+                expected: Ok(false),
+            },
+            TestCase {
+                name: "Power consumption at least threshold",
+                condition: Condition::power_consumption(
+                    device("TV Socket"),
+                    Comparison::AtLeast,
+                    120.0,
+                ),
+                expected: Ok(true),
+            },
+            TestCase {
+                name: "Power state",
+                condition: Condition::is_off(device("Lamp")),
+                expected: Ok(true),
+            },
+            TestCase {
+                name: "And combinator",
+                condition: Condition::is_on(device("TV Socket"))
+                    .and(Condition::is_on(device("Lamp"))),
+                expected: Ok(false),
+            },
+            TestCase {
+                name: "Or combinator",
+                condition: Condition::is_on(device("Lamp"))
+                    .or(Condition::is_on(device("TV Socket"))),
+                expected: Ok(true),
+            },
+            TestCase {
+                name: "Not combinator",
+                condition: !Condition::is_on(device("TV Socket")),
+                expected: Ok(false),
+            },
+            TestCase {
+                name: "Empty All holds",
+                condition: Condition::All { conditions: vec![] },
+                expected: Ok(true),
+            },
+            TestCase {
+                name: "Short circuit skips the failing branch",
+                condition: Condition::is_on(device("TV Socket"))
+                    .or(Condition::is_on(device("Missing"))),
+                expected: Ok(true),
+            },
+            TestCase {
+                name: "Missing device",
+                condition: Condition::is_on(device("Missing")),
+                expected: Err("Device 'Missing' not found in room 'Living Room'"),
+            },
+            TestCase {
+                name: "Missing room",
+                condition: Condition::is_on(DeviceRef::new("Attic", "Fan")),
+                expected: Err("Room 'Attic' not found"),
+            },
+            TestCase {
+                name: "Unsupported reading",
+                condition: Condition::temperature(device("Lamp"), Comparison::Above, 0.0),
+                expected: Err("Device 'Lamp' does not support temperature sensor"),
+            },
+        ];
+
+        let house = create_house();
+        for tc in test_cases {
+            assert_eq!(
+                tc.condition.evaluate(&house).map_err(|e| e.to_string()),
+                tc.expected.map_err(str::to_string),
+                "Failed test: {}",
+                tc.name
+            );
+        }
+    }
+
+    #[test]
+    fn test_condition_serialization() {
+        let condition = Condition::temperature(device("Thermometer"), Comparison::Above, 26.0)
+            .and(!Condition::is_on(device("Lamp")));
+
+        let json = serde_json::to_string(&condition).unwrap();
+        let decoded: Condition = serde_json::from_str(&json).unwrap();
+        assert_eq!(decoded, condition);
+    }
+}
@@ -0,0 +1,333 @@
+use super::action::Action;
+use super::condition::Condition;
+use crate::SmartHouse;
+use crate::error::SmartHomeError;
+use serde::{Deserialize, Serialize};
+
+/// A named condition with the actions to run while it holds
+#[derive(Debug, Clone, PartialEq, Serialize, Deserialize)]
+pub struct Rule {
+    pub name: String,
+    pub condition: Condition,
+    pub actions: Vec<Action>,
+}
+
+impl Rule {
+    /// Creates a rule without actions
+    pub fn new(name: impl Into<String>, condition: Condition) -> Self {
+        Self {
+            name: name.into(),
+            condition,
+            actions: Vec::new(),
+        }
+    }
+
+    /// Adds an action run when the rule fires
+    pub fn then(mut self, action: Action) -> Self {
+        self.actions.push(action);
+        self
+    }
+}
+
+/// An action of a fired rule that could not be applied
+#[derive(Debug)]
+pub struct ActionFailure {
+    pub action: Action,
+    pub error: SmartHomeError,
+}
+
+/// What happened to a rule during an evaluation
+#[derive(Debug)]
+pub enum RuleStatus {
+    /// The condition did not hold
+    Skipped,
+    /// The condition held and the actions ran; failed actions are listed
+    Fired { failures: Vec<ActionFailure> },
+    /// The condition could not be evaluated, no action ran
+    ConditionFailed(SmartHomeError),
+}
+
+/// Outcome of a single rule
+#[derive(Debug)]
+pub struct RuleOutcome {
+    pub rule: String,
+    pub status: RuleStatus,
+}
+
+impl RuleOutcome {
+    /// Checks whether the rule fired, regardless of action failures
+    pub fn fired(&self) -> bool {
+        matches!(self.status, RuleStatus::Fired { .. })
+    }
+}
+
+/// Outcome of running all rules of an engine once
+#[derive(Debug, Default)]
+pub struct EvaluationReport {
+    pub outcomes: Vec<RuleOutcome>,
+}
+
+impl EvaluationReport {
+    /// Returns the names of the rules that fired
+    pub fn fired(&self) -> Vec<&str> {
+        self.outcomes
+            .iter()
+            .filter(|outcome| outcome.fired())
+            .map(|outcome| outcome.rule.as_str())
+            .collect()
+    }
+
+    /// Returns the failed actions together with the rule they belong to
+    pub fn failed_actions(&self) -> Vec<(&str, &ActionFailure)> {
+        self.outcomes
+            .iter()
+            .flat_map(|outcome| match &outcome.status {
+                RuleStatus::Fired { failures } => failures
+                    .iter()
+                    .map(|failure| (outcome.rule.as_str(), failure))
+                    .collect(),
+                _ => Vec::new(),
+            })
+            .collect()
+    }
+
+    /// Checks whether every condition evaluated and every action succeeded
+    pub fn is_clean(&self) -> bool {
+        self.outcomes.iter().all(|outcome| match &outcome.status {
+            RuleStatus::Skipped => true,
+            RuleStatus::Fired { failures } => failures.is_empty(),
+            RuleStatus::ConditionFailed(_) => false,
+        })
+    }
+}
+
+/// Runs a list of rules against a house
+///
+/// Rules are level-triggered: a rule fires on every evaluation in which its
+/// condition holds. Rules run in the order they were added and see the
+/// changes made by earlier rules.
+#[derive(Debug, Clone, Default, PartialEq, Serialize, Deserialize)]
+pub struct RuleEngine {
+    rules: Vec<Rule>,
+}
+
+impl RuleEngine {
+    /// Creates an engine without rules
+    pub fn new() -> Self {
+        Self::default()
+    }
+
+    /// Adds a rule
+    ///
+    /// Fails if the name is empty or already used by another rule.
+    pub fn add_rule(&mut self, rule: Rule) -> Result<(), SmartHomeError> {
+        if rule.name.trim().is_empty() {
+            return Err(SmartHomeError::invalid_value(
+                "rule name",
+                "must not be empty",
+            ));
+        }
+        if self.rule(&rule.name).is_some() {
+            return Err(SmartHomeError::invalid_value(
+                "rule name",
+                format!("'{}' is already used", rule.name),
+            ));
+        }
+
+        self.rules.push(rule);
+        Ok(())
+    }
+
+    /// Removes a rule by name and returns it
+    pub fn remove_rule(&mut self, name: &str) -> Option<Rule> {
+        let index = self.rules.iter().position(|rule| rule.name == name)?;
+        Some(self.rules.remove(index))
+    }
+
+    /// Returns a rule by name
+    pub fn rule(&self, name: &str) -> Option<&Rule> {
+        self.rules.iter().find(|rule| rule.name == name)
+    }
+
+    /// Returns all rules in evaluation order
+    pub fn rules(&self) -> &[Rule] {
+        &self.rules
+    }
+
+    /// Evaluates every rule and runs the actions of the ones that hold
+    pub fn evaluate(&self, house: &mut SmartHouse) -> EvaluationReport {
+        let outcomes = self
+            .rules
+            .iter()
+            .map(|rule| RuleOutcome {
+                rule: rule.name.clone(),
+                status: Self::run_rule(rule, house),
+            })
+            .collect();
+
+        EvaluationReport { outcomes }
+    }
+
+    fn run_rule(rule: &Rule, house: &mut SmartHouse) -> RuleStatus {
+        match rule.condition.evaluate(house) {
+            Ok(false) => RuleStatus::Skipped,
+            Ok(true) => {
+                let failures = rule
+                    .actions
+                    .iter()
+                    .filter_map(|action| {
+                        action.execute(house).err().map(|error| ActionFailure {
+                            action: action.clone(),
+                            error,
+                        })
+                    })
+                    .collect();
+                RuleStatus::Fired { failures }
+            }
+            Err(error) => RuleStatus::ConditionFailed(error),
+        }
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::automation::{Comparison, DeviceRef};
+    use crate::{SmartSocket, SmartThermometer, create_room};
+
+    fn create_house() -> SmartHouse {
+        let room = create_room!(
+            "Living Room",
+            (
+                "Thermometer",
+                SmartThermometer::new("Thermometer".to_string(), 27.0)
+            ),
+            (
+                "TV Socket",
+                SmartSocket::new("TV Socket".to_string(), true, 120.0)
+            ),
+            ("Fan", SmartSocket::new("Fan".to_string(), false, 30.0))
+        );
+        SmartHouse::new(
+            "Test House".to_string(),
+            vec![("Living Room".to_string(), room)],
+        )
+    }
+
+    fn device(name: &str) -> DeviceRef {
+        DeviceRef::new("Living Room", name)
+    }
+
+    fn too_hot() -> Condition {
+        Condition::temperature(device("Thermometer"), Comparison::Above, 26.0)
+    }
+
+    #[test]
+    fn test_evaluate_rules() {
+        let mut engine = RuleEngine::new();
+        engine
+            .add_rule(
+                Rule::new("Too hot for TV", too_hot())
+                    .then(Action::TurnOff(device("TV Socket")))
+                    .then(Action::TurnOn(device("Fan"))),
+            )
+            .unwrap();
+        engine
+            .add_rule(Rule::new("Cold", !too_hot()).then(Action::TurnOff(device("Fan"))))
+            .unwrap();
+        engine
+            .add_rule(
+                Rule::new("Broken action", too_hot())
+                    .then(Action::TurnOn(device("Thermometer")))
+                    .then(Action::TurnOn(device("Heater"))),
+            )
+            .unwrap();
+        engine
+            .add_rule(
+                Rule::new("Broken condition", Condition::is_on(device("Heater")))
+                    .then(Action::TurnOn(device("TV Socket"))),
+            )
+            .unwrap();
+
+        let mut house = create_house();
+        let report = engine.evaluate(&mut house);
+
+        assert_eq!(report.fired(), vec!["Too hot for TV", "Broken action"]);
+        assert!(!report.is_clean());
+
+        let failures: Vec<(&str, String)> = report
+            .failed_actions()
+            .into_iter()
+            .map(|(rule, failure)| (rule, failure.error.to_string()))
+            .collect();
+        assert_eq!(
+            failures,
+            vec![
+                (
+                    "Broken action",
+                    "Device 'Thermometer' does not support power control".to_string()
+                ),
+                (
+                    "Broken action",
+                    "Device 'Heater' not found in room 'Living Room'".to_string()
+                ),
+            ]
+        );
+        assert!(matches!(
+            report.outcomes[3].status,
+            RuleStatus::ConditionFailed(_)
+        ));
+
+        let tv = house
+            .device(&"Living Room".to_string(), &"TV Socket".to_string())
+            .unwrap();
+        let fan = house
+            .device(&"Living Room".to_string(), &"Fan".to_string())
+            .unwrap();
+        assert_eq!(tv.is_on(), Some(false));
+        assert_eq!(fan.is_on(), Some(true));
+    }
+
+    #[test]
+    fn test_rule_management() {
+        struct TestCase {
+            name: &'static str,
+            rule: Rule,
+            expected_success: bool,
+        }
+
+        let test_cases = vec![
+            TestCase {
+                name: "New rule",
+                rule: Rule::new("Too hot", too_hot()),
+                expected_success: true,
+            },
+            TestCase {
+                name: "Duplicate name",
+                rule: Rule::new("Too hot", !too_hot()),
+                expected_success: false,
+            },
+            TestCase {
+                name: "Empty name",
+                rule: Rule::new(" ", too_hot()),
+                expected_success: false,
+            },
+        ];
+
+        let mut engine = RuleEngine::new();
+        for tc in test_cases {
+            assert_eq!(
+                engine.add_rule(tc.rule).is_ok(),
+                tc.expected_success,
+                "Failed test: {}",
+                tc.name
+            );
+        }
+
+        assert_eq!(engine.rules().len(), 1);
+        assert_eq!(engine.rule("Too hot").unwrap().condition, too_hot());
+        assert!(engine.remove_rule("Too hot").is_some());
+        assert!(engine.remove_rule("Too hot").is_none());
+        assert!(engine.evaluate(&mut create_house()).outcomes.is_empty());
+    }
+}
@@ -0,0 +1,26 @@
+//! Declarative rules evaluated against a [`SmartHouse`](crate::SmartHouse)
+//!
+//! ```
+//! use smart_home::automation::{Action, Comparison, Condition, DeviceRef, Rule, RuleEngine};
+//!
+//! let thermometer = DeviceRef::new("Living Room", "Thermometer");
+//! let tv = DeviceRef::new("Living Room", "TV Socket");
+//!
+//! let mut engine = RuleEngine::new();
+//! engine
+//!     .add_rule(
+//!         Rule::new(
+//!             "Too hot for TV",
+//!             Condition::temperature(thermometer, Comparison::Above, 26.0),
+//!         )
+//!         .then(Action::TurnOff(tv)),
+//!     )
+//!     .unwrap();
+//! ```
+mod action;
+mod condition;
+mod engine;
+
+pub use action::{Action, DeviceRef};
+pub use condition::{Comparison, Condition, Quantity};
+pub use engine::{ActionFailure, EvaluationReport, Rule, RuleEngine, RuleOutcome, RuleStatus};
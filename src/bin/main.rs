@@ -1,6 +1,6 @@
 use smart_home::{
-    Reporter, Room, SmartDevice, SmartDeviceTrait, SmartHouse, SmartSocket, SmartThermometer,
-    create_room,
+    RemoteSocket, Reporter, SmartDevice, SmartDeviceTrait, SmartHouse, SmartSocket,
+    SmartThermometer, SocketServer, create_room,
 };
 
 fn demonstrate_dynamic_rooms_and_devices() {
@@ -28,21 +28,26 @@ fn demonstrate_dynamic_rooms_and_devices() {
         )
     );
 
-    house.add_room("Living Room".to_string(), living_room);
-    house.add_room("Bedroom".to_string(), bedroom);
+    for (name, room) in [("Living Room", living_room), ("Bedroom", bedroom)] {
+        if let Err(e) = house.add_room(name.to_string(), room) {
+            println!("Не вдалося додати кімнату: {}", e);
+        }
+    }
 
     println!("Initial House State:");
     println!("{}", house.report());
 
-    if let Some(removed_room) = house.remove_room(&"Bedroom".to_string()) {
+    if let Ok(removed_room) = house.remove_room(&"Bedroom".to_string()) {
         println!("\nВидалена кімната: {}", removed_room.name());
     }
 
     if let Some(living_room) = house.room_mut(&"Living Room".to_string()) {
         let new_socket = SmartSocket::new(String::from("Ceiling Light"), true, 20.0);
-        living_room.add_device("Ceiling Light".to_string(), new_socket.into());
+        if let Err(e) = living_room.add_device("Ceiling Light".to_string(), new_socket.into()) {
+            println!("Не вдалося додати пристрій: {}", e);
+        }
 
-        if let Some(removed_device) = living_room.remove_device(&"TV Socket".to_string()) {
+        if let Ok(removed_device) = living_room.remove_device(&"TV Socket".to_string()) {
             println!("\nВидалений пристрій: {}", removed_device.name());
         }
     }
@@ -60,6 +65,47 @@ fn demonstrate_error_handling() {
         Ok(_) => println!("Несподіваний успіх"),
         Err(e) => println!("Помилка (очікувана): {}", e),
     }
+
+    let mut house = house;
+    let room = create_room!(
+        "Kitchen",
+        (
+            "Thermometer",
+            SmartThermometer::new(String::from("Kitchen Thermo"), 24.0)
+        )
+    );
+    if let Err(e) = house.add_room("Kitchen".to_string(), room) {
+        println!("Не вдалося додати кімнату: {}", e);
+    }
+
+    match house.turn_on_device(&"Kitchen".to_string(), &"Thermometer".to_string()) {
+        Ok(()) => println!("Несподіваний успіх"),
+        Err(e) => println!("Помилка (очікувана): {}", e),
+    }
+}
+
+fn demonstrate_remote_socket() {
+    println!("\n=== Демонстрація керування розеткою через TCP ===");
+
+    let socket = SmartSocket::new(String::from("Kettle Socket"), false, 2000.0);
+    let server = match SocketServer::bind("127.0.0.1:0", socket).and_then(|s| s.spawn()) {
+        Ok(server) => server,
+        Err(e) => {
+            println!("Не вдалося запустити сервер: {}", e);
+            return;
+        }
+    };
+
+    match RemoteSocket::connect(String::from("Kettle Socket"), server.local_addr()) {
+        Ok(remote) => {
+            println!("До ввімкнення: {}", remote.report());
+            match remote.try_turn_on() {
+                Ok(()) => println!("Після ввімкнення: {}", remote.report()),
+                Err(e) => println!("Не вдалося ввімкнути: {}", e),
+            }
+        }
+        Err(e) => println!("Помилка підключення: {}", e),
+    }
 }
 
 fn print_report<T: Reporter>(reportable: &T) {
@@ -70,6 +116,7 @@ fn print_report<T: Reporter>(reportable: &T) {
 fn main() {
     demonstrate_dynamic_rooms_and_devices();
     demonstrate_error_handling();
+    demonstrate_remote_socket();
 
     let thermometer = SmartThermometer::new(String::from("Main Thermometer"), 23.5);
     let socket = SmartSocket::new(String::from("Main Socket"), true, 100.0);
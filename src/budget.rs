@@ -0,0 +1,319 @@
+//! Power budgets limiting how much a room or a house may draw
+//!
+//! A budget is checked before a device is turned on through a [`Room`] or
+//! the house API. Devices switched directly through
+//! [`SmartDevice::as_power_control_mut`] bypass it, and so do networked
+//! devices of the async façade. Setting a budget below the current draw does
+//! not switch anything off.
+//!
+//! [`SmartDevice::as_power_control_mut`]: crate::SmartDevice::as_power_control_mut
+
+use crate::error::{DeviceAccessError, SmartHomeError};
+use crate::events::HouseEvent;
+use crate::room::Room;
+
+/// What happens when turning a device on would exceed a budget
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Default)]
+pub enum BudgetPolicy {
+    /// The device stays off and the call fails with
+    /// [`SmartHomeError::BudgetExceeded`]
+    #[default]
+    Reject,
+    /// Devices with a lower priority are switched off, lowest first, to make
+    /// room; the call fails as with `Reject` if that is not enough
+    Shed,
+}
+
+/// Maximum power a room or a house may draw
+#[derive(Debug, Clone, Copy, PartialEq)]
+pub struct PowerBudget {
+    pub limit_watts: f32,
+    pub policy: BudgetPolicy,
+}
+
+impl PowerBudget {
+    /// Creates a budget that rejects devices going over the limit
+    pub fn reject(limit_watts: f32) -> Self {
+        Self {
+            limit_watts,
+            policy: BudgetPolicy::Reject,
+        }
+    }
+
+    /// Creates a budget that sheds lower-priority devices to stay within the limit
+    pub fn shed(limit_watts: f32) -> Self {
+        Self {
+            limit_watts,
+            policy: BudgetPolicy::Shed,
+        }
+    }
+
+    pub(crate) fn validate(&self) -> Result<(), SmartHomeError> {
+        if !self.limit_watts.is_finite() || self.limit_watts < 0.0 {
+            return Err(SmartHomeError::invalid_value(
+                "power budget",
+                "limit must be a finite, non-negative number of watts",
+            ));
+        }
+        Ok(())
+    }
+}
+
+/// A device that may be switched off to make room, with its location
+pub(crate) struct Candidate<K> {
+    pub key: K,
+    pub priority: i32,
+    pub watts: f32,
+}
+
+/// Picks the devices to switch off to bring `total` within `budget`
+///
+/// Only devices with a priority below `priority` are considered, lowest
+/// priority first and in insertion order among equals. Returns `None` if
+/// switching off all of them would not be enough.
+pub(crate) fn plan_shedding<K>(
+    budget: &PowerBudget,
+    total: f32,
+    priority: i32,
+    candidates: impl IntoIterator<Item = Candidate<K>>,
+) -> Option<Vec<K>> {
+    let mut candidates: Vec<_> = candidates
+        .into_iter()
+        .filter(|candidate| candidate.priority < priority && candidate.watts > 0.0)
+        .collect();
+    candidates.sort_by_key(|candidate| candidate.priority);
+
+    let mut total = total;
+    let mut shed = Vec::new();
+    for candidate in candidates {
+        if total <= budget.limit_watts {
+            break;
+        }
+        total -= candidate.watts;
+        shed.push(candidate.key);
+    }
+    (total <= budget.limit_watts).then_some(shed)
+}
+
+/// Turns a device on within the room budget, then the house budget
+///
+/// Both budgets are checked before anything is switched. Returns the power
+/// state changes to publish: the device itself and every device shed to make
+/// room for it. On failure every device is put back as it was; the error lists
+/// any device that could not be.
+pub(crate) fn turn_on_in_house(
+    house_name: &str,
+    budget: Option<&PowerBudget>,
+    rooms: &mut [(&String, &mut Room)],
+    room_name: &String,
+    device_name: &String,
+) -> Result<Vec<HouseEvent>, SmartHomeError> {
+    let target = rooms
+        .iter()
+        .position(|(name, _)| *name == room_name)
+        .ok_or_else(|| DeviceAccessError::RoomNotFound(room_name.clone()))?;
+
+    let plan = rooms[target].1.plan_switch_on(device_name)?;
+    if plan.was_on {
+        rooms[target].1.switch_on(device_name)?;
+        return Ok(Vec::new());
+    }
+
+    let mut shed: Vec<(usize, String)> = plan.shed.into_iter().map(|name| (target, name)).collect();
+    if let Some(budget) = budget {
+        let total = house_total(rooms, target, plan.total);
+        if total > budget.limit_watts {
+            let candidates = house_candidates(rooms, target, device_name)
+                .into_iter()
+                .filter(|candidate| !shed.contains(&candidate.key));
+            let house_shed = match budget.policy {
+                BudgetPolicy::Reject => None,
+                BudgetPolicy::Shed => plan_shedding(
+                    budget,
+                    total,
+                    rooms[target].1.device_priority(device_name),
+                    candidates,
+                ),
+            }
+            .ok_or_else(|| over_budget(house_name, budget, total))?;
+            shed.extend(house_shed);
+        }
+    }
+
+    shed_devices(rooms, &shed)?;
+    let mut result = rooms[target].1.turn_on_checked(device_name);
+    if let (Ok(()), Some(budget)) = (&result, budget) {
+        // The device may draw more than it announced
+        let total = house_total(rooms, target, rooms[target].1.total_power_consumption());
+        if total > budget.limit_watts {
+            let error = over_budget(house_name, budget, total);
+            result =
+                match rooms[target].1.set_power_state(device_name, false) {
+                    Ok(()) => Err(error),
+                    Err(_) => Err(error
+                        .with_rollback_failures(vec![(room_name.clone(), device_name.clone())])),
+                };
+        }
+    }
+    if let Err(e) = result {
+        return Err(restore_devices(rooms, &shed, e));
+    }
+
+    let mut events = vec![HouseEvent::PowerStateChanged {
+        room: room_name.clone(),
+        device: device_name.clone(),
+        is_on: true,
+    }];
+    events.extend(
+        shed.into_iter()
+            .map(|(index, name)| HouseEvent::PowerStateChanged {
+                room: rooms[index].0.clone(),
+                device: name,
+                is_on: false,
+            }),
+    );
+    Ok(events)
+}
+
+/// Returns the draw of the house with `target_total` as the draw of the target room
+fn house_total(rooms: &[(&String, &mut Room)], target: usize, target_total: f32) -> f32 {
+    rooms
+        .iter()
+        .enumerate()
+        .map(|(index, (_, room))| {
+            if index == target {
+                target_total
+            } else {
+                room.total_power_consumption()
+            }
+        })
+        .sum()
+}
+
+/// Switches devices off anywhere in the house without checking budgets
+///
+/// If one of them fails, the devices already switched off are turned back on
+/// and its error is returned.
+fn shed_devices(
+    rooms: &mut [(&String, &mut Room)],
+    devices: &[(usize, String)],
+) -> Result<(), SmartHomeError> {
+    for (shed, (index, name)) in devices.iter().enumerate() {
+        if let Err(e) = rooms[*index].1.set_power_state(name, false) {
+            return Err(restore_devices(rooms, &devices[..shed], e));
+        }
+    }
+    Ok(())
+}
+
+/// Turns shed devices back on after a change failed with `cause`
+///
+/// Returns `cause`, listing the devices that stayed off.
+fn restore_devices(
+    rooms: &mut [(&String, &mut Room)],
+    devices: &[(usize, String)],
+    cause: SmartHomeError,
+) -> SmartHomeError {
+    let mut failed = Vec::new();
+    for (index, name) in devices {
+        if rooms[*index].1.set_power_state(name, true).is_err() {
+            failed.push((rooms[*index].0.clone(), name.clone()));
+        }
+    }
+    cause.with_rollback_failures(failed)
+}
+
+fn over_budget(house_name: &str, budget: &PowerBudget, total: f32) -> SmartHomeError {
+    SmartHomeError::BudgetExceeded(format!("house '{}'", house_name), budget.limit_watts, total)
+}
+
+/// Lists the devices of the house that may be shed, except the one being turned on
+fn house_candidates(
+    rooms: &[(&String, &mut Room)],
+    target: usize,
+    device_name: &String,
+) -> Vec<Candidate<(usize, String)>> {
+    rooms
+        .iter()
+        .enumerate()
+        .flat_map(|(index, (_, room))| {
+            room.shedding_candidates()
+                .filter(move |candidate| index != target || &candidate.key != device_name)
+                .map(move |candidate| Candidate {
+                    key: (index, candidate.key),
+                    priority: candidate.priority,
+                    watts: candidate.watts,
+                })
+        })
+        .collect()
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn test_plan_shedding() {
+        struct TestCase {
+            name: &'static str,
+            total: f32,
+            priority: i32,
+            expected: Option<Vec<&'static str>>,
+        }
+
+        let candidates = || {
+            [
+                ("Heater", -1, 1500.0),
+                ("Lamp", -5, 60.0),
+                ("Fridge", 10, 150.0),
+                ("TV", -1, 200.0),
+            ]
+            .into_iter()
+            .map(|(key, priority, watts)| Candidate {
+                key,
+                priority,
+                watts,
+            })
+        };
+
+        let test_cases = vec![
+            TestCase {
+                name: "Within budget",
+                total: 1000.0,
+                priority: 0,
+                expected: Some(vec![]),
+            },
+            TestCase {
+                name: "Lowest priority first, insertion order among equals",
+                total: 3000.0,
+                priority: 0,
+                expected: Some(vec!["Lamp", "Heater"]),
+            },
+            TestCase {
+                name: "Higher priority devices are kept",
+                total: 4000.0,
+                priority: 0,
+                expected: None,
+            },
+            TestCase {
+                name: "Only strictly lower priorities are shed",
+                total: 2100.0,
+                priority: -5,
+                expected: None,
+            },
+        ];
+
+        let budget = PowerBudget::shed(2000.0);
+        for tc in test_cases {
+            assert_eq!(
+                plan_shedding(&budget, tc.total, tc.priority, candidates()),
+                tc.expected,
+                "Failed test: {}",
+                tc.name
+            );
+        }
+
+        assert!(PowerBudget::reject(-1.0).validate().is_err());
+        assert!(PowerBudget::reject(f32::INFINITY).validate().is_err());
+    }
+}
@@ -0,0 +1,97 @@
+//! Source of the current time, replaceable in tests
+//!
+//! Time is local wall-clock time without a time zone, matching how people
+//! write schedules such as "07:00 on weekdays".
+
+use chrono::{Local, NaiveDateTime};
+use std::sync::{Arc, Mutex};
+use std::time::Duration;
+
+/// Provides the current local time
+pub trait Clock: Send + Sync {
+    /// Returns the current local date and time
+    fn now(&self) -> NaiveDateTime;
+}
+
+impl<C: Clock + ?Sized> Clock for Arc<C> {
+    fn now(&self) -> NaiveDateTime {
+        (**self).now()
+    }
+}
+
+/// Clock reading the system time
+#[derive(Debug, Clone, Copy, Default)]
+pub struct SystemClock;
+
+impl SystemClock {
+    /// Returns the system clock as a shared trait object, the default of devices with a clock
+    pub(crate) fn shared() -> Arc<dyn Clock> {
+        Arc::new(SystemClock)
+    }
+}
+
+impl Clock for SystemClock {
+    fn now(&self) -> NaiveDateTime {
+        Local::now().naive_local()
+    }
+}
+
+/// Clock that only moves when told to
+///
+/// Share it through an `Arc` to drive several components from one test.
+#[derive(Debug)]
+pub struct ManualClock {
+    now: Mutex<NaiveDateTime>,
+}
+
+impl ManualClock {
+    /// Creates a clock stopped at the given time
+    pub fn new(now: NaiveDateTime) -> Self {
+        Self {
+            now: Mutex::new(now),
+        }
+    }
+
+    /// Moves the clock to the given time
+    pub fn set(&self, now: NaiveDateTime) {
+        *self.now.lock().unwrap_or_else(|e| e.into_inner()) = now;
+    }
+
+    /// Moves the clock forward
+    ///
+    /// # Panics
+    ///
+    /// Panics if the resulting time is out of range.
+    pub fn advance(&self, by: Duration) {
+        let mut now = self.now.lock().unwrap_or_else(|e| e.into_inner());
+        *now += chrono::TimeDelta::from_std(by).expect("duration out of range");
+    }
+}
+
+impl Clock for ManualClock {
+    fn now(&self) -> NaiveDateTime {
+        *self.now.lock().unwrap_or_else(|e| e.into_inner())
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use chrono::NaiveDate;
+
+    #[test]
+    fn test_manual_clock() {
+        let start = NaiveDate::from_ymd_opt(2024, 1, 1)
+            .unwrap()
+            .and_hms_opt(7, 0, 0)
+            .unwrap();
+        let clock = Arc::new(ManualClock::new(start));
+        let shared: Arc<dyn Clock> = clock.clone();
+
+        clock.advance(Duration::from_secs(90));
+        assert_eq!(shared.now(), start + chrono::TimeDelta::seconds(90));
+
+        clock.set(start);
+        assert_eq!(shared.now(), start);
+    }
+}
@@ -0,0 +1,400 @@
+//! Energy cost of the devices of a house under a [`Tariff`]
+//!
+//! Energy meters only count watt-hours, so the [`CostTracker`] samples them
+//! and prices the energy used between two samples as if it had been used
+//! evenly over that time. Sample often enough for time-of-use bands to be
+//! told apart, e.g. every minute.
+
+use crate::SmartHouse;
+use crate::clock::{Clock, SystemClock};
+use crate::error::SmartHomeError;
+use crate::report::Reporter;
+use crate::tariff::Tariff;
+use chrono::NaiveDateTime;
+use indexmap::IndexMap;
+
+/// Energy and cost of a device over the period
+#[derive(Debug, Clone, Default, PartialEq)]
+pub struct DeviceCost {
+    pub name: String,
+    pub energy_kwh: f64,
+    pub cost: f64,
+}
+
+/// Energy and cost of a room over the period
+#[derive(Debug, Clone, Default, PartialEq)]
+pub struct RoomCost {
+    pub name: String,
+    pub energy_kwh: f64,
+    pub cost: f64,
+    pub devices: Vec<DeviceCost>,
+}
+
+/// Energy and cost of a house over the period since tracking started
+#[derive(Debug, Clone, PartialEq)]
+pub struct CostReport {
+    /// First sample of the period, `None` before any sample
+    pub from: Option<NaiveDateTime>,
+    /// Last sample of the period
+    pub to: Option<NaiveDateTime>,
+    pub energy_kwh: f64,
+    pub cost: f64,
+    pub rooms: Vec<RoomCost>,
+}
+
+impl CostReport {
+    /// Returns the costs of a room
+    pub fn room(&self, name: &str) -> Option<&RoomCost> {
+        self.rooms.iter().find(|room| room.name == name)
+    }
+}
+
+impl RoomCost {
+    /// Returns the costs of a device of the room
+    pub fn device(&self, name: &str) -> Option<&DeviceCost> {
+        self.devices.iter().find(|device| device.name == name)
+    }
+}
+
+impl Reporter for CostReport {
+    fn report(&self) -> String {
+        let period = match (self.from, self.to) {
+            (Some(from), Some(to)) => format!(
+                "{} - {}",
+                from.format("%Y-%m-%d %H:%M"),
+                to.format("%Y-%m-%d %H:%M")
+            ),
+            _ => "no samples".to_string(),
+        };
+
+        let mut report = format!("=== Energy Cost: {} ===\n", period);
+        for room in &self.rooms {
+            report.push_str(&format!("=== Room: {} ===\n", room.name));
+            for device in &room.devices {
+                report.push_str(&format!(
+                    "Device: {}, Energy: {:.3} kWh, Cost: {:.2}\n",
+                    device.name, device.energy_kwh, device.cost
+                ));
+            }
+            report.push_str(&format!(
+                "Room total: {:.3} kWh, {:.2}\n",
+                room.energy_kwh, room.cost
+            ));
+        }
+        report.push_str(&format!(
+            "House total: {:.3} kWh, {:.2}\n",
+            self.energy_kwh, self.cost
+        ));
+        report
+    }
+}
+
+/// Running totals of a metered device
+#[derive(Debug, Clone, Copy, Default)]
+struct Meter {
+    /// Counter value at the last sample
+    last_wh: f64,
+    energy_wh: f64,
+    cost: f64,
+}
+
+/// Accumulates the cost of the energy used by the devices of a house
+///
+/// Devices keep their totals after they are removed from the house, and
+/// a reset energy counter is picked up from zero.
+pub struct CostTracker {
+    tariff: Tariff,
+    clock: Box<dyn Clock>,
+    from: Option<NaiveDateTime>,
+    last_sample: Option<NaiveDateTime>,
+    /// Meters by room and device key, in the order they were first seen
+    meters: IndexMap<(String, String), Meter>,
+}
+
+impl CostTracker {
+    /// Creates a tracker driven by the system clock
+    ///
+    /// Fails if the tariff is invalid.
+    pub fn new(tariff: Tariff) -> Result<Self, SmartHomeError> {
+        Self::with_clock(tariff, SystemClock)
+    }
+
+    /// Creates a tracker driven by the given clock
+    pub fn with_clock<C: Clock + 'static>(
+        tariff: Tariff,
+        clock: C,
+    ) -> Result<Self, SmartHomeError> {
+        tariff.validate()?;
+        Ok(Self {
+            tariff,
+            clock: Box::new(clock),
+            from: None,
+            last_sample: None,
+            meters: IndexMap::new(),
+        })
+    }
+
+    /// Returns the tariff energy is priced with
+    pub fn tariff(&self) -> &Tariff {
+        &self.tariff
+    }
+
+    /// Reads the energy meters of the house and prices the energy used since
+    /// the previous sample
+    ///
+    /// The first sample only records the starting counters.
+    pub fn sample(&mut self, house: &SmartHouse) {
+        let now = self.clock.now();
+        let mut period_used_wh: f64 = self.meters.values().map(|meter| meter.energy_wh).sum();
+
+        for (room_name, room) in house.all_rooms() {
+            for (device_name, device) in room.all_devices() {
+                let Some(counter_wh) = device.energy_wh() else {
+                    continue;
+                };
+                let key = (room_name.clone(), device_name.clone());
+                let Some(meter) = self.meters.get_mut(&key) else {
+                    // Energy used before the device was first seen is not ours to price
+                    self.meters.insert(
+                        key,
+                        Meter {
+                            last_wh: counter_wh,
+                            ..Meter::default()
+                        },
+                    );
+                    continue;
+                };
+
+                // A counter lower than last time was reset in between
+                let used_wh = if counter_wh >= meter.last_wh {
+                    counter_wh - meter.last_wh
+                } else {
+                    counter_wh
+                };
+                let from = self.last_sample.unwrap_or(now);
+                meter.cost += self.tariff.cost(from, now, used_wh, period_used_wh);
+                meter.energy_wh += used_wh;
+                meter.last_wh = counter_wh;
+                period_used_wh += used_wh;
+            }
+        }
+
+        self.from.get_or_insert(now);
+        self.last_sample = Some(now);
+    }
+
+    /// Builds the report of the period since the first sample
+    pub fn report(&self) -> CostReport {
+        let mut rooms: IndexMap<&str, RoomCost> = IndexMap::new();
+        for ((room_name, device_name), meter) in &self.meters {
+            let room = rooms.entry(room_name).or_insert_with(|| RoomCost {
+                name: room_name.clone(),
+                ..RoomCost::default()
+            });
+            room.energy_kwh += meter.energy_wh / 1000.0;
+            room.cost += meter.cost;
+            room.devices.push(DeviceCost {
+                name: device_name.clone(),
+                energy_kwh: meter.energy_wh / 1000.0,
+                cost: meter.cost,
+            });
+        }
+
+        let rooms: Vec<RoomCost> = rooms.into_values().collect();
+        CostReport {
+            from: self.from,
+            to: self.last_sample,
+            energy_kwh: rooms.iter().map(|room| room.energy_kwh).sum(),
+            cost: rooms.iter().map(|room| room.cost).sum(),
+            rooms,
+        }
+    }
+
+    /// Starts a new period, keeping the current counters as the baseline
+    pub fn reset(&mut self) {
+        self.from = self.last_sample;
+        for meter in self.meters.values_mut() {
+            meter.energy_wh = 0.0;
+            meter.cost = 0.0;
+        }
+    }
+}
+
+impl std::fmt::Debug for CostTracker {
+    fn fmt(&self, f: &mut std::fmt::Formatter<'_>) -> std::fmt::Result {
+        f.debug_struct("CostTracker")
+            .field("tariff", &self.tariff)
+            .field("from", &self.from)
+            .field("last_sample", &self.last_sample)
+            .field("devices", &self.meters.len())
+            .finish()
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::clock::ManualClock;
+    use crate::tariff::TariffBand;
+    use crate::{SmartSocket, SmartThermometer, create_room};
+    use chrono::{NaiveDate, NaiveTime};
+    use std::sync::Arc;
+    use std::time::Duration;
+
+    fn at(hour: u32) -> NaiveDateTime {
+        NaiveDate::from_ymd_opt(2024, 1, 1)
+            .unwrap()
+            .and_hms_opt(hour, 0, 0)
+            .unwrap()
+    }
+
+    fn test_house(clock: &Arc<ManualClock>) -> SmartHouse {
+        let socket = |name: &str, is_on: bool, power: f32| {
+            SmartSocket::new(name.to_string(), is_on, power).with_clock(Arc::clone(clock))
+        };
+        let kitchen = create_room!(
+            "Kitchen",
+            ("Kettle", socket("Kettle", true, 2000.0)),
+            ("Thermo", SmartThermometer::new("Thermo".to_string(), 21.0))
+        );
+        let office = create_room!("Office", ("Desk", socket("Desk", true, 500.0)));
+        SmartHouse::new(
+            "Test House".to_string(),
+            vec![
+                ("Kitchen".to_string(), kitchen),
+                ("Office".to_string(), office),
+            ],
+        )
+    }
+
+    /// Samples every hour from 16:00 to 19:00 with both sockets on
+    fn run(tariff: Tariff) -> CostReport {
+        let clock = Arc::new(ManualClock::new(at(16)));
+        let house = test_house(&clock);
+        let mut tracker = CostTracker::with_clock(tariff, Arc::clone(&clock)).unwrap();
+
+        tracker.sample(&house);
+        for _ in 0..3 {
+            clock.advance(Duration::from_secs(3600));
+            tracker.sample(&house);
+        }
+        tracker.report()
+    }
+
+    #[test]
+    fn test_cost_report() {
+        struct TestCase {
+            name: &'static str,
+            tariff: Tariff,
+            expected_kettle: f64,
+            expected_desk: f64,
+        }
+
+        let test_cases = vec![
+            TestCase {
+                name: "Flat rate",
+                tariff: Tariff::flat(0.20),
+                // 6 kWh and 1.5 kWh
+                expected_kettle: 1.2,
+                expected_desk: 0.3,
+            },
+            TestCase {
+                name: "Peak band from 17:00",
+                tariff: Tariff::time_of_use(
+                    0.20,
+                    vec![TariffBand::new(
+                        NaiveTime::from_hms_opt(17, 0, 0).unwrap(),
+                        NaiveTime::from_hms_opt(20, 0, 0).unwrap(),
+                        0.50,
+                    )],
+                ),
+                expected_kettle: 2.0 * 0.20 + 4.0 * 0.50,
+                expected_desk: 0.5 * 0.20 + 1.0 * 0.50,
+            },
+            TestCase {
+                name: "Tiers shared by the whole house",
+                tariff: Tariff::tiered(&[(5.0, 0.10)], 0.30),
+                // Hour 1: kettle 2 kWh, desk 0.5 kWh at 0.10
+                // Hour 2: kettle 2 kWh at 0.10, desk 0.5 kWh at 0.10
+                // Hour 3: kettle 2 kWh at 0.30, desk 0.5 kWh at 0.30
+                expected_kettle: 0.2 + 0.2 + 0.6,
+                expected_desk: 0.05 + 0.05 + 0.15,
+            },
+        ];
+
+        for tc in test_cases {
+            let report = run(tc.tariff);
+            let kitchen = report.room("Kitchen").unwrap();
+            let office = report.room("Office").unwrap();
+            let kettle = kitchen.device("Kettle").unwrap();
+            let desk = office.device("Desk").unwrap();
+
+            assert!(
+                (kettle.cost - tc.expected_kettle).abs() < 1e-9,
+                "Failed test: {}: kettle {}",
+                tc.name,
+                kettle.cost
+            );
+            assert!(
+                (desk.cost - tc.expected_desk).abs() < 1e-9,
+                "Failed test: {}: desk {}",
+                tc.name,
+                desk.cost
+            );
+            assert!(
+                (report.cost - kitchen.cost - office.cost).abs() < 1e-9,
+                "Failed test: {}",
+                tc.name
+            );
+            assert!(
+                kitchen.device("Thermo").is_none(),
+                "Failed test: {}",
+                tc.name
+            );
+            assert!(
+                (report.energy_kwh - 7.5).abs() < 1e-9,
+                "Failed test: {}",
+                tc.name
+            );
+        }
+    }
+
+    #[test]
+    fn test_reset_and_counter_reset() {
+        let clock = Arc::new(ManualClock::new(at(8)));
+        let mut house = test_house(&clock);
+        let mut tracker = CostTracker::with_clock(Tariff::flat(1.0), Arc::clone(&clock)).unwrap();
+
+        tracker.sample(&house);
+        clock.advance(Duration::from_secs(3600));
+        tracker.sample(&house);
+        tracker.reset();
+
+        house
+            .reset_device_energy(&"Kitchen".to_string(), &"Kettle".to_string())
+            .unwrap();
+        clock.advance(Duration::from_secs(1800));
+        tracker.sample(&house);
+
+        let report = tracker.report();
+        assert_eq!(report.from, Some(at(9)));
+        assert_eq!(report.to, Some(at(9) + chrono::TimeDelta::minutes(30)));
+        let kettle = report.room("Kitchen").unwrap().device("Kettle").unwrap();
+        assert!((kettle.energy_kwh - 1.0).abs() < 1e-9);
+        assert!((report.cost - 1.25).abs() < 1e-9);
+
+        assert_eq!(
+            report.report(),
+            "=== Energy Cost: 2024-01-01 09:00 - 2024-01-01 09:30 ===\n\
+             === Room: Kitchen ===\n\
+             Device: Kettle, Energy: 1.000 kWh, Cost: 1.00\n\
+             Room total: 1.000 kWh, 1.00\n\
+             === Room: Office ===\n\
+             Device: Desk, Energy: 0.250 kWh, Cost: 0.25\n\
+             Room total: 0.250 kWh, 0.25\n\
+             House total: 1.250 kWh, 1.25\n"
+        );
+
+        assert!(CostTracker::new(Tariff::flat(f64::NAN)).is_err());
+    }
+}
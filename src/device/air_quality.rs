@@ -0,0 +1,92 @@
+use crate::Reporter;
+use crate::device::device_trait::{AirQualityIndex, Co2Sensor, SmartDeviceTrait};
+use serde::{Deserialize, Serialize};
+
+/// Smart air quality monitor device implementation
+///
+/// Measures the CO2 concentration and rates the overall air quality on the
+/// 0–500 air quality index scale.
+#[derive(Debug, Clone, PartialEq, Serialize, Deserialize)]
+pub struct SmartAirQualityMonitor {
+    name: String,
+    co2_ppm: f32,
+    air_quality_index: u16,
+}
+
+impl SmartAirQualityMonitor {
+    /// Creates a new monitor with the specified name and readings
+    pub fn new(name: String, co2_ppm: f32, air_quality_index: u16) -> Self {
+        Self {
+            name,
+            co2_ppm,
+            air_quality_index,
+        }
+    }
+
+    /// Updates the current CO2 reading in ppm
+    pub fn set_co2_ppm(&mut self, co2_ppm: f32) {
+        self.co2_ppm = co2_ppm;
+    }
+
+    /// Updates the current air quality index
+    pub fn set_air_quality_index(&mut self, air_quality_index: u16) {
+        self.air_quality_index = air_quality_index;
+    }
+}
+
+impl SmartDeviceTrait for SmartAirQualityMonitor {
+    fn name(&self) -> &str {
+        &self.name
+    }
+
+    fn as_co2_sensor(&self) -> Option<&dyn Co2Sensor> {
+        Some(self)
+    }
+
+    fn as_air_quality_index(&self) -> Option<&dyn AirQualityIndex> {
+        Some(self)
+    }
+}
+
+impl Co2Sensor for SmartAirQualityMonitor {
+    fn co2_ppm(&self) -> f32 {
+        self.co2_ppm
+    }
+}
+
+impl AirQualityIndex for SmartAirQualityMonitor {
+    fn air_quality_index(&self) -> u16 {
+        self.air_quality_index
+    }
+}
+
+impl Reporter for SmartAirQualityMonitor {
+    fn report(&self) -> String {
+        format!(
+            "Device: {name}, CO2: {co2} ppm, Air quality index: {aqi}",
+            name = self.name(),
+            co2 = self.co2_ppm(),
+            aqi = self.air_quality_index()
+        )
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn test_air_quality_monitor() {
+        let mut monitor = SmartAirQualityMonitor::new("Office".to_string(), 650.0, 30);
+        assert_eq!(monitor.co2_ppm(), 650.0);
+        assert_eq!(monitor.air_quality_index(), 30);
+        assert!(monitor.as_humidity_sensor().is_none());
+
+        monitor.set_co2_ppm(1200.0);
+        monitor.set_air_quality_index(85);
+        assert_eq!(
+            monitor.report(),
+            "Device: Office, CO2: 1200 ppm, Air quality index: 85"
+        );
+    }
+}
@@ -0,0 +1,235 @@
+use crate::Reporter;
+use crate::clock::{Clock, SystemClock};
+use crate::device::device_trait::{BinarySensor, SmartDeviceTrait};
+use chrono::NaiveDateTime;
+use serde::{Deserialize, Serialize};
+use std::fmt;
+use std::sync::Arc;
+
+/// What a binary sensor detects
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Serialize, Deserialize)]
+#[serde(rename_all = "lowercase")]
+pub enum BinarySensorKind {
+    /// Triggered while motion is detected
+    Motion,
+    /// Door or window contact, triggered while open
+    Contact,
+    /// Triggered while water is detected
+    Leak,
+    /// Triggered while smoke is detected
+    Smoke,
+}
+
+impl BinarySensorKind {
+    /// Describes the state of a sensor of this kind
+    fn state(self, triggered: bool) -> &'static str {
+        match (self, triggered) {
+            (BinarySensorKind::Motion, true) => "motion detected",
+            (BinarySensorKind::Contact, true) => "open",
+            (BinarySensorKind::Leak, true) => "leak detected",
+            (BinarySensorKind::Smoke, true) => "smoke detected",
+            (BinarySensorKind::Contact, false) => "closed",
+            (BinarySensorKind::Leak, false) => "dry",
+            (BinarySensorKind::Motion | BinarySensorKind::Smoke, false) => "clear",
+        }
+    }
+}
+
+impl fmt::Display for BinarySensorKind {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        let name = match self {
+            BinarySensorKind::Motion => "motion",
+            BinarySensorKind::Contact => "contact",
+            BinarySensorKind::Leak => "leak",
+            BinarySensorKind::Smoke => "smoke",
+        };
+        f.write_str(name)
+    }
+}
+
+/// Motion, door/window contact, water leak or smoke sensor
+///
+/// Remembers when it was last triggered, timestamped with its clock. The
+/// timestamp is not saved with the house.
+#[derive(Serialize, Deserialize)]
+pub struct SmartBinarySensor {
+    name: String,
+    sensor: BinarySensorKind,
+    #[serde(default)]
+    triggered: bool,
+    #[serde(skip)]
+    last_triggered: Option<NaiveDateTime>,
+    #[serde(skip, default = "SystemClock::shared")]
+    clock: Arc<dyn Clock>,
+}
+
+impl SmartBinarySensor {
+    /// Creates a sensor of the given kind that is not triggered
+    pub fn new(name: String, sensor: BinarySensorKind) -> Self {
+        Self {
+            name,
+            sensor,
+            triggered: false,
+            last_triggered: None,
+            clock: SystemClock::shared(),
+        }
+    }
+
+    /// Timestamps triggers with the given clock instead of the system clock
+    pub fn with_clock<C: Clock + 'static>(mut self, clock: C) -> Self {
+        self.clock = Arc::new(clock);
+        self
+    }
+
+    /// Returns what the sensor detects
+    pub fn sensor(&self) -> BinarySensorKind {
+        self.sensor
+    }
+
+    /// Updates the state of the sensor
+    ///
+    /// The trigger time is only updated when the sensor goes from clear to
+    /// triggered.
+    pub fn set_triggered(&mut self, triggered: bool) {
+        if triggered && !self.triggered {
+            self.last_triggered = Some(self.clock.now());
+        }
+        self.triggered = triggered;
+    }
+}
+
+impl fmt::Debug for SmartBinarySensor {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        f.debug_struct("SmartBinarySensor")
+            .field("name", &self.name)
+            .field("sensor", &self.sensor)
+            .field("triggered", &self.triggered)
+            .field("last_triggered", &self.last_triggered)
+            .finish()
+    }
+}
+
+impl SmartDeviceTrait for SmartBinarySensor {
+    fn name(&self) -> &str {
+        &self.name
+    }
+
+    fn as_binary_sensor(&self) -> Option<&dyn BinarySensor> {
+        Some(self)
+    }
+}
+
+impl BinarySensor for SmartBinarySensor {
+    fn is_triggered(&self) -> bool {
+        self.triggered
+    }
+
+    fn last_triggered(&self) -> Option<NaiveDateTime> {
+        self.last_triggered
+    }
+}
+
+impl Reporter for SmartBinarySensor {
+    fn report(&self) -> String {
+        let mut report = format!(
+            "Device: {}, Sensor: {}, State: {}",
+            self.name(),
+            self.sensor,
+            self.sensor.state(self.triggered)
+        );
+        if let Some(at) = self.last_triggered {
+            report.push_str(&format!(", Last triggered: {}", at));
+        }
+        report
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::clock::ManualClock;
+    use chrono::NaiveDate;
+    use std::time::Duration;
+
+    #[test]
+    fn test_trigger_timestamps() {
+        let start = NaiveDate::from_ymd_opt(2024, 3, 1)
+            .unwrap()
+            .and_hms_opt(22, 0, 0)
+            .unwrap();
+        let clock = Arc::new(ManualClock::new(start));
+        let mut sensor =
+            SmartBinarySensor::new("Front Door".to_string(), BinarySensorKind::Contact)
+                .with_clock(clock.clone());
+        assert_eq!(sensor.last_triggered(), None);
+        assert_eq!(
+            sensor.report(),
+            "Device: Front Door, Sensor: contact, State: closed"
+        );
+
+        sensor.set_triggered(true);
+        clock.advance(Duration::from_secs(60));
+        sensor.set_triggered(true);
+        assert!(sensor.is_triggered());
+        assert_eq!(sensor.last_triggered(), Some(start));
+
+        sensor.set_triggered(false);
+        assert!(!sensor.is_triggered());
+        assert_eq!(
+            sensor.report(),
+            "Device: Front Door, Sensor: contact, State: closed, Last triggered: 2024-03-01 22:00:00"
+        );
+
+        sensor.set_triggered(true);
+        assert_eq!(
+            sensor.last_triggered(),
+            Some(start + chrono::TimeDelta::seconds(60))
+        );
+    }
+
+    #[test]
+    fn test_states() {
+        struct TestCase {
+            name: &'static str,
+            sensor: BinarySensorKind,
+            triggered: bool,
+            expected_state: &'static str,
+        }
+
+        let test_cases = vec![
+            TestCase {
+                name: "Motion detected",
+                sensor: BinarySensorKind::Motion,
+                triggered: true,
+                expected_state: "motion detected",
+            },
+            TestCase {
+                name: "Open contact",
+                sensor: BinarySensorKind::Contact,
+                triggered: true,
+                expected_state: "open",
+            },
+            TestCase {
+                name: "Dry leak sensor",
+                sensor: BinarySensorKind::Leak,
+                triggered: false,
+                expected_state: "dry",
+            },
+            TestCase {
+                name: "Smoke detected",
+                sensor: BinarySensorKind::Smoke,
+                triggered: true,
+                expected_state: "smoke detected",
+            },
+        ];
+
+        for tc in test_cases {
+            assert_eq!(
+                tc.sensor.state(tc.triggered),
+                tc.expected_state,
+                "Failed test: {}",
+                tc.name
+            );
+        }
+    }
+}
@@ -0,0 +1,190 @@
+use std::fmt;
+
+/// A feature a device may support
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Hash, PartialOrd, Ord)]
+pub enum Capability {
+    PowerControl,
+    TemperatureSensor,
+    PowerConsumption,
+    EnergyMeter,
+    HumiditySensor,
+    Co2Sensor,
+    AirQualityIndex,
+    BinarySensor,
+}
+
+impl Capability {
+    /// All capabilities known to the library
+    pub const ALL: [Capability; 8] = [
+        Capability::PowerControl,
+        Capability::TemperatureSensor,
+        Capability::PowerConsumption,
+        Capability::EnergyMeter,
+        Capability::HumiditySensor,
+        Capability::Co2Sensor,
+        Capability::AirQualityIndex,
+        Capability::BinarySensor,
+    ];
+
+    fn bit(self) -> u32 {
+        1 << (self as u32)
+    }
+}
+
+impl fmt::Display for Capability {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        let name = match self {
+            Capability::PowerControl => "power control",
+            Capability::TemperatureSensor => "temperature sensor",
+            Capability::PowerConsumption => "power consumption",
+            Capability::EnergyMeter => "energy meter",
+            Capability::HumiditySensor => "humidity sensor",
+            Capability::Co2Sensor => "CO2 sensor",
+            Capability::AirQualityIndex => "air quality index",
+            Capability::BinarySensor => "binary sensor",
+        };
+        f.write_str(name)
+    }
+}
+
+/// Set of capabilities supported by a device
+#[derive(Debug, Clone, Copy, Default, PartialEq, Eq, Hash)]
+pub struct Capabilities(u32);
+
+impl Capabilities {
+    /// Creates an empty set
+    pub fn empty() -> Self {
+        Self(0)
+    }
+
+    /// Adds a capability to the set
+    pub fn insert(&mut self, capability: Capability) {
+        self.0 |= capability.bit();
+    }
+
+    /// Checks whether the set contains a capability
+    pub fn contains(&self, capability: Capability) -> bool {
+        self.0 & capability.bit() != 0
+    }
+
+    /// Checks whether the set is empty
+    pub fn is_empty(&self) -> bool {
+        self.0 == 0
+    }
+
+    /// Returns the number of capabilities in the set
+    pub fn len(&self) -> usize {
+        self.0.count_ones() as usize
+    }
+
+    /// Iterates over the capabilities in the set in declaration order
+    pub fn iter(&self) -> impl Iterator<Item = Capability> + '_ {
+        Capability::ALL
+            .into_iter()
+            .filter(|capability| self.contains(*capability))
+    }
+}
+
+impl FromIterator<Capability> for Capabilities {
+    fn from_iter<I: IntoIterator<Item = Capability>>(iter: I) -> Self {
+        let mut capabilities = Capabilities::empty();
+        for capability in iter {
+            capabilities.insert(capability);
+        }
+        capabilities
+    }
+}
+
+impl fmt::Display for Capabilities {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        if self.is_empty() {
+            return f.write_str("none");
+        }
+
+        for (i, capability) in self.iter().enumerate() {
+            if i > 0 {
+                f.write_str(", ")?;
+            }
+            write!(f, "{}", capability)?;
+        }
+        Ok(())
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn test_capability_set() {
+        struct TestCase {
+            name: &'static str,
+            capabilities: Capabilities,
+            expected: Vec<Capability>,
+            expected_display: &'static str,
+        }
+
+        let test_cases = vec![
+            TestCase {
+                name: "Empty set",
+                capabilities: Capabilities::empty(),
+                expected: vec![],
+                expected_display: "none",
+            },
+            TestCase {
+                name: "Single capability",
+                capabilities: [Capability::TemperatureSensor].into_iter().collect(),
+                expected: vec![Capability::TemperatureSensor],
+                expected_display: "temperature sensor",
+            },
+            TestCase {
+                name: "Duplicates collapse and order is stable",
+                capabilities: [
+                    Capability::PowerConsumption,
+                    Capability::PowerControl,
+                    Capability::PowerConsumption,
+                ]
+                .into_iter()
+                .collect(),
+                expected: vec![Capability::PowerControl, Capability::PowerConsumption],
+                expected_display: "power control, power consumption",
+            },
+        ];
+
+        for tc in test_cases {
+            assert_eq!(
+                tc.capabilities.iter().collect::<Vec<_>>(),
+                tc.expected,
+                "Failed test: {}",
+                tc.name
+            );
+            assert_eq!(
+                tc.capabilities.len(),
+                tc.expected.len(),
+                "Failed test: {}",
+                tc.name
+            );
+            assert_eq!(
+                tc.capabilities.is_empty(),
+                tc.expected.is_empty(),
+                "Failed test: {}",
+                tc.name
+            );
+            assert_eq!(
+                tc.capabilities.to_string(),
+                tc.expected_display,
+                "Failed test: {}",
+                tc.name
+            );
+            for capability in Capability::ALL {
+                assert_eq!(
+                    tc.capabilities.contains(capability),
+                    tc.expected.contains(&capability),
+                    "Test case '{}': wrong membership for {}",
+                    tc.name,
+                    capability
+                );
+            }
+        }
+    }
+}
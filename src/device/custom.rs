@@ -0,0 +1,135 @@
+use super::device_trait::SmartDeviceTrait;
+use crate::Reporter;
+use crate::report::Metric;
+use std::fmt::Debug;
+
+/// Trait for device types defined outside the library
+///
+/// A custom device is stored in a room as [`SmartDevice::Custom`](super::SmartDevice::Custom).
+/// Its capabilities are discovered through the `as_*` methods of
+/// [`SmartDeviceTrait`], so a device implementing [`PowerControl`](super::PowerControl)
+/// should also override [`SmartDeviceTrait::as_power_control`] and
+/// [`SmartDeviceTrait::as_power_control_mut`].
+pub trait CustomDevice: SmartDeviceTrait + Reporter + Debug + Send + Sync {
+    /// Returns the kind tag identifying the device type, e.g. `"humidifier"`
+    ///
+    /// The tag is written to saved house files and used to find the factory
+    /// registered in a [`DeviceRegistry`](crate::storage::DeviceRegistry).
+    fn kind(&self) -> &str;
+
+    /// Returns the device state for saving, if the device supports persistence
+    ///
+    /// The value must be a JSON object; the `kind` field is added on save.
+    fn state(&self) -> Option<serde_json::Value> {
+        None
+    }
+
+    /// Returns metrics added to the structured report on top of the ones
+    /// derived from the device capabilities
+    fn report_metrics(&self) -> Vec<Metric> {
+        Vec::new()
+    }
+}
+
+#[cfg(test)]
+pub(crate) mod tests {
+    use super::*;
+    use crate::device::device_trait::{PowerConsumption, PowerControl};
+    use serde::{Deserialize, Serialize};
+
+    /// Third-party device used to exercise the open device model
+    #[derive(Debug, Serialize, Deserialize)]
+    pub(crate) struct Humidifier {
+        pub(crate) name: String,
+        pub(crate) is_on: bool,
+        pub(crate) watts: f32,
+    }
+
+    impl Humidifier {
+        pub(crate) fn new(name: &str, is_on: bool, watts: f32) -> Self {
+            Self {
+                name: name.to_string(),
+                is_on,
+                watts,
+            }
+        }
+    }
+
+    impl SmartDeviceTrait for Humidifier {
+        fn name(&self) -> &str {
+            &self.name
+        }
+
+        fn as_power_control(&self) -> Option<&dyn PowerControl> {
+            Some(self)
+        }
+
+        fn as_power_control_mut(&mut self) -> Option<&mut dyn PowerControl> {
+            Some(self)
+        }
+
+        fn as_power_consumption(&self) -> Option<&dyn PowerConsumption> {
+            Some(self)
+        }
+    }
+
+    impl PowerControl for Humidifier {
+        fn is_on(&self) -> bool {
+            self.is_on
+        }
+
+        fn turn_on(&mut self) {
+            self.is_on = true;
+        }
+
+        fn turn_off(&mut self) {
+            self.is_on = false;
+        }
+    }
+
+    impl PowerConsumption for Humidifier {
+        fn power_consumption(&self) -> f32 {
+            if self.is_on { self.watts } else { 0.0 }
+        }
+    }
+
+    impl Reporter for Humidifier {
+        fn report(&self) -> String {
+            format!(
+                "Device: {}, Humidifier: {}",
+                self.name,
+                if self.is_on { "ON" } else { "OFF" }
+            )
+        }
+    }
+
+    impl CustomDevice for Humidifier {
+        fn kind(&self) -> &str {
+            "humidifier"
+        }
+
+        fn state(&self) -> Option<serde_json::Value> {
+            serde_json::to_value(self).ok()
+        }
+    }
+
+    #[test]
+    fn test_custom_device_capabilities() {
+        let mut humidifier = Humidifier::new("Bedroom Humidifier", false, 30.0);
+        let device: &mut dyn CustomDevice = &mut humidifier;
+
+        assert_eq!(device.kind(), "humidifier");
+        assert!(device.as_temperature_sensor().is_none());
+
+        device.as_power_control_mut().unwrap().turn_on();
+        assert!(device.as_power_control().unwrap().is_on());
+        assert_eq!(
+            device.as_power_consumption().unwrap().power_consumption(),
+            30.0
+        );
+
+        let state = device.state().unwrap();
+        assert_eq!(state["name"], "Bedroom Humidifier");
+        assert_eq!(state["is_on"], true);
+    }
+}
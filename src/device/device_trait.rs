@@ -1,7 +1,64 @@
+use crate::error::SmartHomeError;
+use chrono::NaiveDateTime;
+
 /// Base trait for all smart devices
+///
+/// The `as_*` methods expose the optional capabilities of a device.
+/// Implementors override the ones matching the capability traits they
+/// implement, which lets rooms drive devices they know nothing about.
 pub trait SmartDeviceTrait {
     /// Returns the name of the device
     fn name(&self) -> &str;
+
+    /// Returns the device as a power-controllable device, if supported
+    fn as_power_control(&self) -> Option<&dyn PowerControl> {
+        None
+    }
+
+    /// Returns the device as a mutable power-controllable device, if supported
+    fn as_power_control_mut(&mut self) -> Option<&mut dyn PowerControl> {
+        None
+    }
+
+    /// Returns the device as a temperature sensor, if supported
+    fn as_temperature_sensor(&self) -> Option<&dyn TemperatureSensor> {
+        None
+    }
+
+    /// Returns the device as a humidity sensor, if supported
+    fn as_humidity_sensor(&self) -> Option<&dyn HumiditySensor> {
+        None
+    }
+
+    /// Returns the device as a CO2 sensor, if supported
+    fn as_co2_sensor(&self) -> Option<&dyn Co2Sensor> {
+        None
+    }
+
+    /// Returns the device as an air quality sensor, if supported
+    fn as_air_quality_index(&self) -> Option<&dyn AirQualityIndex> {
+        None
+    }
+
+    /// Returns the device as a binary sensor, if supported
+    fn as_binary_sensor(&self) -> Option<&dyn BinarySensor> {
+        None
+    }
+
+    /// Returns the device as a power consumer, if supported
+    fn as_power_consumption(&self) -> Option<&dyn PowerConsumption> {
+        None
+    }
+
+    /// Returns the device as an energy meter, if supported
+    fn as_energy_meter(&self) -> Option<&dyn EnergyMeter> {
+        None
+    }
+
+    /// Returns the device as a mutable energy meter, if supported
+    fn as_energy_meter_mut(&mut self) -> Option<&mut dyn EnergyMeter> {
+        None
+    }
 }
 
 /// Trait for devices that can be turned on/off
@@ -14,6 +71,24 @@ pub trait PowerControl: SmartDeviceTrait {
 
     /// Turns the device off
     fn turn_off(&mut self);
+
+    /// Turns the device on, reporting why it could not be switched
+    ///
+    /// Devices reached over a network override this; the default calls
+    /// [`PowerControl::turn_on`] and never fails.
+    fn try_turn_on(&mut self) -> Result<(), SmartHomeError> {
+        self.turn_on();
+        Ok(())
+    }
+
+    /// Turns the device off, reporting why it could not be switched
+    ///
+    /// Devices reached over a network override this; the default calls
+    /// [`PowerControl::turn_off`] and never fails.
+    fn try_turn_off(&mut self) -> Result<(), SmartHomeError> {
+        self.turn_off();
+        Ok(())
+    }
 }
 
 /// Trait for devices that measure temperature
@@ -22,10 +97,60 @@ pub trait TemperatureSensor: SmartDeviceTrait {
     fn temperature(&self) -> f32;
 }
 
+/// Trait for devices that measure relative humidity
+pub trait HumiditySensor: SmartDeviceTrait {
+    /// Returns the current relative humidity in percent
+    fn humidity(&self) -> f32;
+}
+
+/// Trait for devices that measure the CO2 concentration
+pub trait Co2Sensor: SmartDeviceTrait {
+    /// Returns the current CO2 concentration in parts per million
+    fn co2_ppm(&self) -> f32;
+}
+
+/// Trait for devices that rate the air quality
+pub trait AirQualityIndex: SmartDeviceTrait {
+    /// Returns the current air quality index, from 0 (clean) to 500 (hazardous)
+    fn air_quality_index(&self) -> u16;
+}
+
+/// Trait for sensors that are either triggered or clear, such as motion or smoke sensors
+pub trait BinarySensor: SmartDeviceTrait {
+    /// Checks if the sensor currently detects what it watches for
+    fn is_triggered(&self) -> bool;
+
+    /// Returns when the sensor was last triggered, if it ever was
+    fn last_triggered(&self) -> Option<NaiveDateTime>;
+}
+
 /// Trait for devices that consume power
 pub trait PowerConsumption: SmartDeviceTrait {
     /// Returns the current power consumption in watts
     fn power_consumption(&self) -> f32;
+
+    /// Returns the power the device draws once switched on, in watts
+    ///
+    /// Power budgets check it before a device is switched on. The default is
+    /// the current draw; a device drawing more than announced is switched
+    /// back off once the budget is found exceeded.
+    fn power_when_on(&self) -> f32 {
+        self.power_consumption()
+    }
+}
+
+/// Trait for devices that meter the energy they use over time
+pub trait EnergyMeter: SmartDeviceTrait {
+    /// Returns the energy used since the counter was last reset, in watt-hours
+    fn energy_wh(&self) -> f64;
+
+    /// Resets the energy counter to zero
+    fn reset_energy(&mut self);
+
+    /// Returns the energy used since the counter was last reset, in kilowatt-hours
+    fn energy_kwh(&self) -> f64 {
+        self.energy_wh() / 1000.0
+    }
 }
 
 #[cfg(test)]
@@ -71,4 +196,24 @@ pub mod tests {
             fn power_consumption(&self) -> f32;
         }
     }
+
+    #[test]
+    fn test_capabilities_default_to_none() {
+        let mut device = MockSmartDevice::new();
+        device
+            .expect_name()
+            .return_const("Plain Device".to_string());
+
+        assert_eq!(device.name(), "Plain Device");
+        assert!(device.as_power_control().is_none());
+        assert!(device.as_power_control_mut().is_none());
+        assert!(device.as_temperature_sensor().is_none());
+        assert!(device.as_humidity_sensor().is_none());
+        assert!(device.as_co2_sensor().is_none());
+        assert!(device.as_air_quality_index().is_none());
+        assert!(device.as_binary_sensor().is_none());
+        assert!(device.as_power_consumption().is_none());
+        assert!(device.as_energy_meter().is_none());
+        assert!(device.as_energy_meter_mut().is_none());
+    }
 }
@@ -0,0 +1,317 @@
+use chrono::NaiveDateTime;
+use std::collections::VecDeque;
+use std::ops::RangeBounds;
+
+/// Number of readings kept unless another capacity is chosen
+pub const DEFAULT_HISTORY_CAPACITY: usize = 1024;
+
+/// A temperature reading and when it was taken
+#[derive(Debug, Clone, Copy, PartialEq)]
+pub struct TemperatureReading {
+    pub at: NaiveDateTime,
+    pub temperature: f32,
+}
+
+/// Minimum, maximum and mean of a set of temperatures
+#[derive(Debug, Clone, Copy, PartialEq)]
+pub struct TemperatureStats {
+    /// Number of temperatures the statistics are computed from
+    pub count: usize,
+    pub min: f32,
+    pub max: f32,
+    pub mean: f32,
+}
+
+impl TemperatureStats {
+    /// Computes statistics over the given temperatures, `None` if there are none
+    pub fn from_temperatures(temperatures: impl IntoIterator<Item = f32>) -> Option<Self> {
+        let mut temperatures = temperatures.into_iter();
+        let first = temperatures.next()?;
+        let mut stats = Self {
+            count: 1,
+            min: first,
+            max: first,
+            mean: 0.0,
+        };
+        // Summed in f64 so long histories do not lose precision
+        let mut sum = f64::from(first);
+        for temperature in temperatures {
+            stats.count += 1;
+            stats.min = stats.min.min(temperature);
+            stats.max = stats.max.max(temperature);
+            sum += f64::from(temperature);
+        }
+        stats.mean = (sum / stats.count as f64) as f32;
+        Some(stats)
+    }
+}
+
+/// Bounded history of temperature readings
+///
+/// Once the history is full, every new reading replaces the oldest one.
+/// Queries take a time window such as `since..` or `from..=to`; pass `..`
+/// to cover the whole history.
+#[derive(Debug, Clone, PartialEq)]
+pub struct TemperatureHistory {
+    capacity: usize,
+    readings: VecDeque<TemperatureReading>,
+}
+
+impl Default for TemperatureHistory {
+    fn default() -> Self {
+        Self::new(DEFAULT_HISTORY_CAPACITY)
+    }
+}
+
+impl TemperatureHistory {
+    /// Creates an empty history keeping at most `capacity` readings
+    ///
+    /// A capacity of zero is raised to one.
+    pub fn new(capacity: usize) -> Self {
+        let capacity = capacity.max(1);
+        Self {
+            capacity,
+            readings: VecDeque::with_capacity(capacity.min(DEFAULT_HISTORY_CAPACITY)),
+        }
+    }
+
+    /// Returns the maximum number of readings kept
+    pub fn capacity(&self) -> usize {
+        self.capacity
+    }
+
+    /// Returns the number of readings kept
+    pub fn len(&self) -> usize {
+        self.readings.len()
+    }
+
+    /// Checks whether no reading was recorded
+    pub fn is_empty(&self) -> bool {
+        self.readings.is_empty()
+    }
+
+    /// Records a reading, dropping the oldest one if the history is full
+    pub fn record(&mut self, at: NaiveDateTime, temperature: f32) {
+        if self.readings.len() == self.capacity {
+            self.readings.pop_front();
+        }
+        self.readings
+            .push_back(TemperatureReading { at, temperature });
+    }
+
+    /// Removes all readings
+    pub fn clear(&mut self) {
+        self.readings.clear();
+    }
+
+    /// Returns the readings from oldest to newest
+    pub fn readings(&self) -> impl Iterator<Item = &TemperatureReading> {
+        self.readings.iter()
+    }
+
+    /// Returns the most recent reading
+    pub fn latest(&self) -> Option<&TemperatureReading> {
+        self.readings.back()
+    }
+
+    /// Returns the readings taken within the window
+    pub fn window<R: RangeBounds<NaiveDateTime>>(
+        &self,
+        window: R,
+    ) -> impl Iterator<Item = &TemperatureReading> {
+        self.readings
+            .iter()
+            .filter(move |reading| window.contains(&reading.at))
+    }
+
+    /// Returns the statistics of the readings within the window
+    pub fn stats(&self, window: impl RangeBounds<NaiveDateTime>) -> Option<TemperatureStats> {
+        TemperatureStats::from_temperatures(self.window(window).map(|r| r.temperature))
+    }
+
+    /// Returns the lowest temperature within the window
+    pub fn min(&self, window: impl RangeBounds<NaiveDateTime>) -> Option<f32> {
+        self.stats(window).map(|stats| stats.min)
+    }
+
+    /// Returns the highest temperature within the window
+    pub fn max(&self, window: impl RangeBounds<NaiveDateTime>) -> Option<f32> {
+        self.stats(window).map(|stats| stats.max)
+    }
+
+    /// Returns the mean temperature within the window
+    pub fn mean(&self, window: impl RangeBounds<NaiveDateTime>) -> Option<f32> {
+        self.stats(window).map(|stats| stats.mean)
+    }
+
+    /// Returns the given percentile (0 to 100) of the temperatures within the window
+    ///
+    /// Interpolates linearly between the two closest readings. Returns `None`
+    /// if the window is empty or the percentile is out of range.
+    pub fn percentile(
+        &self,
+        window: impl RangeBounds<NaiveDateTime>,
+        percentile: f32,
+    ) -> Option<f32> {
+        if !(0.0..=100.0).contains(&percentile) {
+            return None;
+        }
+
+        let mut temperatures: Vec<f32> = self.window(window).map(|r| r.temperature).collect();
+        if temperatures.is_empty() {
+            return None;
+        }
+        temperatures.sort_by(f32::total_cmp);
+
+        let rank = percentile / 100.0 * (temperatures.len() - 1) as f32;
+        let lower = rank.floor() as usize;
+        let upper = rank.ceil() as usize;
+        let fraction = rank - lower as f32;
+        Some(temperatures[lower] + (temperatures[upper] - temperatures[lower]) * fraction)
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use chrono::NaiveDate;
+
+    fn at(minute: u32) -> NaiveDateTime {
+        NaiveDate::from_ymd_opt(2024, 1, 1)
+            .unwrap()
+            .and_hms_opt(12, minute, 0)
+            .unwrap()
+    }
+
+    fn test_history() -> TemperatureHistory {
+        let mut history = TemperatureHistory::new(10);
+        for (minute, temperature) in [(0, 20.0), (10, 22.0), (20, 21.0), (30, 25.0), (40, 19.0)] {
+            history.record(at(minute), temperature);
+        }
+        history
+    }
+
+    #[test]
+    fn test_ring_buffer() {
+        let mut history = TemperatureHistory::new(3);
+        for minute in 0..5 {
+            history.record(at(minute), minute as f32);
+        }
+
+        assert_eq!(history.len(), 3);
+        let temperatures: Vec<f32> = history.readings().map(|r| r.temperature).collect();
+        assert_eq!(temperatures, vec![2.0, 3.0, 4.0]);
+        assert_eq!(history.latest().map(|r| r.at), Some(at(4)));
+
+        assert_eq!(TemperatureHistory::new(0).capacity(), 1);
+    }
+
+    #[test]
+    fn test_stats() {
+        struct TestCase {
+            name: &'static str,
+            from: NaiveDateTime,
+            to: NaiveDateTime,
+            expected: Option<TemperatureStats>,
+        }
+
+        let test_cases = vec![
+            TestCase {
+                name: "Whole history",
+                from: at(0),
+                to: at(40),
+                expected: Some(TemperatureStats {
+                    count: 5,
+                    min: 19.0,
+                    max: 25.0,
+                    mean: 21.4,
+                }),
+            },
+            TestCase {
+                name: "Window in the middle",
+                from: at(5),
+                to: at(25),
+                expected: Some(TemperatureStats {
+                    count: 2,
+                    min: 21.0,
+                    max: 22.0,
+                    mean: 21.5,
+                }),
+            },
+            TestCase {
+                name: "Empty window",
+                from: at(41),
+                to: at(59),
+                expected: None,
+            },
+        ];
+
+        let history = test_history();
+        for tc in test_cases {
+            assert_eq!(
+                history.stats(tc.from..=tc.to),
+                tc.expected,
+                "Failed test: {}",
+                tc.name
+            );
+        }
+
+        assert_eq!(history.min(at(20)..), Some(19.0));
+        assert_eq!(history.max(..at(30)), Some(22.0));
+        assert_eq!(history.mean(..), Some(21.4));
+    }
+
+    #[test]
+    fn test_percentile() {
+        struct TestCase {
+            name: &'static str,
+            percentile: f32,
+            expected: Option<f32>,
+        }
+
+        let test_cases = vec![
+            TestCase {
+                name: "Lowest",
+                percentile: 0.0,
+                expected: Some(19.0),
+            },
+            TestCase {
+                name: "Median",
+                percentile: 50.0,
+                expected: Some(21.0),
+            },
+            TestCase {
+                name: "Between two readings",
+                percentile: 90.0,
+                expected: Some(23.8),
+            },
+            TestCase {
+                name: "Highest",
+                percentile: 100.0,
+                expected: Some(25.0),
+            },
+            TestCase {
+                name: "Out of range",
+                percentile: 101.0,
+                expected: None,
+            },
+        ];
+
+        let history = test_history();
+        for tc in test_cases {
+            let result = history.percentile(.., tc.percentile);
+            match (result, tc.expected) {
+                (Some(result), Some(expected)) => assert!(
+                    (result - expected).abs() < 1e-4,
+                    "Failed test: {}: {} != {}",
+                    tc.name,
+                    result,
+                    expected
+                ),
+                (result, expected) => assert_eq!(result, expected, "Failed test: {}", tc.name),
+            }
+        }
+
+        assert_eq!(TemperatureHistory::default().percentile(.., 50.0), None);
+    }
+}
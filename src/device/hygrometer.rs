@@ -0,0 +1,65 @@
+use crate::Reporter;
+use crate::device::device_trait::{HumiditySensor, SmartDeviceTrait};
+use serde::{Deserialize, Serialize};
+
+/// Smart hygrometer device implementation
+///
+/// Provides relative humidity readings in percent.
+#[derive(Debug, Clone, PartialEq, Serialize, Deserialize)]
+pub struct SmartHygrometer {
+    name: String,
+    humidity: f32,
+}
+
+impl SmartHygrometer {
+    /// Creates a new hygrometer with the specified name and relative humidity
+    pub fn new(name: String, humidity: f32) -> Self {
+        Self { name, humidity }
+    }
+
+    /// Updates the current relative humidity reading
+    pub fn set_humidity(&mut self, humidity: f32) {
+        self.humidity = humidity;
+    }
+}
+
+impl SmartDeviceTrait for SmartHygrometer {
+    fn name(&self) -> &str {
+        &self.name
+    }
+
+    fn as_humidity_sensor(&self) -> Option<&dyn HumiditySensor> {
+        Some(self)
+    }
+}
+
+impl HumiditySensor for SmartHygrometer {
+    fn humidity(&self) -> f32 {
+        self.humidity
+    }
+}
+
+impl Reporter for SmartHygrometer {
+    fn report(&self) -> String {
+        format!(
+            "Device: {name}, Humidity: {humidity}%",
+            name = self.name(),
+            humidity = self.humidity()
+        )
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn test_hygrometer() {
+        let mut hygrometer = SmartHygrometer::new("Bathroom".to_string(), 55.0);
+        assert_eq!(hygrometer.humidity(), 55.0);
+        assert!(hygrometer.as_temperature_sensor().is_none());
+
+        hygrometer.set_humidity(72.5);
+        assert_eq!(hygrometer.report(), "Device: Bathroom, Humidity: 72.5%");
+    }
+}
@@ -0,0 +1,310 @@
+use crate::Reporter;
+use crate::device::device_trait::{PowerConsumption, PowerControl, SmartDeviceTrait};
+use crate::error::SmartHomeError;
+use serde::{Deserialize, Deserializer, Serialize};
+use std::fmt;
+
+/// Lowest colour temperature accepted by [`SmartLight::set_color_temperature`], in kelvin
+pub const MIN_COLOR_TEMPERATURE: u16 = 1000;
+
+/// Highest colour temperature accepted by [`SmartLight::set_color_temperature`], in kelvin
+pub const MAX_COLOR_TEMPERATURE: u16 = 10000;
+
+/// Colour of a light as red, green and blue components
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Serialize, Deserialize)]
+pub struct Rgb {
+    pub red: u8,
+    pub green: u8,
+    pub blue: u8,
+}
+
+impl Rgb {
+    /// Creates a colour from its components
+    pub fn new(red: u8, green: u8, blue: u8) -> Self {
+        Self { red, green, blue }
+    }
+}
+
+impl fmt::Display for Rgb {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        write!(f, "#{:02X}{:02X}{:02X}", self.red, self.green, self.blue)
+    }
+}
+
+/// Dimmable smart light
+///
+/// The power draw scales linearly with the brightness, from nothing at 0%
+/// to the rated power at 100%. A light shows either white light of a given
+/// colour temperature or an RGB colour, so setting one clears the other.
+#[derive(Debug, Clone, PartialEq, Serialize, Deserialize)]
+pub struct SmartLight {
+    name: String,
+    is_on: bool,
+    /// Brightness in percent, kept when the light is off
+    #[serde(deserialize_with = "brightness")]
+    brightness: u8,
+    /// Power drawn at full brightness, in watts
+    rated_power: f32,
+    #[serde(default, skip_serializing_if = "Option::is_none")]
+    color_temperature: Option<u16>,
+    #[serde(default, skip_serializing_if = "Option::is_none")]
+    color: Option<Rgb>,
+}
+
+/// Rejects brightness values above 100% in saved house files
+fn brightness<'de, D: Deserializer<'de>>(deserializer: D) -> Result<u8, D::Error> {
+    let brightness = u8::deserialize(deserializer)?;
+    if brightness > 100 {
+        return Err(serde::de::Error::custom(format!(
+            "brightness must be between 0 and 100, got {}",
+            brightness
+        )));
+    }
+    Ok(brightness)
+}
+
+impl SmartLight {
+    /// Creates a light at full brightness drawing `rated_power` watts when on
+    pub fn new(name: String, is_on: bool, rated_power: f32) -> Self {
+        Self {
+            name,
+            is_on,
+            brightness: 100,
+            rated_power,
+            color_temperature: None,
+            color: None,
+        }
+    }
+
+    /// Sets the initial brightness in percent; values above 100 are capped
+    pub fn with_brightness(mut self, brightness: u8) -> Self {
+        self.brightness = brightness.min(100);
+        self
+    }
+
+    /// Returns the brightness in percent
+    pub fn brightness(&self) -> u8 {
+        self.brightness
+    }
+
+    /// Sets the brightness in percent
+    ///
+    /// Fails if the brightness is above 100. The light stays on or off.
+    pub fn set_brightness(&mut self, brightness: u8) -> Result<(), SmartHomeError> {
+        if brightness > 100 {
+            return Err(SmartHomeError::invalid_value(
+                "brightness",
+                format!("must be between 0 and 100, got {}", brightness),
+            ));
+        }
+        self.brightness = brightness;
+        Ok(())
+    }
+
+    /// Returns the power drawn at full brightness, in watts
+    pub fn rated_power(&self) -> f32 {
+        self.rated_power
+    }
+
+    /// Returns the colour temperature in kelvin, if the light shows white light
+    pub fn color_temperature(&self) -> Option<u16> {
+        self.color_temperature
+    }
+
+    /// Sets or clears the colour temperature in kelvin, clearing the RGB colour
+    ///
+    /// Fails if the temperature is outside
+    /// [`MIN_COLOR_TEMPERATURE`]..=[`MAX_COLOR_TEMPERATURE`].
+    pub fn set_color_temperature(&mut self, kelvin: Option<u16>) -> Result<(), SmartHomeError> {
+        let range = MIN_COLOR_TEMPERATURE..=MAX_COLOR_TEMPERATURE;
+        if let Some(kelvin) = kelvin.filter(|kelvin| !range.contains(kelvin)) {
+            return Err(SmartHomeError::invalid_value(
+                "color temperature",
+                format!(
+                    "must be between {} K and {} K, got {} K",
+                    MIN_COLOR_TEMPERATURE, MAX_COLOR_TEMPERATURE, kelvin
+                ),
+            ));
+        }
+        self.color_temperature = kelvin;
+        if kelvin.is_some() {
+            self.color = None;
+        }
+        Ok(())
+    }
+
+    /// Returns the RGB colour, if the light shows a colour
+    pub fn color(&self) -> Option<Rgb> {
+        self.color
+    }
+
+    /// Sets or clears the RGB colour, clearing the colour temperature
+    pub fn set_color(&mut self, color: Option<Rgb>) {
+        self.color = color;
+        if color.is_some() {
+            self.color_temperature = None;
+        }
+    }
+}
+
+impl SmartDeviceTrait for SmartLight {
+    fn name(&self) -> &str {
+        &self.name
+    }
+
+    fn as_power_control(&self) -> Option<&dyn PowerControl> {
+        Some(self)
+    }
+
+    fn as_power_control_mut(&mut self) -> Option<&mut dyn PowerControl> {
+        Some(self)
+    }
+
+    fn as_power_consumption(&self) -> Option<&dyn PowerConsumption> {
+        Some(self)
+    }
+}
+
+impl PowerControl for SmartLight {
+    fn is_on(&self) -> bool {
+        self.is_on
+    }
+
+    fn turn_on(&mut self) {
+        self.is_on = true;
+    }
+
+    fn turn_off(&mut self) {
+        self.is_on = false;
+    }
+}
+
+impl PowerConsumption for SmartLight {
+    fn power_consumption(&self) -> f32 {
+        if self.is_on {
+            self.power_when_on()
+        } else {
+            0.0
+        }
+    }
+
+    fn power_when_on(&self) -> f32 {
+        self.rated_power * f32::from(self.brightness) / 100.0
+    }
+}
+
+impl Reporter for SmartLight {
+    fn report(&self) -> String {
+        let status = if self.is_on() { "ON" } else { "OFF" };
+        let mut report = format!(
+            "Device: {}, Status: {}, Brightness: {}%",
+            self.name(),
+            status,
+            self.brightness
+        );
+        if let Some(kelvin) = self.color_temperature {
+            report.push_str(&format!(", Color temperature: {}K", kelvin));
+        }
+        if let Some(color) = self.color {
+            report.push_str(&format!(", Color: {}", color));
+        }
+        report.push_str(&format!(
+            ", Power consumption: {}W",
+            self.power_consumption()
+        ));
+        report
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn test_power_scales_with_brightness() {
+        struct TestCase {
+            name: &'static str,
+            is_on: bool,
+            brightness: u8,
+            expected_power: f32,
+        }
+
+        let test_cases = vec![
+            TestCase {
+                name: "Full brightness",
+                is_on: true,
+                brightness: 100,
+                expected_power: 10.0,
+            },
+            TestCase {
+                name: "Dimmed",
+                is_on: true,
+                brightness: 25,
+                expected_power: 2.5,
+            },
+            TestCase {
+                name: "On at zero brightness",
+                is_on: true,
+                brightness: 0,
+                expected_power: 0.0,
+            },
+            TestCase {
+                name: "Off",
+                is_on: false,
+                brightness: 80,
+                expected_power: 0.0,
+            },
+        ];
+
+        for tc in test_cases {
+            let light =
+                SmartLight::new("Lamp".to_string(), tc.is_on, 10.0).with_brightness(tc.brightness);
+            assert_eq!(
+                light.power_consumption(),
+                tc.expected_power,
+                "Failed test: {}",
+                tc.name
+            );
+        }
+
+        assert_eq!(
+            SmartLight::new("Lamp".to_string(), true, 10.0)
+                .with_brightness(150)
+                .brightness(),
+            100
+        );
+    }
+
+    #[test]
+    fn test_settings() {
+        let mut light = SmartLight::new("Lamp".to_string(), true, 8.0);
+
+        assert!(light.set_brightness(101).is_err());
+        light.set_brightness(50).unwrap();
+        assert_eq!(light.brightness(), 50);
+
+        assert_eq!(
+            light
+                .set_color_temperature(Some(500))
+                .unwrap_err()
+                .to_string(),
+            "Invalid color temperature: must be between 1000 K and 10000 K, got 500 K"
+        );
+        light.set_color_temperature(Some(2700)).unwrap();
+        assert_eq!(
+            light.report(),
+            "Device: Lamp, Status: ON, Brightness: 50%, Color temperature: 2700K, Power consumption: 4W"
+        );
+
+        light.set_color(Some(Rgb::new(255, 136, 0)));
+        assert_eq!(light.color_temperature(), None);
+        light.turn_off();
+        assert_eq!(
+            light.report(),
+            "Device: Lamp, Status: OFF, Brightness: 50%, Color: #FF8800, Power consumption: 0W"
+        );
+
+        light.set_color_temperature(Some(4000)).unwrap();
+        assert_eq!(light.color(), None);
+    }
+}
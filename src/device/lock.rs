@@ -0,0 +1,621 @@
+use crate::Reporter;
+use crate::clock::{Clock, SystemClock};
+use crate::device::device_trait::SmartDeviceTrait;
+use crate::error::{LockError, SmartHomeError};
+use chrono::{NaiveDateTime, TimeDelta};
+use indexmap::IndexMap;
+use serde::{Deserialize, Serialize};
+use std::collections::VecDeque;
+use std::fmt;
+use std::sync::Arc;
+use std::time::Duration;
+
+/// Number of wrong PINs in a row that trigger a lockout unless another one is chosen
+pub const DEFAULT_MAX_FAILED_ATTEMPTS: u32 = 3;
+
+/// How long a lockout lasts unless another duration is chosen, in seconds
+pub const DEFAULT_LOCKOUT_SECS: u64 = 300;
+
+/// Number of audit entries a lock keeps unless another capacity is chosen
+pub const DEFAULT_AUDIT_CAPACITY: usize = 100;
+
+/// Whether the bolt of a lock is thrown
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub enum LockState {
+    Locked,
+    Unlocked,
+    /// The bolt is stuck and neither locks nor unlocks
+    Jammed,
+}
+
+impl fmt::Display for LockState {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        let name = match self {
+            LockState::Locked => "locked",
+            LockState::Unlocked => "unlocked",
+            LockState::Jammed => "jammed",
+        };
+        f.write_str(name)
+    }
+}
+
+/// PIN code of a lock user, optionally valid only within a time window
+///
+/// The code itself is never shown: `Debug` prints it masked and there is no
+/// accessor for it.
+#[derive(Clone, PartialEq, Eq)]
+pub struct PinCode {
+    code: String,
+    valid_from: Option<NaiveDateTime>,
+    valid_until: Option<NaiveDateTime>,
+}
+
+impl PinCode {
+    /// Creates a PIN valid at any time
+    ///
+    /// Fails unless the code is made of 4 to 8 digits.
+    pub fn new(code: impl Into<String>) -> Result<Self, SmartHomeError> {
+        let code = code.into();
+        if !(4..=8).contains(&code.len()) || !code.chars().all(|c| c.is_ascii_digit()) {
+            return Err(SmartHomeError::invalid_value(
+                "PIN",
+                "must be made of 4 to 8 digits",
+            ));
+        }
+        Ok(Self {
+            code,
+            valid_from: None,
+            valid_until: None,
+        })
+    }
+
+    /// Makes the PIN valid from the given time onwards
+    pub fn valid_from(mut self, from: NaiveDateTime) -> Self {
+        self.valid_from = Some(from);
+        self
+    }
+
+    /// Makes the PIN valid until, but not including, the given time
+    pub fn valid_until(mut self, until: NaiveDateTime) -> Self {
+        self.valid_until = Some(until);
+        self
+    }
+
+    /// Checks if the PIN may be used at the given time
+    pub fn is_valid_at(&self, at: NaiveDateTime) -> bool {
+        self.valid_from.is_none_or(|from| from <= at)
+            && self.valid_until.is_none_or(|until| at < until)
+    }
+
+    fn matches(&self, code: &str) -> bool {
+        self.code == code
+    }
+}
+
+impl fmt::Debug for PinCode {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        f.debug_struct("PinCode")
+            .field("code", &"****")
+            .field("valid_from", &self.valid_from)
+            .field("valid_until", &self.valid_until)
+            .finish()
+    }
+}
+
+/// What happened to a lock
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub enum LockAction {
+    Locked,
+    /// Unlocked with the PIN of the given user
+    Unlocked {
+        user: String,
+    },
+    /// A wrong, expired or not yet valid PIN was entered
+    FailedAttempt,
+    /// Too many wrong PINs were entered in a row
+    LockedOut,
+    Jammed,
+    JamCleared,
+}
+
+impl fmt::Display for LockAction {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        match self {
+            LockAction::Locked => f.write_str("locked"),
+            LockAction::Unlocked { user } => write!(f, "unlocked by {}", user),
+            LockAction::FailedAttempt => f.write_str("failed attempt"),
+            LockAction::LockedOut => f.write_str("locked out"),
+            LockAction::Jammed => f.write_str("jammed"),
+            LockAction::JamCleared => f.write_str("jam cleared"),
+        }
+    }
+}
+
+/// Entry of the audit trail of a lock
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub struct AuditEntry {
+    pub at: NaiveDateTime,
+    pub action: LockAction,
+}
+
+/// Door lock unlocked with user PIN codes
+///
+/// Entering [`max_failed_attempts`](SmartLock::with_lockout) wrong PINs in a
+/// row locks the keypad out for a while, during which even valid PINs are
+/// refused. Every lock, unlock, failed attempt and jam is written to a
+/// bounded audit trail.
+///
+/// Only the name, state and lockout settings are saved with the house: PIN
+/// codes and the audit trail are kept out of house files.
+#[derive(Serialize, Deserialize)]
+pub struct SmartLock {
+    name: String,
+    locked: bool,
+    #[serde(default)]
+    jammed: bool,
+    #[serde(default = "default_max_failed_attempts")]
+    max_failed_attempts: u32,
+    #[serde(default = "default_lockout_secs")]
+    lockout_secs: u64,
+    #[serde(skip)]
+    pins: IndexMap<String, PinCode>,
+    #[serde(skip)]
+    failed_attempts: u32,
+    #[serde(skip)]
+    locked_out_until: Option<NaiveDateTime>,
+    #[serde(skip)]
+    audit: AuditTrail,
+    #[serde(skip, default = "SystemClock::shared")]
+    clock: Arc<dyn Clock>,
+}
+
+fn default_max_failed_attempts() -> u32 {
+    DEFAULT_MAX_FAILED_ATTEMPTS
+}
+
+fn default_lockout_secs() -> u64 {
+    DEFAULT_LOCKOUT_SECS
+}
+
+/// Bounded log of what happened to a lock, oldest entry first
+struct AuditTrail {
+    capacity: usize,
+    entries: VecDeque<AuditEntry>,
+}
+
+impl Default for AuditTrail {
+    fn default() -> Self {
+        Self {
+            capacity: DEFAULT_AUDIT_CAPACITY,
+            entries: VecDeque::new(),
+        }
+    }
+}
+
+impl AuditTrail {
+    fn record(&mut self, at: NaiveDateTime, action: LockAction) {
+        if self.entries.len() == self.capacity {
+            self.entries.pop_front();
+        }
+        self.entries.push_back(AuditEntry { at, action });
+    }
+}
+
+impl SmartLock {
+    /// Creates a lock without users in the given state
+    pub fn new(name: String, locked: bool) -> Self {
+        Self {
+            name,
+            locked,
+            jammed: false,
+            max_failed_attempts: DEFAULT_MAX_FAILED_ATTEMPTS,
+            lockout_secs: DEFAULT_LOCKOUT_SECS,
+            pins: IndexMap::new(),
+            failed_attempts: 0,
+            locked_out_until: None,
+            audit: AuditTrail::default(),
+            clock: SystemClock::shared(),
+        }
+    }
+
+    /// Timestamps audit entries and checks PIN validity with the given clock
+    /// instead of the system clock
+    pub fn with_clock<C: Clock + 'static>(mut self, clock: C) -> Self {
+        self.clock = Arc::new(clock);
+        self
+    }
+
+    /// Locks the keypad out for `lockout` after `max_failed_attempts` wrong PINs in a row
+    ///
+    /// A limit of zero is raised to one.
+    pub fn with_lockout(mut self, max_failed_attempts: u32, lockout: Duration) -> Self {
+        self.max_failed_attempts = max_failed_attempts.max(1);
+        self.lockout_secs = lockout.as_secs();
+        self
+    }
+
+    /// Keeps at most `capacity` audit entries, dropping the oldest ones
+    ///
+    /// A capacity of zero is raised to one.
+    pub fn with_audit_capacity(mut self, capacity: usize) -> Self {
+        let capacity = capacity.max(1);
+        let skip = self.audit.entries.len().saturating_sub(capacity);
+        self.audit.entries.drain(..skip);
+        self.audit.capacity = capacity;
+        self
+    }
+
+    /// Returns whether the lock is locked, unlocked or jammed
+    pub fn state(&self) -> LockState {
+        match (self.jammed, self.locked) {
+            (true, _) => LockState::Jammed,
+            (false, true) => LockState::Locked,
+            (false, false) => LockState::Unlocked,
+        }
+    }
+
+    /// Checks if the bolt is thrown
+    pub fn is_locked(&self) -> bool {
+        self.locked
+    }
+
+    /// Checks if the bolt is jammed
+    pub fn is_jammed(&self) -> bool {
+        self.jammed
+    }
+
+    /// Sets the PIN of a user, replacing the previous one
+    pub fn set_pin(&mut self, user: String, pin: PinCode) {
+        self.pins.insert(user, pin);
+    }
+
+    /// Removes the PIN of a user, returning whether the user had one
+    pub fn remove_pin(&mut self, user: &str) -> bool {
+        self.pins.shift_remove(user).is_some()
+    }
+
+    /// Returns the users with a PIN, in the order they were added
+    pub fn users(&self) -> impl Iterator<Item = &str> {
+        self.pins.keys().map(String::as_str)
+    }
+
+    /// Throws the bolt; no PIN is needed to lock
+    pub fn lock(&mut self) -> Result<(), LockError> {
+        if self.jammed {
+            return Err(LockError::Jammed(self.name.clone()));
+        }
+        if !self.locked {
+            self.locked = true;
+            self.record(LockAction::Locked);
+        }
+        Ok(())
+    }
+
+    /// Unlocks with a PIN, returning the user it belongs to
+    ///
+    /// Fails if the lock is jammed or locked out, or if the PIN belongs to no
+    /// user allowed in right now. A failure counts towards the lockout and a
+    /// success resets the count.
+    pub fn unlock(&mut self, pin: &str) -> Result<String, LockError> {
+        if self.jammed {
+            return Err(LockError::Jammed(self.name.clone()));
+        }
+
+        let now = self.clock.now();
+        if let Some(until) = self.locked_out_until {
+            if now < until {
+                return Err(LockError::LockedOut(self.name.clone(), until));
+            }
+            self.locked_out_until = None;
+        }
+
+        let user = self
+            .pins
+            .iter()
+            .find(|(_, code)| code.matches(pin) && code.is_valid_at(now))
+            .map(|(user, _)| user.clone());
+        let Some(user) = user else {
+            self.failed_attempts += 1;
+            self.record(LockAction::FailedAttempt);
+            if self.failed_attempts >= self.max_failed_attempts {
+                let lockout = TimeDelta::seconds(self.lockout_secs.try_into().unwrap_or(i64::MAX));
+                let until = now
+                    .checked_add_signed(lockout)
+                    .unwrap_or(NaiveDateTime::MAX);
+                self.failed_attempts = 0;
+                self.locked_out_until = Some(until);
+                self.record(LockAction::LockedOut);
+                return Err(LockError::LockedOut(self.name.clone(), until));
+            }
+            return Err(LockError::InvalidPin(self.name.clone()));
+        };
+
+        self.failed_attempts = 0;
+        self.locked = false;
+        self.record(LockAction::Unlocked { user: user.clone() });
+        Ok(user)
+    }
+
+    /// Returns when the keypad accepts PINs again, if it is locked out
+    pub fn locked_out_until(&self) -> Option<NaiveDateTime> {
+        self.locked_out_until
+            .filter(|until| self.clock.now() < *until)
+    }
+
+    /// Marks the bolt as jammed or freed
+    pub fn set_jammed(&mut self, jammed: bool) {
+        if jammed != self.jammed {
+            self.jammed = jammed;
+            self.record(if jammed {
+                LockAction::Jammed
+            } else {
+                LockAction::JamCleared
+            });
+        }
+    }
+
+    /// Returns the audit trail, oldest entry first
+    pub fn audit_trail(&self) -> impl Iterator<Item = &AuditEntry> {
+        self.audit.entries.iter()
+    }
+
+    fn record(&mut self, action: LockAction) {
+        self.audit.record(self.clock.now(), action);
+    }
+}
+
+impl fmt::Debug for SmartLock {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        f.debug_struct("SmartLock")
+            .field("name", &self.name)
+            .field("state", &self.state())
+            .field("users", &self.pins.len())
+            .field("failed_attempts", &self.failed_attempts)
+            .field("locked_out_until", &self.locked_out_until)
+            .field("audit", &self.audit.entries.len())
+            .finish()
+    }
+}
+
+impl SmartDeviceTrait for SmartLock {
+    fn name(&self) -> &str {
+        &self.name
+    }
+}
+
+impl Reporter for SmartLock {
+    fn report(&self) -> String {
+        let mut report = format!(
+            "Device: {}, Lock: {}, Users: {}",
+            self.name(),
+            self.state(),
+            self.pins.len()
+        );
+        if let Some(until) = self.locked_out_until() {
+            report.push_str(&format!(", Locked out until: {}", until));
+        }
+        report
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::clock::ManualClock;
+    use chrono::NaiveDate;
+
+    fn at(hour: u32, minute: u32) -> NaiveDateTime {
+        NaiveDate::from_ymd_opt(2024, 5, 6)
+            .unwrap()
+            .and_hms_opt(hour, minute, 0)
+            .unwrap()
+    }
+
+    fn create_lock(clock: &Arc<ManualClock>) -> SmartLock {
+        let mut lock = SmartLock::new("Front Door".to_string(), true)
+            .with_clock(clock.clone())
+            .with_lockout(3, Duration::from_secs(600));
+        lock.set_pin("Alice".to_string(), PinCode::new("1234").unwrap());
+        lock.set_pin(
+            "Cleaner".to_string(),
+            PinCode::new("5678")
+                .unwrap()
+                .valid_from(at(9, 0))
+                .valid_until(at(12, 0)),
+        );
+        lock
+    }
+
+    #[test]
+    fn test_pin_validation() {
+        struct TestCase {
+            name: &'static str,
+            code: &'static str,
+            expected_valid: bool,
+        }
+
+        let test_cases = vec![
+            TestCase {
+                name: "Four digits",
+                code: "0000",
+                expected_valid: true,
+            },
+            TestCase {
+                name: "Eight digits",
+                code: "12345678",
+                expected_valid: true,
+            },
+            TestCase {
+                name: "Too short",
+                code: "123",
+                expected_valid: false,
+            },
+            TestCase {
+                name: "Too long",
+                code: "123456789",
+                expected_valid: false,
+            },
+            TestCase {
+                name: "Not digits",
+                code: "12a4",
+                expected_valid: false,
+            },
+        ];
+
+        for tc in test_cases {
+            assert_eq!(
+                PinCode::new(tc.code).is_ok(),
+                tc.expected_valid,
+                "Failed test: {}",
+                tc.name
+            );
+        }
+    }
+
+    #[test]
+    fn test_unlock() {
+        struct TestCase {
+            name: &'static str,
+            now: NaiveDateTime,
+            pin: &'static str,
+            expected: Result<&'static str, LockError>,
+        }
+
+        let door = || "Front Door".to_string();
+        let test_cases = vec![
+            TestCase {
+                name: "Permanent PIN",
+                now: at(7, 0),
+                pin: "1234",
+                expected: Ok("Alice"),
+            },
+            TestCase {
+                name: "PIN within its window",
+                now: at(9, 0),
+                pin: "5678",
+                expected: Ok("Cleaner"),
+            },
+            TestCase {
+                name: "PIN before its window",
+                now: at(8, 59),
+                pin: "5678",
+                expected: Err(LockError::InvalidPin(door())),
+            },
+            TestCase {
+                name: "PIN after its window",
+                now: at(12, 0),
+                pin: "5678",
+                expected: Err(LockError::InvalidPin(door())),
+            },
+            TestCase {
+                name: "Unknown PIN",
+                now: at(10, 0),
+                pin: "0000",
+                expected: Err(LockError::InvalidPin(door())),
+            },
+        ];
+
+        for tc in test_cases {
+            let clock = Arc::new(ManualClock::new(tc.now));
+            let mut lock = create_lock(&clock);
+            let result = lock.unlock(tc.pin);
+            assert_eq!(
+                result.as_deref().map_err(Clone::clone),
+                tc.expected,
+                "Failed test: {}",
+                tc.name
+            );
+            assert_eq!(
+                lock.is_locked(),
+                tc.expected.is_err(),
+                "Failed test: {}",
+                tc.name
+            );
+        }
+    }
+
+    #[test]
+    fn test_lockout() {
+        let clock = Arc::new(ManualClock::new(at(20, 0)));
+        let mut lock = create_lock(&clock);
+
+        assert!(lock.unlock("0000").is_err());
+        assert!(lock.unlock("1111").is_err());
+        assert_eq!(
+            lock.unlock("2222"),
+            Err(LockError::LockedOut("Front Door".to_string(), at(20, 10)))
+        );
+        assert_eq!(lock.locked_out_until(), Some(at(20, 10)));
+        assert!(
+            matches!(lock.unlock("1234"), Err(LockError::LockedOut(..))),
+            "A valid PIN is refused during the lockout"
+        );
+        assert_eq!(
+            lock.report(),
+            "Device: Front Door, Lock: locked, Users: 2, Locked out until: 2024-05-06 20:10:00"
+        );
+
+        clock.set(at(20, 10));
+        assert_eq!(lock.locked_out_until(), None);
+        assert_eq!(lock.unlock("1234"), Ok("Alice".to_string()));
+
+        // A success resets the count of failed attempts
+        lock.lock().unwrap();
+        assert!(lock.unlock("0000").is_err());
+        assert!(lock.unlock("1111").is_err());
+        assert!(lock.unlock("1234").is_ok());
+        assert!(matches!(lock.unlock("0000"), Err(LockError::InvalidPin(_))));
+    }
+
+    #[test]
+    fn test_jam_and_audit_trail() {
+        let clock = Arc::new(ManualClock::new(at(8, 0)));
+        let mut lock = create_lock(&clock).with_audit_capacity(4);
+
+        lock.unlock("1234").unwrap();
+        clock.set(at(8, 5));
+        lock.set_jammed(true);
+        assert_eq!(lock.state(), LockState::Jammed);
+        assert_eq!(
+            lock.lock(),
+            Err(LockError::Jammed("Front Door".to_string()))
+        );
+        assert_eq!(
+            lock.unlock("1234"),
+            Err(LockError::Jammed("Front Door".to_string()))
+        );
+
+        lock.set_jammed(false);
+        lock.lock().unwrap();
+        assert!(lock.unlock("9999").is_err());
+
+        let actions: Vec<String> = lock
+            .audit_trail()
+            .map(|entry| entry.action.to_string())
+            .collect();
+        assert_eq!(
+            actions,
+            vec!["jammed", "jam cleared", "locked", "failed attempt"]
+        );
+        assert_eq!(lock.audit_trail().next().unwrap().at, at(8, 5));
+    }
+
+    #[test]
+    fn test_pins_are_never_printed() {
+        let clock = Arc::new(ManualClock::new(at(8, 0)));
+        let mut lock = create_lock(&clock);
+        lock.unlock("1234").unwrap();
+        assert!(lock.remove_pin("Cleaner"));
+        assert_eq!(lock.users().collect::<Vec<_>>(), vec!["Alice"]);
+
+        let outputs = [
+            lock.report(),
+            format!("{:?}", lock),
+            format!("{:?}", PinCode::new("1234").unwrap()),
+            serde_json::to_string(&lock).unwrap(),
+        ];
+        for output in outputs {
+            assert!(!output.contains("1234"), "PIN leaked in {}", output);
+        }
+    }
+}
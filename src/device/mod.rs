@@ -1,12 +1,38 @@
 // Export all device-related modules and types
+mod air_quality;
+mod binary_sensor;
+mod capability;
+pub(crate) mod custom;
 pub(crate) mod device_trait;
+mod history;
+mod hygrometer;
+mod light;
+mod lock;
 mod smart_device;
 mod socket;
 mod thermometer;
+mod thermostat;
 
 // Re-export for easier access
 pub use crate::report::Reporter;
-pub use device_trait::SmartDeviceTrait;
+pub use air_quality::SmartAirQualityMonitor;
+pub use binary_sensor::{BinarySensorKind, SmartBinarySensor};
+pub use capability::{Capabilities, Capability};
+pub use custom::CustomDevice;
+pub use device_trait::{
+    AirQualityIndex, BinarySensor, Co2Sensor, EnergyMeter, HumiditySensor, PowerConsumption,
+    PowerControl, SmartDeviceTrait, TemperatureSensor,
+};
+pub use history::{
+    DEFAULT_HISTORY_CAPACITY, TemperatureHistory, TemperatureReading, TemperatureStats,
+};
+pub use hygrometer::SmartHygrometer;
+pub use light::{MAX_COLOR_TEMPERATURE, MIN_COLOR_TEMPERATURE, Rgb, SmartLight};
+pub use lock::{
+    AuditEntry, DEFAULT_AUDIT_CAPACITY, DEFAULT_LOCKOUT_SECS, DEFAULT_MAX_FAILED_ATTEMPTS,
+    LockAction, LockState, PinCode, SmartLock,
+};
 pub use smart_device::SmartDevice;
 pub use socket::SmartSocket;
 pub use thermometer::SmartThermometer;
+pub use thermostat::{DEFAULT_HYSTERESIS, HvacAction, SmartThermostat, ThermostatMode};
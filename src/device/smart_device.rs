@@ -1,21 +1,71 @@
-use super::device_trait::{PowerConsumption, PowerControl, SmartDeviceTrait, TemperatureSensor};
-use super::{SmartSocket, SmartThermometer};
+use super::capability::{Capabilities, Capability};
+use super::custom::CustomDevice;
+use super::device_trait::{
+    AirQualityIndex, BinarySensor, Co2Sensor, EnergyMeter, HumiditySensor, PowerConsumption,
+    PowerControl, SmartDeviceTrait, TemperatureSensor,
+};
+use super::{
+    SmartAirQualityMonitor, SmartBinarySensor, SmartHygrometer, SmartLight, SmartLock, SmartSocket,
+    SmartThermometer, SmartThermostat,
+};
 use crate::Reporter;
+use crate::error::{DeviceAccessError, SmartHomeError};
+use crate::report::{DeviceReport, Metric, StructuredReport};
+use serde::ser::Error as _;
+use serde::{Serialize, Serializer};
 use std::fmt::Debug;
 
 #[derive(Debug)]
 pub enum SmartDevice {
     Thermometer(SmartThermometer),
     Socket(SmartSocket),
+    Light(SmartLight),
+    Thermostat(SmartThermostat),
+    Hygrometer(SmartHygrometer),
+    AirQualityMonitor(SmartAirQualityMonitor),
+    BinarySensor(SmartBinarySensor),
+    Lock(SmartLock),
+
+    /// Device type provided by a downstream crate
+    Custom(Box<dyn CustomDevice>),
 }
 
 // Basic device functionality implemented for all devices
 impl SmartDeviceTrait for SmartDevice {
     fn name(&self) -> &str {
-        match self {
-            SmartDevice::Thermometer(thermometer) => thermometer.name(),
-            SmartDevice::Socket(socket) => socket.name(),
-        }
+        self.as_device().name()
+    }
+
+    fn as_power_control(&self) -> Option<&dyn PowerControl> {
+        SmartDevice::as_power_control(self)
+    }
+
+    fn as_power_control_mut(&mut self) -> Option<&mut dyn PowerControl> {
+        SmartDevice::as_power_control_mut(self)
+    }
+
+    fn as_temperature_sensor(&self) -> Option<&dyn TemperatureSensor> {
+        SmartDevice::as_temperature_sensor(self)
+    }
+
+    fn as_humidity_sensor(&self) -> Option<&dyn HumiditySensor> {
+        SmartDevice::as_humidity_sensor(self)
+    }
+
+    fn as_co2_sensor(&self) -> Option<&dyn Co2Sensor> {
+        SmartDevice::as_co2_sensor(self)
+    }
+
+    fn as_air_quality_index(&self) -> Option<&dyn AirQualityIndex> {
+        SmartDevice::as_air_quality_index(self)
+    }
+
+    fn as_binary_sensor(&self) -> Option<&dyn BinarySensor> {
+        SmartDevice::as_binary_sensor(self)
+    }
+
+    fn as_power_consumption(&self) -> Option<&dyn PowerConsumption> {
+        SmartDevice::as_power_consumption(self)
     }
 }
 
@@ -31,69 +81,420 @@ impl From<SmartThermometer> for SmartDevice {
     }
 }
 
+impl From<SmartLight> for SmartDevice {
+    fn from(light: SmartLight) -> Self {
+        SmartDevice::Light(light)
+    }
+}
+
+impl From<SmartThermostat> for SmartDevice {
+    fn from(thermostat: SmartThermostat) -> Self {
+        SmartDevice::Thermostat(thermostat)
+    }
+}
+
+impl From<SmartHygrometer> for SmartDevice {
+    fn from(hygrometer: SmartHygrometer) -> Self {
+        SmartDevice::Hygrometer(hygrometer)
+    }
+}
+
+impl From<SmartAirQualityMonitor> for SmartDevice {
+    fn from(monitor: SmartAirQualityMonitor) -> Self {
+        SmartDevice::AirQualityMonitor(monitor)
+    }
+}
+
+impl From<SmartBinarySensor> for SmartDevice {
+    fn from(sensor: SmartBinarySensor) -> Self {
+        SmartDevice::BinarySensor(sensor)
+    }
+}
+
+impl From<SmartLock> for SmartDevice {
+    fn from(lock: SmartLock) -> Self {
+        SmartDevice::Lock(lock)
+    }
+}
+
+impl From<Box<dyn CustomDevice>> for SmartDevice {
+    fn from(device: Box<dyn CustomDevice>) -> Self {
+        SmartDevice::Custom(device)
+    }
+}
+
 impl Reporter for SmartDevice {
     fn report(&self) -> String {
         match self {
             SmartDevice::Thermometer(thermometer) => thermometer.report(),
             SmartDevice::Socket(socket) => socket.report(),
+            SmartDevice::Light(light) => light.report(),
+            SmartDevice::Thermostat(thermostat) => thermostat.report(),
+            SmartDevice::Hygrometer(hygrometer) => hygrometer.report(),
+            SmartDevice::AirQualityMonitor(monitor) => monitor.report(),
+            SmartDevice::BinarySensor(sensor) => sensor.report(),
+            SmartDevice::Lock(lock) => lock.report(),
+            SmartDevice::Custom(device) => device.report(),
         }
     }
 }
 
-impl SmartDevice {
-    /// Checks if the device supports power control functionality
-    pub fn supports_power_control(&self) -> bool {
+impl StructuredReport for SmartDevice {
+    type Report = DeviceReport;
+
+    fn structured_report(&self) -> DeviceReport {
+        let mut metrics = Vec::new();
+        if let Some(is_on) = self.is_on() {
+            metrics.push(Metric::flag("is_on", is_on));
+        }
+        if let Some(consumption) = self.power_consumption() {
+            metrics.push(Metric::number("power", consumption, "W"));
+        }
+        // Sensors without a reading yet report NaN, which has no place in a typed report
+        if let Some(temperature) = self.temperature().filter(|t| t.is_finite()) {
+            metrics.push(Metric::number("temperature", temperature, "°C"));
+        }
+        if let Some(humidity) = self.humidity().filter(|h| h.is_finite()) {
+            metrics.push(Metric::number("humidity", humidity, "%"));
+        }
+        if let Some(co2) = self.co2_ppm().filter(|c| c.is_finite()) {
+            metrics.push(Metric::number("co2", co2, "ppm"));
+        }
+        if let Some(index) = self.air_quality_index() {
+            metrics.push(Metric::number("air_quality_index", f32::from(index), "AQI"));
+        }
+        if let Some(sensor) = self.as_binary_sensor() {
+            metrics.push(Metric::flag("triggered", sensor.is_triggered()));
+            if let Some(at) = sensor.last_triggered() {
+                metrics.push(Metric::text("last_triggered", &at.to_string()));
+            }
+        }
         match self {
-            SmartDevice::Socket(_) => true,
-            SmartDevice::Thermometer(_) => false,
+            SmartDevice::Light(light) => {
+                metrics.push(Metric::number(
+                    "brightness",
+                    f32::from(light.brightness()),
+                    "%",
+                ));
+                if let Some(kelvin) = light.color_temperature() {
+                    metrics.push(Metric::number("color_temperature", f32::from(kelvin), "K"));
+                }
+                if let Some(color) = light.color() {
+                    metrics.push(Metric::text("color", &color.to_string()));
+                }
+            }
+            SmartDevice::Thermostat(thermostat) => {
+                metrics.push(Metric::number("setpoint", thermostat.setpoint(), "°C"));
+                metrics.push(Metric::text("mode", &thermostat.mode().to_string()));
+                metrics.push(Metric::text("action", &thermostat.action().to_string()));
+            }
+            SmartDevice::BinarySensor(sensor) => {
+                metrics.push(Metric::text("sensor", &sensor.sensor().to_string()));
+            }
+            SmartDevice::Lock(lock) => {
+                metrics.push(Metric::text("lock_state", &lock.state().to_string()));
+                metrics.push(Metric::flag(
+                    "locked_out",
+                    lock.locked_out_until().is_some(),
+                ));
+            }
+            SmartDevice::Custom(device) => metrics.extend(device.report_metrics()),
+            SmartDevice::Thermometer(_)
+            | SmartDevice::Socket(_)
+            | SmartDevice::Hygrometer(_)
+            | SmartDevice::AirQualityMonitor(_) => {}
+        }
+
+        DeviceReport {
+            name: self.name().to_string(),
+            kind: self.kind().to_string(),
+            metrics,
         }
     }
+}
+
+/// Device fields preceded by the `kind` tag used in saved house files
+#[derive(Serialize)]
+struct Tagged<'a, T: Serialize> {
+    kind: &'a str,
+    #[serde(flatten)]
+    device: &'a T,
+}
+
+impl Serialize for SmartDevice {
+    fn serialize<S: Serializer>(&self, serializer: S) -> Result<S::Ok, S::Error> {
+        let kind = self.kind();
 
-    /// Checks if the device is on (if it supports power control)
-    pub fn is_on(&self) -> Option<bool> {
         match self {
-            SmartDevice::Socket(socket) => Some(socket.is_on()),
-            SmartDevice::Thermometer(_) => None,
+            SmartDevice::Thermometer(thermometer) => Tagged {
+                kind,
+                device: thermometer,
+            }
+            .serialize(serializer),
+            SmartDevice::Socket(socket) => Tagged {
+                kind,
+                device: socket,
+            }
+            .serialize(serializer),
+            SmartDevice::Light(light) => Tagged {
+                kind,
+                device: light,
+            }
+            .serialize(serializer),
+            SmartDevice::Thermostat(thermostat) => Tagged {
+                kind,
+                device: thermostat,
+            }
+            .serialize(serializer),
+            SmartDevice::Hygrometer(hygrometer) => Tagged {
+                kind,
+                device: hygrometer,
+            }
+            .serialize(serializer),
+            SmartDevice::AirQualityMonitor(monitor) => Tagged {
+                kind,
+                device: monitor,
+            }
+            .serialize(serializer),
+            SmartDevice::BinarySensor(sensor) => Tagged {
+                kind,
+                device: sensor,
+            }
+            .serialize(serializer),
+            SmartDevice::Lock(lock) => Tagged { kind, device: lock }.serialize(serializer),
+            SmartDevice::Custom(device) => match device.state() {
+                Some(state @ serde_json::Value::Object(_)) => Tagged {
+                    kind,
+                    device: &state,
+                }
+                .serialize(serializer),
+                _ => Err(S::Error::custom(format!(
+                    "device '{}' of kind '{}' does not support serialization",
+                    device.name(),
+                    kind
+                ))),
+            },
         }
     }
+}
+
+impl SmartDevice {
+    /// Wraps a third-party device
+    pub fn custom<D: CustomDevice + 'static>(device: D) -> Self {
+        SmartDevice::Custom(Box::new(device))
+    }
 
-    /// Turns the device on (if it supports power control)
-    /// Returns true if operation was successful
-    pub fn turn_on(&mut self) -> bool {
+    /// Returns the kind tag of the device as used in saved house files
+    pub fn kind(&self) -> &str {
         match self {
-            SmartDevice::Socket(socket) => {
-                socket.turn_on();
-                true
-            }
-            SmartDevice::Thermometer(_) => false,
+            SmartDevice::Thermometer(_) => "thermometer",
+            SmartDevice::Socket(_) => "socket",
+            SmartDevice::Light(_) => "light",
+            SmartDevice::Thermostat(_) => "thermostat",
+            SmartDevice::Hygrometer(_) => "hygrometer",
+            SmartDevice::AirQualityMonitor(_) => "air_quality_monitor",
+            SmartDevice::BinarySensor(_) => "binary_sensor",
+            SmartDevice::Lock(_) => "lock",
+            SmartDevice::Custom(device) => device.kind(),
         }
     }
 
-    /// Turns the device off (if it supports power control)
-    /// Returns true if operation was successful
-    pub fn turn_off(&mut self) -> bool {
+    /// Returns the wrapped device as a trait object
+    fn as_device(&self) -> &dyn SmartDeviceTrait {
         match self {
-            SmartDevice::Socket(socket) => {
-                socket.turn_off();
-                true
-            }
-            SmartDevice::Thermometer(_) => false,
+            SmartDevice::Thermometer(thermometer) => thermometer,
+            SmartDevice::Socket(socket) => socket,
+            SmartDevice::Light(light) => light,
+            SmartDevice::Thermostat(thermostat) => thermostat,
+            SmartDevice::Hygrometer(hygrometer) => hygrometer,
+            SmartDevice::AirQualityMonitor(monitor) => monitor,
+            SmartDevice::BinarySensor(sensor) => sensor,
+            SmartDevice::Lock(lock) => lock,
+            SmartDevice::Custom(device) => device.as_ref(),
         }
     }
 
-    /// Gets the temperature (if the device is a thermometer)
-    pub fn temperature(&self) -> Option<f32> {
+    /// Returns the wrapped device as a mutable trait object
+    fn as_device_mut(&mut self) -> &mut dyn SmartDeviceTrait {
         match self {
-            SmartDevice::Thermometer(thermometer) => Some(thermometer.temperature()),
-            SmartDevice::Socket(_) => None,
+            SmartDevice::Thermometer(thermometer) => thermometer,
+            SmartDevice::Socket(socket) => socket,
+            SmartDevice::Light(light) => light,
+            SmartDevice::Thermostat(thermostat) => thermostat,
+            SmartDevice::Hygrometer(hygrometer) => hygrometer,
+            SmartDevice::AirQualityMonitor(monitor) => monitor,
+            SmartDevice::BinarySensor(sensor) => sensor,
+            SmartDevice::Lock(lock) => lock,
+            SmartDevice::Custom(device) => device.as_mut(),
         }
     }
 
-    /// Gets power consumption (if the device is a socket)
+    /// Returns the set of capabilities the device supports
+    pub fn capabilities(&self) -> Capabilities {
+        Capability::ALL
+            .into_iter()
+            .filter(|capability| self.supports(*capability))
+            .collect()
+    }
+
+    /// Checks if the device supports the given capability
+    pub fn supports(&self, capability: Capability) -> bool {
+        match capability {
+            Capability::PowerControl => self.as_power_control().is_some(),
+            Capability::TemperatureSensor => self.as_temperature_sensor().is_some(),
+            Capability::PowerConsumption => self.as_power_consumption().is_some(),
+            Capability::EnergyMeter => self.as_energy_meter().is_some(),
+            Capability::HumiditySensor => self.as_humidity_sensor().is_some(),
+            Capability::Co2Sensor => self.as_co2_sensor().is_some(),
+            Capability::AirQualityIndex => self.as_air_quality_index().is_some(),
+            Capability::BinarySensor => self.as_binary_sensor().is_some(),
+        }
+    }
+
+    /// Returns the device as a power-controllable device, if supported
+    pub fn as_power_control(&self) -> Option<&dyn PowerControl> {
+        self.as_device().as_power_control()
+    }
+
+    /// Returns the device as a mutable power-controllable device, if supported
+    pub fn as_power_control_mut(&mut self) -> Option<&mut dyn PowerControl> {
+        self.as_device_mut().as_power_control_mut()
+    }
+
+    /// Returns the device as a temperature sensor, if supported
+    pub fn as_temperature_sensor(&self) -> Option<&dyn TemperatureSensor> {
+        self.as_device().as_temperature_sensor()
+    }
+
+    /// Returns the device as a humidity sensor, if supported
+    pub fn as_humidity_sensor(&self) -> Option<&dyn HumiditySensor> {
+        self.as_device().as_humidity_sensor()
+    }
+
+    /// Returns the device as a CO2 sensor, if supported
+    pub fn as_co2_sensor(&self) -> Option<&dyn Co2Sensor> {
+        self.as_device().as_co2_sensor()
+    }
+
+    /// Returns the device as an air quality sensor, if supported
+    pub fn as_air_quality_index(&self) -> Option<&dyn AirQualityIndex> {
+        self.as_device().as_air_quality_index()
+    }
+
+    /// Returns the device as a binary sensor, if supported
+    pub fn as_binary_sensor(&self) -> Option<&dyn BinarySensor> {
+        self.as_device().as_binary_sensor()
+    }
+
+    /// Returns the device as a power consumer, if supported
+    pub fn as_power_consumption(&self) -> Option<&dyn PowerConsumption> {
+        self.as_device().as_power_consumption()
+    }
+
+    /// Returns the device as an energy meter, if supported
+    pub fn as_energy_meter(&self) -> Option<&dyn EnergyMeter> {
+        self.as_device().as_energy_meter()
+    }
+
+    /// Returns the device as a mutable energy meter, if supported
+    pub fn as_energy_meter_mut(&mut self) -> Option<&mut dyn EnergyMeter> {
+        self.as_device_mut().as_energy_meter_mut()
+    }
+
+    /// Builds the error returned when the device lacks a capability
+    pub fn unsupported(&self, capability: Capability) -> DeviceAccessError {
+        DeviceAccessError::UnsupportedCapability(self.name().to_string(), capability)
+    }
+
+    /// Checks if the device supports power control functionality
+    pub fn supports_power_control(&self) -> bool {
+        self.supports(Capability::PowerControl)
+    }
+
+    /// Checks if the device is on (if it supports power control)
+    pub fn is_on(&self) -> Option<bool> {
+        self.as_power_control().map(|device| device.is_on())
+    }
+
+    /// Turns the device on
+    ///
+    /// Fails with [`DeviceAccessError::UnsupportedCapability`] if the device
+    /// cannot be switched, or with the error of a device that could not be
+    /// reached.
+    pub fn turn_on(&mut self) -> Result<(), SmartHomeError> {
+        match self.as_power_control_mut() {
+            Some(device) => device.try_turn_on(),
+            None => Err(self.unsupported(Capability::PowerControl).into()),
+        }
+    }
+
+    /// Turns the device off
+    ///
+    /// Fails with [`DeviceAccessError::UnsupportedCapability`] if the device
+    /// cannot be switched, or with the error of a device that could not be
+    /// reached.
+    pub fn turn_off(&mut self) -> Result<(), SmartHomeError> {
+        match self.as_power_control_mut() {
+            Some(device) => device.try_turn_off(),
+            None => Err(self.unsupported(Capability::PowerControl).into()),
+        }
+    }
+
+    /// Gets the temperature (if the device measures temperature)
+    pub fn temperature(&self) -> Option<f32> {
+        self.as_temperature_sensor()
+            .map(|device| device.temperature())
+    }
+
+    /// Gets the relative humidity in percent (if the device measures humidity)
+    pub fn humidity(&self) -> Option<f32> {
+        self.as_humidity_sensor().map(|device| device.humidity())
+    }
+
+    /// Gets the CO2 concentration in ppm (if the device measures CO2)
+    pub fn co2_ppm(&self) -> Option<f32> {
+        self.as_co2_sensor().map(|device| device.co2_ppm())
+    }
+
+    /// Gets the air quality index (if the device rates the air quality)
+    pub fn air_quality_index(&self) -> Option<u16> {
+        self.as_air_quality_index()
+            .map(|device| device.air_quality_index())
+    }
+
+    /// Checks if the sensor is triggered (if the device is a binary sensor)
+    pub fn is_triggered(&self) -> Option<bool> {
+        self.as_binary_sensor().map(|device| device.is_triggered())
+    }
+
+    /// Gets power consumption (if the device consumes power)
     pub fn power_consumption(&self) -> Option<f32> {
-        match self {
-            SmartDevice::Socket(socket) => Some(socket.power_consumption()),
-            SmartDevice::Thermometer(_) => None,
+        self.as_power_consumption()
+            .map(|device| device.power_consumption())
+    }
+
+    /// Gets the power drawn once switched on (if the device consumes power)
+    pub fn power_when_on(&self) -> Option<f32> {
+        self.as_power_consumption()
+            .map(|device| device.power_when_on())
+    }
+
+    /// Gets the metered energy in watt-hours (if the device meters energy)
+    pub fn energy_wh(&self) -> Option<f64> {
+        self.as_energy_meter().map(|device| device.energy_wh())
+    }
+
+    /// Resets the energy counter of the device
+    ///
+    /// Fails with [`DeviceAccessError::UnsupportedCapability`] if the device
+    /// does not meter energy.
+    pub fn reset_energy(&mut self) -> Result<(), DeviceAccessError> {
+        match self.as_energy_meter_mut() {
+            Some(device) => {
+                device.reset_energy();
+                Ok(())
+            }
+            None => Err(self.unsupported(Capability::EnergyMeter)),
         }
     }
 }
@@ -101,6 +502,7 @@ impl SmartDevice {
 #[cfg(test)]
 mod tests {
     use super::*;
+    use crate::device::custom::tests::Humidifier;
 
     fn create_test_thermometer() -> SmartDevice {
         let thermometer = SmartThermometer::new(String::from("Test Thermometer"), 22.5);
@@ -117,6 +519,54 @@ mod tests {
         SmartDevice::Socket(socket)
     }
 
+    fn create_test_light() -> SmartDevice {
+        let mut light = SmartLight::new(String::from("Test Light"), true, 10.0).with_brightness(60);
+        light.set_color_temperature(Some(3000)).unwrap();
+        SmartDevice::Light(light)
+    }
+
+    fn create_test_thermostat() -> SmartDevice {
+        let thermostat = SmartThermostat::new(String::from("Test Thermostat"), 19.0, 21.0)
+            .with_mode(crate::device::ThermostatMode::Heat)
+            .with_power(1800.0, 0.0);
+        SmartDevice::Thermostat(thermostat)
+    }
+
+    fn create_test_hygrometer() -> SmartDevice {
+        SmartDevice::Hygrometer(SmartHygrometer::new(String::from("Test Hygrometer"), 48.5))
+    }
+
+    fn create_test_air_quality_monitor() -> SmartDevice {
+        SmartDevice::AirQualityMonitor(SmartAirQualityMonitor::new(
+            String::from("Test Monitor"),
+            900.0,
+            55,
+        ))
+    }
+
+    fn create_test_smoke_alarm() -> SmartDevice {
+        let mut sensor = SmartBinarySensor::new(
+            String::from("Test Smoke Alarm"),
+            crate::device::BinarySensorKind::Smoke,
+        );
+        sensor.set_triggered(true);
+        sensor.set_triggered(false);
+        SmartDevice::BinarySensor(sensor)
+    }
+
+    fn create_test_lock() -> SmartDevice {
+        let mut lock = SmartLock::new(String::from("Test Lock"), true);
+        lock.set_pin(
+            String::from("Alice"),
+            crate::device::PinCode::new("2468").unwrap(),
+        );
+        SmartDevice::Lock(lock)
+    }
+
+    fn create_test_custom_off() -> SmartDevice {
+        SmartDevice::custom(Humidifier::new("Test Humidifier", false, 40.0))
+    }
+
     #[test]
     fn test_supports_power_control() {
         struct TestCase {
@@ -136,6 +586,11 @@ mod tests {
                 device: create_test_socket_on(),
                 expected: true,
             },
+            TestCase {
+                name: "Custom device advertising power control should support it",
+                device: create_test_custom_off(),
+                expected: true,
+            },
         ];
 
         for tc in test_cases {
@@ -172,6 +627,11 @@ mod tests {
                 device: create_test_socket_off(),
                 expected: Some(false),
             },
+            TestCase {
+                name: "Custom device OFF should return Some(false)",
+                device: create_test_custom_off(),
+                expected: Some(false),
+            },
         ];
 
         for tc in test_cases {
@@ -198,6 +658,11 @@ mod tests {
                 device: create_test_socket_on(),
                 expected: None,
             },
+            TestCase {
+                name: "Custom device without a sensor should return None",
+                device: create_test_custom_off(),
+                expected: None,
+            },
         ];
 
         for tc in test_cases {
@@ -251,48 +716,65 @@ mod tests {
         struct TestCase {
             name: &'static str,
             device_factory: fn() -> SmartDevice,
-            operation: fn(&mut SmartDevice) -> bool,
+            operation: fn(&mut SmartDevice) -> Result<(), SmartHomeError>,
             expected_result: bool,
             expected_state: Option<bool>,
         }
 
         let test_cases = vec![
             TestCase {
-                name: "Thermometer turn_on should return false and not change state",
+                name: "Thermometer turn_on should fail and not change state",
                 device_factory: create_test_thermometer,
                 operation: |d| d.turn_on(),
                 expected_result: false,
                 expected_state: None,
             },
             TestCase {
-                name: "Thermometer turn_off should return false and not change state",
+                name: "Thermometer turn_off should fail and not change state",
                 device_factory: create_test_thermometer,
                 operation: |d| d.turn_off(),
                 expected_result: false,
                 expected_state: None,
             },
             TestCase {
-                name: "Socket turn_on should return true and change state to ON",
+                name: "Socket turn_on should succeed and change state to ON",
                 device_factory: create_test_socket_off,
                 operation: |d| d.turn_on(),
                 expected_result: true,
                 expected_state: Some(true),
             },
             TestCase {
-                name: "Socket turn_off should return true and change state to OFF",
+                name: "Socket turn_off should succeed and change state to OFF",
                 device_factory: create_test_socket_on,
                 operation: |d| d.turn_off(),
                 expected_result: true,
                 expected_state: Some(false),
             },
+            TestCase {
+                name: "Custom device turn_on should succeed and change state to ON",
+                device_factory: create_test_custom_off,
+                operation: |d| d.turn_on(),
+                expected_result: true,
+                expected_state: Some(true),
+            },
         ];
 
         for tc in test_cases {
             let mut device = (tc.device_factory)();
 
             let result = (tc.operation)(&mut device);
+            if let Err(e) = &result {
+                assert!(
+                    matches!(e, SmartHomeError::Access(DeviceAccessError::UnsupportedCapability(name, Capability::PowerControl))
+                        if name == device.name()),
+                    "Unexpected error {:?} for test: {}",
+                    e,
+                    tc.name
+                );
+            }
             assert_eq!(
-                result, tc.expected_result,
+                result.is_ok(),
+                tc.expected_result,
                 "Operation result incorrect for test: {}",
                 tc.name
             );
@@ -305,4 +787,337 @@ mod tests {
             );
         }
     }
+
+    #[test]
+    fn test_kind_and_report() {
+        struct TestCase {
+            name: &'static str,
+            device: SmartDevice,
+            expected_kind: &'static str,
+            expected_report: &'static str,
+        }
+
+        let test_cases = vec![
+            TestCase {
+                name: "Thermometer",
+                device: create_test_thermometer(),
+                expected_kind: "thermometer",
+                expected_report: "Device: Test Thermometer, Temperature: 22.5°C",
+            },
+            TestCase {
+                name: "Socket",
+                device: create_test_socket_on(),
+                expected_kind: "socket",
+                expected_report: "Device: Test Socket, Status: ON, Power consumption: 100W",
+            },
+            TestCase {
+                name: "Light",
+                device: create_test_light(),
+                expected_kind: "light",
+                expected_report: "Device: Test Light, Status: ON, Brightness: 60%, Color temperature: 3000K, Power consumption: 6W",
+            },
+            TestCase {
+                name: "Thermostat",
+                device: create_test_thermostat(),
+                expected_kind: "thermostat",
+                expected_report: "Device: Test Thermostat, Temperature: 19°C, Setpoint: 21°C, Mode: heat, Action: heating, Power consumption: 1800W",
+            },
+            TestCase {
+                name: "Hygrometer",
+                device: create_test_hygrometer(),
+                expected_kind: "hygrometer",
+                expected_report: "Device: Test Hygrometer, Humidity: 48.5%",
+            },
+            TestCase {
+                name: "Air quality monitor",
+                device: create_test_air_quality_monitor(),
+                expected_kind: "air_quality_monitor",
+                expected_report: "Device: Test Monitor, CO2: 900 ppm, Air quality index: 55",
+            },
+            TestCase {
+                name: "Binary sensor",
+                device: SmartBinarySensor::new(
+                    String::from("Test Motion"),
+                    crate::device::BinarySensorKind::Motion,
+                )
+                .into(),
+                expected_kind: "binary_sensor",
+                expected_report: "Device: Test Motion, Sensor: motion, State: clear",
+            },
+            TestCase {
+                name: "Lock",
+                device: create_test_lock(),
+                expected_kind: "lock",
+                expected_report: "Device: Test Lock, Lock: locked, Users: 1",
+            },
+            TestCase {
+                name: "Custom device",
+                device: create_test_custom_off(),
+                expected_kind: "humidifier",
+                expected_report: "Device: Test Humidifier, Humidifier: OFF",
+            },
+        ];
+
+        for tc in test_cases {
+            assert_eq!(
+                tc.device.kind(),
+                tc.expected_kind,
+                "Failed test: {}",
+                tc.name
+            );
+            assert_eq!(
+                tc.device.report(),
+                tc.expected_report,
+                "Failed test: {}",
+                tc.name
+            );
+        }
+    }
+
+    #[test]
+    fn test_structured_report() {
+        struct TestCase {
+            name: &'static str,
+            device: SmartDevice,
+            expected: DeviceReport,
+        }
+
+        let test_cases = vec![
+            TestCase {
+                name: "Thermometer",
+                device: create_test_thermometer(),
+                expected: DeviceReport {
+                    name: "Test Thermometer".to_string(),
+                    kind: "thermometer".to_string(),
+                    metrics: vec![Metric::number("temperature", 22.5, "°C")],
+                },
+            },
+            TestCase {
+                name: "Socket",
+                device: create_test_socket_on(),
+                expected: DeviceReport {
+                    name: "Test Socket".to_string(),
+                    kind: "socket".to_string(),
+                    metrics: vec![
+                        Metric::flag("is_on", true),
+                        Metric::number("power", 100.0, "W"),
+                    ],
+                },
+            },
+            TestCase {
+                name: "Light",
+                device: create_test_light(),
+                expected: DeviceReport {
+                    name: "Test Light".to_string(),
+                    kind: "light".to_string(),
+                    metrics: vec![
+                        Metric::flag("is_on", true),
+                        Metric::number("power", 6.0, "W"),
+                        Metric::number("brightness", 60.0, "%"),
+                        Metric::number("color_temperature", 3000.0, "K"),
+                    ],
+                },
+            },
+            TestCase {
+                name: "Thermostat",
+                device: create_test_thermostat(),
+                expected: DeviceReport {
+                    name: "Test Thermostat".to_string(),
+                    kind: "thermostat".to_string(),
+                    metrics: vec![
+                        Metric::number("power", 1800.0, "W"),
+                        Metric::number("temperature", 19.0, "°C"),
+                        Metric::number("setpoint", 21.0, "°C"),
+                        Metric::text("mode", "heat"),
+                        Metric::text("action", "heating"),
+                    ],
+                },
+            },
+            TestCase {
+                name: "Hygrometer",
+                device: create_test_hygrometer(),
+                expected: DeviceReport {
+                    name: "Test Hygrometer".to_string(),
+                    kind: "hygrometer".to_string(),
+                    metrics: vec![Metric::number("humidity", 48.5, "%")],
+                },
+            },
+            TestCase {
+                name: "Air quality monitor",
+                device: create_test_air_quality_monitor(),
+                expected: DeviceReport {
+                    name: "Test Monitor".to_string(),
+                    kind: "air_quality_monitor".to_string(),
+                    metrics: vec![
+                        Metric::number("co2", 900.0, "ppm"),
+                        Metric::number("air_quality_index", 55.0, "AQI"),
+                    ],
+                },
+            },
+            TestCase {
+                name: "Binary sensor",
+                device: SmartBinarySensor::new(
+                    String::from("Test Leak"),
+                    crate::device::BinarySensorKind::Leak,
+                )
+                .into(),
+                expected: DeviceReport {
+                    name: "Test Leak".to_string(),
+                    kind: "binary_sensor".to_string(),
+                    metrics: vec![
+                        Metric::flag("triggered", false),
+                        Metric::text("sensor", "leak"),
+                    ],
+                },
+            },
+            TestCase {
+                name: "Lock",
+                device: create_test_lock(),
+                expected: DeviceReport {
+                    name: "Test Lock".to_string(),
+                    kind: "lock".to_string(),
+                    metrics: vec![
+                        Metric::text("lock_state", "locked"),
+                        Metric::flag("locked_out", false),
+                    ],
+                },
+            },
+            TestCase {
+                name: "Custom device",
+                device: create_test_custom_off(),
+                expected: DeviceReport {
+                    name: "Test Humidifier".to_string(),
+                    kind: "humidifier".to_string(),
+                    metrics: vec![
+                        Metric::flag("is_on", false),
+                        Metric::number("power", 0.0, "W"),
+                    ],
+                },
+            },
+        ];
+
+        for tc in test_cases {
+            assert_eq!(
+                tc.device.structured_report(),
+                tc.expected,
+                "Failed test: {}",
+                tc.name
+            );
+        }
+    }
+
+    #[test]
+    fn test_capabilities() {
+        struct TestCase {
+            name: &'static str,
+            device: SmartDevice,
+            expected: Vec<Capability>,
+        }
+
+        let test_cases = vec![
+            TestCase {
+                name: "Thermometer only senses temperature",
+                device: create_test_thermometer(),
+                expected: vec![Capability::TemperatureSensor],
+            },
+            TestCase {
+                name: "Socket switches, consumes power and meters energy",
+                device: create_test_socket_off(),
+                expected: vec![
+                    Capability::PowerControl,
+                    Capability::PowerConsumption,
+                    Capability::EnergyMeter,
+                ],
+            },
+            TestCase {
+                name: "Light switches and consumes power",
+                device: create_test_light(),
+                expected: vec![Capability::PowerControl, Capability::PowerConsumption],
+            },
+            TestCase {
+                name: "Thermostat senses temperature and consumes power",
+                device: create_test_thermostat(),
+                expected: vec![Capability::TemperatureSensor, Capability::PowerConsumption],
+            },
+            TestCase {
+                name: "Hygrometer only senses humidity",
+                device: create_test_hygrometer(),
+                expected: vec![Capability::HumiditySensor],
+            },
+            TestCase {
+                name: "Air quality monitor senses CO2 and rates the air",
+                device: create_test_air_quality_monitor(),
+                expected: vec![Capability::Co2Sensor, Capability::AirQualityIndex],
+            },
+            TestCase {
+                name: "Smoke alarm is a binary sensor",
+                device: create_test_smoke_alarm(),
+                expected: vec![Capability::BinarySensor],
+            },
+            TestCase {
+                name: "Lock has no generic capability",
+                device: create_test_lock(),
+                expected: vec![],
+            },
+            TestCase {
+                name: "Custom device advertises its own capabilities",
+                device: create_test_custom_off(),
+                expected: vec![Capability::PowerControl, Capability::PowerConsumption],
+            },
+        ];
+
+        for tc in test_cases {
+            let capabilities = tc.device.capabilities();
+            assert_eq!(
+                capabilities.iter().collect::<Vec<_>>(),
+                tc.expected,
+                "Failed test: {}",
+                tc.name
+            );
+            for capability in Capability::ALL {
+                assert_eq!(
+                    tc.device.supports(capability),
+                    capabilities.contains(capability),
+                    "Test case '{}': supports() disagrees for {}",
+                    tc.name,
+                    capability
+                );
+            }
+        }
+    }
+
+    #[test]
+    fn test_typed_accessors() {
+        let mut socket = create_test_socket_off();
+        socket.as_power_control_mut().unwrap().turn_on();
+        assert_eq!(socket.as_power_control().map(|d| d.is_on()), Some(true));
+        assert_eq!(
+            socket.as_power_consumption().map(|d| d.power_consumption()),
+            Some(100.0)
+        );
+        assert!(socket.as_temperature_sensor().is_none());
+
+        let mut thermometer = create_test_thermometer();
+        assert!(thermometer.as_power_control_mut().is_none());
+        assert_eq!(
+            thermometer.as_temperature_sensor().map(|d| d.temperature()),
+            Some(22.5)
+        );
+        assert_eq!(thermometer.humidity(), None);
+
+        let monitor = create_test_air_quality_monitor();
+        assert_eq!(create_test_hygrometer().humidity(), Some(48.5));
+        assert_eq!(monitor.co2_ppm(), Some(900.0));
+        assert_eq!(monitor.air_quality_index(), Some(55));
+        assert_eq!(monitor.is_triggered(), None);
+
+        let smoke_alarm = create_test_smoke_alarm();
+        assert_eq!(smoke_alarm.is_triggered(), Some(false));
+        assert!(
+            smoke_alarm
+                .as_binary_sensor()
+                .and_then(|d| d.last_triggered())
+                .is_some()
+        );
+    }
 }
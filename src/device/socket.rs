@@ -1,15 +1,33 @@
 use crate::Reporter;
-use crate::device::device_trait::{PowerConsumption, PowerControl, SmartDeviceTrait};
+use crate::clock::{Clock, SystemClock};
+use crate::device::device_trait::{EnergyMeter, PowerConsumption, PowerControl, SmartDeviceTrait};
+use chrono::NaiveDateTime;
+use serde::{Deserialize, Serialize};
+use std::fmt;
+use std::sync::Arc;
 
 /// Smart socket device implementation
 ///
 /// Controls a smart power socket that can be turned on/off
-/// and provides power consumption metrics
-#[derive(Debug)]
+/// and provides power consumption metrics. Energy is metered by integrating
+/// the power draw over the time the socket is on; the counter is not saved
+/// with the house.
+#[derive(Serialize, Deserialize)]
 pub struct SmartSocket {
     name: String,
     is_on: bool,
     power_consumption: f32,
+    /// Energy used up to `metered_until`, in watt-hours
+    #[serde(skip)]
+    energy_wh: f64,
+    #[serde(skip, default = "now")]
+    metered_until: NaiveDateTime,
+    #[serde(skip, default = "SystemClock::shared")]
+    clock: Arc<dyn Clock>,
+}
+
+fn now() -> NaiveDateTime {
+    SystemClock.now()
 }
 
 impl SmartSocket {
@@ -19,9 +37,21 @@ impl SmartSocket {
             name,
             is_on,
             power_consumption,
+            energy_wh: 0.0,
+            metered_until: now(),
+            clock: SystemClock::shared(),
         }
     }
 
+    /// Meters energy with the given clock instead of the system clock
+    ///
+    /// Metering restarts from the current time of the new clock.
+    pub fn with_clock<C: Clock + 'static>(mut self, clock: C) -> Self {
+        self.clock = Arc::new(clock);
+        self.metered_until = self.clock.now();
+        self
+    }
+
     /// Calculates the active power consumption based on the current state
     fn calculate_active_power(&self) -> f32 {
         if self.is_on {
@@ -30,12 +60,57 @@ impl SmartSocket {
             0.0
         }
     }
+
+    /// Returns the energy used since `metered_until`, in watt-hours
+    fn pending_energy_wh(&self, now: NaiveDateTime) -> f64 {
+        // A clock moved backwards adds nothing
+        let millis = (now - self.metered_until).num_milliseconds().max(0);
+        f64::from(self.calculate_active_power()) * millis as f64 / 3_600_000.0
+    }
+
+    /// Adds the energy used so far to the counter, before the power draw changes
+    fn meter(&mut self) {
+        let now = self.clock.now();
+        self.energy_wh += self.pending_energy_wh(now);
+        self.metered_until = now;
+    }
+}
+
+impl fmt::Debug for SmartSocket {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        f.debug_struct("SmartSocket")
+            .field("name", &self.name)
+            .field("is_on", &self.is_on)
+            .field("power_consumption", &self.power_consumption)
+            .field("energy_wh", &self.energy_wh())
+            .finish()
+    }
 }
 
 impl SmartDeviceTrait for SmartSocket {
     fn name(&self) -> &str {
         &self.name
     }
+
+    fn as_power_control(&self) -> Option<&dyn PowerControl> {
+        Some(self)
+    }
+
+    fn as_power_control_mut(&mut self) -> Option<&mut dyn PowerControl> {
+        Some(self)
+    }
+
+    fn as_power_consumption(&self) -> Option<&dyn PowerConsumption> {
+        Some(self)
+    }
+
+    fn as_energy_meter(&self) -> Option<&dyn EnergyMeter> {
+        Some(self)
+    }
+
+    fn as_energy_meter_mut(&mut self) -> Option<&mut dyn EnergyMeter> {
+        Some(self)
+    }
 }
 
 impl PowerControl for SmartSocket {
@@ -44,10 +119,12 @@ impl PowerControl for SmartSocket {
     }
 
     fn turn_on(&mut self) {
+        self.meter();
         self.is_on = true;
     }
 
     fn turn_off(&mut self) {
+        self.meter();
         self.is_on = false;
     }
 }
@@ -56,6 +133,21 @@ impl PowerConsumption for SmartSocket {
     fn power_consumption(&self) -> f32 {
         self.calculate_active_power()
     }
+
+    fn power_when_on(&self) -> f32 {
+        self.power_consumption
+    }
+}
+
+impl EnergyMeter for SmartSocket {
+    fn energy_wh(&self) -> f64 {
+        self.energy_wh + self.pending_energy_wh(self.clock.now())
+    }
+
+    fn reset_energy(&mut self) {
+        self.energy_wh = 0.0;
+        self.metered_until = self.clock.now();
+    }
 }
 
 impl Reporter for SmartSocket {
@@ -374,4 +466,96 @@ mod tests {
             );
         }
     }
+
+    #[test]
+    fn test_energy_metering() {
+        use crate::clock::ManualClock;
+        use chrono::NaiveDate;
+        use std::time::Duration;
+
+        enum Step {
+            Wait(u64),
+            TurnOn,
+            TurnOff,
+            Reset,
+        }
+
+        struct TestCase {
+            name: &'static str,
+            is_on: bool,
+            power: f32,
+            steps: Vec<Step>,
+            expected_wh: f64,
+        }
+
+        let test_cases = vec![
+            TestCase {
+                name: "On for an hour",
+                is_on: true,
+                power: 1000.0,
+                steps: vec![Step::Wait(60)],
+                expected_wh: 1000.0,
+            },
+            TestCase {
+                name: "Off time is not metered",
+                is_on: true,
+                power: 1000.0,
+                steps: vec![Step::Wait(30), Step::TurnOff, Step::Wait(60)],
+                expected_wh: 500.0,
+            },
+            TestCase {
+                name: "Switched on twice",
+                is_on: false,
+                power: 2000.0,
+                steps: vec![
+                    Step::Wait(10),
+                    Step::TurnOn,
+                    Step::Wait(15),
+                    Step::TurnOff,
+                    Step::Wait(5),
+                    Step::TurnOn,
+                    Step::Wait(15),
+                ],
+                expected_wh: 1000.0,
+            },
+            TestCase {
+                name: "Reset counter",
+                is_on: true,
+                power: 1200.0,
+                steps: vec![Step::Wait(30), Step::Reset, Step::Wait(15)],
+                expected_wh: 300.0,
+            },
+        ];
+
+        for tc in test_cases {
+            let start = NaiveDate::from_ymd_opt(2024, 1, 1)
+                .unwrap()
+                .and_hms_opt(0, 0, 0)
+                .unwrap();
+            let clock = Arc::new(ManualClock::new(start));
+            let mut socket = SmartSocket::new("Heater".to_string(), tc.is_on, tc.power)
+                .with_clock(Arc::clone(&clock));
+
+            for step in tc.steps {
+                match step {
+                    Step::Wait(minutes) => clock.advance(Duration::from_secs(minutes * 60)),
+                    Step::TurnOn => socket.turn_on(),
+                    Step::TurnOff => socket.turn_off(),
+                    Step::Reset => socket.reset_energy(),
+                }
+            }
+
+            assert!(
+                (socket.energy_wh() - tc.expected_wh).abs() < 1e-9,
+                "Failed test: {}: {} Wh",
+                tc.name,
+                socket.energy_wh()
+            );
+            assert!(
+                (socket.energy_kwh() - tc.expected_wh / 1000.0).abs() < 1e-12,
+                "Failed test: {}",
+                tc.name
+            );
+        }
+    }
 }
@@ -1,19 +1,86 @@
 use crate::Reporter;
+use crate::clock::{Clock, SystemClock};
 use crate::device::device_trait::{SmartDeviceTrait, TemperatureSensor};
+use crate::device::history::{TemperatureHistory, TemperatureStats};
+use serde::{Deserialize, Serialize};
+use std::fmt;
+use std::sync::Arc;
+use std::time::Duration;
 
 /// Smart thermometer device implementation
 ///
-/// Provides temperature readings from a smart home thermometer
-#[derive(Debug)]
+/// Provides temperature readings from a smart home thermometer and keeps a
+/// bounded history of the readings set through
+/// [`set_temperature`](SmartThermometer::set_temperature). The history is not
+/// saved with the house.
+#[derive(Serialize, Deserialize)]
 pub struct SmartThermometer {
     name: String,
     temperature: f32,
+    #[serde(skip)]
+    history: TemperatureHistory,
+    #[serde(skip, default = "SystemClock::shared")]
+    clock: Arc<dyn Clock>,
 }
 
 impl SmartThermometer {
     /// Creates a new thermometer with the specified name and temperature
     pub fn new(name: String, temperature: f32) -> Self {
-        Self { name, temperature }
+        Self {
+            name,
+            temperature,
+            history: TemperatureHistory::default(),
+            clock: SystemClock::shared(),
+        }
+    }
+
+    /// Timestamps readings with the given clock instead of the system clock
+    pub fn with_clock<C: Clock + 'static>(mut self, clock: C) -> Self {
+        self.clock = Arc::new(clock);
+        self
+    }
+
+    /// Keeps at most `capacity` readings in the history, dropping the oldest ones
+    pub fn with_history_capacity(mut self, capacity: usize) -> Self {
+        let mut history = TemperatureHistory::new(capacity);
+        for reading in self.history.readings() {
+            history.record(reading.at, reading.temperature);
+        }
+        self.history = history;
+        self
+    }
+
+    /// Updates the current temperature reading and records it in the history
+    pub fn set_temperature(&mut self, temperature: f32) {
+        self.temperature = temperature;
+        self.history.record(self.clock.now(), temperature);
+    }
+
+    /// Returns the history of readings
+    pub fn history(&self) -> &TemperatureHistory {
+        &self.history
+    }
+
+    /// Returns the statistics of the readings taken within the last `period`
+    pub fn stats_over(&self, period: Duration) -> Option<TemperatureStats> {
+        let now = self.clock.now();
+        let since = chrono::TimeDelta::from_std(period)
+            .ok()
+            .and_then(|period| now.checked_sub_signed(period));
+        match since {
+            Some(since) => self.history.stats(since..=now),
+            None => self.history.stats(..=now),
+        }
+    }
+}
+
+impl fmt::Debug for SmartThermometer {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        f.debug_struct("SmartThermometer")
+            .field("name", &self.name)
+            .field("temperature", &self.temperature)
+            .field("history", &self.history.len())
+            .finish()
     }
 }
 
@@ -21,6 +88,10 @@ impl SmartDeviceTrait for SmartThermometer {
     fn name(&self) -> &str {
         &self.name
     }
+
+    fn as_temperature_sensor(&self) -> Option<&dyn TemperatureSensor> {
+        Some(self)
+    }
 }
 
 impl TemperatureSensor for SmartThermometer {
@@ -195,4 +266,43 @@ mod tests {
             );
         }
     }
+
+    #[test]
+    fn test_temperature_history() {
+        use crate::clock::ManualClock;
+        use chrono::NaiveDate;
+
+        let start = NaiveDate::from_ymd_opt(2024, 1, 1)
+            .unwrap()
+            .and_hms_opt(12, 0, 0)
+            .unwrap();
+        let clock = Arc::new(ManualClock::new(start));
+        let mut thermometer = SmartThermometer::new("Test Sensor".to_string(), 20.0)
+            .with_clock(Arc::clone(&clock))
+            .with_history_capacity(3);
+
+        for temperature in [18.0, 21.0, 24.0, 19.0] {
+            clock.advance(Duration::from_secs(10 * 60));
+            thermometer.set_temperature(temperature);
+        }
+
+        assert_eq!(thermometer.temperature(), 19.0);
+        assert_eq!(thermometer.history().len(), 3);
+        assert_eq!(
+            thermometer.stats_over(Duration::from_secs(15 * 60)),
+            Some(TemperatureStats {
+                count: 2,
+                min: 19.0,
+                max: 24.0,
+                mean: 21.5,
+            })
+        );
+        assert_eq!(
+            thermometer
+                .stats_over(Duration::from_secs(24 * 60 * 60))
+                .map(|stats| stats.count),
+            Some(3)
+        );
+        assert!(format!("{:?}", thermometer).contains("history: 3"));
+    }
 }
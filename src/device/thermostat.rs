@@ -0,0 +1,354 @@
+use crate::Reporter;
+use crate::device::device_trait::{PowerConsumption, SmartDeviceTrait, TemperatureSensor};
+use crate::error::SmartHomeError;
+use serde::{Deserialize, Serialize};
+use std::fmt;
+
+/// Hysteresis used unless another one is chosen, in °C
+pub const DEFAULT_HYSTERESIS: f32 = 0.5;
+
+/// What a thermostat is allowed to do to reach its setpoint
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Default, Serialize, Deserialize)]
+#[serde(rename_all = "lowercase")]
+pub enum ThermostatMode {
+    /// Neither heats nor cools
+    #[default]
+    Off,
+    /// Only heats
+    Heat,
+    /// Only cools
+    Cool,
+    /// Heats or cools, whichever is needed
+    Auto,
+}
+
+impl ThermostatMode {
+    fn heats(self) -> bool {
+        matches!(self, ThermostatMode::Heat | ThermostatMode::Auto)
+    }
+
+    fn cools(self) -> bool {
+        matches!(self, ThermostatMode::Cool | ThermostatMode::Auto)
+    }
+}
+
+impl fmt::Display for ThermostatMode {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        let name = match self {
+            ThermostatMode::Off => "off",
+            ThermostatMode::Heat => "heat",
+            ThermostatMode::Cool => "cool",
+            ThermostatMode::Auto => "auto",
+        };
+        f.write_str(name)
+    }
+}
+
+/// What a thermostat is currently doing
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Default, Serialize, Deserialize)]
+#[serde(rename_all = "lowercase")]
+pub enum HvacAction {
+    #[default]
+    Idle,
+    Heating,
+    Cooling,
+}
+
+impl fmt::Display for HvacAction {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        let name = match self {
+            HvacAction::Idle => "idle",
+            HvacAction::Heating => "heating",
+            HvacAction::Cooling => "cooling",
+        };
+        f.write_str(name)
+    }
+}
+
+/// Thermostat driving a heating and cooling system
+///
+/// The thermostat measures the temperature itself and decides after every
+/// change whether to heat or cool. It starts heating once the temperature
+/// drops more than the hysteresis below the setpoint and keeps heating until
+/// the setpoint is reached; cooling works the same way above the setpoint.
+#[derive(Debug, Clone, PartialEq, Serialize, Deserialize)]
+pub struct SmartThermostat {
+    name: String,
+    temperature: f32,
+    setpoint: f32,
+    #[serde(default)]
+    mode: ThermostatMode,
+    #[serde(default = "default_hysteresis")]
+    hysteresis: f32,
+    /// Power drawn while heating, in watts
+    #[serde(default)]
+    heating_power: f32,
+    /// Power drawn while cooling, in watts
+    #[serde(default)]
+    cooling_power: f32,
+    /// Saved so that a reloaded thermostat stays within its hysteresis band
+    #[serde(default)]
+    action: HvacAction,
+}
+
+fn default_hysteresis() -> f32 {
+    DEFAULT_HYSTERESIS
+}
+
+impl SmartThermostat {
+    /// Creates a thermostat that is off, with the given reading and setpoint in °C
+    pub fn new(name: String, temperature: f32, setpoint: f32) -> Self {
+        Self {
+            name,
+            temperature,
+            setpoint,
+            mode: ThermostatMode::Off,
+            hysteresis: DEFAULT_HYSTERESIS,
+            heating_power: 0.0,
+            cooling_power: 0.0,
+            action: HvacAction::Idle,
+        }
+    }
+
+    /// Sets the initial mode
+    pub fn with_mode(mut self, mode: ThermostatMode) -> Self {
+        self.set_mode(mode);
+        self
+    }
+
+    /// Sets the initial hysteresis in °C; negative values are raised to zero
+    pub fn with_hysteresis(mut self, hysteresis: f32) -> Self {
+        self.hysteresis = hysteresis.max(0.0);
+        self.regulate();
+        self
+    }
+
+    /// Sets the power drawn while heating and while cooling, in watts
+    pub fn with_power(mut self, heating_power: f32, cooling_power: f32) -> Self {
+        self.heating_power = heating_power;
+        self.cooling_power = cooling_power;
+        self
+    }
+
+    /// Returns the target temperature in °C
+    pub fn setpoint(&self) -> f32 {
+        self.setpoint
+    }
+
+    /// Sets the target temperature in °C
+    pub fn set_setpoint(&mut self, setpoint: f32) -> Result<(), SmartHomeError> {
+        if !setpoint.is_finite() {
+            return Err(SmartHomeError::invalid_value(
+                "setpoint",
+                "must be a finite number",
+            ));
+        }
+        self.setpoint = setpoint;
+        self.regulate();
+        Ok(())
+    }
+
+    /// Returns the mode
+    pub fn mode(&self) -> ThermostatMode {
+        self.mode
+    }
+
+    /// Sets the mode
+    pub fn set_mode(&mut self, mode: ThermostatMode) {
+        self.mode = mode;
+        self.regulate();
+    }
+
+    /// Returns the hysteresis in °C
+    pub fn hysteresis(&self) -> f32 {
+        self.hysteresis
+    }
+
+    /// Sets the hysteresis in °C
+    pub fn set_hysteresis(&mut self, hysteresis: f32) -> Result<(), SmartHomeError> {
+        if !hysteresis.is_finite() || hysteresis < 0.0 {
+            return Err(SmartHomeError::invalid_value(
+                "hysteresis",
+                "must be a finite, non-negative number",
+            ));
+        }
+        self.hysteresis = hysteresis;
+        self.regulate();
+        Ok(())
+    }
+
+    /// Updates the temperature reading and reacts to it
+    pub fn set_temperature(&mut self, temperature: f32) {
+        self.temperature = temperature;
+        self.regulate();
+    }
+
+    /// Returns whether the thermostat is heating, cooling or idle
+    pub fn action(&self) -> HvacAction {
+        self.action
+    }
+
+    /// Decides whether to heat or cool from the reading, setpoint and mode
+    fn regulate(&mut self) {
+        let (temperature, setpoint) = (self.temperature, self.setpoint);
+        self.action = match self.action {
+            HvacAction::Heating if self.mode.heats() && temperature < setpoint => {
+                HvacAction::Heating
+            }
+            HvacAction::Cooling if self.mode.cools() && temperature > setpoint => {
+                HvacAction::Cooling
+            }
+            _ if self.mode.heats() && temperature < setpoint - self.hysteresis => {
+                HvacAction::Heating
+            }
+            _ if self.mode.cools() && temperature > setpoint + self.hysteresis => {
+                HvacAction::Cooling
+            }
+            _ => HvacAction::Idle,
+        };
+    }
+}
+
+impl SmartDeviceTrait for SmartThermostat {
+    fn name(&self) -> &str {
+        &self.name
+    }
+
+    fn as_temperature_sensor(&self) -> Option<&dyn TemperatureSensor> {
+        Some(self)
+    }
+
+    fn as_power_consumption(&self) -> Option<&dyn PowerConsumption> {
+        Some(self)
+    }
+}
+
+impl TemperatureSensor for SmartThermostat {
+    fn temperature(&self) -> f32 {
+        self.temperature
+    }
+}
+
+impl PowerConsumption for SmartThermostat {
+    fn power_consumption(&self) -> f32 {
+        match self.action {
+            HvacAction::Idle => 0.0,
+            HvacAction::Heating => self.heating_power,
+            HvacAction::Cooling => self.cooling_power,
+        }
+    }
+}
+
+impl Reporter for SmartThermostat {
+    fn report(&self) -> String {
+        format!(
+            "Device: {}, Temperature: {}°C, Setpoint: {}°C, Mode: {}, Action: {}, Power consumption: {}W",
+            self.name(),
+            self.temperature,
+            self.setpoint,
+            self.mode,
+            self.action,
+            self.power_consumption()
+        )
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    fn create_thermostat(mode: ThermostatMode) -> SmartThermostat {
+        SmartThermostat::new("Hall".to_string(), 21.0, 21.0)
+            .with_mode(mode)
+            .with_power(2000.0, 1500.0)
+    }
+
+    #[test]
+    fn test_regulation() {
+        struct TestCase {
+            name: &'static str,
+            mode: ThermostatMode,
+            temperatures: Vec<f32>,
+            expected_actions: Vec<HvacAction>,
+        }
+
+        use HvacAction::{Cooling, Heating, Idle};
+        let test_cases = vec![
+            TestCase {
+                name: "Heats below the band and stops at the setpoint",
+                mode: ThermostatMode::Heat,
+                temperatures: vec![20.7, 20.4, 20.8, 21.0, 20.7],
+                expected_actions: vec![Idle, Heating, Heating, Idle, Idle],
+            },
+            TestCase {
+                name: "Cools above the band and stops at the setpoint",
+                mode: ThermostatMode::Cool,
+                temperatures: vec![21.3, 21.6, 21.2, 21.0, 21.3],
+                expected_actions: vec![Idle, Cooling, Cooling, Idle, Idle],
+            },
+            TestCase {
+                name: "Heat mode never cools",
+                mode: ThermostatMode::Heat,
+                temperatures: vec![25.0],
+                expected_actions: vec![Idle],
+            },
+            TestCase {
+                name: "Auto mode heats and cools",
+                mode: ThermostatMode::Auto,
+                temperatures: vec![20.0, 21.0, 22.0, 21.2, 20.9],
+                expected_actions: vec![Heating, Idle, Cooling, Cooling, Idle],
+            },
+            TestCase {
+                name: "Off does nothing",
+                mode: ThermostatMode::Off,
+                temperatures: vec![10.0, 30.0],
+                expected_actions: vec![Idle, Idle],
+            },
+        ];
+
+        for tc in test_cases {
+            let mut thermostat = create_thermostat(tc.mode);
+            let actions: Vec<HvacAction> = tc
+                .temperatures
+                .iter()
+                .map(|temperature| {
+                    thermostat.set_temperature(*temperature);
+                    thermostat.action()
+                })
+                .collect();
+            assert_eq!(actions, tc.expected_actions, "Failed test: {}", tc.name);
+        }
+    }
+
+    #[test]
+    fn test_settings_and_power() {
+        let mut thermostat = create_thermostat(ThermostatMode::Heat);
+        assert_eq!(thermostat.power_consumption(), 0.0);
+
+        thermostat.set_setpoint(23.0).unwrap();
+        assert_eq!(thermostat.action(), HvacAction::Heating);
+        assert_eq!(thermostat.power_consumption(), 2000.0);
+        assert_eq!(
+            thermostat.report(),
+            "Device: Hall, Temperature: 21°C, Setpoint: 23°C, Mode: heat, Action: heating, Power consumption: 2000W"
+        );
+
+        thermostat.set_mode(ThermostatMode::Off);
+        assert_eq!(thermostat.power_consumption(), 0.0);
+
+        thermostat.set_mode(ThermostatMode::Cool);
+        thermostat.set_setpoint(20.0).unwrap();
+        assert_eq!(thermostat.power_consumption(), 1500.0);
+
+        assert!(thermostat.set_setpoint(f32::NAN).is_err());
+        assert!(thermostat.set_hysteresis(-1.0).is_err());
+        thermostat.set_hysteresis(2.0).unwrap();
+        assert_eq!(thermostat.hysteresis(), 2.0);
+        assert_eq!(
+            SmartThermostat::new("Hall".to_string(), 21.0, 21.0)
+                .with_hysteresis(-1.0)
+                .hysteresis(),
+            0.0
+        );
+    }
+}
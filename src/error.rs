@@ -1,36 +1,20 @@
-// Original AccessError kept for backward compatibility
-#[derive(Debug)]
-pub struct AccessError {
-    /// Type of resource being accessed (e.g., "Room", "Device")
-    pub resource_type: String,
-    /// Index that was requested
-    pub requested_index: usize,
-    /// Total number of available resources
-    pub total_count: usize,
-}
-
-impl std::fmt::Display for AccessError {
-    fn fmt(&self, f: &mut std::fmt::Formatter<'_>) -> std::fmt::Result {
-        write!(
-            f,
-            "{} index {} is out of bounds. Total {}: {}",
-            self.resource_type,
-            self.requested_index,
-            self.resource_type.to_lowercase(),
-            self.total_count,
-        )
-    }
-}
+use crate::device::Capability;
+use crate::network::Response;
+use crate::storage::HouseFormat;
+use chrono::NaiveDateTime;
+use std::path::PathBuf;
 
-impl std::error::Error for AccessError {}
-
-#[derive(Debug)]
+#[derive(Debug, Clone, PartialEq)]
 pub enum DeviceAccessError {
     RoomNotFound(String),
 
     /// Device not found in a specific room
     /// Example: DeviceNotFound(device_name, room_name)
     DeviceNotFound(String, String),
+
+    /// Device exists but lacks the capability needed for the operation
+    /// Example: UnsupportedCapability(device_name, Capability::PowerControl)
+    UnsupportedCapability(String, Capability),
 }
 
 impl std::fmt::Display for DeviceAccessError {
@@ -46,119 +30,372 @@ impl std::fmt::Display for DeviceAccessError {
                     device_name, room_name
                 )
             }
+            DeviceAccessError::UnsupportedCapability(device_name, capability) => {
+                write!(
+                    f,
+                    "Device '{}' does not support {}",
+                    device_name, capability
+                )
+            }
         }
     }
 }
 
 impl std::error::Error for DeviceAccessError {}
 
-#[cfg(test)]
-mod tests {
-    use super::*;
-    use std::error::Error;
+/// Errors raised when operating a [`SmartLock`](crate::SmartLock)
+#[derive(Debug, Clone, PartialEq)]
+pub enum LockError {
+    /// The bolt is jammed and cannot move
+    /// Example: Jammed(lock_name)
+    Jammed(String),
 
-    #[test]
-    fn test_access_error_creation() {
-        let error = AccessError {
-            resource_type: "Room".to_string(),
-            requested_index: 5,
-            total_count: 3,
-        };
+    /// The PIN belongs to no user allowed in at this time
+    /// Example: InvalidPin(lock_name)
+    InvalidPin(String),
+
+    /// Too many wrong PINs were entered; unlocking is refused until the given time
+    /// Example: LockedOut(lock_name, until)
+    LockedOut(String, NaiveDateTime),
+}
 
-        assert_eq!(error.resource_type, "Room");
-        assert_eq!(error.requested_index, 5);
-        assert_eq!(error.total_count, 3);
+impl std::fmt::Display for LockError {
+    fn fmt(&self, f: &mut std::fmt::Formatter<'_>) -> std::fmt::Result {
+        match self {
+            LockError::Jammed(lock_name) => write!(f, "Lock '{}' is jammed", lock_name),
+            LockError::InvalidPin(lock_name) => {
+                write!(f, "Wrong or expired PIN for lock '{}'", lock_name)
+            }
+            LockError::LockedOut(lock_name, until) => write!(
+                f,
+                "Lock '{}' refuses PINs until {} after too many failed attempts",
+                lock_name, until
+            ),
+        }
     }
+}
 
-    #[test]
-    fn test_error_trait_implementation() {
-        let error = AccessError {
-            resource_type: "Room".to_string(),
-            requested_index: 5,
-            total_count: 3,
-        };
+impl std::error::Error for LockError {}
+
+/// Errors raised while talking to a networked device
+#[derive(Debug)]
+pub enum ProtocolError {
+    /// Underlying transport failure
+    Io(std::io::Error),
+
+    /// The command byte is not part of the protocol
+    UnknownCommand(u8),
+
+    /// The response tag byte is not part of the protocol
+    UnknownResponse(u8),
 
-        // Check that our error type implements the Error trait
-        let _: &dyn Error = &error;
+    /// The peer answered with a response that does not match the command
+    UnexpectedResponse(Response),
+
+    /// The peer refused the command with the given error code
+    Rejected(u8),
+
+    /// A datagram of the given size could not be decoded
+    MalformedDatagram(usize),
+
+    /// The device has not sent a reading yet
+    NoReading,
+
+    /// The device did not answer within the given time
+    Timeout(std::time::Duration),
+
+    /// The request was cancelled before the device answered
+    Cancelled,
+}
+
+impl std::fmt::Display for ProtocolError {
+    fn fmt(&self, f: &mut std::fmt::Formatter<'_>) -> std::fmt::Result {
+        match self {
+            ProtocolError::Io(e) => write!(f, "Transport error: {}", e),
+            ProtocolError::UnknownCommand(byte) => {
+                write!(f, "Unknown command byte 0x{:02X}", byte)
+            }
+            ProtocolError::UnknownResponse(byte) => {
+                write!(f, "Unknown response tag 0x{:02X}", byte)
+            }
+            ProtocolError::UnexpectedResponse(response) => {
+                write!(f, "Unexpected response {:?}", response)
+            }
+            ProtocolError::Rejected(code) => {
+                write!(f, "Command rejected with error code 0x{:02X}", code)
+            }
+            ProtocolError::MalformedDatagram(size) => {
+                write!(f, "Malformed datagram of {} bytes", size)
+            }
+            ProtocolError::NoReading => write!(f, "No reading received yet"),
+            ProtocolError::Timeout(timeout) => {
+                write!(f, "No response within {} ms", timeout.as_millis())
+            }
+            ProtocolError::Cancelled => write!(f, "Request cancelled"),
+        }
     }
+}
 
-    #[test]
-    fn test_debug_implementation() {
-        let error = AccessError {
-            resource_type: "Room".to_string(),
-            requested_index: 5,
-            total_count: 3,
-        };
+impl std::error::Error for ProtocolError {
+    fn source(&self) -> Option<&(dyn std::error::Error + 'static)> {
+        match self {
+            ProtocolError::Io(e) => Some(e),
+            _ => None,
+        }
+    }
+}
+
+impl From<std::io::Error> for ProtocolError {
+    fn from(e: std::io::Error) -> Self {
+        ProtocolError::Io(e)
+    }
+}
+
+/// Errors raised while loading or saving a house file
+#[derive(Debug)]
+pub enum PersistenceError {
+    /// The file could not be read or written
+    Io {
+        path: PathBuf,
+        source: std::io::Error,
+    },
+
+    /// The file extension does not map to a known format
+    UnsupportedFormat(PathBuf),
+
+    /// The document is not valid for the given format
+    Parse {
+        format: HouseFormat,
+        message: String,
+    },
+
+    /// The house could not be encoded in the given format
+    Serialize {
+        format: HouseFormat,
+        message: String,
+    },
 
-        let debug_output = format!("{:?}", error);
+    /// A device record carries a kind the library does not know
+    UnknownDeviceKind {
+        room: String,
+        device: String,
+        kind: String,
+    },
 
-        assert!(debug_output.contains("resource_type"));
-        assert!(debug_output.contains("Room"));
-        assert!(debug_output.contains("requested_index"));
-        assert!(debug_output.contains("5"));
-        assert!(debug_output.contains("total_count"));
-        assert!(debug_output.contains("3"));
+    /// A device record has missing or mistyped fields
+    InvalidDevice {
+        room: String,
+        device: String,
+        message: String,
+    },
+
+    /// Two rooms share the same key
+    DuplicateRoom(String),
+
+    /// Two devices in the same room share the same key
+    DuplicateDevice { room: String, device: String },
+}
+
+impl std::fmt::Display for PersistenceError {
+    fn fmt(&self, f: &mut std::fmt::Formatter<'_>) -> std::fmt::Result {
+        match self {
+            PersistenceError::Io { path, source } => {
+                write!(f, "Cannot access '{}': {}", path.display(), source)
+            }
+            PersistenceError::UnsupportedFormat(path) => write!(
+                f,
+                "Unsupported house file '{}': expected a .json or .toml extension",
+                path.display()
+            ),
+            PersistenceError::Parse { format, message } => {
+                write!(f, "Malformed {} house file: {}", format, message)
+            }
+            PersistenceError::Serialize { format, message } => {
+                write!(f, "Cannot encode house as {}: {}", format, message)
+            }
+            PersistenceError::UnknownDeviceKind { room, device, kind } => write!(
+                f,
+                "Device '{}' in room '{}' has unknown kind '{}'",
+                device, room, kind
+            ),
+            PersistenceError::InvalidDevice {
+                room,
+                device,
+                message,
+            } => write!(
+                f,
+                "Device '{}' in room '{}' is invalid: {}",
+                device, room, message
+            ),
+            PersistenceError::DuplicateRoom(room) => {
+                write!(f, "Room '{}' is defined more than once", room)
+            }
+            PersistenceError::DuplicateDevice { room, device } => write!(
+                f,
+                "Device '{}' is defined more than once in room '{}'",
+                device, room
+            ),
+        }
     }
+}
 
-    #[test]
-    fn test_with_unusual_resource_types() {
-        let error = AccessError {
-            resource_type: "SensorNode".to_string(),
-            requested_index: 8,
-            total_count: 4,
-        };
+impl std::error::Error for PersistenceError {
+    fn source(&self) -> Option<&(dyn std::error::Error + 'static)> {
+        match self {
+            PersistenceError::Io { source, .. } => Some(source),
+            _ => None,
+        }
+    }
+}
 
-        let error_message = format!("{}", error);
-        assert_eq!(
-            error_message,
-            "SensorNode index 8 is out of bounds. Total sensornode: 4"
-        );
+/// Top-level error for room, house and device operations
+///
+/// Lookup failures keep their [`DeviceAccessError`] detail, and errors
+/// from the network and storage layers are wrapped so that callers can
+/// propagate everything with a single `?`.
+#[derive(Debug)]
+pub enum SmartHomeError {
+    /// Room, device or capability lookup failed
+    Access(DeviceAccessError),
+
+    /// A room with this name already exists in the house
+    DuplicateRoom(String),
+
+    /// A device with this name already exists in the room
+    /// Example: DuplicateDevice(device_name, room_name)
+    DuplicateDevice(String, String),
+
+    /// A value is outside the range accepted by the operation
+    /// Example: InvalidValue("room name", "must not be empty")
+    InvalidValue(String, String),
+
+    /// Turning a device on would draw more power than a budget allows
+    /// Example: BudgetExceeded("room 'Kitchen'", limit_watts, requested_watts)
+    BudgetExceeded(String, f32, f32),
+
+    /// A change failed and some devices could not be switched back
+    /// Example: RollbackFailed(cause, vec![(room_name, device_name)])
+    RollbackFailed(Box<SmartHomeError>, Vec<(String, String)>),
+
+    /// Local I/O failure
+    Io(std::io::Error),
+
+    /// A smart lock refused to lock or unlock
+    Lock(LockError),
+
+    /// Failure while talking to a networked device
+    Protocol(ProtocolError),
+
+    /// Failure while loading or saving a house file
+    Persistence(PersistenceError),
+}
+
+impl SmartHomeError {
+    /// Builds an [`SmartHomeError::InvalidValue`] error
+    pub fn invalid_value(what: impl Into<String>, reason: impl Into<String>) -> Self {
+        SmartHomeError::InvalidValue(what.into(), reason.into())
     }
 
-    #[test]
-    fn test_with_empty_resource_type() {
-        let error = AccessError {
-            resource_type: "".to_string(),
-            requested_index: 3,
-            total_count: 2,
-        };
+    /// Adds the devices a rollback could not switch back, if there are any
+    pub(crate) fn with_rollback_failures(self, devices: Vec<(String, String)>) -> Self {
+        match (self, devices) {
+            (error, devices) if devices.is_empty() => error,
+            (SmartHomeError::RollbackFailed(cause, mut failed), devices) => {
+                failed.extend(devices);
+                SmartHomeError::RollbackFailed(cause, failed)
+            }
+            (error, devices) => SmartHomeError::RollbackFailed(Box::new(error), devices),
+        }
+    }
+}
 
-        let error_message = format!("{}", error);
-        assert_eq!(error_message, " index 3 is out of bounds. Total : 2");
+impl std::fmt::Display for SmartHomeError {
+    fn fmt(&self, f: &mut std::fmt::Formatter<'_>) -> std::fmt::Result {
+        match self {
+            SmartHomeError::Access(e) => write!(f, "{}", e),
+            SmartHomeError::DuplicateRoom(room_name) => {
+                write!(f, "Room '{}' already exists", room_name)
+            }
+            SmartHomeError::DuplicateDevice(device_name, room_name) => write!(
+                f,
+                "Device '{}' already exists in room '{}'",
+                device_name, room_name
+            ),
+            SmartHomeError::InvalidValue(what, reason) => {
+                write!(f, "Invalid {}: {}", what, reason)
+            }
+            SmartHomeError::BudgetExceeded(scope, limit, requested) => write!(
+                f,
+                "Power budget of {} exceeded: {:.1} W requested, limit is {:.1} W",
+                scope, requested, limit
+            ),
+            SmartHomeError::RollbackFailed(cause, devices) => {
+                write!(f, "{}; could not switch back", cause)?;
+                for (index, (room_name, device_name)) in devices.iter().enumerate() {
+                    let separator = if index == 0 { "" } else { "," };
+                    write!(f, "{} '{}' in room '{}'", separator, device_name, room_name)?;
+                }
+                Ok(())
+            }
+            SmartHomeError::Lock(e) => write!(f, "{}", e),
+            SmartHomeError::Io(e) => write!(f, "I/O error: {}", e),
+            SmartHomeError::Protocol(e) => write!(f, "{}", e),
+            SmartHomeError::Persistence(e) => write!(f, "{}", e),
+        }
     }
+}
 
-    #[test]
-    fn test_with_zero_total_count() {
-        let error = AccessError {
-            resource_type: "Room".to_string(),
-            requested_index: 0,
-            total_count: 0,
-        };
+impl std::error::Error for SmartHomeError {
+    fn source(&self) -> Option<&(dyn std::error::Error + 'static)> {
+        match self {
+            SmartHomeError::Access(e) => Some(e),
+            SmartHomeError::RollbackFailed(cause, _) => Some(cause.as_ref()),
+            SmartHomeError::Lock(e) => Some(e),
+            SmartHomeError::Io(e) => Some(e),
+            SmartHomeError::Protocol(e) => Some(e),
+            SmartHomeError::Persistence(e) => Some(e),
+            SmartHomeError::DuplicateRoom(_)
+            | SmartHomeError::DuplicateDevice(_, _)
+            | SmartHomeError::InvalidValue(_, _)
+            | SmartHomeError::BudgetExceeded(_, _, _) => None,
+        }
+    }
+}
 
-        let error_message = format!("{}", error);
-        assert_eq!(
-            error_message,
-            "Room index 0 is out of bounds. Total room: 0"
-        );
+impl From<DeviceAccessError> for SmartHomeError {
+    fn from(e: DeviceAccessError) -> Self {
+        SmartHomeError::Access(e)
     }
+}
 
-    #[test]
-    fn test_with_large_indices() {
-        let error = AccessError {
-            resource_type: "Room".to_string(),
-            requested_index: usize::MAX,
-            total_count: 1000000,
-        };
+impl From<LockError> for SmartHomeError {
+    fn from(e: LockError) -> Self {
+        SmartHomeError::Lock(e)
+    }
+}
 
-        let error_message = format!("{}", error);
-        assert_eq!(
-            error_message,
-            format!(
-                "Room index {} is out of bounds. Total room: 1000000",
-                usize::MAX
-            )
-        );
+impl From<std::io::Error> for SmartHomeError {
+    fn from(e: std::io::Error) -> Self {
+        SmartHomeError::Io(e)
+    }
+}
+
+impl From<ProtocolError> for SmartHomeError {
+    fn from(e: ProtocolError) -> Self {
+        SmartHomeError::Protocol(e)
+    }
+}
+
+impl From<PersistenceError> for SmartHomeError {
+    fn from(e: PersistenceError) -> Self {
+        SmartHomeError::Persistence(e)
     }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use std::error::Error;
 
     #[test]
     fn test_device_access_error() {
@@ -177,5 +414,258 @@ mod tests {
         let debug_output = format!("{:?}", room_error);
         assert!(debug_output.contains("RoomNotFound"));
         assert!(debug_output.contains("Living Room"));
+
+        let capability_error = DeviceAccessError::UnsupportedCapability(
+            "Thermometer".to_string(),
+            Capability::PowerControl,
+        );
+        assert_eq!(
+            capability_error.to_string(),
+            "Device 'Thermometer' does not support power control"
+        );
+    }
+
+    #[test]
+    fn test_protocol_error() {
+        struct TestCase {
+            name: &'static str,
+            error: ProtocolError,
+            expected_message: &'static str,
+            has_source: bool,
+        }
+
+        let test_cases = vec![
+            TestCase {
+                name: "I/O error keeps its source",
+                error: std::io::Error::new(std::io::ErrorKind::BrokenPipe, "pipe closed").into(),
+                expected_message: "Transport error: pipe closed",
+                has_source: true,
+            },
+            TestCase {
+                name: "Unknown command",
+                error: ProtocolError::UnknownCommand(0x2A),
+                expected_message: "Unknown command byte 0x2A",
+                has_source: false,
+            },
+            TestCase {
+                name: "Unknown response",
+                error: ProtocolError::UnknownResponse(0x10),
+                expected_message: "Unknown response tag 0x10",
+                has_source: false,
+            },
+            TestCase {
+                name: "Unexpected response",
+                error: ProtocolError::UnexpectedResponse(Response::Ok),
+                expected_message: "Unexpected response Ok",
+                has_source: false,
+            },
+            TestCase {
+                name: "Rejected command",
+                error: ProtocolError::Rejected(0x01),
+                expected_message: "Command rejected with error code 0x01",
+                has_source: false,
+            },
+            TestCase {
+                name: "Malformed datagram",
+                error: ProtocolError::MalformedDatagram(3),
+                expected_message: "Malformed datagram of 3 bytes",
+                has_source: false,
+            },
+            TestCase {
+                name: "No reading",
+                error: ProtocolError::NoReading,
+                expected_message: "No reading received yet",
+                has_source: false,
+            },
+            TestCase {
+                name: "Timeout",
+                error: ProtocolError::Timeout(std::time::Duration::from_millis(1500)),
+                expected_message: "No response within 1500 ms",
+                has_source: false,
+            },
+            TestCase {
+                name: "Cancelled",
+                error: ProtocolError::Cancelled,
+                expected_message: "Request cancelled",
+                has_source: false,
+            },
+        ];
+
+        for tc in test_cases {
+            assert_eq!(
+                tc.error.to_string(),
+                tc.expected_message,
+                "Failed test: {}",
+                tc.name
+            );
+            assert_eq!(
+                tc.error.source().is_some(),
+                tc.has_source,
+                "Failed test: {}",
+                tc.name
+            );
+        }
+    }
+
+    #[test]
+    fn test_persistence_error() {
+        struct TestCase {
+            name: &'static str,
+            error: PersistenceError,
+            expected_message: &'static str,
+        }
+
+        let test_cases = vec![
+            TestCase {
+                name: "Unsupported format",
+                error: PersistenceError::UnsupportedFormat(PathBuf::from("house.yaml")),
+                expected_message: "Unsupported house file 'house.yaml': expected a .json or .toml extension",
+            },
+            TestCase {
+                name: "Parse error",
+                error: PersistenceError::Parse {
+                    format: HouseFormat::Toml,
+                    message: "expected `=`".to_string(),
+                },
+                expected_message: "Malformed TOML house file: expected `=`",
+            },
+            TestCase {
+                name: "Unknown device kind",
+                error: PersistenceError::UnknownDeviceKind {
+                    room: "Kitchen".to_string(),
+                    device: "Fridge".to_string(),
+                    kind: "fridge".to_string(),
+                },
+                expected_message: "Device 'Fridge' in room 'Kitchen' has unknown kind 'fridge'",
+            },
+            TestCase {
+                name: "Duplicate room",
+                error: PersistenceError::DuplicateRoom("Kitchen".to_string()),
+                expected_message: "Room 'Kitchen' is defined more than once",
+            },
+            TestCase {
+                name: "Duplicate device",
+                error: PersistenceError::DuplicateDevice {
+                    room: "Kitchen".to_string(),
+                    device: "Kettle".to_string(),
+                },
+                expected_message: "Device 'Kettle' is defined more than once in room 'Kitchen'",
+            },
+        ];
+
+        for tc in test_cases {
+            assert_eq!(
+                tc.error.to_string(),
+                tc.expected_message,
+                "Failed test: {}",
+                tc.name
+            );
+        }
+
+        let io_error = PersistenceError::Io {
+            path: PathBuf::from("missing.json"),
+            source: std::io::Error::new(std::io::ErrorKind::NotFound, "not found"),
+        };
+        assert_eq!(
+            io_error.to_string(),
+            "Cannot access 'missing.json': not found"
+        );
+        assert!(io_error.source().is_some());
+    }
+
+    #[test]
+    fn test_smart_home_error() {
+        struct TestCase {
+            name: &'static str,
+            error: SmartHomeError,
+            expected_message: &'static str,
+            has_source: bool,
+        }
+
+        let test_cases = vec![
+            TestCase {
+                name: "Room not found",
+                error: DeviceAccessError::RoomNotFound("Attic".to_string()).into(),
+                expected_message: "Room 'Attic' not found",
+                has_source: true,
+            },
+            TestCase {
+                name: "Unsupported capability",
+                error: DeviceAccessError::UnsupportedCapability(
+                    "Thermo".to_string(),
+                    Capability::PowerControl,
+                )
+                .into(),
+                expected_message: "Device 'Thermo' does not support power control",
+                has_source: true,
+            },
+            TestCase {
+                name: "Duplicate room",
+                error: SmartHomeError::DuplicateRoom("Kitchen".to_string()),
+                expected_message: "Room 'Kitchen' already exists",
+                has_source: false,
+            },
+            TestCase {
+                name: "Duplicate device",
+                error: SmartHomeError::DuplicateDevice("Kettle".to_string(), "Kitchen".to_string()),
+                expected_message: "Device 'Kettle' already exists in room 'Kitchen'",
+                has_source: false,
+            },
+            TestCase {
+                name: "Invalid value",
+                error: SmartHomeError::invalid_value("room name", "must not be empty"),
+                expected_message: "Invalid room name: must not be empty",
+                has_source: false,
+            },
+            TestCase {
+                name: "Budget exceeded",
+                error: SmartHomeError::BudgetExceeded("room 'Kitchen'".to_string(), 2000.0, 2150.0),
+                expected_message: "Power budget of room 'Kitchen' exceeded: 2150.0 W requested, limit is 2000.0 W",
+                has_source: false,
+            },
+            TestCase {
+                name: "Rollback failed",
+                error: SmartHomeError::BudgetExceeded("room 'Kitchen'".to_string(), 2000.0, 2150.0)
+                    .with_rollback_failures(vec![
+                        ("Kitchen".to_string(), "Radio".to_string()),
+                        ("Hall".to_string(), "Heater".to_string()),
+                    ]),
+                expected_message: "Power budget of room 'Kitchen' exceeded: 2150.0 W requested, limit is 2000.0 W; could not switch back 'Radio' in room 'Kitchen', 'Heater' in room 'Hall'",
+                has_source: true,
+            },
+            TestCase {
+                name: "I/O error",
+                error: std::io::Error::other("disk full").into(),
+                expected_message: "I/O error: disk full",
+                has_source: true,
+            },
+            TestCase {
+                name: "Protocol error",
+                error: ProtocolError::UnknownResponse(0x10).into(),
+                expected_message: "Unknown response tag 0x10",
+                has_source: true,
+            },
+            TestCase {
+                name: "Persistence error",
+                error: PersistenceError::DuplicateRoom("Hall".to_string()).into(),
+                expected_message: "Room 'Hall' is defined more than once",
+                has_source: true,
+            },
+        ];
+
+        for tc in test_cases {
+            assert_eq!(
+                tc.error.to_string(),
+                tc.expected_message,
+                "Failed test: {}",
+                tc.name
+            );
+            assert_eq!(
+                tc.error.source().is_some(),
+                tc.has_source,
+                "Failed test: {}",
+                tc.name
+            );
+        }
     }
 }
@@ -0,0 +1,285 @@
+//! Notifications about changes made through a [`SmartHouse`](crate::SmartHouse)
+//!
+//! Subscribers either register a synchronous callback with
+//! [`EventBus::subscribe`] or take a channel receiver from
+//! [`EventBus::channel`]. Only changes made through the house API are
+//! observed; a device mutated directly through `device_mut` emits nothing.
+
+use std::fmt;
+use std::sync::Arc;
+use std::sync::mpsc::{self, Receiver, Sender};
+
+/// A change in a smart house
+#[derive(Debug, Clone, PartialEq)]
+pub enum HouseEvent {
+    RoomAdded {
+        room: String,
+    },
+    RoomRemoved {
+        room: String,
+    },
+    DeviceAdded {
+        room: String,
+        device: String,
+    },
+    DeviceRemoved {
+        room: String,
+        device: String,
+    },
+    PowerStateChanged {
+        room: String,
+        device: String,
+        is_on: bool,
+    },
+    TemperatureUpdated {
+        room: String,
+        device: String,
+        temperature: f32,
+    },
+}
+
+impl HouseEvent {
+    /// Returns the key of the room the event happened in
+    pub fn room(&self) -> &str {
+        match self {
+            HouseEvent::RoomAdded { room }
+            | HouseEvent::RoomRemoved { room }
+            | HouseEvent::DeviceAdded { room, .. }
+            | HouseEvent::DeviceRemoved { room, .. }
+            | HouseEvent::PowerStateChanged { room, .. }
+            | HouseEvent::TemperatureUpdated { room, .. } => room,
+        }
+    }
+
+    /// Returns the key of the device the event is about, if any
+    pub fn device(&self) -> Option<&str> {
+        match self {
+            HouseEvent::RoomAdded { .. } | HouseEvent::RoomRemoved { .. } => None,
+            HouseEvent::DeviceAdded { device, .. }
+            | HouseEvent::DeviceRemoved { device, .. }
+            | HouseEvent::PowerStateChanged { device, .. }
+            | HouseEvent::TemperatureUpdated { device, .. } => Some(device),
+        }
+    }
+}
+
+impl fmt::Display for HouseEvent {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        match self {
+            HouseEvent::RoomAdded { room } => write!(f, "Room '{}' added", room),
+            HouseEvent::RoomRemoved { room } => write!(f, "Room '{}' removed", room),
+            HouseEvent::DeviceAdded { room, device } => {
+                write!(f, "Device '{}' added to room '{}'", device, room)
+            }
+            HouseEvent::DeviceRemoved { room, device } => {
+                write!(f, "Device '{}' removed from room '{}'", device, room)
+            }
+            HouseEvent::PowerStateChanged {
+                room,
+                device,
+                is_on,
+            } => write!(
+                f,
+                "Device '{}' in room '{}' turned {}",
+                device,
+                room,
+                if *is_on { "ON" } else { "OFF" }
+            ),
+            HouseEvent::TemperatureUpdated {
+                room,
+                device,
+                temperature,
+            } => write!(
+                f,
+                "Device '{}' in room '{}' reads {}°C",
+                device, room, temperature
+            ),
+        }
+    }
+}
+
+/// Handle returned by [`EventBus::subscribe`], used to unsubscribe
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Hash)]
+pub struct SubscriptionId(u64);
+
+pub(crate) type Callback = Arc<dyn Fn(&HouseEvent) + Send + Sync>;
+
+/// Delivers house events to callbacks and channel receivers
+#[derive(Default)]
+pub struct EventBus {
+    next_id: u64,
+    callbacks: Vec<(SubscriptionId, Callback)>,
+    senders: Vec<Sender<HouseEvent>>,
+}
+
+impl EventBus {
+    /// Creates a bus without subscribers
+    pub fn new() -> Self {
+        Self::default()
+    }
+
+    /// Registers a callback invoked synchronously for every event
+    ///
+    /// Callbacks run on the thread that changed the house, in subscription
+    /// order, before the changing method returns.
+    pub fn subscribe<F>(&mut self, callback: F) -> SubscriptionId
+    where
+        F: Fn(&HouseEvent) + Send + Sync + 'static,
+    {
+        let id = SubscriptionId(self.next_id);
+        self.next_id += 1;
+        self.callbacks.push((id, Arc::new(callback)));
+        id
+    }
+
+    /// Removes a callback; returns `false` if it was not registered
+    pub fn unsubscribe(&mut self, id: SubscriptionId) -> bool {
+        let before = self.callbacks.len();
+        self.callbacks.retain(|(callback_id, _)| *callback_id != id);
+        self.callbacks.len() != before
+    }
+
+    /// Returns a receiver getting a copy of every subsequent event
+    ///
+    /// Dropping the receiver unsubscribes it.
+    pub fn channel(&mut self) -> Receiver<HouseEvent> {
+        let (sender, receiver) = mpsc::channel();
+        self.senders.push(sender);
+        receiver
+    }
+
+    /// Returns the number of callbacks and live channel receivers
+    pub fn subscriber_count(&self) -> usize {
+        self.callbacks.len() + self.senders.len()
+    }
+
+    /// Delivers an event to all subscribers
+    pub fn publish(&mut self, event: HouseEvent) {
+        for (_, callback) in &self.callbacks {
+            callback(&event);
+        }
+        self.send(&event);
+    }
+
+    /// Delivers an event to the channel receivers only
+    pub(crate) fn send(&mut self, event: &HouseEvent) {
+        // Receivers that were dropped are forgotten on the next send
+        self.senders
+            .retain(|sender| sender.send(event.clone()).is_ok());
+    }
+
+    /// Returns the registered callbacks, to be run without the bus borrowed
+    pub(crate) fn callbacks(&self) -> Vec<Callback> {
+        self.callbacks
+            .iter()
+            .map(|(_, callback)| Arc::clone(callback))
+            .collect()
+    }
+}
+
+impl fmt::Debug for EventBus {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        f.debug_struct("EventBus")
+            .field("callbacks", &self.callbacks.len())
+            .field("receivers", &self.senders.len())
+            .finish()
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use std::sync::{Arc, Mutex};
+
+    fn device_added(device: &str) -> HouseEvent {
+        HouseEvent::DeviceAdded {
+            room: "Kitchen".to_string(),
+            device: device.to_string(),
+        }
+    }
+
+    #[test]
+    fn test_callbacks_and_channels() {
+        let mut bus = EventBus::new();
+        let seen = Arc::new(Mutex::new(Vec::new()));
+
+        let id = {
+            let seen = Arc::clone(&seen);
+            bus.subscribe(move |event| seen.lock().unwrap().push(event.clone()))
+        };
+        let receiver = bus.channel();
+        assert_eq!(bus.subscriber_count(), 2);
+
+        bus.publish(device_added("Kettle"));
+        assert!(bus.unsubscribe(id));
+        assert!(!bus.unsubscribe(id));
+        bus.publish(device_added("Toaster"));
+
+        assert_eq!(*seen.lock().unwrap(), vec![device_added("Kettle")]);
+        assert_eq!(
+            receiver.try_iter().collect::<Vec<_>>(),
+            vec![device_added("Kettle"), device_added("Toaster")]
+        );
+
+        drop(receiver);
+        bus.publish(device_added("Fridge"));
+        assert_eq!(bus.subscriber_count(), 0);
+    }
+
+    #[test]
+    fn test_event_accessors() {
+        struct TestCase {
+            name: &'static str,
+            event: HouseEvent,
+            expected_device: Option<&'static str>,
+            expected_display: &'static str,
+        }
+
+        let test_cases = vec![
+            TestCase {
+                name: "Room event",
+                event: HouseEvent::RoomAdded {
+                    room: "Kitchen".to_string(),
+                },
+                expected_device: None,
+                expected_display: "Room 'Kitchen' added",
+            },
+            TestCase {
+                name: "Power event",
+                event: HouseEvent::PowerStateChanged {
+                    room: "Kitchen".to_string(),
+                    device: "Kettle".to_string(),
+                    is_on: true,
+                },
+                expected_device: Some("Kettle"),
+                expected_display: "Device 'Kettle' in room 'Kitchen' turned ON",
+            },
+            TestCase {
+                name: "Temperature event",
+                event: HouseEvent::TemperatureUpdated {
+                    room: "Kitchen".to_string(),
+                    device: "Thermo".to_string(),
+                    temperature: 21.5,
+                },
+                expected_device: Some("Thermo"),
+                expected_display: "Device 'Thermo' in room 'Kitchen' reads 21.5°C",
+            },
+        ];
+
+        for tc in test_cases {
+            assert_eq!(tc.event.room(), "Kitchen", "Failed test: {}", tc.name);
+            assert_eq!(
+                tc.event.device(),
+                tc.expected_device,
+                "Failed test: {}",
+                tc.name
+            );
+            assert_eq!(
+                tc.event.to_string(),
+                tc.expected_display,
+                "Failed test: {}",
+                tc.name
+            );
+        }
+    }
+}
@@ -1,39 +1,236 @@
-use crate::error::DeviceAccessError;
-use crate::room::Room;
+use crate::automation::DeviceRef;
+use crate::budget::{self, PowerBudget};
+use crate::device::{Capability, TemperatureStats};
+use crate::error::{DeviceAccessError, PersistenceError, SmartHomeError};
+use crate::events::{EventBus, HouseEvent};
+use crate::report::{HouseReport, ReportOrder, StructuredReport};
+use crate::room::{Room, average, validate_name};
+use crate::scene::{Scene, SceneReport, TargetOutcome, TargetStatus};
+use crate::storage::{self, DeviceRegistry};
 use crate::{Reporter, SmartDevice};
+use indexmap::IndexMap;
 use std::collections::HashMap;
+use std::path::Path;
 
 /// Represents a smart house with multiple rooms
+///
+/// Rooms are kept in insertion order. Changes made through the house API
+/// are published on its [`EventBus`].
 #[derive(Debug)]
 pub struct SmartHouse {
     name: String,
-    rooms: HashMap<String, Room>,
+    rooms: IndexMap<String, Room>,
+    events: EventBus,
+    budget: Option<PowerBudget>,
+    /// Last temperature published per (room, device), see [`SmartHouse::poll_temperatures`]
+    temperatures: HashMap<(String, String), f32>,
 }
 
 impl SmartHouse {
     /// Creates a new smart house with the specified name and rooms
-    pub fn new(name: String, rooms: HashMap<String, Room>) -> Self {
-        Self { name, rooms }
+    ///
+    /// Rooms keep the order of the iterator, so pass a `Vec` or an
+    /// `IndexMap` rather than a `HashMap` when the order matters.
+    pub fn new<I>(name: String, rooms: I) -> Self
+    where
+        I: IntoIterator<Item = (String, Room)>,
+    {
+        Self {
+            name,
+            rooms: rooms.into_iter().collect(),
+            events: EventBus::new(),
+            budget: None,
+            temperatures: HashMap::new(),
+        }
     }
 
     /// Creates a new smart house with the specified name and an empty list of rooms
     pub fn new_empty(name: String) -> Self {
+        Self::new(name, IndexMap::new())
+    }
+
+    /// Reassembles a house taken apart with [`SmartHouse::into_parts`]
+    pub(crate) fn from_parts(
+        name: String,
+        rooms: IndexMap<String, Room>,
+        events: EventBus,
+        budget: Option<PowerBudget>,
+    ) -> Self {
         Self {
             name,
-            rooms: HashMap::new(),
+            rooms,
+            events,
+            budget,
+            temperatures: HashMap::new(),
         }
     }
 
+    /// Splits the house into its name, rooms, event bus and power budget
+    pub(crate) fn into_parts(
+        self,
+    ) -> (
+        String,
+        IndexMap<String, Room>,
+        EventBus,
+        Option<PowerBudget>,
+    ) {
+        (self.name, self.rooms, self.events, self.budget)
+    }
+
     /// Returns the name of the house
     pub fn name(&self) -> &str {
         &self.name
     }
 
-    /// Returns all rooms in the house
-    pub fn all_rooms(&self) -> &HashMap<String, Room> {
+    /// Returns the event bus of the house
+    pub fn events(&self) -> &EventBus {
+        &self.events
+    }
+
+    /// Returns the event bus of the house for subscribing
+    pub fn events_mut(&mut self) -> &mut EventBus {
+        &mut self.events
+    }
+
+    /// Returns all rooms in the house in insertion order
+    pub fn all_rooms(&self) -> &IndexMap<String, Room> {
         &self.rooms
     }
 
+    /// Returns the rooms of the house in the given order
+    pub fn rooms_ordered(&self, order: ReportOrder) -> Vec<(&String, &Room)> {
+        let mut rooms: Vec<_> = self.rooms.iter().collect();
+        order.sort(
+            &mut rooms,
+            |(_, room)| room.name(),
+            |(_, room)| room.total_power_consumption(),
+        );
+        rooms
+    }
+
+    /// Returns the total power drawn by all devices in the house in watts
+    pub fn total_power_consumption(&self) -> f32 {
+        self.rooms.values().map(Room::total_power_consumption).sum()
+    }
+
+    /// Returns the power budget of the house
+    pub fn power_budget(&self) -> Option<&PowerBudget> {
+        self.budget.as_ref()
+    }
+
+    /// Sets or clears the power budget of the whole house
+    ///
+    /// It is checked after the budget of the room, so a device must fit in
+    /// both. Devices already on stay on even if they draw more than the new
+    /// limit.
+    pub fn set_power_budget(&mut self, budget: Option<PowerBudget>) -> Result<(), SmartHomeError> {
+        if let Some(budget) = &budget {
+            budget.validate()?;
+        }
+        self.budget = budget;
+        Ok(())
+    }
+
+    /// Returns statistics over the current readings of all temperature sensors in the house
+    pub fn temperature_stats(&self) -> Option<TemperatureStats> {
+        TemperatureStats::from_temperatures(self.rooms.values().flat_map(Room::temperatures))
+    }
+
+    /// Returns the average temperature across all temperature sensors in the house
+    ///
+    /// Every sensor counts once, whatever the room it is in.
+    pub fn average_temperature(&self) -> Option<f32> {
+        self.temperature_stats().map(|stats| stats.mean)
+    }
+
+    /// Returns the average relative humidity in percent across all humidity sensors in the house
+    pub fn average_humidity(&self) -> Option<f32> {
+        average(
+            self.rooms
+                .values()
+                .flat_map(|room| room.readings(SmartDevice::humidity)),
+        )
+    }
+
+    /// Returns the average CO2 concentration in ppm across all CO2 sensors in the house
+    pub fn average_co2_ppm(&self) -> Option<f32> {
+        average(
+            self.rooms
+                .values()
+                .flat_map(|room| room.readings(SmartDevice::co2_ppm)),
+        )
+    }
+
+    /// Returns the worst air quality index reported in the house
+    pub fn max_air_quality_index(&self) -> Option<u16> {
+        self.rooms
+            .values()
+            .filter_map(Room::max_air_quality_index)
+            .max()
+    }
+
+    /// Lists the triggered binary sensors of every room, such as open doors or detected smoke
+    ///
+    /// Rooms without a triggered sensor are left out.
+    pub fn active_alarms(&self) -> Vec<(&String, Vec<&String>)> {
+        self.rooms
+            .iter()
+            .map(|(name, room)| (name, room.triggered_sensors()))
+            .filter(|(_, sensors)| !sensors.is_empty())
+            .collect()
+    }
+
+    /// Returns the energy metered by all devices in the house in watt-hours
+    pub fn energy_wh(&self) -> f64 {
+        self.rooms.values().map(Room::energy_wh).sum()
+    }
+
+    /// Returns the energy metered by all devices in the house in kilowatt-hours
+    pub fn energy_kwh(&self) -> f64 {
+        self.energy_wh() / 1000.0
+    }
+
+    /// Resets the energy counter of a device in a room of the house
+    pub fn reset_device_energy(
+        &mut self,
+        room_name: &String,
+        device_name: &String,
+    ) -> Result<(), SmartHomeError> {
+        self.find_room_mut(room_name)?
+            .reset_device_energy(device_name)
+    }
+
+    /// Resets the energy counters of all devices in the house
+    pub fn reset_energy(&mut self) {
+        for room in self.rooms.values_mut() {
+            room.reset_energy();
+        }
+    }
+
+    /// Generates the text report with rooms and devices in the given order
+    pub fn report_ordered(&self, order: ReportOrder) -> String {
+        let rooms: Vec<String> = self
+            .rooms_ordered(order)
+            .into_iter()
+            .map(|(_, room)| room.report_ordered(order))
+            .collect();
+        let header_format = format!("=== Smart House: {} ===\n", self.name);
+
+        let estimated_capacity =
+            header_format.len() + rooms.iter().map(|r| r.len()).sum::<usize>() + rooms.len();
+
+        let mut report = String::with_capacity(estimated_capacity);
+
+        report.push_str(&header_format);
+
+        for room in rooms {
+            report.push_str(&room);
+            report.push('\n');
+        }
+
+        report
+    }
+
     /// Returns a reference to a specific room by name
     pub fn room(&self, name: &String) -> Option<&Room> {
         self.rooms.get(name)
@@ -45,13 +242,338 @@ impl SmartHouse {
     }
 
     /// Adds a new room to the house
-    pub fn add_room(&mut self, name: String, room: Room) -> Option<Room> {
-        self.rooms.insert(name, room)
+    ///
+    /// Fails if the name is empty or already taken by another room
+    pub fn add_room(&mut self, name: String, room: Room) -> Result<(), SmartHomeError> {
+        validate_name("room name", &name)?;
+        if self.rooms.contains_key(&name) {
+            return Err(SmartHomeError::DuplicateRoom(name));
+        }
+
+        self.rooms.insert(name.clone(), room);
+        self.events.publish(HouseEvent::RoomAdded { room: name });
+        Ok(())
+    }
+
+    /// Removes a room from the house by name and returns it
+    pub fn remove_room(&mut self, name: &String) -> Result<Room, SmartHomeError> {
+        let room = self
+            .rooms
+            .shift_remove(name)
+            .ok_or_else(|| DeviceAccessError::RoomNotFound(name.clone()))?;
+
+        self.temperatures
+            .retain(|(room_name, _), _| room_name != name);
+        self.events
+            .publish(HouseEvent::RoomRemoved { room: name.clone() });
+        Ok(room)
+    }
+
+    /// Returns a mutable room by name or a `RoomNotFound` error
+    fn find_room_mut(&mut self, name: &String) -> Result<&mut Room, DeviceAccessError> {
+        self.rooms
+            .get_mut(name)
+            .ok_or_else(|| DeviceAccessError::RoomNotFound(name.clone()))
+    }
+
+    /// Adds a device to a room of the house
+    pub fn add_device(
+        &mut self,
+        room_name: &String,
+        device_name: String,
+        device: SmartDevice,
+    ) -> Result<(), SmartHomeError> {
+        self.find_room_mut(room_name)?
+            .add_device(device_name.clone(), device)?;
+
+        self.events.publish(HouseEvent::DeviceAdded {
+            room: room_name.clone(),
+            device: device_name,
+        });
+        Ok(())
+    }
+
+    /// Removes a device from a room of the house and returns it
+    pub fn remove_device(
+        &mut self,
+        room_name: &String,
+        device_name: &String,
+    ) -> Result<SmartDevice, SmartHomeError> {
+        let device = self.find_room_mut(room_name)?.remove_device(device_name)?;
+
+        self.temperatures
+            .remove(&(room_name.clone(), device_name.clone()));
+        self.events.publish(HouseEvent::DeviceRemoved {
+            room: room_name.clone(),
+            device: device_name.clone(),
+        });
+        Ok(device)
+    }
+
+    /// Turns on a device in a room of the house
+    ///
+    /// Publishes [`HouseEvent::PowerStateChanged`] if the device was off, and
+    /// for every device shed to keep the room and the house within budget.
+    pub fn turn_on_device(
+        &mut self,
+        room_name: &String,
+        device_name: &String,
+    ) -> Result<(), SmartHomeError> {
+        self.switch_device(room_name, device_name, true)
+    }
+
+    /// Turns off a device in a room of the house
+    ///
+    /// Publishes [`HouseEvent::PowerStateChanged`] if the device was on.
+    pub fn turn_off_device(
+        &mut self,
+        room_name: &String,
+        device_name: &String,
+    ) -> Result<(), SmartHomeError> {
+        self.switch_device(room_name, device_name, false)
+    }
+
+    fn switch_device(
+        &mut self,
+        room_name: &String,
+        device_name: &String,
+        on: bool,
+    ) -> Result<(), SmartHomeError> {
+        for event in self.switch_device_quietly(room_name, device_name, on)? {
+            self.events.publish(event);
+        }
+        Ok(())
+    }
+
+    /// Switches a device and returns the events to publish instead of publishing them
+    fn switch_device_quietly(
+        &mut self,
+        room_name: &String,
+        device_name: &String,
+        on: bool,
+    ) -> Result<Vec<HouseEvent>, SmartHomeError> {
+        if on {
+            let mut rooms: Vec<(&String, &mut Room)> = self.rooms.iter_mut().collect();
+            return budget::turn_on_in_house(
+                &self.name,
+                self.budget.as_ref(),
+                &mut rooms,
+                room_name,
+                device_name,
+            );
+        }
+
+        let room = self.find_room_mut(room_name)?;
+        let was_on = room.device(device_name).and_then(SmartDevice::is_on);
+        room.turn_off_device(device_name)?;
+
+        let is_on = room.device(device_name).and_then(SmartDevice::is_on);
+        Ok(is_on
+            .filter(|is_on| Some(*is_on) != was_on)
+            .map(|is_on| HouseEvent::PowerStateChanged {
+                room: room_name.clone(),
+                device: device_name.clone(),
+                is_on,
+            })
+            .into_iter()
+            .collect())
+    }
+
+    /// Switches back the devices changed by `events`, newest change first
+    ///
+    /// Returns the devices that could not be switched back, with the error.
+    fn undo_power_changes(&mut self, events: &[HouseEvent]) -> Vec<(DeviceRef, String)> {
+        let mut failures = Vec::new();
+        for event in events.iter().rev() {
+            let HouseEvent::PowerStateChanged {
+                room,
+                device,
+                is_on,
+            } = event
+            else {
+                continue;
+            };
+            let result = self
+                .device_mut(room, device)
+                .map_err(SmartHomeError::from)
+                .and_then(|target| {
+                    if *is_on {
+                        target.turn_off()
+                    } else {
+                        target.turn_on()
+                    }
+                });
+            if let Err(error) = result {
+                failures.push((
+                    DeviceRef::new(room.as_str(), device.as_str()),
+                    error.to_string(),
+                ));
+            }
+        }
+        failures
+    }
+
+    /// Applies a scene atomically
+    ///
+    /// Every target is checked first. If any of them is missing or cannot be
+    /// switched, no device is touched and the report lists the rejected
+    /// targets; otherwise all devices are switched to their target state.
+    /// If a target would exceed a power budget or fails to switch, the devices
+    /// already switched, including the ones shed for them, are put back and no
+    /// event is published. Devices that could not be put back are listed in
+    /// [`SceneReport::not_restored`].
+    pub fn apply_scene(&mut self, scene: &Scene) -> SceneReport {
+        let checks: Vec<_> = scene
+            .targets()
+            .iter()
+            .map(|target| (target, Scene::validate(self, &target.target)))
+            .collect();
+        let valid = checks.iter().all(|(_, check)| check.is_ok());
+
+        let mut events = Vec::new();
+        let mut failed = false;
+        let mut outcomes: Vec<TargetOutcome> = Vec::with_capacity(checks.len());
+        for (target, check) in checks {
+            let device = &target.target;
+            let status = match check {
+                Err(error) => TargetStatus::Rejected(error),
+                Ok(_) if !valid || failed => TargetStatus::NotApplied,
+                // A scene may list a device twice, so read the state again
+                Ok(_) if Scene::validate(self, device) == Ok(target.is_on) => {
+                    TargetStatus::Unchanged
+                }
+                Ok(_) => {
+                    match self.switch_device_quietly(&device.room, &device.device, target.is_on) {
+                        Ok(changes) => {
+                            events.extend(changes);
+                            TargetStatus::Changed
+                        }
+                        Err(error @ SmartHomeError::BudgetExceeded(..)) => {
+                            failed = true;
+                            TargetStatus::OverBudget(error.to_string())
+                        }
+                        Err(SmartHomeError::Access(error)) => {
+                            failed = true;
+                            TargetStatus::Rejected(error)
+                        }
+                        Err(error) => {
+                            failed = true;
+                            TargetStatus::Failed(error.to_string())
+                        }
+                    }
+                }
+            };
+            outcomes.push(TargetOutcome {
+                target: device.clone(),
+                status,
+            });
+        }
+
+        let mut not_restored = Vec::new();
+        if failed {
+            not_restored = self.undo_power_changes(&events);
+            for outcome in &mut outcomes {
+                if outcome.status == TargetStatus::Changed {
+                    outcome.status = TargetStatus::NotApplied;
+                }
+            }
+        } else {
+            for event in events {
+                self.events.publish(event);
+            }
+        }
+
+        SceneReport {
+            scene: scene.name().to_string(),
+            outcomes,
+            not_restored,
+        }
     }
 
-    /// Removes a room from the house by name
-    pub fn remove_room(&mut self, name: &String) -> Option<Room> {
-        self.rooms.remove(name)
+    /// Sets the reading of a [`SmartThermometer`](crate::SmartThermometer) or a
+    /// [`SmartThermostat`](crate::SmartThermostat) in a room
+    ///
+    /// Publishes [`HouseEvent::TemperatureUpdated`] if the reading changed.
+    /// Devices measuring temperature on their own, such as
+    /// [`UdpThermometer`](crate::UdpThermometer), are picked up by
+    /// [`SmartHouse::poll_temperatures`] instead.
+    pub fn update_temperature(
+        &mut self,
+        room_name: &String,
+        device_name: &String,
+        temperature: f32,
+    ) -> Result<(), SmartHomeError> {
+        if !temperature.is_finite() {
+            return Err(SmartHomeError::invalid_value(
+                "temperature",
+                "must be a finite number",
+            ));
+        }
+
+        match self.device_mut(room_name, device_name)? {
+            SmartDevice::Thermometer(thermometer) => thermometer.set_temperature(temperature),
+            SmartDevice::Thermostat(thermostat) => thermostat.set_temperature(temperature),
+            device if device.as_temperature_sensor().is_some() => {
+                return Err(SmartHomeError::invalid_value(
+                    "temperature",
+                    format!("device '{}' measures temperature itself", device_name),
+                ));
+            }
+            device => return Err(device.unsupported(Capability::TemperatureSensor).into()),
+        }
+
+        self.publish_temperature(room_name, device_name, temperature);
+        Ok(())
+    }
+
+    /// Reads every temperature sensor in the house and publishes
+    /// [`HouseEvent::TemperatureUpdated`] for readings that changed since the
+    /// last poll
+    ///
+    /// Returns the number of events published.
+    pub fn poll_temperatures(&mut self) -> usize {
+        let readings: Vec<(String, String, f32)> = self
+            .rooms
+            .iter()
+            .flat_map(|(room_name, room)| {
+                room.all_devices()
+                    .iter()
+                    .filter_map(move |(device_name, device)| {
+                        device
+                            .temperature()
+                            .filter(|t| t.is_finite())
+                            .map(|t| (room_name.clone(), device_name.clone(), t))
+                    })
+            })
+            .collect();
+
+        readings
+            .into_iter()
+            .filter(|(room_name, device_name, temperature)| {
+                self.publish_temperature(room_name, device_name, *temperature)
+            })
+            .count()
+    }
+
+    /// Publishes a temperature reading unless it equals the last published one
+    fn publish_temperature(
+        &mut self,
+        room_name: &str,
+        device_name: &str,
+        temperature: f32,
+    ) -> bool {
+        let key = (room_name.to_string(), device_name.to_string());
+        if self.temperatures.get(&key) == Some(&temperature) {
+            return false;
+        }
+
+        self.temperatures.insert(key, temperature);
+        self.events.publish(HouseEvent::TemperatureUpdated {
+            room: room_name.to_string(),
+            device: device_name.to_string(),
+            temperature,
+        });
+        true
     }
 
     /// Returns a reference to a specific device in a room by room and device name
@@ -81,36 +603,53 @@ impl SmartHouse {
             None => Err(DeviceAccessError::RoomNotFound(room_name.clone())),
         }
     }
-}
 
-impl Reporter for SmartHouse {
-    fn report(&self) -> String {
-        let header_format = format!("=== Smart House: {} ===\n", self.name);
-        let newlines = self.rooms.len();
+    /// Loads a house from a `.json` or `.toml` file
+    pub fn load_from_path<P: AsRef<Path>>(path: P) -> Result<Self, PersistenceError> {
+        storage::load(path.as_ref())
+    }
 
-        let estimated_capacity = header_format.len()
-            + self.rooms.values().map(|r| r.report().len()).sum::<usize>()
-            + newlines;
+    /// Loads a house from a `.json` or `.toml` file, building custom devices
+    /// through the given registry
+    pub fn load_from_path_with<P: AsRef<Path>>(
+        path: P,
+        registry: &DeviceRegistry,
+    ) -> Result<Self, PersistenceError> {
+        storage::load_with(path.as_ref(), registry)
+    }
 
-        let mut report = String::with_capacity(estimated_capacity);
+    /// Saves the house to a `.json` or `.toml` file, picking the format from the extension
+    pub fn save_to_path<P: AsRef<Path>>(&self, path: P) -> Result<(), PersistenceError> {
+        storage::save(self, path.as_ref())
+    }
+}
 
-        report.push_str(&header_format);
+impl StructuredReport for SmartHouse {
+    type Report = HouseReport;
 
-        for room in self.rooms.values() {
-            report.push_str(&room.report());
-            report.push('\n');
-        }
+    fn structured_report(&self) -> HouseReport {
+        HouseReport::new(
+            self.name.clone(),
+            self.rooms.values().map(Room::structured_report).collect(),
+        )
+    }
+}
 
-        report
+impl Reporter for SmartHouse {
+    fn report(&self) -> String {
+        self.report_ordered(ReportOrder::Insertion)
     }
 }
 
 #[cfg(test)]
 mod tests {
     use super::*;
+    use crate::create_room;
     use crate::device::{SmartDevice, SmartSocket, SmartThermometer};
+    use crate::report::Metric;
     use mockall::mock;
     use std::collections::HashMap;
+    use std::sync::{Arc, Mutex};
 
     mock! {
         pub Room {}
@@ -137,7 +676,7 @@ mod tests {
     }
 
     fn create_house_with_devices() -> SmartHouse {
-        let mut living_room_devices = HashMap::new();
+        let mut living_room_devices = IndexMap::new();
         living_room_devices.insert(
             "Living Room Thermometer".to_string(),
             SmartDevice::Thermometer(SmartThermometer::new(
@@ -154,7 +693,7 @@ mod tests {
             )),
         );
 
-        let mut bedroom_devices = HashMap::new();
+        let mut bedroom_devices = IndexMap::new();
         bedroom_devices.insert(
             "Bedroom Thermometer".to_string(),
             SmartDevice::Thermometer(SmartThermometer::new(
@@ -166,7 +705,7 @@ mod tests {
         let living_room = Room::new("Living Room".to_string(), living_room_devices);
         let bedroom = Room::new("Bedroom".to_string(), bedroom_devices);
 
-        let mut hash_rooms = HashMap::new();
+        let mut hash_rooms = IndexMap::new();
         hash_rooms.insert("Living Room".to_string(), living_room);
         hash_rooms.insert("Bedroom".to_string(), bedroom);
         SmartHouse::new("Smart Home".to_string(), hash_rooms)
@@ -198,18 +737,40 @@ mod tests {
                     let room = Room::new("Test Room".to_string(), devices);
                     let room_name = room.name().to_string();
 
-                    house.add_room(room_name, room);
-                    (house.all_rooms().len(), true)
+                    let success = house.add_room(room_name, room).is_ok();
+                    (house.all_rooms().len(), success)
                 },
                 expected_rooms_count: 1,
                 expected_success: true,
             },
+            AddRemoveRoomTestCase {
+                name: "Add a room with a taken name",
+                initial_house: create_house_with_rooms(),
+                operation: |house| {
+                    let room = Room::new_empty("Another Room 1".to_string());
+                    let success = house.add_room("Room 1".to_string(), room).is_ok();
+                    (house.all_rooms().len(), success)
+                },
+                expected_rooms_count: 2,
+                expected_success: false,
+            },
+            AddRemoveRoomTestCase {
+                name: "Add a room with an empty name",
+                initial_house: create_empty_house(),
+                operation: |house| {
+                    let room = Room::new_empty("Nameless".to_string());
+                    let success = house.add_room(String::new(), room).is_ok();
+                    (house.all_rooms().len(), success)
+                },
+                expected_rooms_count: 0,
+                expected_success: false,
+            },
             AddRemoveRoomTestCase {
                 name: "Remove a room from house with rooms",
                 initial_house: create_house_with_rooms(),
                 operation: |house| match house.remove_room(&"Room 1".to_string()) {
-                    Some(_) => (house.all_rooms().len(), true),
-                    None => (house.all_rooms().len(), false),
+                    Ok(_) => (house.all_rooms().len(), true),
+                    Err(_) => (house.all_rooms().len(), false),
                 },
                 expected_rooms_count: 1,
                 expected_success: true,
@@ -218,8 +779,8 @@ mod tests {
                 name: "Try to remove non-existent room",
                 initial_house: create_house_with_rooms(),
                 operation: |house| match house.remove_room(&"Room 3".to_string()) {
-                    Some(_) => (house.all_rooms().len(), true),
-                    None => (house.all_rooms().len(), false),
+                    Ok(_) => (house.all_rooms().len(), true),
+                    Err(_) => (house.all_rooms().len(), false),
                 },
                 expected_rooms_count: 2,
                 expected_success: false,
@@ -344,6 +905,94 @@ mod tests {
         }
     }
 
+    #[test]
+    fn test_device_operations() {
+        struct DeviceOperationTestCase {
+            name: &'static str,
+            operation: fn(&mut SmartHouse) -> Result<(), SmartHomeError>,
+            expected_error: Option<&'static str>,
+        }
+
+        let test_cases = vec![
+            DeviceOperationTestCase {
+                name: "Turn off a socket",
+                operation: |house| {
+                    house.turn_off_device(
+                        &"Living Room".to_string(),
+                        &"Living Room Socket".to_string(),
+                    )
+                },
+                expected_error: None,
+            },
+            DeviceOperationTestCase {
+                name: "Turn on a device in a missing room",
+                operation: |house| house.turn_on_device(&"Garage".to_string(), &"Door".to_string()),
+                expected_error: Some("Room 'Garage' not found"),
+            },
+            DeviceOperationTestCase {
+                name: "Turn on a thermometer",
+                operation: |house| {
+                    house.turn_on_device(&"Bedroom".to_string(), &"Bedroom Thermometer".to_string())
+                },
+                expected_error: Some("Device 'Bedroom Thermometer' does not support power control"),
+            },
+            DeviceOperationTestCase {
+                name: "Add a device to a room",
+                operation: |house| {
+                    house.add_device(
+                        &"Bedroom".to_string(),
+                        "Lamp".to_string(),
+                        SmartSocket::new("Lamp".to_string(), false, 5.0).into(),
+                    )
+                },
+                expected_error: None,
+            },
+            DeviceOperationTestCase {
+                name: "Add a device with a taken name",
+                operation: |house| {
+                    house.add_device(
+                        &"Bedroom".to_string(),
+                        "Bedroom Thermometer".to_string(),
+                        SmartSocket::new("Lamp".to_string(), false, 5.0).into(),
+                    )
+                },
+                expected_error: Some(
+                    "Device 'Bedroom Thermometer' already exists in room 'Bedroom'",
+                ),
+            },
+            DeviceOperationTestCase {
+                name: "Remove a device",
+                operation: |house| {
+                    house
+                        .remove_device(&"Bedroom".to_string(), &"Bedroom Thermometer".to_string())
+                        .map(|_| ())
+                },
+                expected_error: None,
+            },
+            DeviceOperationTestCase {
+                name: "Remove a missing device",
+                operation: |house| {
+                    house
+                        .remove_device(&"Bedroom".to_string(), &"Lamp".to_string())
+                        .map(|_| ())
+                },
+                expected_error: Some("Device 'Lamp' not found in room 'Bedroom'"),
+            },
+        ];
+
+        for tc in test_cases {
+            let mut house = create_house_with_devices();
+            let result = (tc.operation)(&mut house);
+
+            assert_eq!(
+                result.err().map(|e| e.to_string()).as_deref(),
+                tc.expected_error,
+                "Failed test case '{}'",
+                tc.name
+            );
+        }
+    }
+
     #[test]
     fn test_report_generation() {
         struct ReportTestCase {
@@ -408,4 +1057,853 @@ mod tests {
             }
         }
     }
+
+    #[test]
+    fn test_temperature_aggregates() {
+        let mut house = create_house_with_devices();
+        house
+            .add_device(
+                &"Bedroom".to_string(),
+                "Window".to_string(),
+                SmartThermometer::new("Window".to_string(), 18.0).into(),
+            )
+            .unwrap();
+
+        // Every sensor counts once: (21.0 + 19.5 + 18.0) / 3
+        assert_eq!(house.average_temperature(), Some(19.5));
+        assert_eq!(
+            house.temperature_stats(),
+            Some(TemperatureStats {
+                count: 3,
+                min: 18.0,
+                max: 21.0,
+                mean: 19.5,
+            })
+        );
+        assert_eq!(
+            house
+                .room(&"Bedroom".to_string())
+                .unwrap()
+                .average_temperature(),
+            Some(18.75)
+        );
+
+        let report = house.structured_report();
+        assert_eq!(
+            report.summary.first(),
+            Some(&Metric::number("average_temperature", 19.5, "°C"))
+        );
+        assert_eq!(
+            report.rooms[1].summary.first(),
+            Some(&Metric::number("average_temperature", 18.75, "°C"))
+        );
+
+        assert_eq!(
+            SmartHouse::new_empty("Empty".to_string()).average_temperature(),
+            None
+        );
+    }
+
+    #[test]
+    fn test_air_quality_aggregates() {
+        use crate::device::{SmartAirQualityMonitor, SmartHygrometer};
+
+        let mut house = create_house_with_devices();
+        let devices: [(&str, &str, SmartDevice); 3] = [
+            (
+                "Living Room",
+                "Hygro",
+                SmartHygrometer::new("Hygro".to_string(), 40.0).into(),
+            ),
+            (
+                "Bedroom",
+                "Hygro",
+                SmartHygrometer::new("Hygro".to_string(), 50.0).into(),
+            ),
+            (
+                "Bedroom",
+                "Air",
+                SmartAirQualityMonitor::new("Air".to_string(), 1200.0, 75).into(),
+            ),
+        ];
+        for (room, name, device) in devices {
+            house
+                .add_device(&room.to_string(), name.to_string(), device)
+                .unwrap();
+        }
+
+        assert_eq!(house.average_humidity(), Some(45.0));
+        assert_eq!(house.average_co2_ppm(), Some(1200.0));
+        assert_eq!(house.max_air_quality_index(), Some(75));
+
+        let report = house.structured_report();
+        assert_eq!(
+            report.summary[3..],
+            [
+                Metric::number("average_humidity", 45.0, "%"),
+                Metric::number("average_co2", 1200.0, "ppm"),
+                Metric::number("max_air_quality_index", 75.0, "AQI"),
+            ]
+        );
+        assert!(
+            report.rooms[0]
+                .summary
+                .iter()
+                .all(|metric| metric.name != "average_co2")
+        );
+
+        assert_eq!(
+            SmartHouse::new_empty("Empty".to_string()).max_air_quality_index(),
+            None
+        );
+    }
+
+    #[test]
+    fn test_active_alarms() {
+        use crate::device::{BinarySensorKind, SmartBinarySensor};
+
+        let mut house = create_house_with_devices();
+        let sensors = [
+            ("Living Room", "Motion", BinarySensorKind::Motion),
+            ("Living Room", "Smoke", BinarySensorKind::Smoke),
+            ("Bedroom", "Window", BinarySensorKind::Contact),
+        ];
+        for (room, name, kind) in sensors {
+            house
+                .add_device(
+                    &room.to_string(),
+                    name.to_string(),
+                    SmartBinarySensor::new(name.to_string(), kind).into(),
+                )
+                .unwrap();
+        }
+        assert!(house.active_alarms().is_empty());
+
+        for (room, name) in [("Living Room", "Smoke"), ("Bedroom", "Window")] {
+            let Ok(SmartDevice::BinarySensor(sensor)) =
+                house.device_mut(&room.to_string(), &name.to_string())
+            else {
+                panic!("{} should be a binary sensor", name);
+            };
+            sensor.set_triggered(true);
+        }
+
+        let living_room = "Living Room".to_string();
+        let bedroom = "Bedroom".to_string();
+        let (smoke, window) = ("Smoke".to_string(), "Window".to_string());
+        assert_eq!(
+            house.active_alarms(),
+            vec![(&living_room, vec![&smoke]), (&bedroom, vec![&window])]
+        );
+    }
+
+    #[test]
+    fn test_update_thermostat() {
+        use crate::device::{HvacAction, SmartThermostat, ThermostatMode};
+
+        let mut house = create_house_with_devices();
+        house
+            .add_device(
+                &"Bedroom".to_string(),
+                "Thermostat".to_string(),
+                SmartThermostat::new("Thermostat".to_string(), 21.0, 21.0)
+                    .with_mode(ThermostatMode::Heat)
+                    .with_power(1500.0, 0.0)
+                    .into(),
+            )
+            .unwrap();
+        let receiver = house.events_mut().channel();
+
+        house
+            .update_temperature(&"Bedroom".to_string(), &"Thermostat".to_string(), 20.0)
+            .unwrap();
+
+        let Ok(SmartDevice::Thermostat(thermostat)) =
+            house.device(&"Bedroom".to_string(), &"Thermostat".to_string())
+        else {
+            panic!("Thermostat should stay a thermostat");
+        };
+        assert_eq!(thermostat.action(), HvacAction::Heating);
+        assert_eq!(house.total_power_consumption(), 1580.0);
+        assert_eq!(
+            receiver.try_iter().collect::<Vec<_>>(),
+            vec![HouseEvent::TemperatureUpdated {
+                room: "Bedroom".to_string(),
+                device: "Thermostat".to_string(),
+                temperature: 20.0,
+            }]
+        );
+    }
+
+    #[test]
+    fn test_energy_totals() {
+        use crate::clock::ManualClock;
+        use chrono::NaiveDate;
+        use std::time::Duration;
+
+        let start = NaiveDate::from_ymd_opt(2024, 1, 1)
+            .unwrap()
+            .and_hms_opt(0, 0, 0)
+            .unwrap();
+        let clock = Arc::new(ManualClock::new(start));
+        let socket = |name: &str, is_on: bool, power: f32| {
+            SmartSocket::new(name.to_string(), is_on, power).with_clock(Arc::clone(&clock))
+        };
+
+        let kitchen = create_room!(
+            "Kitchen",
+            ("Kettle", socket("Kettle", true, 2000.0)),
+            ("Fridge", socket("Fridge", true, 150.0)),
+            ("Thermo", SmartThermometer::new("Thermo".to_string(), 21.0))
+        );
+        let office = create_room!("Office", ("Desk", socket("Desk", false, 100.0)));
+        let mut house = SmartHouse::new(
+            "Test House".to_string(),
+            vec![
+                ("Kitchen".to_string(), kitchen),
+                ("Office".to_string(), office),
+            ],
+        );
+
+        clock.advance(Duration::from_secs(30 * 60));
+        house
+            .turn_off_device(&"Kitchen".to_string(), &"Kettle".to_string())
+            .unwrap();
+        house
+            .turn_on_device(&"Office".to_string(), &"Desk".to_string())
+            .unwrap();
+        clock.advance(Duration::from_secs(90 * 60));
+
+        // Kettle 1000 Wh, fridge 300 Wh, desk 150 Wh
+        let kitchen = house.room(&"Kitchen".to_string()).unwrap();
+        assert_eq!(kitchen.energy_wh(), 1300.0);
+        assert_eq!(house.energy_wh(), 1450.0);
+        assert_eq!(house.energy_kwh(), 1.45);
+
+        house
+            .reset_device_energy(&"Kitchen".to_string(), &"Fridge".to_string())
+            .unwrap();
+        assert_eq!(house.energy_wh(), 1150.0);
+        assert!(matches!(
+            house.reset_device_energy(&"Kitchen".to_string(), &"Thermo".to_string()),
+            Err(SmartHomeError::Access(
+                DeviceAccessError::UnsupportedCapability(_, Capability::EnergyMeter)
+            ))
+        ));
+
+        house.reset_energy();
+        assert_eq!(house.energy_wh(), 0.0);
+    }
+
+    #[test]
+    fn test_power_budget() {
+        use crate::budget::PowerBudget;
+
+        struct TestCase {
+            name: &'static str,
+            room_budget: Option<PowerBudget>,
+            house_budget: PowerBudget,
+            expected_error: Option<&'static str>,
+            expected_events: Vec<(&'static str, &'static str, bool)>,
+            expected_power: f32,
+        }
+
+        let create_house = || {
+            let mut kitchen = create_room!(
+                "Kitchen",
+                (
+                    "Kettle",
+                    SmartSocket::new("Kettle".to_string(), false, 2000.0)
+                ),
+                ("Radio", SmartSocket::new("Radio".to_string(), true, 20.0)),
+                (
+                    "Fridge",
+                    SmartSocket::new("Fridge".to_string(), true, 150.0)
+                )
+            );
+            kitchen
+                .set_device_priority(&"Radio".to_string(), -2)
+                .unwrap();
+            kitchen
+                .set_device_priority(&"Fridge".to_string(), 10)
+                .unwrap();
+            let mut office = create_room!(
+                "Office",
+                (
+                    "Heater",
+                    SmartSocket::new("Heater".to_string(), true, 1000.0)
+                )
+            );
+            office
+                .set_device_priority(&"Heater".to_string(), -1)
+                .unwrap();
+            SmartHouse::new(
+                "Test House".to_string(),
+                vec![
+                    ("Kitchen".to_string(), kitchen),
+                    ("Office".to_string(), office),
+                ],
+            )
+        };
+
+        let test_cases = vec![
+            TestCase {
+                name: "Within budget",
+                room_budget: None,
+                house_budget: PowerBudget::reject(4000.0),
+                expected_error: None,
+                expected_events: vec![("Kitchen", "Kettle", true)],
+                expected_power: 3170.0,
+            },
+            TestCase {
+                name: "Rejected by the house",
+                room_budget: None,
+                house_budget: PowerBudget::reject(3000.0),
+                expected_error: Some(
+                    "Power budget of house 'Test House' exceeded: 3170.0 W requested, limit is 3000.0 W",
+                ),
+                expected_events: vec![],
+                expected_power: 1170.0,
+            },
+            TestCase {
+                name: "Devices are shed across rooms",
+                room_budget: None,
+                house_budget: PowerBudget::shed(2200.0),
+                expected_error: None,
+                expected_events: vec![
+                    ("Kitchen", "Kettle", true),
+                    ("Kitchen", "Radio", false),
+                    ("Office", "Heater", false),
+                ],
+                expected_power: 2150.0,
+            },
+            TestCase {
+                name: "Devices shed by the room are restored when the house rejects",
+                room_budget: Some(PowerBudget::shed(2160.0)),
+                house_budget: PowerBudget::reject(3000.0),
+                expected_error: Some(
+                    "Power budget of house 'Test House' exceeded: 3150.0 W requested, limit is 3000.0 W",
+                ),
+                expected_events: vec![],
+                expected_power: 1170.0,
+            },
+        ];
+
+        for tc in test_cases {
+            let mut house = create_house();
+            house
+                .room_mut(&"Kitchen".to_string())
+                .unwrap()
+                .set_power_budget(tc.room_budget)
+                .unwrap();
+            house.set_power_budget(Some(tc.house_budget)).unwrap();
+            let receiver = house.events_mut().channel();
+
+            let result = house.turn_on_device(&"Kitchen".to_string(), &"Kettle".to_string());
+
+            assert_eq!(
+                result.err().map(|e| e.to_string()).as_deref(),
+                tc.expected_error,
+                "Failed test: {}",
+                tc.name
+            );
+            assert_eq!(
+                receiver.try_iter().collect::<Vec<_>>(),
+                tc.expected_events
+                    .into_iter()
+                    .map(|(room, device, is_on)| HouseEvent::PowerStateChanged {
+                        room: room.to_string(),
+                        device: device.to_string(),
+                        is_on,
+                    })
+                    .collect::<Vec<_>>(),
+                "Failed test: {}",
+                tc.name
+            );
+            assert_eq!(
+                house.total_power_consumption(),
+                tc.expected_power,
+                "Failed test: {}",
+                tc.name
+            );
+        }
+    }
+
+    #[test]
+    fn test_scene_over_budget() {
+        use crate::automation::DeviceRef;
+        use crate::budget::PowerBudget;
+
+        let mut house = create_house_with_devices();
+        house
+            .add_device(
+                &"Bedroom".to_string(),
+                "Heater".to_string(),
+                SmartSocket::new("Heater".to_string(), false, 1000.0).into(),
+            )
+            .unwrap();
+        house
+            .turn_off_device(
+                &"Living Room".to_string(),
+                &"Living Room Socket".to_string(),
+            )
+            .unwrap();
+        house
+            .set_power_budget(Some(PowerBudget::reject(1050.0)))
+            .unwrap();
+        let receiver = house.events_mut().channel();
+
+        let scene = Scene::new("Warm evening")
+            .with_on(DeviceRef::new("Living Room", "Living Room Socket"))
+            .with_on(DeviceRef::new("Bedroom", "Heater"));
+        let report = house.apply_scene(&scene);
+
+        assert!(!report.applied());
+        assert_eq!(
+            report
+                .outcomes
+                .iter()
+                .map(|outcome| outcome.status.clone())
+                .collect::<Vec<_>>(),
+            vec![
+                TargetStatus::NotApplied,
+                TargetStatus::OverBudget(
+                    "Power budget of house 'Smart Home' exceeded: 1080.0 W requested, limit is 1050.0 W"
+                        .to_string()
+                ),
+            ]
+        );
+        assert_eq!(house.total_power_consumption(), 0.0);
+        assert_eq!(receiver.try_iter().count(), 0);
+    }
+
+    #[test]
+    fn test_scene_rollback_only_touches_switched_devices() {
+        use crate::automation::DeviceRef;
+        use crate::device::{CustomDevice, PowerControl, SmartDeviceTrait};
+        use crate::error::ProtocolError;
+        use std::sync::atomic::{AtomicUsize, Ordering};
+
+        /// Relay counting its switches, optionally refusing to turn on
+        #[derive(Debug)]
+        struct Relay {
+            name: String,
+            is_on: bool,
+            fails_on: bool,
+            switches: Arc<AtomicUsize>,
+        }
+
+        impl SmartDeviceTrait for Relay {
+            fn name(&self) -> &str {
+                &self.name
+            }
+
+            fn as_power_control(&self) -> Option<&dyn PowerControl> {
+                Some(self)
+            }
+
+            fn as_power_control_mut(&mut self) -> Option<&mut dyn PowerControl> {
+                Some(self)
+            }
+        }
+
+        impl PowerControl for Relay {
+            fn is_on(&self) -> bool {
+                self.is_on
+            }
+
+            fn turn_on(&mut self) {
+                self.switches.fetch_add(1, Ordering::SeqCst);
+                self.is_on = true;
+            }
+
+            fn turn_off(&mut self) {
+                self.switches.fetch_add(1, Ordering::SeqCst);
+                self.is_on = false;
+            }
+
+            fn try_turn_on(&mut self) -> Result<(), SmartHomeError> {
+                if self.fails_on {
+                    return Err(ProtocolError::Rejected(0x01).into());
+                }
+                self.turn_on();
+                Ok(())
+            }
+        }
+
+        impl Reporter for Relay {
+            fn report(&self) -> String {
+                format!("Device: {}", self.name)
+            }
+        }
+
+        impl CustomDevice for Relay {
+            fn kind(&self) -> &str {
+                "relay"
+            }
+        }
+
+        let mut house = create_house_with_devices();
+        let bedroom = "Bedroom".to_string();
+        let untouched = Arc::new(AtomicUsize::new(0));
+        for (name, is_on, fails_on, switches) in [
+            ("Spy", true, false, Arc::clone(&untouched)),
+            ("Fan", true, true, Arc::new(AtomicUsize::new(0))),
+            ("Broken", false, true, Arc::new(AtomicUsize::new(0))),
+        ] {
+            let relay = Relay {
+                name: name.to_string(),
+                is_on,
+                fails_on,
+                switches,
+            };
+            house
+                .add_device(&bedroom, name.to_string(), SmartDevice::custom(relay))
+                .unwrap();
+        }
+        let receiver = house.events_mut().channel();
+
+        let report = house.apply_scene(
+            &Scene::new("Night")
+                .with_off(DeviceRef::new("Living Room", "Living Room Socket"))
+                .with_off(DeviceRef::new("Bedroom", "Fan"))
+                .with_on(DeviceRef::new("Bedroom", "Broken")),
+        );
+
+        assert!(!report.applied());
+        assert_eq!(
+            report
+                .outcomes
+                .iter()
+                .map(|outcome| outcome.status.clone())
+                .collect::<Vec<_>>(),
+            vec![
+                TargetStatus::NotApplied,
+                TargetStatus::NotApplied,
+                TargetStatus::Failed("Command rejected with error code 0x01".to_string()),
+            ]
+        );
+        assert_eq!(
+            report.not_restored,
+            vec![(
+                DeviceRef::new("Bedroom", "Fan"),
+                "Command rejected with error code 0x01".to_string()
+            )]
+        );
+        assert_eq!(
+            house
+                .device(
+                    &"Living Room".to_string(),
+                    &"Living Room Socket".to_string()
+                )
+                .unwrap()
+                .is_on(),
+            Some(true)
+        );
+        assert_eq!(
+            house.device(&bedroom, &"Fan".to_string()).unwrap().is_on(),
+            Some(false)
+        );
+        assert_eq!(untouched.load(Ordering::SeqCst), 0);
+        assert_eq!(receiver.try_iter().count(), 0);
+    }
+
+    #[test]
+    fn test_structured_report_rendering() {
+        use crate::report::{
+            CsvRenderer, JsonRenderer, MarkdownRenderer, ReportRenderer, TextRenderer,
+        };
+
+        struct RenderTestCase {
+            name: &'static str,
+            renderer: &'static dyn ReportRenderer,
+            expected_content: Vec<&'static str>,
+        }
+
+        let test_cases = vec![
+            RenderTestCase {
+                name: "Plain text",
+                renderer: &TextRenderer,
+                expected_content: vec![
+                    "=== Smart House: Smart Home ===",
+                    "Device: Living Room Socket (socket), is_on: true, power: 80W",
+                    "Device: Bedroom Thermometer (thermometer), temperature: 19.5°C",
+                ],
+            },
+            RenderTestCase {
+                name: "JSON",
+                renderer: &JsonRenderer,
+                expected_content: vec!["\"name\": \"Smart Home\"", "\"kind\": \"socket\""],
+            },
+            RenderTestCase {
+                name: "CSV",
+                renderer: &CsvRenderer,
+                expected_content: vec![
+                    "room,device,kind,metric,value,unit",
+                    "Living Room,Living Room Socket,socket,power,80,W",
+                    "Bedroom,Bedroom Thermometer,thermometer,temperature,19.5,°C",
+                ],
+            },
+            RenderTestCase {
+                name: "Markdown",
+                renderer: &MarkdownRenderer,
+                expected_content: vec![
+                    "# Smart House: Smart Home",
+                    "## Room: Bedroom",
+                    "| Living Room Socket | socket | is_on: true, power: 80W |",
+                ],
+            },
+        ];
+
+        let house = create_house_with_devices();
+        let report = house.structured_report();
+        assert_eq!(report.rooms.len(), 2);
+
+        for tc in test_cases {
+            let output = house.render(tc.renderer);
+
+            for expected in tc.expected_content {
+                assert!(
+                    output.contains(expected),
+                    "Test case '{}': Output should contain '{}', got: '{}'",
+                    tc.name,
+                    expected,
+                    output
+                );
+            }
+        }
+
+        let bedroom = house.room(&"Bedroom".to_string()).unwrap();
+        assert_eq!(
+            bedroom.render(&TextRenderer),
+            "=== Room: Bedroom ===\n\
+             Device: Bedroom Thermometer (thermometer), temperature: 19.5°C\n\
+             Summary: average_temperature: 19.5°C, min_temperature: 19.5°C, max_temperature: 19.5°C\n"
+        );
+    }
+
+    #[test]
+    fn test_room_order() {
+        struct RoomOrderTestCase {
+            name: &'static str,
+            order: ReportOrder,
+            expected_rooms: Vec<&'static str>,
+            expected_devices: Vec<&'static str>,
+        }
+
+        let test_cases = vec![
+            RoomOrderTestCase {
+                name: "Insertion order",
+                order: ReportOrder::Insertion,
+                expected_rooms: vec!["Living Room", "Bedroom"],
+                expected_devices: vec![
+                    "Living Room Thermometer",
+                    "Living Room Socket",
+                    "Bedroom Thermometer",
+                ],
+            },
+            RoomOrderTestCase {
+                name: "By name",
+                order: ReportOrder::Name,
+                expected_rooms: vec!["Bedroom", "Living Room"],
+                expected_devices: vec![
+                    "Bedroom Thermometer",
+                    "Living Room Socket",
+                    "Living Room Thermometer",
+                ],
+            },
+            RoomOrderTestCase {
+                name: "By power draw",
+                order: ReportOrder::PowerDraw,
+                expected_rooms: vec!["Living Room", "Bedroom"],
+                expected_devices: vec![
+                    "Living Room Socket",
+                    "Living Room Thermometer",
+                    "Bedroom Thermometer",
+                ],
+            },
+        ];
+
+        let house = create_house_with_devices();
+
+        for tc in test_cases {
+            let rooms: Vec<&str> = house
+                .rooms_ordered(tc.order)
+                .into_iter()
+                .map(|(_, room)| room.name())
+                .collect();
+            assert_eq!(rooms, tc.expected_rooms, "Failed test case '{}'", tc.name);
+
+            let report = house.structured_report_ordered(tc.order);
+            let devices: Vec<&str> = report
+                .rooms
+                .iter()
+                .flat_map(|room| room.devices.iter().map(|d| d.name.as_str()))
+                .collect();
+            assert_eq!(
+                devices, tc.expected_devices,
+                "Failed test case '{}'",
+                tc.name
+            );
+
+            // Reports are identical from run to run
+            assert_eq!(
+                house.report_ordered(tc.order),
+                house.report_ordered(tc.order)
+            );
+        }
+    }
+
+    #[test]
+    fn test_events() {
+        struct EventTestCase {
+            name: &'static str,
+            operation: fn(&mut SmartHouse) -> Result<(), SmartHomeError>,
+            expected_events: Vec<HouseEvent>,
+        }
+
+        fn event_in(
+            room: &str,
+            device: &str,
+            make: fn(String, String) -> HouseEvent,
+        ) -> HouseEvent {
+            make(room.to_string(), device.to_string())
+        }
+
+        let test_cases = vec![
+            EventTestCase {
+                name: "Add a room",
+                operation: |house| house.add_room("Garage".to_string(), create_room!("Garage")),
+                expected_events: vec![HouseEvent::RoomAdded {
+                    room: "Garage".to_string(),
+                }],
+            },
+            EventTestCase {
+                name: "Remove a room",
+                operation: |house| house.remove_room(&"Bedroom".to_string()).map(|_| ()),
+                expected_events: vec![HouseEvent::RoomRemoved {
+                    room: "Bedroom".to_string(),
+                }],
+            },
+            EventTestCase {
+                name: "Add and remove a device",
+                operation: |house| {
+                    let socket = SmartSocket::new("Lamp".to_string(), false, 5.0);
+                    house.add_device(&"Bedroom".to_string(), "Lamp".to_string(), socket.into())?;
+                    house
+                        .remove_device(&"Bedroom".to_string(), &"Lamp".to_string())
+                        .map(|_| ())
+                },
+                expected_events: vec![
+                    event_in("Bedroom", "Lamp", |room, device| HouseEvent::DeviceAdded {
+                        room,
+                        device,
+                    }),
+                    event_in("Bedroom", "Lamp", |room, device| {
+                        HouseEvent::DeviceRemoved { room, device }
+                    }),
+                ],
+            },
+            EventTestCase {
+                name: "Only actual power changes are published",
+                operation: |house| {
+                    let room = "Living Room".to_string();
+                    let socket = "Living Room Socket".to_string();
+                    house.turn_on_device(&room, &socket)?;
+                    house.turn_off_device(&room, &socket)?;
+                    house.turn_off_device(&room, &socket)
+                },
+                expected_events: vec![HouseEvent::PowerStateChanged {
+                    room: "Living Room".to_string(),
+                    device: "Living Room Socket".to_string(),
+                    is_on: false,
+                }],
+            },
+            EventTestCase {
+                name: "Failed operations publish nothing",
+                operation: |house| {
+                    let _ = house.add_room("Bedroom".to_string(), create_room!("Bedroom"));
+                    house.turn_on_device(&"Bedroom".to_string(), &"Bedroom Thermometer".to_string())
+                },
+                expected_events: vec![],
+            },
+            EventTestCase {
+                name: "Temperature updates",
+                operation: |house| {
+                    let room = "Bedroom".to_string();
+                    let thermometer = "Bedroom Thermometer".to_string();
+                    house.update_temperature(&room, &thermometer, 20.5)?;
+                    house.update_temperature(&room, &thermometer, 20.5)?;
+                    house.update_temperature(&room, &thermometer, f32::NAN)
+                },
+                expected_events: vec![HouseEvent::TemperatureUpdated {
+                    room: "Bedroom".to_string(),
+                    device: "Bedroom Thermometer".to_string(),
+                    temperature: 20.5,
+                }],
+            },
+        ];
+
+        for tc in test_cases {
+            let mut house = create_house_with_devices();
+            let callback_events = Arc::new(Mutex::new(Vec::new()));
+            {
+                let callback_events = Arc::clone(&callback_events);
+                house
+                    .events_mut()
+                    .subscribe(move |event| callback_events.lock().unwrap().push(event.clone()));
+            }
+            let receiver = house.events_mut().channel();
+
+            let _ = (tc.operation)(&mut house);
+
+            assert_eq!(
+                *callback_events.lock().unwrap(),
+                tc.expected_events,
+                "Callback events differ in test case '{}'",
+                tc.name
+            );
+            assert_eq!(
+                receiver.try_iter().collect::<Vec<_>>(),
+                tc.expected_events,
+                "Channel events differ in test case '{}'",
+                tc.name
+            );
+        }
+    }
+
+    #[test]
+    fn test_poll_temperatures() {
+        let mut house = create_house_with_devices();
+        let receiver = house.events_mut().channel();
+
+        assert_eq!(
+            house.poll_temperatures(),
+            2,
+            "First poll publishes every sensor"
+        );
+        assert_eq!(
+            house.poll_temperatures(),
+            0,
+            "Unchanged readings are not published"
+        );
+
+        let Ok(SmartDevice::Thermometer(thermometer)) =
+            house.device_mut(&"Bedroom".to_string(), &"Bedroom Thermometer".to_string())
+        else {
+            panic!("Bedroom Thermometer should be a thermometer");
+        };
+        thermometer.set_temperature(18.0);
+        assert_eq!(house.poll_temperatures(), 1);
+
+        let last = receiver.try_iter().last().unwrap();
+        assert_eq!(
+            last,
+            HouseEvent::TemperatureUpdated {
+                room: "Bedroom".to_string(),
+                device: "Bedroom Thermometer".to_string(),
+                temperature: 18.0,
+            }
+        );
+    }
 }
@@ -0,0 +1,470 @@
+use super::message::{Request, Response};
+use crate::error::{DeviceAccessError, LockError, PersistenceError, SmartHomeError};
+use crate::report::{CsvRenderer, JsonRenderer, MarkdownRenderer, ReportRenderer, TextRenderer};
+use crate::storage::{self, DeviceRegistry};
+use crate::{Reporter, SharedHouse, StructuredReport};
+use serde_json::Value;
+
+/// An error answered with a status code and a JSON message
+#[derive(Debug)]
+struct ApiError {
+    status: u16,
+    message: String,
+}
+
+impl ApiError {
+    fn new(status: u16, message: impl Into<String>) -> Self {
+        Self {
+            status,
+            message: message.into(),
+        }
+    }
+}
+
+impl From<DeviceAccessError> for ApiError {
+    fn from(error: DeviceAccessError) -> Self {
+        let status = match error {
+            DeviceAccessError::RoomNotFound(_) | DeviceAccessError::DeviceNotFound(..) => 404,
+            DeviceAccessError::UnsupportedCapability(..) => 422,
+        };
+        Self::new(status, error.to_string())
+    }
+}
+
+impl From<SmartHomeError> for ApiError {
+    fn from(error: SmartHomeError) -> Self {
+        let status = match error {
+            SmartHomeError::Access(error) => return error.into(),
+            SmartHomeError::DuplicateRoom(_)
+            | SmartHomeError::DuplicateDevice(..)
+            | SmartHomeError::BudgetExceeded(..) => 409,
+            SmartHomeError::InvalidValue(..) => 400,
+            SmartHomeError::Lock(LockError::InvalidPin(_)) => 403,
+            SmartHomeError::Lock(LockError::Jammed(_)) => 409,
+            SmartHomeError::Lock(LockError::LockedOut(..)) => 429,
+            SmartHomeError::RollbackFailed(..)
+            | SmartHomeError::Io(_)
+            | SmartHomeError::Persistence(_) => 500,
+            SmartHomeError::Protocol(_) => 502,
+        };
+        Self::new(status, error.to_string())
+    }
+}
+
+/// Bodies are decoded with the house file format, so a bad record is the client's fault
+impl From<PersistenceError> for ApiError {
+    fn from(error: PersistenceError) -> Self {
+        Self::new(400, error.to_string())
+    }
+}
+
+/// Answers a request against the house
+pub(crate) fn handle(
+    house: &SharedHouse,
+    registry: &DeviceRegistry,
+    request: &Request,
+) -> Response {
+    route(house, registry, request)
+        .unwrap_or_else(|error| Response::error(error.status, &error.message))
+}
+
+fn route(
+    house: &SharedHouse,
+    registry: &DeviceRegistry,
+    request: &Request,
+) -> Result<Response, ApiError> {
+    let segments: Vec<&str> = request.segments.iter().map(String::as_str).collect();
+    let method = request.method.as_str();
+
+    match (method, segments.as_slice()) {
+        ("GET", ["report"]) => report(house, request),
+        ("GET", ["rooms"]) => Ok(Response::json(200, &house.room_names())),
+        ("POST", ["rooms"]) => {
+            let (key, room) = storage::room_from_value(json_body(request)?, registry)?;
+            let report = room.structured_report();
+            house.add_room(key, room)?;
+            Ok(Response::json(201, &report))
+        }
+        ("GET", ["rooms", room]) => {
+            let report = house.with_room(&room.to_string(), |room| room.structured_report())?;
+            Ok(Response::json(200, &report))
+        }
+        ("DELETE", ["rooms", room]) => {
+            house.remove_room(&room.to_string())?;
+            Ok(Response::empty(204))
+        }
+        ("GET", ["rooms", room, "devices"]) => {
+            let keys = house.with_room(&room.to_string(), |room| {
+                room.all_devices().keys().cloned().collect::<Vec<_>>()
+            })?;
+            Ok(Response::json(200, &keys))
+        }
+        ("POST", ["rooms", room, "devices"]) => {
+            let (key, device) = storage::device_from_value(json_body(request)?, room, registry)?;
+            let report = device.structured_report();
+            house.add_device(&room.to_string(), key, device)?;
+            Ok(Response::json(201, &report))
+        }
+        ("GET", ["rooms", room, "devices", device]) => device_state(house, room, device),
+        ("DELETE", ["rooms", room, "devices", device]) => {
+            house.remove_device(&room.to_string(), &device.to_string())?;
+            Ok(Response::empty(204))
+        }
+        (
+            "POST",
+            [
+                "rooms",
+                room,
+                "devices",
+                device,
+                action @ ("turn_on" | "turn_off"),
+            ],
+        ) => {
+            let (room_key, device_key) = (room.to_string(), device.to_string());
+            if *action == "turn_on" {
+                house.turn_on_device(&room_key, &device_key)?;
+            } else {
+                house.turn_off_device(&room_key, &device_key)?;
+            }
+            device_state(house, room, device)
+        }
+        (_, ["report"])
+        | (_, ["rooms"])
+        | (_, ["rooms", _])
+        | (_, ["rooms", _, "devices"])
+        | (_, ["rooms", _, "devices", _])
+        | (_, ["rooms", _, "devices", _, "turn_on" | "turn_off"]) => Err(ApiError::new(
+            405,
+            format!("Method {} is not allowed here", method),
+        )),
+        _ => Err(ApiError::new(404, "No such resource")),
+    }
+}
+
+fn device_state(house: &SharedHouse, room: &str, device: &str) -> Result<Response, ApiError> {
+    let report = house.with_device(&room.to_string(), &device.to_string(), |device| {
+        device.structured_report()
+    })?;
+    Ok(Response::json(200, &report))
+}
+
+/// Renders the house report in the format picked by `?format=` or the `Accept` header
+fn report(house: &SharedHouse, request: &Request) -> Result<Response, ApiError> {
+    let accepts_json = request
+        .header("accept")
+        .is_some_and(|accept| accept.contains("application/json"));
+    let format = request
+        .query("format")
+        .unwrap_or(if accepts_json { "json" } else { "text" });
+
+    let (content_type, renderer): (_, &dyn ReportRenderer) = match format {
+        // The plain report of the `Reporter` trait
+        "text" => {
+            return Ok(Response::text(
+                200,
+                "text/plain; charset=utf-8",
+                house.report(),
+            ));
+        }
+        "json" => ("application/json", &JsonRenderer),
+        "csv" => ("text/csv; charset=utf-8", &CsvRenderer),
+        "markdown" => ("text/markdown; charset=utf-8", &MarkdownRenderer),
+        "table" => ("text/plain; charset=utf-8", &TextRenderer),
+        other => {
+            return Err(ApiError::new(
+                400,
+                format!("Unknown report format '{}'", other),
+            ));
+        }
+    };
+    Ok(Response::text(200, content_type, house.render(renderer)))
+}
+
+fn json_body(request: &Request) -> Result<Value, ApiError> {
+    if request
+        .header("content-type")
+        .is_some_and(|content_type| !content_type.starts_with("application/json"))
+    {
+        return Err(ApiError::new(415, "Request body must be JSON"));
+    }
+    serde_json::from_slice(&request.body)
+        .map_err(|e| ApiError::new(400, format!("Invalid JSON body: {}", e)))
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::{SmartHouse, SmartSocket, SmartThermometer, create_room};
+
+    fn create_house() -> SharedHouse {
+        let living_room = create_room!(
+            "Living Room",
+            ("TV", SmartSocket::new("TV".to_string(), false, 120.0)),
+            (
+                "Thermometer",
+                SmartThermometer::new("Thermometer".to_string(), 22.0)
+            )
+        );
+        SmartHouse::new(
+            "Test House".to_string(),
+            vec![("Living Room".to_string(), living_room)],
+        )
+        .into()
+    }
+
+    fn request(method: &str, target: &str, body: &str) -> Request {
+        let mut request = Request::new(method, target);
+        request.body = body.as_bytes().to_vec();
+        request
+    }
+
+    fn body(response: &Response) -> Value {
+        serde_json::from_slice(&response.body).unwrap_or(Value::Null)
+    }
+
+    #[test]
+    fn test_routes() {
+        struct TestCase {
+            name: &'static str,
+            method: &'static str,
+            target: &'static str,
+            body: &'static str,
+            expected_status: u16,
+            expected_body: Value,
+        }
+
+        let test_cases = vec![
+            TestCase {
+                name: "List rooms",
+                method: "GET",
+                target: "/rooms",
+                body: "",
+                expected_status: 200,
+                expected_body: serde_json::json!(["Living Room"]),
+            },
+            TestCase {
+                name: "List devices",
+                method: "GET",
+                target: "/rooms/Living%20Room/devices",
+                body: "",
+                expected_status: 200,
+                expected_body: serde_json::json!(["TV", "Thermometer"]),
+            },
+            TestCase {
+                name: "Device state",
+                method: "GET",
+                target: "/rooms/Living%20Room/devices/TV",
+                body: "",
+                expected_status: 200,
+                expected_body: serde_json::json!({
+                    "name": "TV",
+                    "kind": "socket",
+                    "metrics": [
+                        {"name": "is_on", "value": false},
+                        {"name": "power", "value": 0.0, "unit": "W"},
+                    ],
+                }),
+            },
+            TestCase {
+                name: "Turn on",
+                method: "POST",
+                target: "/rooms/Living%20Room/devices/TV/turn_on",
+                body: "",
+                expected_status: 200,
+                expected_body: serde_json::json!({
+                    "name": "TV",
+                    "kind": "socket",
+                    "metrics": [
+                        {"name": "is_on", "value": true},
+                        {"name": "power", "value": 120.0, "unit": "W"},
+                    ],
+                }),
+            },
+            TestCase {
+                name: "Missing room",
+                method: "GET",
+                target: "/rooms/Attic/devices",
+                body: "",
+                expected_status: 404,
+                expected_body: serde_json::json!({"error": "Room 'Attic' not found"}),
+            },
+            TestCase {
+                name: "Missing device",
+                method: "POST",
+                target: "/rooms/Living%20Room/devices/Radio/turn_off",
+                body: "",
+                expected_status: 404,
+                expected_body: serde_json::json!({
+                    "error": "Device 'Radio' not found in room 'Living Room'"
+                }),
+            },
+            TestCase {
+                name: "Switching a thermometer",
+                method: "POST",
+                target: "/rooms/Living%20Room/devices/Thermometer/turn_on",
+                body: "",
+                expected_status: 422,
+                expected_body: serde_json::json!({
+                    "error": "Device 'Thermometer' does not support power control"
+                }),
+            },
+            TestCase {
+                name: "Add a room with a device",
+                method: "POST",
+                target: "/rooms",
+                body: r#"{"key": "Garage", "devices": [
+                    {"key": "Charger", "kind": "socket", "is_on": true, "power_consumption": 7000.0}
+                ]}"#,
+                expected_status: 201,
+                expected_body: serde_json::json!({
+                    "name": "Garage",
+                    "devices": [{
+                        "name": "Charger",
+                        "kind": "socket",
+                        "metrics": [
+                            {"name": "is_on", "value": true},
+                            {"name": "power", "value": 7000.0, "unit": "W"},
+                        ],
+                    }],
+                }),
+            },
+            TestCase {
+                name: "Duplicate room",
+                method: "POST",
+                target: "/rooms",
+                body: r#"{"key": "Living Room"}"#,
+                expected_status: 409,
+                expected_body: serde_json::json!({
+                    "error": "Room 'Living Room' already exists"
+                }),
+            },
+            TestCase {
+                name: "Unknown device kind",
+                method: "POST",
+                target: "/rooms/Living%20Room/devices",
+                body: r#"{"key": "Fan", "kind": "fan"}"#,
+                expected_status: 400,
+                expected_body: serde_json::json!({
+                    "error": "Device 'Fan' in room 'Living Room' has unknown kind 'fan'"
+                }),
+            },
+            TestCase {
+                name: "Remove a device",
+                method: "DELETE",
+                target: "/rooms/Living%20Room/devices/Thermometer",
+                body: "",
+                expected_status: 204,
+                expected_body: Value::Null,
+            },
+            TestCase {
+                name: "Wrong method",
+                method: "PUT",
+                target: "/rooms",
+                body: "",
+                expected_status: 405,
+                expected_body: serde_json::json!({"error": "Method PUT is not allowed here"}),
+            },
+            TestCase {
+                name: "Unknown route",
+                method: "GET",
+                target: "/garden",
+                body: "",
+                expected_status: 404,
+                expected_body: serde_json::json!({"error": "No such resource"}),
+            },
+        ];
+
+        let house = create_house();
+        let registry = DeviceRegistry::new();
+        for tc in test_cases {
+            let response = handle(&house, &registry, &request(tc.method, tc.target, tc.body));
+            assert_eq!(
+                response.status, tc.expected_status,
+                "Failed test: {}",
+                tc.name
+            );
+            assert_eq!(
+                body(&response),
+                tc.expected_body,
+                "Failed test: {}",
+                tc.name
+            );
+        }
+
+        assert_eq!(house.room_names(), vec!["Living Room", "Garage"]);
+    }
+
+    #[test]
+    fn test_report_formats() {
+        struct TestCase {
+            name: &'static str,
+            target: &'static str,
+            accept: Option<&'static str>,
+            expected_status: u16,
+            expected_content_type: &'static str,
+            expected_start: &'static str,
+        }
+
+        let test_cases = vec![
+            TestCase {
+                name: "Text by default",
+                target: "/report",
+                accept: None,
+                expected_status: 200,
+                expected_content_type: "text/plain; charset=utf-8",
+                expected_start: "=== Smart House: Test House ===",
+            },
+            TestCase {
+                name: "JSON from the Accept header",
+                target: "/report",
+                accept: Some("application/json"),
+                expected_status: 200,
+                expected_content_type: "application/json",
+                expected_start: "{",
+            },
+            TestCase {
+                name: "CSV from the query",
+                target: "/report?format=csv",
+                accept: Some("application/json"),
+                expected_status: 200,
+                expected_content_type: "text/csv; charset=utf-8",
+                expected_start: "room,device,kind,metric,value,unit",
+            },
+            TestCase {
+                name: "Unknown format",
+                target: "/report?format=pdf",
+                accept: None,
+                expected_status: 400,
+                expected_content_type: "application/json",
+                expected_start: "{",
+            },
+        ];
+
+        let house = create_house();
+        for tc in test_cases {
+            let mut request = Request::new("GET", tc.target);
+            if let Some(accept) = tc.accept {
+                request
+                    .headers
+                    .push(("accept".to_string(), accept.to_string()));
+            }
+
+            let response = handle(&house, &DeviceRegistry::new(), &request);
+            assert_eq!(
+                response.status, tc.expected_status,
+                "Failed test: {}",
+                tc.name
+            );
+            assert_eq!(
+                response.content_type, tc.expected_content_type,
+                "Failed test: {}",
+                tc.name
+            );
+            assert!(
+                String::from_utf8_lossy(&response.body).starts_with(tc.expected_start),
+                "Failed test: {}",
+                tc.name
+            );
+        }
+    }
+}
@@ -0,0 +1,415 @@
+use serde::Serialize;
+use std::io::{self, BufRead, Read, Write};
+
+/// Largest request body accepted by the server
+pub(crate) const MAX_BODY: usize = 1 << 20;
+
+/// Largest request line or header line accepted by the server
+const MAX_LINE: usize = 8 * 1024;
+
+/// Most headers accepted in a single request
+const MAX_HEADERS: usize = 100;
+
+/// A parsed HTTP/1.1 request
+#[derive(Debug, Clone, PartialEq)]
+pub(crate) struct Request {
+    pub method: String,
+    /// Percent-decoded path segments, e.g. `["rooms", "Living Room"]`
+    pub segments: Vec<String>,
+    pub query: Vec<(String, String)>,
+    pub headers: Vec<(String, String)>,
+    pub body: Vec<u8>,
+}
+
+/// Reasons a request could not be read
+#[derive(Debug)]
+pub(crate) enum ReadError {
+    Io(io::Error),
+    Malformed(&'static str),
+    TooLarge,
+    TooManyHeaders,
+}
+
+impl From<io::Error> for ReadError {
+    fn from(e: io::Error) -> Self {
+        ReadError::Io(e)
+    }
+}
+
+impl Request {
+    /// Creates a request without headers or body
+    #[cfg(test)]
+    pub fn new(method: &str, target: &str) -> Self {
+        let (segments, query) = parse_target(target).expect("valid request target");
+        Self {
+            method: method.to_string(),
+            segments,
+            query,
+            headers: Vec::new(),
+            body: Vec::new(),
+        }
+    }
+
+    /// Reads a request from a stream
+    ///
+    /// Returns `Ok(None)` when the peer closed the connection before sending anything.
+    pub fn read_from<R: BufRead>(reader: &mut R) -> Result<Option<Self>, ReadError> {
+        let Some(line) = read_line(reader)? else {
+            return Ok(None);
+        };
+
+        let mut parts = line.split(' ');
+        let (Some(method), Some(target), Some(version), None) =
+            (parts.next(), parts.next(), parts.next(), parts.next())
+        else {
+            return Err(ReadError::Malformed("invalid request line"));
+        };
+        if !version.starts_with("HTTP/1.") {
+            return Err(ReadError::Malformed("unsupported HTTP version"));
+        }
+        let (segments, query) = parse_target(target)?;
+
+        let mut headers = Vec::new();
+        loop {
+            let line = read_line(reader)?.ok_or(ReadError::Malformed("truncated headers"))?;
+            if line.is_empty() {
+                break;
+            }
+            if headers.len() == MAX_HEADERS {
+                return Err(ReadError::TooManyHeaders);
+            }
+            let (name, value) = line
+                .split_once(':')
+                .ok_or(ReadError::Malformed("invalid header"))?;
+            headers.push((name.trim().to_ascii_lowercase(), value.trim().to_string()));
+        }
+
+        let mut request = Self {
+            method: method.to_string(),
+            segments,
+            query,
+            headers,
+            body: Vec::new(),
+        };
+
+        if request.header("transfer-encoding").is_some() {
+            return Err(ReadError::Malformed("chunked bodies are not supported"));
+        }
+        let length = match request.header("content-length") {
+            Some(value) => value
+                .parse::<usize>()
+                .map_err(|_| ReadError::Malformed("invalid content length"))?,
+            None => 0,
+        };
+        if length > MAX_BODY {
+            return Err(ReadError::TooLarge);
+        }
+        request.body = vec![0; length];
+        reader.read_exact(&mut request.body)?;
+
+        Ok(Some(request))
+    }
+
+    /// Returns the value of a header, matching the name case-insensitively
+    pub fn header(&self, name: &str) -> Option<&str> {
+        self.headers
+            .iter()
+            .find(|(header, _)| header.eq_ignore_ascii_case(name))
+            .map(|(_, value)| value.as_str())
+    }
+
+    /// Returns the value of a query parameter
+    pub fn query(&self, name: &str) -> Option<&str> {
+        self.query
+            .iter()
+            .find(|(key, _)| key == name)
+            .map(|(_, value)| value.as_str())
+    }
+}
+
+/// Reads a CRLF- or LF-terminated line without the terminator
+fn read_line<R: BufRead>(reader: &mut R) -> Result<Option<String>, ReadError> {
+    let mut line = Vec::new();
+    let read = reader
+        .by_ref()
+        .take(MAX_LINE as u64 + 1)
+        .read_until(b'\n', &mut line)?;
+    if read == 0 {
+        return Ok(None);
+    }
+    if line.last() != Some(&b'\n') {
+        return Err(if line.len() > MAX_LINE {
+            ReadError::TooLarge
+        } else {
+            ReadError::Malformed("truncated line")
+        });
+    }
+
+    line.pop();
+    if line.last() == Some(&b'\r') {
+        line.pop();
+    }
+    String::from_utf8(line)
+        .map(Some)
+        .map_err(|_| ReadError::Malformed("line is not UTF-8"))
+}
+
+type Target = (Vec<String>, Vec<(String, String)>);
+
+/// Splits a request target into decoded path segments and query parameters
+fn parse_target(target: &str) -> Result<Target, ReadError> {
+    let (path, query) = target.split_once('?').unwrap_or((target, ""));
+    if !path.starts_with('/') {
+        return Err(ReadError::Malformed(
+            "request target must be an absolute path",
+        ));
+    }
+
+    let segments = path
+        .split('/')
+        .filter(|segment| !segment.is_empty())
+        .map(|segment| percent_decode(segment, false))
+        .collect::<Result<_, _>>()?;
+    let query = query
+        .split('&')
+        .filter(|pair| !pair.is_empty())
+        .map(|pair| {
+            let (key, value) = pair.split_once('=').unwrap_or((pair, ""));
+            Ok((percent_decode(key, true)?, percent_decode(value, true)?))
+        })
+        .collect::<Result<_, ReadError>>()?;
+
+    Ok((segments, query))
+}
+
+/// Decodes `%XX` escapes, and `+` as a space in query strings
+fn percent_decode(input: &str, plus_is_space: bool) -> Result<String, ReadError> {
+    let mut bytes = Vec::with_capacity(input.len());
+    let mut rest = input.as_bytes();
+
+    while let Some((&byte, tail)) = rest.split_first() {
+        match byte {
+            b'%' => {
+                let hex = tail
+                    .get(..2)
+                    .and_then(|hex| std::str::from_utf8(hex).ok())
+                    .and_then(|hex| u8::from_str_radix(hex, 16).ok())
+                    .ok_or(ReadError::Malformed("invalid percent escape"))?;
+                bytes.push(hex);
+                rest = &tail[2..];
+                continue;
+            }
+            b'+' if plus_is_space => bytes.push(b' '),
+            other => bytes.push(other),
+        }
+        rest = tail;
+    }
+
+    String::from_utf8(bytes).map_err(|_| ReadError::Malformed("path is not UTF-8"))
+}
+
+/// An HTTP response; the connection is always closed after it is sent
+#[derive(Debug, Clone, PartialEq)]
+pub(crate) struct Response {
+    pub status: u16,
+    pub content_type: &'static str,
+    pub body: Vec<u8>,
+}
+
+impl Response {
+    /// Creates a response with a JSON body
+    pub fn json<T: Serialize + ?Sized>(status: u16, value: &T) -> Self {
+        match serde_json::to_vec_pretty(value) {
+            Ok(body) => Self {
+                status,
+                content_type: "application/json",
+                body,
+            },
+            Err(e) => Self::error(500, &e.to_string()),
+        }
+    }
+
+    /// Creates a response with a body of the given content type
+    pub fn text(status: u16, content_type: &'static str, body: String) -> Self {
+        Self {
+            status,
+            content_type,
+            body: body.into_bytes(),
+        }
+    }
+
+    /// Creates a response without a body
+    pub fn empty(status: u16) -> Self {
+        Self {
+            status,
+            content_type: "text/plain; charset=utf-8",
+            body: Vec::new(),
+        }
+    }
+
+    /// Creates a JSON error response, e.g. `{"error": "Room 'Attic' not found"}`
+    pub fn error(status: u16, message: &str) -> Self {
+        Self::json(status, &serde_json::json!({ "error": message }))
+    }
+
+    /// Writes the response to a stream
+    pub fn write_to<W: Write>(&self, writer: &mut W) -> io::Result<()> {
+        write!(
+            writer,
+            "HTTP/1.1 {} {}\r\nContent-Type: {}\r\nContent-Length: {}\r\nConnection: close\r\n\r\n",
+            self.status,
+            reason(self.status),
+            self.content_type,
+            self.body.len()
+        )?;
+        writer.write_all(&self.body)?;
+        writer.flush()
+    }
+}
+
+fn reason(status: u16) -> &'static str {
+    match status {
+        200 => "OK",
+        201 => "Created",
+        204 => "No Content",
+        400 => "Bad Request",
+        404 => "Not Found",
+        405 => "Method Not Allowed",
+        409 => "Conflict",
+        413 => "Payload Too Large",
+        415 => "Unsupported Media Type",
+        422 => "Unprocessable Entity",
+        431 => "Request Header Fields Too Large",
+        500 => "Internal Server Error",
+        502 => "Bad Gateway",
+        503 => "Service Unavailable",
+        _ => "Unknown",
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn test_read_request() {
+        struct TestCase {
+            name: &'static str,
+            input: &'static str,
+            expected_segments: Vec<&'static str>,
+            expected_query: Vec<(&'static str, &'static str)>,
+            expected_body: &'static str,
+        }
+
+        let test_cases = vec![
+            TestCase {
+                name: "Simple GET",
+                input: "GET /rooms HTTP/1.1\r\nHost: localhost\r\n\r\n",
+                expected_segments: vec!["rooms"],
+                expected_query: vec![],
+                expected_body: "",
+            },
+            TestCase {
+                name: "Escaped segments and query",
+                input: "GET /rooms/Living%20Room/?format=json&x=a+b HTTP/1.1\r\n\r\n",
+                expected_segments: vec!["rooms", "Living Room"],
+                expected_query: vec![("format", "json"), ("x", "a b")],
+                expected_body: "",
+            },
+            TestCase {
+                name: "Body with content length and bare LF lines",
+                input: "POST /rooms HTTP/1.0\nContent-Length: 16\n\n{\"key\":\"Attic\"}\n",
+                expected_segments: vec!["rooms"],
+                expected_query: vec![],
+                expected_body: "{\"key\":\"Attic\"}\n",
+            },
+        ];
+
+        for tc in test_cases {
+            let request = Request::read_from(&mut tc.input.as_bytes())
+                .unwrap()
+                .unwrap();
+            assert_eq!(
+                request.segments, tc.expected_segments,
+                "Failed test: {}",
+                tc.name
+            );
+            assert_eq!(
+                request.query,
+                tc.expected_query
+                    .iter()
+                    .map(|(k, v)| (k.to_string(), v.to_string()))
+                    .collect::<Vec<_>>(),
+                "Failed test: {}",
+                tc.name
+            );
+            assert_eq!(
+                request.body,
+                tc.expected_body.as_bytes(),
+                "Failed test: {}",
+                tc.name
+            );
+        }
+    }
+
+    #[test]
+    fn test_invalid_requests() {
+        let oversized = format!(
+            "POST / HTTP/1.1\r\nContent-Length: {}\r\n\r\n",
+            MAX_BODY + 1
+        );
+        let inputs = [
+            "GET /rooms\r\n\r\n",
+            "GET rooms HTTP/1.1\r\n\r\n",
+            "GET /rooms/%zz HTTP/1.1\r\n\r\n",
+            "GET / HTTP/2\r\n\r\n",
+            "GET / HTTP/1.1\r\nno colon\r\n\r\n",
+            "POST / HTTP/1.1\r\nContent-Length: ten\r\n\r\n",
+            "POST / HTTP/1.1\r\nTransfer-Encoding: chunked\r\n\r\n",
+            oversized.as_str(),
+        ];
+
+        for input in inputs {
+            assert!(
+                Request::read_from(&mut input.as_bytes()).is_err(),
+                "Accepted invalid request: {:?}",
+                input
+            );
+        }
+        assert!(Request::read_from(&mut "".as_bytes()).unwrap().is_none());
+    }
+
+    #[test]
+    fn test_header_count_is_capped() {
+        let request = |headers: usize| {
+            let mut input = "GET / HTTP/1.1\r\n".to_string();
+            for i in 0..headers {
+                input.push_str(&format!("X-Header-{}: {}\r\n", i, i));
+            }
+            input.push_str("\r\n");
+            Request::read_from(&mut input.as_bytes())
+        };
+
+        assert_eq!(
+            request(MAX_HEADERS).unwrap().unwrap().headers.len(),
+            MAX_HEADERS
+        );
+        assert!(matches!(
+            request(MAX_HEADERS + 1),
+            Err(ReadError::TooManyHeaders)
+        ));
+    }
+
+    #[test]
+    fn test_write_response() {
+        let mut output = Vec::new();
+        Response::error(404, "Room 'Attic' not found")
+            .write_to(&mut output)
+            .unwrap();
+        let output = String::from_utf8(output).unwrap();
+
+        assert!(output.starts_with("HTTP/1.1 404 Not Found\r\n"));
+        assert!(output.contains("Content-Type: application/json\r\n"));
+        assert!(output.ends_with("{\n  \"error\": \"Room 'Attic' not found\"\n}"));
+    }
+}
@@ -0,0 +1,29 @@
+//! REST API over a [`SharedHouse`](crate::SharedHouse), enabled with the `http` feature
+//!
+//! Room and device keys are path segments, percent-encoded where needed
+//! (`/rooms/Living%20Room`). Request and response bodies are JSON; room and
+//! device bodies use the records of the house file format.
+//!
+//! | Method   | Path                                          | Response                         |
+//! |----------|-----------------------------------------------|----------------------------------|
+//! | `GET`    | `/rooms`                                      | room keys                        |
+//! | `POST`   | `/rooms`                                      | `201` with the new room report   |
+//! | `GET`    | `/rooms/{room}`                               | room report                      |
+//! | `DELETE` | `/rooms/{room}`                               | `204`                            |
+//! | `GET`    | `/rooms/{room}/devices`                       | device keys                      |
+//! | `POST`   | `/rooms/{room}/devices`                       | `201` with the new device report |
+//! | `GET`    | `/rooms/{room}/devices/{device}`              | device report                    |
+//! | `DELETE` | `/rooms/{room}/devices/{device}`              | `204`                            |
+//! | `POST`   | `/rooms/{room}/devices/{device}/turn_on`      | device report                    |
+//! | `POST`   | `/rooms/{room}/devices/{device}/turn_off`     | device report                    |
+//! | `GET`    | `/report?format=text\|json\|csv\|markdown\|table` | house report                 |
+//!
+//! Errors are answered as `{"error": "..."}`: `404` for a missing room or
+//! device, `409` for a duplicate, `422` for a device without the needed
+//! capability and `400` for an invalid request.
+
+mod api;
+mod message;
+mod server;
+
+pub use server::{HttpHandle, HttpServer};
@@ -0,0 +1,306 @@
+use super::api;
+use super::message::{ReadError, Request, Response};
+use crate::SharedHouse;
+use crate::storage::DeviceRegistry;
+use std::io::{self, BufReader, Read};
+use std::net::{SocketAddr, TcpListener, TcpStream, ToSocketAddrs};
+use std::sync::Arc;
+use std::sync::atomic::{AtomicBool, AtomicUsize, Ordering};
+use std::thread::{self, JoinHandle};
+use std::time::Duration;
+
+/// How long a client may take to send its request
+const READ_TIMEOUT: Duration = Duration::from_secs(10);
+
+/// How long the server waits for a busy client to take its 503 answer
+const WRITE_TIMEOUT: Duration = Duration::from_secs(1);
+
+/// HTTP server exposing a [`SharedHouse`] through the REST API
+///
+/// Every accepted connection is served on its own thread and answers a
+/// single request. Connections beyond the limit set with
+/// [`HttpServer::with_max_connections`] are answered with
+/// `503 Service Unavailable` and closed.
+#[derive(Debug)]
+pub struct HttpServer {
+    listener: TcpListener,
+    house: SharedHouse,
+    registry: Arc<DeviceRegistry>,
+    max_connections: usize,
+}
+
+impl HttpServer {
+    /// Default number of connections served at the same time
+    pub const DEFAULT_MAX_CONNECTIONS: usize = 64;
+
+    /// Binds a new server to the given address
+    ///
+    /// Use port `0` to let the operating system pick a free port.
+    pub fn bind<A: ToSocketAddrs>(addr: A, house: impl Into<SharedHouse>) -> io::Result<Self> {
+        Ok(Self {
+            listener: TcpListener::bind(addr)?,
+            house: house.into(),
+            registry: Arc::new(DeviceRegistry::new()),
+            max_connections: Self::DEFAULT_MAX_CONNECTIONS,
+        })
+    }
+
+    /// Uses the given registry to build custom devices posted to the API
+    pub fn with_registry(mut self, registry: DeviceRegistry) -> Self {
+        self.registry = Arc::new(registry);
+        self
+    }
+
+    /// Sets how many connections are served at the same time
+    pub fn with_max_connections(mut self, max_connections: usize) -> Self {
+        self.max_connections = max_connections;
+        self
+    }
+
+    /// Returns the address the server is listening on
+    pub fn local_addr(&self) -> io::Result<SocketAddr> {
+        self.listener.local_addr()
+    }
+
+    /// Returns a handle to the house served by this server
+    pub fn house(&self) -> SharedHouse {
+        self.house.clone()
+    }
+
+    /// Starts accepting connections on a background thread
+    pub fn spawn(self) -> io::Result<HttpHandle> {
+        let addr = self.local_addr()?;
+        let shutdown = Arc::new(AtomicBool::new(false));
+        let house = self.house();
+
+        let thread = {
+            let shutdown = Arc::clone(&shutdown);
+            thread::spawn(move || self.accept_loop(&shutdown))
+        };
+
+        Ok(HttpHandle {
+            addr,
+            house,
+            shutdown,
+            thread: Some(thread),
+        })
+    }
+
+    fn accept_loop(self, shutdown: &AtomicBool) {
+        let active = Arc::new(AtomicUsize::new(0));
+        for stream in self.listener.incoming() {
+            if shutdown.load(Ordering::SeqCst) {
+                break;
+            }
+            let Ok(stream) = stream else {
+                continue;
+            };
+
+            if active.load(Ordering::SeqCst) >= self.max_connections {
+                let _ = reject_connection(stream);
+                continue;
+            }
+            let slot = ConnectionSlot::take(&active);
+            let house = self.house.clone();
+            let registry = Arc::clone(&self.registry);
+            thread::spawn(move || {
+                let _slot = slot;
+                // A broken connection only affects its own client
+                let _ = serve_connection(stream, &house, &registry);
+            });
+        }
+    }
+}
+
+/// Counts a connection as active until it is dropped
+struct ConnectionSlot(Arc<AtomicUsize>);
+
+impl ConnectionSlot {
+    fn take(active: &Arc<AtomicUsize>) -> Self {
+        active.fetch_add(1, Ordering::SeqCst);
+        Self(Arc::clone(active))
+    }
+}
+
+impl Drop for ConnectionSlot {
+    fn drop(&mut self) {
+        self.0.fetch_sub(1, Ordering::SeqCst);
+    }
+}
+
+/// Handle to a running [`HttpServer`]
+///
+/// The server is stopped when the handle is dropped.
+#[derive(Debug)]
+pub struct HttpHandle {
+    addr: SocketAddr,
+    house: SharedHouse,
+    shutdown: Arc<AtomicBool>,
+    thread: Option<JoinHandle<()>>,
+}
+
+impl HttpHandle {
+    /// Returns the address the server is listening on
+    pub fn local_addr(&self) -> SocketAddr {
+        self.addr
+    }
+
+    /// Returns a handle to the house served by the server
+    pub fn house(&self) -> SharedHouse {
+        self.house.clone()
+    }
+
+    /// Stops accepting new connections and waits for the accept loop to exit
+    pub fn shutdown(mut self) {
+        self.stop();
+    }
+
+    fn stop(&mut self) {
+        if let Some(thread) = self.thread.take() {
+            self.shutdown.store(true, Ordering::SeqCst);
+            // Wake up the blocking accept call
+            let _ = TcpStream::connect(self.addr);
+            let _ = thread.join();
+        }
+    }
+}
+
+impl Drop for HttpHandle {
+    fn drop(&mut self) {
+        self.stop();
+    }
+}
+
+/// Reads one request, answers it and closes the connection
+fn serve_connection(
+    stream: TcpStream,
+    house: &SharedHouse,
+    registry: &DeviceRegistry,
+) -> io::Result<()> {
+    stream.set_read_timeout(Some(READ_TIMEOUT))?;
+    let mut writer = stream.try_clone()?;
+    let mut reader = BufReader::new(stream);
+
+    let response = match Request::read_from(&mut reader) {
+        Ok(Some(request)) => api::handle(house, registry, &request),
+        Ok(None) => return Ok(()),
+        Err(ReadError::Io(e)) => return Err(e),
+        Err(ReadError::Malformed(reason)) => Response::error(400, reason),
+        Err(ReadError::TooLarge) => Response::error(413, "Request is too large"),
+        Err(ReadError::TooManyHeaders) => Response::error(431, "Too many headers"),
+    };
+    response.write_to(&mut writer)
+}
+
+/// Answers a connection over the limit without waiting for its request
+fn reject_connection(mut stream: TcpStream) -> io::Result<()> {
+    stream.set_write_timeout(Some(WRITE_TIMEOUT))?;
+    Response::error(503, "Too many connections").write_to(&mut stream)?;
+
+    // Closing with unread data resets the connection, which may discard the
+    // answer, so drop what has already arrived
+    stream.set_nonblocking(true)?;
+    let mut buf = [0u8; 1024];
+    while matches!(stream.read(&mut buf), Ok(read) if read > 0) {}
+    Ok(())
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::{SmartHouse, SmartSocket, create_room};
+    use std::io::{Read, Write};
+
+    fn send(addr: SocketAddr, request: &str) -> String {
+        let mut stream = TcpStream::connect(addr).unwrap();
+        stream.write_all(request.as_bytes()).unwrap();
+        let mut response = String::new();
+        stream.read_to_string(&mut response).unwrap();
+        response
+    }
+
+    #[test]
+    fn test_http_server() {
+        let kitchen = create_room!(
+            "Kitchen",
+            (
+                "Kettle",
+                SmartSocket::new("Kettle".to_string(), false, 2000.0)
+            )
+        );
+        let house = SmartHouse::new(
+            "Test House".to_string(),
+            vec![("Kitchen".to_string(), kitchen)],
+        );
+        let server = HttpServer::bind("127.0.0.1:0", house)
+            .unwrap()
+            .spawn()
+            .unwrap();
+
+        let response = send(
+            server.local_addr(),
+            "POST /rooms/Kitchen/devices/Kettle/turn_on HTTP/1.1\r\nHost: localhost\r\n\r\n",
+        );
+        assert!(response.starts_with("HTTP/1.1 200 OK\r\n"), "{}", response);
+        assert_eq!(
+            server
+                .house()
+                .with_device(&"Kitchen".to_string(), &"Kettle".to_string(), |device| {
+                    device.is_on()
+                }),
+            Ok(Some(true))
+        );
+
+        let response = send(server.local_addr(), "GET /rooms/Attic HTTP/1.1\r\n\r\n");
+        assert!(
+            response.starts_with("HTTP/1.1 404 Not Found\r\n"),
+            "{}",
+            response
+        );
+        assert!(response.ends_with("\"error\": \"Room 'Attic' not found\"\n}"));
+
+        let response = send(server.local_addr(), "nonsense\r\n\r\n");
+        assert!(
+            response.starts_with("HTTP/1.1 400 Bad Request\r\n"),
+            "{}",
+            response
+        );
+
+        server.shutdown();
+    }
+
+    #[test]
+    fn test_connection_limit() {
+        let server = HttpServer::bind("127.0.0.1:0", SmartHouse::new("Test House".to_string(), []))
+            .unwrap()
+            .with_max_connections(1)
+            .spawn()
+            .unwrap();
+
+        // Holds the only slot until it is dropped
+        let idle = TcpStream::connect(server.local_addr()).unwrap();
+        let mut response = String::new();
+        TcpStream::connect(server.local_addr())
+            .unwrap()
+            .read_to_string(&mut response)
+            .unwrap();
+        assert!(
+            response.starts_with("HTTP/1.1 503 Service Unavailable\r\n"),
+            "{}",
+            response
+        );
+
+        drop(idle);
+        let deadline = std::time::Instant::now() + Duration::from_secs(2);
+        while std::time::Instant::now() < deadline {
+            response = send(server.local_addr(), "GET /rooms HTTP/1.1\r\n\r\n");
+            if response.starts_with("HTTP/1.1 200 OK\r\n") {
+                break;
+            }
+            thread::sleep(Duration::from_millis(5));
+        }
+        assert!(response.starts_with("HTTP/1.1 200 OK\r\n"), "{}", response);
+
+        server.shutdown();
+    }
+}
@@ -4,17 +4,48 @@
 //! with various device types and room configurations.
 
 // Export all modules
+#[cfg(feature = "async")]
+pub mod asynchronous;
+pub mod automation;
+pub mod budget;
+pub mod clock;
+pub mod cost;
 pub mod device;
-mod error;
+pub mod error;
+pub mod events;
 pub mod house;
+#[cfg(feature = "http")]
+pub mod http;
+#[cfg(feature = "mqtt")]
+pub mod mqtt;
+pub mod network;
 pub mod report;
 pub mod room;
+pub mod scene;
+pub mod scheduler;
+pub mod shared;
+pub mod storage;
+pub mod tariff;
 
 // Re-export main types for easier access
-pub use device::{SmartDevice, SmartDeviceTrait, SmartSocket, SmartThermometer};
+pub use budget::{BudgetPolicy, PowerBudget};
+pub use cost::{CostReport, CostTracker};
+pub use device::{
+    Capabilities, Capability, CustomDevice, SmartAirQualityMonitor, SmartBinarySensor, SmartDevice,
+    SmartDeviceTrait, SmartHygrometer, SmartLight, SmartLock, SmartSocket, SmartThermometer,
+    SmartThermostat,
+};
+pub use error::{DeviceAccessError, SmartHomeError};
+pub use events::{EventBus, HouseEvent};
 pub use house::SmartHouse;
-pub use report::Reporter;
+pub use network::{RemoteSocket, SocketServer, UdpThermometer};
+pub use report::{ReportOrder, ReportRenderer, Reporter, StructuredReport};
 pub use room::Room;
+pub use scene::Scene;
+pub use scheduler::{Schedule, Scheduler};
+pub use shared::SharedHouse;
+pub use storage::{DeviceRegistry, HouseFormat};
+pub use tariff::Tariff;
 
 #[cfg(test)]
 mod tests {
@@ -48,13 +79,14 @@ mod tests {
 
         assert_eq!(
             room.get_temperature(&"Test Thermo".to_string()).unwrap(),
-            Some(22.5)
+            22.5
         );
         assert_eq!(
             room.get_power_consumption(&"Test Socket".to_string())
                 .unwrap(),
-            Some(100.0)
+            100.0
         );
+        assert!(room.get_temperature(&"Test Socket".to_string()).is_err());
     }
 
     #[test]
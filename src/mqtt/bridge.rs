@@ -0,0 +1,360 @@
+use super::{Message, MqttTransport, decode_level, encode_level};
+use crate::SmartHouse;
+use crate::error::{ProtocolError, SmartHomeError};
+use crate::report::StructuredReport;
+use serde_json::{Map, Value};
+use std::collections::BTreeMap;
+
+/// Topic prefix used unless [`MqttBridge::with_prefix`] is called
+const DEFAULT_PREFIX: &str = "home";
+
+/// Bridge between a [`SmartHouse`] and an MQTT broker
+///
+/// Each call to [`run_once`](MqttBridge::run_once) applies the commands
+/// received since the previous call and then publishes the state of every
+/// device whose state changed, so thermometer readings go out as soon as
+/// they differ from the last published ones.
+#[derive(Debug)]
+pub struct MqttBridge<T: MqttTransport> {
+    transport: T,
+    prefix: String,
+    subscribed: bool,
+    /// Last payload published on each state topic
+    published: BTreeMap<String, String>,
+}
+
+/// Result of a command received on a `set` topic
+#[derive(Debug, Clone, PartialEq)]
+pub struct CommandOutcome {
+    pub topic: String,
+    /// Error message if the command was rejected
+    pub result: Result<(), String>,
+}
+
+/// What a single [`MqttBridge::run_once`] call did
+#[derive(Debug, Clone, Default, PartialEq)]
+pub struct BridgeReport {
+    /// Commands in the order they were received
+    pub commands: Vec<CommandOutcome>,
+    /// State topics that were published, including cleared ones
+    pub published: Vec<String>,
+}
+
+impl<T: MqttTransport> MqttBridge<T> {
+    /// Creates a bridge publishing under the `home` prefix
+    pub fn new(transport: T) -> Self {
+        Self {
+            transport,
+            prefix: DEFAULT_PREFIX.to_string(),
+            subscribed: false,
+            published: BTreeMap::new(),
+        }
+    }
+
+    /// Publishes and listens under another topic prefix
+    pub fn with_prefix(mut self, prefix: impl Into<String>) -> Self {
+        self.prefix = prefix.into();
+        self
+    }
+
+    /// Returns the transport used by the bridge
+    pub fn transport(&self) -> &T {
+        &self.transport
+    }
+
+    /// Returns the transport used by the bridge mutably
+    pub fn transport_mut(&mut self) -> &mut T {
+        &mut self.transport
+    }
+
+    /// Returns the state topic of a device
+    pub fn state_topic(&self, room_name: &str, device_name: &str) -> String {
+        self.device_topic(room_name, device_name, "state")
+    }
+
+    /// Returns the command topic of a device
+    pub fn command_topic(&self, room_name: &str, device_name: &str) -> String {
+        self.device_topic(room_name, device_name, "set")
+    }
+
+    fn device_topic(&self, room_name: &str, device_name: &str, suffix: &str) -> String {
+        format!(
+            "{}/{}/{}/{}",
+            self.prefix,
+            encode_level(room_name),
+            encode_level(device_name),
+            suffix
+        )
+    }
+
+    /// Applies pending commands and publishes changed device states
+    ///
+    /// Subscribes to the command topics on the first call. A command that
+    /// cannot be applied is reported in [`BridgeReport::commands`]; only
+    /// transport failures are returned as errors.
+    pub fn run_once(&mut self, house: &mut SmartHouse) -> Result<BridgeReport, ProtocolError> {
+        if !self.subscribed {
+            let filter = format!("{}/+/+/set", self.prefix);
+            self.transport.subscribe(&filter)?;
+            self.subscribed = true;
+        }
+
+        let mut report = BridgeReport::default();
+        while let Some(message) = self.transport.try_recv()? {
+            if let Some(result) = self.apply_command(house, &message) {
+                report.commands.push(CommandOutcome {
+                    topic: message.topic,
+                    result: result.map_err(|e| e.to_string()),
+                });
+            }
+        }
+
+        report.published = self.sync(house)?;
+        Ok(report)
+    }
+
+    /// Applies a message received on a `set` topic, ignoring any other topic
+    fn apply_command(
+        &self,
+        house: &mut SmartHouse,
+        message: &Message,
+    ) -> Option<Result<(), SmartHomeError>> {
+        let levels = message
+            .topic
+            .strip_prefix(&self.prefix)?
+            .strip_prefix('/')?
+            .strip_suffix("/set")?;
+        let (room_level, device_level) = levels.split_once('/')?;
+
+        let (Some(room_name), Some(device_name)) =
+            (decode_level(room_level), decode_level(device_level))
+        else {
+            return Some(Err(SmartHomeError::invalid_value(
+                "topic",
+                format!("'{}' is not a valid device topic", message.topic),
+            )));
+        };
+
+        Some(match parse_switch(&message.payload) {
+            Some(true) => house.turn_on_device(&room_name, &device_name),
+            Some(false) => house.turn_off_device(&room_name, &device_name),
+            None => Err(SmartHomeError::invalid_value(
+                "payload",
+                format!("expected ON or OFF, got '{}'", message.payload.trim()),
+            )),
+        })
+    }
+
+    /// Publishes states that differ from the last published ones
+    fn sync(&mut self, house: &SmartHouse) -> Result<Vec<String>, ProtocolError> {
+        let mut current = BTreeMap::new();
+        let mut published = Vec::new();
+
+        for (room_name, room) in house.all_rooms() {
+            for (device_name, device) in room.all_devices() {
+                let topic = self.state_topic(room_name, device_name);
+                let payload = state_payload(&device.structured_report());
+                if self.published.get(&topic) != Some(&payload) {
+                    self.transport
+                        .publish(Message::retained(topic.clone(), payload.clone()))?;
+                    published.push(topic.clone());
+                }
+                current.insert(topic, payload);
+            }
+        }
+
+        // An empty retained message removes the state of a device that is gone
+        for topic in self.published.keys() {
+            if !current.contains_key(topic) {
+                self.transport
+                    .publish(Message::retained(topic.clone(), ""))?;
+                published.push(topic.clone());
+            }
+        }
+
+        self.published = current;
+        Ok(published)
+    }
+}
+
+/// Parses an `ON`/`OFF` command payload
+fn parse_switch(payload: &str) -> Option<bool> {
+    match payload.trim().to_ascii_lowercase().as_str() {
+        "on" | "true" | "1" => Some(true),
+        "off" | "false" | "0" => Some(false),
+        _ => None,
+    }
+}
+
+/// Builds the JSON state of a device from its structured report
+fn state_payload(report: &crate::report::DeviceReport) -> String {
+    let mut state = Map::new();
+    state.insert("kind".to_string(), Value::from(report.kind.clone()));
+    for metric in &report.metrics {
+        let value = serde_json::to_value(&metric.value).unwrap_or(Value::Null);
+        state.insert(metric.name.clone(), value);
+    }
+    Value::Object(state).to_string()
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::mqtt::{LocalBroker, LocalClient};
+    use crate::{SmartSocket, SmartThermometer, create_room};
+
+    fn test_house() -> SmartHouse {
+        let kitchen = create_room!(
+            "Kitchen",
+            (
+                "Kettle",
+                SmartSocket::new("Kettle".to_string(), false, 2000.0)
+            ),
+            ("Thermo", SmartThermometer::new("Thermo".to_string(), 21.5))
+        );
+        SmartHouse::new(
+            "Test House".to_string(),
+            vec![("Kitchen".to_string(), kitchen)],
+        )
+    }
+
+    fn test_bridge() -> (MqttBridge<LocalClient>, LocalClient, LocalBroker) {
+        let broker = LocalBroker::new();
+        let bridge = MqttBridge::new(broker.client());
+        (bridge, broker.client(), broker)
+    }
+
+    #[test]
+    fn test_publish_state() {
+        let (mut bridge, _, broker) = test_bridge();
+        let mut house = test_house();
+
+        let report = bridge.run_once(&mut house).unwrap();
+        assert_eq!(
+            report.published,
+            vec![
+                "home/Kitchen/Kettle/state".to_string(),
+                "home/Kitchen/Thermo/state".to_string()
+            ]
+        );
+        assert_eq!(
+            broker.retained("home/Kitchen/Kettle/state").as_deref(),
+            Some(r#"{"is_on":false,"kind":"socket","power":0.0}"#)
+        );
+        assert_eq!(
+            broker.retained("home/Kitchen/Thermo/state").as_deref(),
+            Some(r#"{"kind":"thermometer","temperature":21.5}"#)
+        );
+
+        // Nothing changed, nothing is published
+        assert!(bridge.run_once(&mut house).unwrap().published.is_empty());
+
+        house
+            .update_temperature(&"Kitchen".to_string(), &"Thermo".to_string(), 23.0)
+            .unwrap();
+        let report = bridge.run_once(&mut house).unwrap();
+        assert_eq!(report.published, vec!["home/Kitchen/Thermo/state"]);
+        assert_eq!(
+            broker.retained("home/Kitchen/Thermo/state").as_deref(),
+            Some(r#"{"kind":"thermometer","temperature":23.0}"#)
+        );
+
+        house
+            .remove_device(&"Kitchen".to_string(), &"Thermo".to_string())
+            .unwrap();
+        let report = bridge.run_once(&mut house).unwrap();
+        assert_eq!(report.published, vec!["home/Kitchen/Thermo/state"]);
+        assert_eq!(broker.retained("home/Kitchen/Thermo/state"), None);
+    }
+
+    #[test]
+    fn test_commands() {
+        struct TestCase {
+            name: &'static str,
+            topic: &'static str,
+            payload: &'static str,
+            expected_result: Result<(), &'static str>,
+            expected_is_on: bool,
+        }
+
+        let test_cases = vec![
+            TestCase {
+                name: "Turn on",
+                topic: "home/Kitchen/Kettle/set",
+                payload: "ON",
+                expected_result: Ok(()),
+                expected_is_on: true,
+            },
+            TestCase {
+                name: "Turn off with a lowercase payload",
+                topic: "home/Kitchen/Kettle/set",
+                payload: " off\n",
+                expected_result: Ok(()),
+                expected_is_on: false,
+            },
+            TestCase {
+                name: "Invalid payload",
+                topic: "home/Kitchen/Kettle/set",
+                payload: "toggle",
+                expected_result: Err("Invalid payload: expected ON or OFF, got 'toggle'"),
+                expected_is_on: false,
+            },
+            TestCase {
+                name: "Unknown room",
+                topic: "home/Attic/Kettle/set",
+                payload: "ON",
+                expected_result: Err("Room 'Attic' not found"),
+                expected_is_on: false,
+            },
+        ];
+
+        for tc in test_cases {
+            let (mut bridge, mut client, _broker) = test_bridge();
+            let mut house = test_house();
+            bridge.run_once(&mut house).unwrap();
+
+            client.publish(Message::new(tc.topic, tc.payload)).unwrap();
+            let report = bridge.run_once(&mut house).unwrap();
+
+            assert_eq!(
+                report.commands,
+                vec![CommandOutcome {
+                    topic: tc.topic.to_string(),
+                    result: tc.expected_result.map_err(str::to_string),
+                }],
+                "Failed test: {}",
+                tc.name
+            );
+            assert_eq!(
+                house
+                    .device(&"Kitchen".to_string(), &"Kettle".to_string())
+                    .unwrap()
+                    .is_on(),
+                Some(tc.expected_is_on),
+                "Failed test: {}",
+                tc.name
+            );
+        }
+    }
+
+    #[test]
+    fn test_command_publishes_new_state() {
+        let (mut bridge, mut client, broker) = test_bridge();
+        let mut house = test_house();
+        bridge.run_once(&mut house).unwrap();
+
+        client
+            .publish(Message::new(
+                bridge.command_topic("Kitchen", "Kettle"),
+                "ON",
+            ))
+            .unwrap();
+        let report = bridge.run_once(&mut house).unwrap();
+
+        assert_eq!(report.published, vec!["home/Kitchen/Kettle/state"]);
+        assert_eq!(
+            broker.retained("home/Kitchen/Kettle/state").as_deref(),
+            Some(r#"{"is_on":true,"kind":"socket","power":2000.0}"#)
+        );
+    }
+}
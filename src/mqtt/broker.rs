@@ -0,0 +1,269 @@
+use super::{Message, MqttTransport};
+use crate::error::ProtocolError;
+use std::collections::BTreeMap;
+use std::sync::mpsc::{self, Receiver, Sender};
+use std::sync::{Arc, Mutex, MutexGuard};
+
+/// In-process stand-in for an MQTT broker
+///
+/// Routes messages between its [`LocalClient`]s with the usual topic
+/// filters and keeps retained messages. Clones share the same broker.
+#[derive(Debug, Clone, Default)]
+pub struct LocalBroker {
+    state: Arc<Mutex<BrokerState>>,
+}
+
+#[derive(Debug, Default)]
+struct BrokerState {
+    next_client: u64,
+    clients: Vec<Subscriber>,
+    retained: BTreeMap<String, String>,
+}
+
+#[derive(Debug)]
+struct Subscriber {
+    client: u64,
+    filters: Vec<String>,
+    inbox: Sender<Message>,
+}
+
+impl LocalBroker {
+    /// Creates a broker without clients or retained messages
+    pub fn new() -> Self {
+        Self::default()
+    }
+
+    fn lock(&self) -> MutexGuard<'_, BrokerState> {
+        self.state.lock().unwrap_or_else(|e| e.into_inner())
+    }
+
+    /// Connects a new client
+    pub fn client(&self) -> LocalClient {
+        let (inbox, receiver) = mpsc::channel();
+        let mut state = self.lock();
+        let client = state.next_client;
+        state.next_client += 1;
+        state.clients.push(Subscriber {
+            client,
+            filters: Vec::new(),
+            inbox,
+        });
+
+        LocalClient {
+            id: client,
+            broker: self.clone(),
+            inbox: receiver,
+        }
+    }
+
+    /// Returns the retained payload of a topic
+    pub fn retained(&self, topic: &str) -> Option<String> {
+        self.lock().retained.get(topic).cloned()
+    }
+
+    /// Returns all retained messages ordered by topic
+    pub fn retained_messages(&self) -> Vec<(String, String)> {
+        self.lock()
+            .retained
+            .iter()
+            .map(|(topic, payload)| (topic.clone(), payload.clone()))
+            .collect()
+    }
+
+    /// Delivers a message to every matching subscriber
+    ///
+    /// A retained message with an empty payload clears the retained message
+    /// of its topic, as on a real broker.
+    pub fn publish(&self, message: Message) {
+        let mut state = self.lock();
+        if message.retain {
+            if message.payload.is_empty() {
+                state.retained.remove(&message.topic);
+            } else {
+                state
+                    .retained
+                    .insert(message.topic.clone(), message.payload.clone());
+            }
+        }
+
+        for subscriber in &state.clients {
+            if subscriber
+                .filters
+                .iter()
+                .any(|filter| topic_matches(filter, &message.topic))
+            {
+                // A client whose receiver is gone is removed when it is dropped
+                let _ = subscriber.inbox.send(message.clone());
+            }
+        }
+    }
+
+    fn subscribe(&self, client: u64, filter: &str) {
+        let mut state = self.lock();
+        let retained: Vec<Message> = state
+            .retained
+            .iter()
+            .filter(|(topic, _)| topic_matches(filter, topic))
+            .map(|(topic, payload)| Message::retained(topic.clone(), payload.clone()))
+            .collect();
+
+        if let Some(subscriber) = state.clients.iter_mut().find(|s| s.client == client) {
+            if !subscriber.filters.iter().any(|f| f == filter) {
+                subscriber.filters.push(filter.to_string());
+            }
+            for message in retained {
+                let _ = subscriber.inbox.send(message);
+            }
+        }
+    }
+
+    fn disconnect(&self, client: u64) {
+        self.lock().clients.retain(|s| s.client != client);
+    }
+}
+
+/// Client connected to a [`LocalBroker`]
+#[derive(Debug)]
+pub struct LocalClient {
+    id: u64,
+    broker: LocalBroker,
+    inbox: Receiver<Message>,
+}
+
+impl LocalClient {
+    /// Returns the broker the client is connected to
+    pub fn broker(&self) -> &LocalBroker {
+        &self.broker
+    }
+}
+
+impl MqttTransport for LocalClient {
+    fn publish(&mut self, message: Message) -> Result<(), ProtocolError> {
+        self.broker.publish(message);
+        Ok(())
+    }
+
+    fn subscribe(&mut self, filter: &str) -> Result<(), ProtocolError> {
+        self.broker.subscribe(self.id, filter);
+        Ok(())
+    }
+
+    fn try_recv(&mut self) -> Result<Option<Message>, ProtocolError> {
+        Ok(self.inbox.try_recv().ok())
+    }
+}
+
+impl Drop for LocalClient {
+    fn drop(&mut self) {
+        self.broker.disconnect(self.id);
+    }
+}
+
+/// Checks whether a topic matches a filter with `+` and `#` wildcards
+fn topic_matches(filter: &str, topic: &str) -> bool {
+    let mut filter = filter.split('/');
+    let mut topic = topic.split('/');
+
+    loop {
+        match (filter.next(), topic.next()) {
+            (Some("#"), _) => return true,
+            (Some("+"), Some(_)) => {}
+            (Some(expected), Some(level)) if expected == level => {}
+            (None, None) => return true,
+            _ => return false,
+        }
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn test_topic_matches() {
+        struct TestCase {
+            filter: &'static str,
+            topic: &'static str,
+            expected: bool,
+        }
+
+        let test_cases = vec![
+            TestCase {
+                filter: "home/+/+/set",
+                topic: "home/Kitchen/Kettle/set",
+                expected: true,
+            },
+            TestCase {
+                filter: "home/+/+/set",
+                topic: "home/Kitchen/Kettle/state",
+                expected: false,
+            },
+            TestCase {
+                filter: "home/+/+/set",
+                topic: "home/Kitchen/set",
+                expected: false,
+            },
+            TestCase {
+                filter: "home/#",
+                topic: "home/Kitchen/Kettle/state",
+                expected: true,
+            },
+            TestCase {
+                filter: "home/#",
+                topic: "home",
+                expected: true,
+            },
+            TestCase {
+                filter: "home/Kitchen",
+                topic: "home/Kitchen/Kettle",
+                expected: false,
+            },
+        ];
+
+        for tc in test_cases {
+            assert_eq!(
+                topic_matches(tc.filter, tc.topic),
+                tc.expected,
+                "Failed test: {} ~ {}",
+                tc.filter,
+                tc.topic
+            );
+        }
+    }
+
+    #[test]
+    fn test_local_broker() {
+        let broker = LocalBroker::new();
+        let mut publisher = broker.client();
+        let mut subscriber = broker.client();
+
+        publisher
+            .publish(Message::retained("home/Kitchen/Kettle/state", "{}"))
+            .unwrap();
+        subscriber.subscribe("home/#").unwrap();
+        publisher
+            .publish(Message::new("home/Kitchen/Kettle/set", "ON"))
+            .unwrap();
+        publisher
+            .publish(Message::new("garden/Pump/set", "ON"))
+            .unwrap();
+
+        assert_eq!(
+            subscriber.try_recv().unwrap(),
+            Some(Message::retained("home/Kitchen/Kettle/state", "{}"))
+        );
+        assert_eq!(
+            subscriber.try_recv().unwrap(),
+            Some(Message::new("home/Kitchen/Kettle/set", "ON"))
+        );
+        assert_eq!(subscriber.try_recv().unwrap(), None);
+
+        publisher
+            .publish(Message::retained("home/Kitchen/Kettle/state", ""))
+            .unwrap();
+        assert!(broker.retained_messages().is_empty());
+
+        drop(subscriber);
+        assert_eq!(broker.lock().clients.len(), 1);
+    }
+}
@@ -0,0 +1,134 @@
+//! MQTT bridge for a [`SmartHouse`](crate::SmartHouse), enabled with the `mqtt` feature
+//!
+//! The [`MqttBridge`] publishes the state of every device as a retained JSON
+//! message on `home/<room>/<device>/state` and accepts `ON`/`OFF` commands on
+//! `home/<room>/<device>/set`. It talks to the broker through the
+//! [`MqttTransport`] trait, so any client library can be plugged in;
+//! [`LocalBroker`] is an in-process stand-in for tests and local setups.
+//!
+//! Room and device keys are used verbatim as topic levels, except that
+//! `%`, `/`, `+` and `#` are percent-encoded.
+
+mod bridge;
+mod broker;
+
+pub use bridge::{BridgeReport, CommandOutcome, MqttBridge};
+pub use broker::{LocalBroker, LocalClient};
+
+use crate::error::ProtocolError;
+
+/// A message published to or received from a broker
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub struct Message {
+    pub topic: String,
+    pub payload: String,
+    /// Whether the broker keeps the message for future subscribers
+    pub retain: bool,
+}
+
+impl Message {
+    /// Creates a message that is not retained
+    pub fn new(topic: impl Into<String>, payload: impl Into<String>) -> Self {
+        Self {
+            topic: topic.into(),
+            payload: payload.into(),
+            retain: false,
+        }
+    }
+
+    /// Creates a message the broker keeps for future subscribers
+    pub fn retained(topic: impl Into<String>, payload: impl Into<String>) -> Self {
+        Self {
+            retain: true,
+            ..Self::new(topic, payload)
+        }
+    }
+}
+
+/// Connection to an MQTT broker
+pub trait MqttTransport {
+    /// Publishes a message
+    fn publish(&mut self, message: Message) -> Result<(), ProtocolError>;
+
+    /// Subscribes to a topic filter; `+` matches one level and `#` the rest
+    fn subscribe(&mut self, filter: &str) -> Result<(), ProtocolError>;
+
+    /// Returns the next received message without blocking
+    fn try_recv(&mut self) -> Result<Option<Message>, ProtocolError>;
+}
+
+/// Encodes a room or device key as a single topic level
+pub fn encode_level(key: &str) -> String {
+    let mut level = String::with_capacity(key.len());
+    for c in key.chars() {
+        match c {
+            '%' => level.push_str("%25"),
+            '/' => level.push_str("%2F"),
+            '+' => level.push_str("%2B"),
+            '#' => level.push_str("%23"),
+            c => level.push(c),
+        }
+    }
+    level
+}
+
+/// Decodes a topic level produced by [`encode_level`]
+pub fn decode_level(level: &str) -> Option<String> {
+    let mut key = String::with_capacity(level.len());
+    let mut rest = level;
+    while let Some(index) = rest.find('%') {
+        key.push_str(&rest[..index]);
+        let decoded = match rest.get(index + 1..index + 3)? {
+            "25" => '%',
+            "2F" | "2f" => '/',
+            "2B" | "2b" => '+',
+            "23" => '#',
+            _ => return None,
+        };
+        key.push(decoded);
+        rest = &rest[index + 3..];
+    }
+    key.push_str(rest);
+    Some(key)
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn test_topic_levels() {
+        struct TestCase {
+            name: &'static str,
+            key: &'static str,
+            expected_level: &'static str,
+        }
+
+        let test_cases = vec![
+            TestCase {
+                name: "Plain key with a space",
+                key: "Living Room",
+                expected_level: "Living Room",
+            },
+            TestCase {
+                name: "Reserved characters",
+                key: "A/C #1 + 50%",
+                expected_level: "A%2FC %231 %2B 50%25",
+            },
+        ];
+
+        for tc in test_cases {
+            let level = encode_level(tc.key);
+            assert_eq!(level, tc.expected_level, "Failed test: {}", tc.name);
+            assert_eq!(
+                decode_level(&level).as_deref(),
+                Some(tc.key),
+                "Failed test: {}",
+                tc.name
+            );
+        }
+
+        assert_eq!(decode_level("50%"), None);
+        assert_eq!(decode_level("%41"), None);
+    }
+}
@@ -0,0 +1,11 @@
+// Network transports for remotely controlled devices
+pub mod protocol;
+mod remote_socket;
+mod socket_server;
+pub(crate) mod udp_thermometer;
+
+// Re-export for easier access
+pub use protocol::{Command, Response, TemperatureDatagram};
+pub use remote_socket::RemoteSocket;
+pub use socket_server::{ServerHandle, SocketServer};
+pub use udp_thermometer::{ThermometerEmulator, UdpTemperatureReading, UdpThermometer};
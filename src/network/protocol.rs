@@ -0,0 +1,323 @@
+use crate::error::ProtocolError;
+use std::io::{ErrorKind, Read, Write};
+
+/// Commands understood by a [`SocketServer`](super::SocketServer)
+///
+/// Every command is encoded on the wire as a single byte
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub enum Command {
+    TurnOn,
+    TurnOff,
+    IsOn,
+    PowerConsumption,
+}
+
+impl Command {
+    /// Returns the wire representation of the command
+    pub fn to_byte(self) -> u8 {
+        match self {
+            Command::TurnOn => 0x01,
+            Command::TurnOff => 0x02,
+            Command::IsOn => 0x03,
+            Command::PowerConsumption => 0x04,
+        }
+    }
+
+    /// Decodes a command from its wire representation
+    pub fn from_byte(byte: u8) -> Result<Self, ProtocolError> {
+        match byte {
+            0x01 => Ok(Command::TurnOn),
+            0x02 => Ok(Command::TurnOff),
+            0x03 => Ok(Command::IsOn),
+            0x04 => Ok(Command::PowerConsumption),
+            other => Err(ProtocolError::UnknownCommand(other)),
+        }
+    }
+
+    /// Writes the command to a stream
+    pub fn write_to<W: Write>(self, writer: &mut W) -> Result<(), ProtocolError> {
+        writer.write_all(&[self.to_byte()])?;
+        writer.flush()?;
+        Ok(())
+    }
+
+    /// Reads a raw command byte from a stream
+    ///
+    /// Returns `Ok(None)` when the peer closed the connection cleanly.
+    /// The byte is returned undecoded so that the server can answer
+    /// unknown commands instead of dropping the connection.
+    pub fn read_byte<R: Read>(reader: &mut R) -> Result<Option<u8>, ProtocolError> {
+        let mut buf = [0u8; 1];
+        match reader.read_exact(&mut buf) {
+            Ok(()) => Ok(Some(buf[0])),
+            Err(e) if e.kind() == ErrorKind::UnexpectedEof => Ok(None),
+            Err(e) => Err(e.into()),
+        }
+    }
+}
+
+/// Error codes carried by [`Response::Error`]
+pub mod error_code {
+    /// The server did not recognise the command byte
+    pub const UNKNOWN_COMMAND: u8 = 0x01;
+}
+
+/// Responses sent back by a [`SocketServer`](super::SocketServer)
+///
+/// Encoded as a tag byte followed by a tag-specific payload:
+/// `Ok` has no payload, `State` carries one byte (0 or 1),
+/// `Power` carries a big-endian `f32` and `Error` carries an error code.
+#[derive(Debug, Clone, Copy, PartialEq)]
+pub enum Response {
+    Ok,
+    State(bool),
+    Power(f32),
+    Error(u8),
+}
+
+impl Response {
+    const TAG_OK: u8 = 0x00;
+    const TAG_STATE: u8 = 0x01;
+    const TAG_POWER: u8 = 0x02;
+    const TAG_ERROR: u8 = 0xFF;
+
+    /// Encodes the response into its wire representation
+    pub fn to_bytes(self) -> Vec<u8> {
+        match self {
+            Response::Ok => vec![Self::TAG_OK],
+            Response::State(on) => vec![Self::TAG_STATE, u8::from(on)],
+            Response::Power(watts) => {
+                let mut bytes = Vec::with_capacity(5);
+                bytes.push(Self::TAG_POWER);
+                bytes.extend_from_slice(&watts.to_be_bytes());
+                bytes
+            }
+            Response::Error(code) => vec![Self::TAG_ERROR, code],
+        }
+    }
+
+    /// Writes the response to a stream
+    pub fn write_to<W: Write>(self, writer: &mut W) -> Result<(), ProtocolError> {
+        writer.write_all(&self.to_bytes())?;
+        writer.flush()?;
+        Ok(())
+    }
+
+    /// Reads a single response from a stream
+    pub fn read_from<R: Read>(reader: &mut R) -> Result<Self, ProtocolError> {
+        let mut tag = [0u8; 1];
+        reader.read_exact(&mut tag)?;
+
+        let mut payload = [0u8; 4];
+        let payload = &mut payload[..Self::payload_len(tag[0])?];
+        reader.read_exact(payload)?;
+        Ok(Self::decode(tag[0], payload))
+    }
+
+    /// Returns the number of payload bytes following a tag byte
+    pub(crate) fn payload_len(tag: u8) -> Result<usize, ProtocolError> {
+        match tag {
+            Self::TAG_OK => Ok(0),
+            Self::TAG_STATE | Self::TAG_ERROR => Ok(1),
+            Self::TAG_POWER => Ok(4),
+            other => Err(ProtocolError::UnknownResponse(other)),
+        }
+    }
+
+    /// Decodes a response from a known tag and a payload of [`Response::payload_len`] bytes
+    pub(crate) fn decode(tag: u8, payload: &[u8]) -> Self {
+        match tag {
+            Self::TAG_OK => Response::Ok,
+            Self::TAG_STATE => Response::State(payload[0] != 0),
+            Self::TAG_POWER => {
+                let mut bytes = [0u8; 4];
+                bytes.copy_from_slice(payload);
+                Response::Power(f32::from_be_bytes(bytes))
+            }
+            _ => Response::Error(payload[0]),
+        }
+    }
+}
+
+/// Telemetry datagram sent by a thermometer to a
+/// [`UdpThermometer`](super::UdpThermometer)
+///
+/// Encoded as a single big-endian `f32` holding degrees Celsius.
+#[derive(Debug, Clone, Copy, PartialEq)]
+pub struct TemperatureDatagram {
+    pub temperature: f32,
+}
+
+impl TemperatureDatagram {
+    /// Size of an encoded datagram in bytes
+    pub const SIZE: usize = 4;
+
+    /// Encodes the datagram into its wire representation
+    pub fn to_bytes(self) -> [u8; Self::SIZE] {
+        self.temperature.to_be_bytes()
+    }
+
+    /// Decodes a datagram, rejecting wrong sizes and non-finite readings
+    pub fn from_bytes(bytes: &[u8]) -> Result<Self, ProtocolError> {
+        let payload: [u8; Self::SIZE] = bytes
+            .try_into()
+            .map_err(|_| ProtocolError::MalformedDatagram(bytes.len()))?;
+        let temperature = f32::from_be_bytes(payload);
+
+        if temperature.is_finite() {
+            Ok(Self { temperature })
+        } else {
+            Err(ProtocolError::MalformedDatagram(bytes.len()))
+        }
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use std::io::Cursor;
+
+    #[test]
+    fn test_command_round_trip() {
+        let commands = vec![
+            Command::TurnOn,
+            Command::TurnOff,
+            Command::IsOn,
+            Command::PowerConsumption,
+        ];
+
+        for command in commands {
+            let mut buf = Vec::new();
+            command.write_to(&mut buf).unwrap();
+
+            let byte = Command::read_byte(&mut Cursor::new(buf)).unwrap().unwrap();
+            assert_eq!(
+                Command::from_byte(byte).unwrap(),
+                command,
+                "Command {:?} should survive encoding",
+                command
+            );
+        }
+    }
+
+    #[test]
+    fn test_command_decoding_errors() {
+        assert!(matches!(
+            Command::from_byte(0x42),
+            Err(ProtocolError::UnknownCommand(0x42))
+        ));
+        assert!(
+            Command::read_byte(&mut Cursor::new(Vec::new()))
+                .unwrap()
+                .is_none()
+        );
+    }
+
+    #[test]
+    fn test_response_round_trip() {
+        struct TestCase {
+            name: &'static str,
+            response: Response,
+            expected_len: usize,
+        }
+
+        let test_cases = vec![
+            TestCase {
+                name: "Ok has no payload",
+                response: Response::Ok,
+                expected_len: 1,
+            },
+            TestCase {
+                name: "State ON",
+                response: Response::State(true),
+                expected_len: 2,
+            },
+            TestCase {
+                name: "State OFF",
+                response: Response::State(false),
+                expected_len: 2,
+            },
+            TestCase {
+                name: "Power carries an f32",
+                response: Response::Power(123.5),
+                expected_len: 5,
+            },
+            TestCase {
+                name: "Error carries a code",
+                response: Response::Error(error_code::UNKNOWN_COMMAND),
+                expected_len: 2,
+            },
+        ];
+
+        for tc in test_cases {
+            let bytes = tc.response.to_bytes();
+            assert_eq!(bytes.len(), tc.expected_len, "Failed test: {}", tc.name);
+
+            let decoded = Response::read_from(&mut Cursor::new(bytes)).unwrap();
+            assert_eq!(decoded, tc.response, "Failed test: {}", tc.name);
+        }
+    }
+
+    #[test]
+    fn test_response_decoding_errors() {
+        assert!(matches!(
+            Response::read_from(&mut Cursor::new(vec![0x10])),
+            Err(ProtocolError::UnknownResponse(0x10))
+        ));
+        assert!(matches!(
+            Response::read_from(&mut Cursor::new(vec![0x02, 0x00])),
+            Err(ProtocolError::Io(_))
+        ));
+    }
+
+    #[test]
+    fn test_temperature_datagram() {
+        struct TestCase {
+            name: &'static str,
+            bytes: Vec<u8>,
+            expected: Option<f32>,
+        }
+
+        let test_cases = vec![
+            TestCase {
+                name: "Positive reading",
+                bytes: TemperatureDatagram { temperature: 21.5 }
+                    .to_bytes()
+                    .to_vec(),
+                expected: Some(21.5),
+            },
+            TestCase {
+                name: "Negative reading",
+                bytes: TemperatureDatagram { temperature: -7.25 }
+                    .to_bytes()
+                    .to_vec(),
+                expected: Some(-7.25),
+            },
+            TestCase {
+                name: "Too short",
+                bytes: vec![0x41, 0xAC],
+                expected: None,
+            },
+            TestCase {
+                name: "Too long",
+                bytes: vec![0; 8],
+                expected: None,
+            },
+            TestCase {
+                name: "NaN is rejected",
+                bytes: f32::NAN.to_be_bytes().to_vec(),
+                expected: None,
+            },
+        ];
+
+        for tc in test_cases {
+            let decoded = TemperatureDatagram::from_bytes(&tc.bytes).ok();
+            assert_eq!(
+                decoded.map(|d| d.temperature),
+                tc.expected,
+                "Failed test: {}",
+                tc.name
+            );
+        }
+    }
+}
@@ -0,0 +1,381 @@
+use super::protocol::{Command, Response};
+use crate::Reporter;
+use crate::device::CustomDevice;
+use crate::device::device_trait::{PowerConsumption, PowerControl, SmartDeviceTrait};
+use crate::error::{ProtocolError, SmartHomeError};
+use std::net::{SocketAddr, TcpStream, ToSocketAddrs};
+use std::sync::{Mutex, MutexGuard};
+use std::time::Duration;
+
+/// Client for a smart socket exposed by a [`SocketServer`](super::SocketServer)
+///
+/// The `try_*` methods surface transport and protocol failures, and so do
+/// [`SmartDevice::turn_on`](crate::SmartDevice::turn_on) and
+/// [`SmartDevice::turn_off`](crate::SmartDevice::turn_off). The infallible
+/// [`PowerControl`] and [`PowerConsumption`] methods are best-effort: a
+/// failed request leaves the remote socket untouched and is reported as an
+/// OFF socket drawing no power.
+///
+/// After a transport or protocol error, including a timeout, the connection
+/// is closed and the next request opens a new one, so a late response is
+/// never mistaken for the answer to another command.
+#[derive(Debug)]
+pub struct RemoteSocket {
+    name: String,
+    addr: SocketAddr,
+    connection: Mutex<Connection>,
+}
+
+#[derive(Debug)]
+struct Connection {
+    /// `None` after the connection was dropped
+    stream: Option<TcpStream>,
+    timeout: Option<Duration>,
+}
+
+impl RemoteSocket {
+    /// Default read/write timeout applied to new connections
+    pub const DEFAULT_TIMEOUT: Duration = Duration::from_secs(5);
+
+    /// Connects to a socket server at the given address
+    pub fn connect<A: ToSocketAddrs>(name: String, addr: A) -> Result<Self, ProtocolError> {
+        let stream = TcpStream::connect(addr)?;
+        let addr = stream.peer_addr()?;
+        let timeout = Some(Self::DEFAULT_TIMEOUT);
+        configure(&stream, timeout)?;
+
+        Ok(Self {
+            name,
+            addr,
+            connection: Mutex::new(Connection {
+                stream: Some(stream),
+                timeout,
+            }),
+        })
+    }
+
+    /// Returns the address of the socket server
+    pub fn peer_addr(&self) -> SocketAddr {
+        self.addr
+    }
+
+    /// Changes the read/write timeout of the connection and of later ones
+    pub fn set_timeout(&self, timeout: Option<Duration>) -> Result<(), ProtocolError> {
+        let mut connection = self.lock_connection();
+        if let Some(stream) = &connection.stream {
+            stream.set_read_timeout(timeout)?;
+            stream.set_write_timeout(timeout)?;
+        }
+        connection.timeout = timeout;
+        Ok(())
+    }
+
+    /// Sends a raw command and waits for the response
+    ///
+    /// Reconnects first if the previous request failed.
+    pub fn send(&self, command: Command) -> Result<Response, ProtocolError> {
+        let mut connection = self.lock_connection();
+        let mut stream = match connection.stream.take() {
+            Some(stream) => stream,
+            None => {
+                let stream = TcpStream::connect(self.addr)?;
+                configure(&stream, connection.timeout)?;
+                stream
+            }
+        };
+
+        command.write_to(&mut stream)?;
+        let response = Response::read_from(&mut stream)?;
+        connection.stream = Some(stream);
+
+        match response {
+            Response::Error(code) => Err(ProtocolError::Rejected(code)),
+            response => Ok(response),
+        }
+    }
+
+    /// Turns the remote socket on
+    pub fn try_turn_on(&self) -> Result<(), ProtocolError> {
+        self.expect_ok(Command::TurnOn)
+    }
+
+    /// Turns the remote socket off
+    pub fn try_turn_off(&self) -> Result<(), ProtocolError> {
+        self.expect_ok(Command::TurnOff)
+    }
+
+    /// Queries whether the remote socket is on
+    pub fn try_is_on(&self) -> Result<bool, ProtocolError> {
+        match self.send(Command::IsOn)? {
+            Response::State(on) => Ok(on),
+            other => Err(ProtocolError::UnexpectedResponse(other)),
+        }
+    }
+
+    /// Queries the current power consumption of the remote socket in watts
+    pub fn try_power_consumption(&self) -> Result<f32, ProtocolError> {
+        match self.send(Command::PowerConsumption)? {
+            Response::Power(watts) => Ok(watts),
+            other => Err(ProtocolError::UnexpectedResponse(other)),
+        }
+    }
+
+    fn expect_ok(&self, command: Command) -> Result<(), ProtocolError> {
+        match self.send(command)? {
+            Response::Ok => Ok(()),
+            other => Err(ProtocolError::UnexpectedResponse(other)),
+        }
+    }
+
+    fn lock_connection(&self) -> MutexGuard<'_, Connection> {
+        self.connection.lock().unwrap_or_else(|e| e.into_inner())
+    }
+}
+
+fn configure(stream: &TcpStream, timeout: Option<Duration>) -> Result<(), ProtocolError> {
+    stream.set_read_timeout(timeout)?;
+    stream.set_write_timeout(timeout)?;
+    stream.set_nodelay(true)?;
+    Ok(())
+}
+
+impl SmartDeviceTrait for RemoteSocket {
+    fn name(&self) -> &str {
+        &self.name
+    }
+
+    fn as_power_control(&self) -> Option<&dyn PowerControl> {
+        Some(self)
+    }
+
+    fn as_power_control_mut(&mut self) -> Option<&mut dyn PowerControl> {
+        Some(self)
+    }
+
+    fn as_power_consumption(&self) -> Option<&dyn PowerConsumption> {
+        Some(self)
+    }
+}
+
+impl PowerControl for RemoteSocket {
+    fn is_on(&self) -> bool {
+        self.try_is_on().unwrap_or(false)
+    }
+
+    fn turn_on(&mut self) {
+        let _ = self.try_turn_on();
+    }
+
+    fn turn_off(&mut self) {
+        let _ = self.try_turn_off();
+    }
+
+    fn try_turn_on(&mut self) -> Result<(), SmartHomeError> {
+        Ok(RemoteSocket::try_turn_on(self)?)
+    }
+
+    fn try_turn_off(&mut self) -> Result<(), SmartHomeError> {
+        Ok(RemoteSocket::try_turn_off(self)?)
+    }
+}
+
+impl PowerConsumption for RemoteSocket {
+    fn power_consumption(&self) -> f32 {
+        self.try_power_consumption().unwrap_or(0.0)
+    }
+}
+
+impl Reporter for RemoteSocket {
+    fn report(&self) -> String {
+        match (self.try_is_on(), self.try_power_consumption()) {
+            (Ok(is_on), Ok(consumption)) => format!(
+                "Device: {name}, Status: {status}, Power consumption: {consumption}W",
+                name = self.name(),
+                status = if is_on { "ON" } else { "OFF" },
+                consumption = consumption
+            ),
+            (Err(e), _) | (_, Err(e)) => {
+                format!("Device: {}, Status: UNREACHABLE ({})", self.name(), e)
+            }
+        }
+    }
+}
+
+impl CustomDevice for RemoteSocket {
+    fn kind(&self) -> &str {
+        "remote_socket"
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::device::{SmartDevice, SmartSocket};
+    use crate::network::{Response, ServerHandle, SocketServer};
+    use std::io::Read;
+    use std::net::TcpListener;
+    use std::thread;
+
+    fn start_server(is_on: bool, consumption: f32) -> ServerHandle {
+        let socket = SmartSocket::new("Server Socket".to_string(), is_on, consumption);
+        SocketServer::bind("127.0.0.1:0", socket)
+            .unwrap()
+            .spawn()
+            .unwrap()
+    }
+
+    #[test]
+    fn test_remote_socket_power_control() {
+        struct TestCase {
+            name: &'static str,
+            operation: fn(&mut RemoteSocket),
+            expected_state: bool,
+            expected_consumption: f32,
+        }
+
+        let test_cases = vec![
+            TestCase {
+                name: "Turn on an OFF socket",
+                operation: |s| s.turn_on(),
+                expected_state: true,
+                expected_consumption: 120.0,
+            },
+            TestCase {
+                name: "Turn on an already ON socket",
+                operation: |s| s.turn_on(),
+                expected_state: true,
+                expected_consumption: 120.0,
+            },
+            TestCase {
+                name: "Turn off an ON socket",
+                operation: |s| s.turn_off(),
+                expected_state: false,
+                expected_consumption: 0.0,
+            },
+        ];
+
+        let server = start_server(false, 120.0);
+        let mut remote =
+            RemoteSocket::connect("Remote Socket".to_string(), server.local_addr()).unwrap();
+
+        for tc in test_cases {
+            (tc.operation)(&mut remote);
+
+            assert_eq!(
+                remote.is_on(),
+                tc.expected_state,
+                "Failed test: {}",
+                tc.name
+            );
+            assert_eq!(
+                remote.power_consumption(),
+                tc.expected_consumption,
+                "Failed test: {}",
+                tc.name
+            );
+            assert_eq!(
+                server.socket().lock().unwrap().is_on(),
+                tc.expected_state,
+                "Server state out of sync in test: {}",
+                tc.name
+            );
+        }
+    }
+
+    #[test]
+    fn test_remote_socket_report() {
+        let server = start_server(true, 75.0);
+        let remote =
+            RemoteSocket::connect("Remote Socket".to_string(), server.local_addr()).unwrap();
+
+        assert_eq!(remote.name(), "Remote Socket");
+        assert_eq!(
+            remote.report(),
+            "Device: Remote Socket, Status: ON, Power consumption: 75W"
+        );
+    }
+
+    #[test]
+    fn test_remote_socket_as_smart_device() {
+        let server = start_server(false, 40.0);
+        let remote =
+            RemoteSocket::connect("Remote Socket".to_string(), server.local_addr()).unwrap();
+        let mut device = SmartDevice::custom(remote);
+
+        assert_eq!(device.kind(), "remote_socket");
+        assert!(device.turn_on().is_ok());
+        assert_eq!(device.is_on(), Some(true));
+        assert_eq!(device.power_consumption(), Some(40.0));
+        assert!(server.socket().lock().unwrap().is_on());
+    }
+
+    #[test]
+    fn test_remote_socket_surfaces_errors() {
+        // A peer that answers every request with an unknown response tag
+        let listener = TcpListener::bind("127.0.0.1:0").unwrap();
+        let addr = listener.local_addr().unwrap();
+        let peer = thread::spawn(move || {
+            let (mut stream, _) = listener.accept().unwrap();
+            let mut buf = [0u8; 1];
+            stream.read_exact(&mut buf).unwrap();
+            std::io::Write::write_all(&mut stream, &[0x42]).unwrap();
+        });
+
+        let remote = RemoteSocket::connect("Broken".to_string(), addr).unwrap();
+        assert!(matches!(
+            remote.try_is_on(),
+            Err(ProtocolError::UnknownResponse(0x42))
+        ));
+        peer.join().unwrap();
+
+        // The peer hung up, so the next request fails at the transport level
+        assert!(matches!(remote.try_turn_on(), Err(ProtocolError::Io(_))));
+        assert!(!remote.is_on());
+        assert!(remote.report().contains("UNREACHABLE"));
+    }
+
+    #[test]
+    fn test_remote_socket_reconnects_after_timeout() {
+        // The first connection answers too late, the second one in time
+        let listener = TcpListener::bind("127.0.0.1:0").unwrap();
+        let addr = listener.local_addr().unwrap();
+        let peer = thread::spawn(move || {
+            let (mut slow, _) = listener.accept().unwrap();
+            let mut buf = [0u8; 1];
+            slow.read_exact(&mut buf).unwrap();
+
+            // The client only reconnects once the first request timed out
+            let (mut fresh, _) = listener.accept().unwrap();
+            Response::State(true).write_to(&mut slow).unwrap();
+            fresh.read_exact(&mut buf).unwrap();
+            Response::Ok.write_to(&mut fresh).unwrap();
+            slow
+        });
+
+        let remote = RemoteSocket::connect("Slow".to_string(), addr).unwrap();
+        remote
+            .set_timeout(Some(Duration::from_millis(100)))
+            .unwrap();
+        assert!(matches!(remote.try_is_on(), Err(ProtocolError::Io(_))));
+        assert!(remote.try_turn_on().is_ok());
+        drop(peer.join().unwrap());
+    }
+
+    #[test]
+    fn test_smart_device_surfaces_remote_errors() {
+        let server = start_server(false, 40.0);
+        let remote =
+            RemoteSocket::connect("Remote Socket".to_string(), server.local_addr()).unwrap();
+        let mut device = SmartDevice::custom(remote);
+        drop(server);
+
+        assert!(matches!(
+            device.turn_on(),
+            Err(SmartHomeError::Protocol(ProtocolError::Io(_)))
+        ));
+        assert!(matches!(
+            device.turn_off(),
+            Err(SmartHomeError::Protocol(ProtocolError::Io(_)))
+        ));
+    }
+}
@@ -0,0 +1,299 @@
+use super::protocol::{Command, Response, error_code};
+use crate::device::SmartSocket;
+use crate::device::device_trait::{PowerConsumption, PowerControl};
+use crate::error::ProtocolError;
+use std::io;
+use std::net::{Shutdown, SocketAddr, TcpListener, TcpStream, ToSocketAddrs};
+use std::sync::atomic::{AtomicBool, Ordering};
+use std::sync::{Arc, Mutex};
+use std::thread::{self, JoinHandle};
+
+/// TCP server exposing a [`SmartSocket`] through the binary control protocol
+///
+/// Every accepted connection is served on its own thread; all connections
+/// share the same underlying socket.
+#[derive(Debug)]
+pub struct SocketServer {
+    listener: TcpListener,
+    socket: Arc<Mutex<SmartSocket>>,
+}
+
+impl SocketServer {
+    /// Binds a new server to the given address
+    ///
+    /// Use port `0` to let the operating system pick a free port.
+    pub fn bind<A: ToSocketAddrs>(addr: A, socket: SmartSocket) -> io::Result<Self> {
+        Ok(Self {
+            listener: TcpListener::bind(addr)?,
+            socket: Arc::new(Mutex::new(socket)),
+        })
+    }
+
+    /// Returns the address the server is listening on
+    pub fn local_addr(&self) -> io::Result<SocketAddr> {
+        self.listener.local_addr()
+    }
+
+    /// Returns a handle to the socket served by this server
+    pub fn socket(&self) -> Arc<Mutex<SmartSocket>> {
+        Arc::clone(&self.socket)
+    }
+
+    /// Starts accepting connections on a background thread
+    pub fn spawn(self) -> io::Result<ServerHandle> {
+        let addr = self.local_addr()?;
+        let shutdown = Arc::new(AtomicBool::new(false));
+        let socket = self.socket();
+
+        let thread = {
+            let shutdown = Arc::clone(&shutdown);
+            thread::spawn(move || self.accept_loop(&shutdown))
+        };
+
+        Ok(ServerHandle {
+            addr,
+            socket,
+            shutdown,
+            thread: Some(thread),
+        })
+    }
+
+    fn accept_loop(self, shutdown: &AtomicBool) {
+        let mut connections: Vec<(TcpStream, JoinHandle<()>)> = Vec::new();
+        for stream in self.listener.incoming() {
+            if shutdown.load(Ordering::SeqCst) {
+                break;
+            }
+
+            let Ok(stream) = stream else {
+                continue;
+            };
+            // Kept to close the connection on shutdown
+            let Ok(handle) = stream.try_clone() else {
+                continue;
+            };
+            let socket = Arc::clone(&self.socket);
+            let worker = thread::spawn(move || {
+                // A broken connection only affects its own client
+                let _ = serve_connection(stream, &socket);
+            });
+            connections.retain(|(_, worker)| !worker.is_finished());
+            connections.push((handle, worker));
+        }
+
+        for (stream, worker) in connections {
+            let _ = stream.shutdown(Shutdown::Both);
+            let _ = worker.join();
+        }
+    }
+}
+
+/// Handle to a running [`SocketServer`]
+///
+/// The server is stopped and its connections closed when the handle is dropped.
+#[derive(Debug)]
+pub struct ServerHandle {
+    addr: SocketAddr,
+    socket: Arc<Mutex<SmartSocket>>,
+    shutdown: Arc<AtomicBool>,
+    thread: Option<JoinHandle<()>>,
+}
+
+impl ServerHandle {
+    /// Returns the address the server is listening on
+    pub fn local_addr(&self) -> SocketAddr {
+        self.addr
+    }
+
+    /// Returns a handle to the socket served by the server
+    pub fn socket(&self) -> Arc<Mutex<SmartSocket>> {
+        Arc::clone(&self.socket)
+    }
+
+    /// Stops the server, closing every open connection
+    ///
+    /// Waits for the accept loop and the connection threads to exit.
+    pub fn shutdown(mut self) {
+        self.stop();
+    }
+
+    fn stop(&mut self) {
+        if let Some(thread) = self.thread.take() {
+            self.shutdown.store(true, Ordering::SeqCst);
+            // Wake up the blocking accept call
+            let _ = TcpStream::connect(self.addr);
+            let _ = thread.join();
+        }
+    }
+}
+
+impl Drop for ServerHandle {
+    fn drop(&mut self) {
+        self.stop();
+    }
+}
+
+/// Serves commands from a single client until it disconnects
+fn serve_connection(
+    mut stream: TcpStream,
+    socket: &Mutex<SmartSocket>,
+) -> Result<(), ProtocolError> {
+    while let Some(byte) = Command::read_byte(&mut stream)? {
+        let response = match Command::from_byte(byte) {
+            Ok(command) => execute(command, socket),
+            Err(_) => Response::Error(error_code::UNKNOWN_COMMAND),
+        };
+        response.write_to(&mut stream)?;
+    }
+
+    Ok(())
+}
+
+fn execute(command: Command, socket: &Mutex<SmartSocket>) -> Response {
+    let mut socket = socket.lock().unwrap_or_else(|e| e.into_inner());
+
+    match command {
+        Command::TurnOn => {
+            socket.turn_on();
+            Response::Ok
+        }
+        Command::TurnOff => {
+            socket.turn_off();
+            Response::Ok
+        }
+        Command::IsOn => Response::State(socket.is_on()),
+        Command::PowerConsumption => Response::Power(socket.power_consumption()),
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use std::io::Write;
+
+    fn start_server(is_on: bool, consumption: f32) -> ServerHandle {
+        let socket = SmartSocket::new("Server Socket".to_string(), is_on, consumption);
+        SocketServer::bind("127.0.0.1:0", socket)
+            .unwrap()
+            .spawn()
+            .unwrap()
+    }
+
+    fn round_trip(stream: &mut TcpStream, command: Command) -> Response {
+        command.write_to(stream).unwrap();
+        Response::read_from(stream).unwrap()
+    }
+
+    #[test]
+    fn test_server_executes_commands() {
+        struct TestCase {
+            name: &'static str,
+            command: Command,
+            expected_response: Response,
+        }
+
+        let test_cases = vec![
+            TestCase {
+                name: "Initial state is OFF",
+                command: Command::IsOn,
+                expected_response: Response::State(false),
+            },
+            TestCase {
+                name: "OFF socket draws no power",
+                command: Command::PowerConsumption,
+                expected_response: Response::Power(0.0),
+            },
+            TestCase {
+                name: "Turn on",
+                command: Command::TurnOn,
+                expected_response: Response::Ok,
+            },
+            TestCase {
+                name: "State is ON after turn on",
+                command: Command::IsOn,
+                expected_response: Response::State(true),
+            },
+            TestCase {
+                name: "ON socket reports its power",
+                command: Command::PowerConsumption,
+                expected_response: Response::Power(60.0),
+            },
+            TestCase {
+                name: "Turn off",
+                command: Command::TurnOff,
+                expected_response: Response::Ok,
+            },
+        ];
+
+        let server = start_server(false, 60.0);
+        let mut stream = TcpStream::connect(server.local_addr()).unwrap();
+
+        for tc in test_cases {
+            assert_eq!(
+                round_trip(&mut stream, tc.command),
+                tc.expected_response,
+                "Failed test: {}",
+                tc.name
+            );
+        }
+
+        assert!(!server.socket().lock().unwrap().is_on());
+    }
+
+    #[test]
+    fn test_server_rejects_unknown_command() {
+        let server = start_server(true, 10.0);
+        let mut stream = TcpStream::connect(server.local_addr()).unwrap();
+
+        stream.write_all(&[0x7F]).unwrap();
+        assert_eq!(
+            Response::read_from(&mut stream).unwrap(),
+            Response::Error(error_code::UNKNOWN_COMMAND)
+        );
+
+        // The connection stays usable after a rejected command
+        assert_eq!(
+            round_trip(&mut stream, Command::IsOn),
+            Response::State(true)
+        );
+    }
+
+    #[test]
+    fn test_server_shares_state_between_clients() {
+        let server = start_server(false, 25.0);
+        let mut first = TcpStream::connect(server.local_addr()).unwrap();
+        let mut second = TcpStream::connect(server.local_addr()).unwrap();
+
+        assert_eq!(round_trip(&mut first, Command::TurnOn), Response::Ok);
+        assert_eq!(
+            round_trip(&mut second, Command::IsOn),
+            Response::State(true)
+        );
+    }
+
+    #[test]
+    fn test_shutdown_stops_accepting() {
+        let server = start_server(false, 25.0);
+        let addr = server.local_addr();
+        server.shutdown();
+
+        assert!(TcpStream::connect(addr).is_err());
+    }
+
+    #[test]
+    fn test_shutdown_closes_open_connections() {
+        let server = start_server(false, 25.0);
+        let mut stream = TcpStream::connect(server.local_addr()).unwrap();
+        assert_eq!(
+            round_trip(&mut stream, Command::IsOn),
+            Response::State(false)
+        );
+
+        server.shutdown();
+        let mut buf = [0u8; 1];
+        assert!(matches!(
+            std::io::Read::read(&mut stream, &mut buf),
+            Ok(0) | Err(_)
+        ));
+    }
+}
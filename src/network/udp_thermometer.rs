@@ -0,0 +1,442 @@
+use super::protocol::TemperatureDatagram;
+use crate::Reporter;
+use crate::device::CustomDevice;
+use crate::device::device_trait::{SmartDeviceTrait, TemperatureSensor};
+use crate::error::ProtocolError;
+use crate::report::Metric;
+use std::io::{self, ErrorKind};
+use std::net::{SocketAddr, ToSocketAddrs, UdpSocket};
+use std::sync::atomic::{AtomicBool, Ordering};
+use std::sync::{Arc, Mutex};
+use std::thread::{self, JoinHandle};
+use std::time::{Duration, SystemTime};
+
+/// How often the receiver thread checks whether it should stop
+const POLL_INTERVAL: Duration = Duration::from_millis(50);
+
+/// A temperature value together with the moment its datagram was received
+#[derive(Debug, Clone, Copy, PartialEq)]
+pub struct UdpTemperatureReading {
+    pub temperature: f32,
+    pub received_at: SystemTime,
+}
+
+/// Thermometer fed by UDP telemetry datagrams
+///
+/// A background thread listens on a UDP socket and keeps the latest
+/// reading. Until the first datagram arrives [`TemperatureSensor::temperature`]
+/// returns `NaN`; use [`UdpThermometer::try_temperature`] to tell the cases apart.
+/// Socket errors do not stop the thread; the last one is kept for
+/// [`UdpThermometer::last_error`].
+#[derive(Debug)]
+pub struct UdpThermometer {
+    name: String,
+    local_addr: SocketAddr,
+    stale_after: Duration,
+    latest: Arc<Mutex<Option<UdpTemperatureReading>>>,
+    last_error: Arc<Mutex<Option<io::Error>>>,
+    running: Arc<AtomicBool>,
+    receiver: Option<JoinHandle<()>>,
+}
+
+impl UdpThermometer {
+    /// Default window after which a reading is considered stale
+    pub const DEFAULT_STALE_AFTER: Duration = Duration::from_secs(10);
+
+    /// Binds the thermometer to a UDP address and starts receiving readings
+    ///
+    /// Use port `0` to let the operating system pick a free port.
+    pub fn bind<A: ToSocketAddrs>(name: String, addr: A) -> io::Result<Self> {
+        Self::spawn(name, UdpSocket::bind(addr)?)
+    }
+
+    /// Starts receiving readings on a bound socket
+    fn spawn(name: String, socket: UdpSocket) -> io::Result<Self> {
+        socket.set_read_timeout(Some(POLL_INTERVAL))?;
+        let local_addr = socket.local_addr()?;
+
+        let latest = Arc::new(Mutex::new(None));
+        let last_error = Arc::new(Mutex::new(None));
+        let running = Arc::new(AtomicBool::new(true));
+
+        let receiver = {
+            let latest = Arc::clone(&latest);
+            let last_error = Arc::clone(&last_error);
+            let running = Arc::clone(&running);
+            thread::spawn(move || receive_loop(&socket, &latest, &last_error, &running))
+        };
+
+        Ok(Self {
+            name,
+            local_addr,
+            stale_after: Self::DEFAULT_STALE_AFTER,
+            latest,
+            last_error,
+            running,
+            receiver: Some(receiver),
+        })
+    }
+
+    /// Sets the window after which the latest reading is considered stale
+    pub fn with_stale_after(mut self, stale_after: Duration) -> Self {
+        self.stale_after = stale_after;
+        self
+    }
+
+    /// Returns the address the thermometer is listening on
+    pub fn local_addr(&self) -> SocketAddr {
+        self.local_addr
+    }
+
+    /// Returns the latest reading, if any datagram has arrived yet
+    pub fn latest_reading(&self) -> Option<UdpTemperatureReading> {
+        *self.latest.lock().unwrap_or_else(|e| e.into_inner())
+    }
+
+    /// Returns the last error the socket reported since the latest reading
+    ///
+    /// The thread keeps receiving after an error, so a reading arriving later
+    /// clears it.
+    pub fn last_error(&self) -> Option<io::Error> {
+        self.last_error
+            .lock()
+            .unwrap_or_else(|e| e.into_inner())
+            .as_ref()
+            .map(|e| io::Error::new(e.kind(), e.to_string()))
+    }
+
+    /// Returns the latest temperature if it can be trusted
+    ///
+    /// Fails with the last socket error, with [`ProtocolError::NoReading`]
+    /// before the first datagram and with [`ProtocolError::Timeout`] once the
+    /// reading is stale.
+    pub fn try_temperature(&self) -> Result<f32, ProtocolError> {
+        if let Some(e) = self.last_error() {
+            return Err(ProtocolError::Io(e));
+        }
+        let reading = self.latest_reading().ok_or(ProtocolError::NoReading)?;
+        if self.is_stale() {
+            return Err(ProtocolError::Timeout(self.stale_after));
+        }
+        Ok(reading.temperature)
+    }
+
+    /// Checks whether no datagram arrived within the staleness window
+    pub fn is_stale(&self) -> bool {
+        match self.latest_reading() {
+            Some(reading) => reading
+                .received_at
+                .elapsed()
+                .map(|age| age > self.stale_after)
+                // The system clock went backwards; the reading is brand new
+                .unwrap_or(false),
+            None => true,
+        }
+    }
+}
+
+impl Drop for UdpThermometer {
+    fn drop(&mut self) {
+        self.running.store(false, Ordering::SeqCst);
+        if let Some(receiver) = self.receiver.take() {
+            let _ = receiver.join();
+        }
+    }
+}
+
+impl SmartDeviceTrait for UdpThermometer {
+    fn name(&self) -> &str {
+        &self.name
+    }
+
+    fn as_temperature_sensor(&self) -> Option<&dyn TemperatureSensor> {
+        Some(self)
+    }
+}
+
+impl TemperatureSensor for UdpThermometer {
+    fn temperature(&self) -> f32 {
+        self.latest_reading()
+            .map(|reading| reading.temperature)
+            .unwrap_or(f32::NAN)
+    }
+}
+
+impl Reporter for UdpThermometer {
+    fn report(&self) -> String {
+        match self.latest_reading() {
+            Some(reading) => format!(
+                "Device: {name}, Temperature: {temperature}°C{stale}",
+                name = self.name(),
+                temperature = reading.temperature,
+                stale = if self.is_stale() { " (stale)" } else { "" }
+            ),
+            None => format!("Device: {}, Temperature: no data", self.name()),
+        }
+    }
+}
+
+impl CustomDevice for UdpThermometer {
+    fn kind(&self) -> &str {
+        "udp_thermometer"
+    }
+
+    fn report_metrics(&self) -> Vec<Metric> {
+        vec![Metric::flag("stale", self.is_stale())]
+    }
+}
+
+fn receive_loop(
+    socket: &UdpSocket,
+    latest: &Mutex<Option<UdpTemperatureReading>>,
+    last_error: &Mutex<Option<io::Error>>,
+    running: &AtomicBool,
+) {
+    // One byte more than a datagram so oversized packets are detected
+    let mut buf = [0u8; TemperatureDatagram::SIZE + 1];
+
+    while running.load(Ordering::SeqCst) {
+        match socket.recv(&mut buf) {
+            Ok(len) => {
+                // Malformed datagrams are dropped, the previous reading stays
+                if let Ok(datagram) = TemperatureDatagram::from_bytes(&buf[..len]) {
+                    let reading = UdpTemperatureReading {
+                        temperature: datagram.temperature,
+                        received_at: SystemTime::now(),
+                    };
+                    *latest.lock().unwrap_or_else(|e| e.into_inner()) = Some(reading);
+                    *last_error.lock().unwrap_or_else(|e| e.into_inner()) = None;
+                }
+            }
+            Err(e) if matches!(e.kind(), ErrorKind::WouldBlock | ErrorKind::TimedOut) => {}
+            Err(e) => {
+                // Errors such as a reset from an earlier send may be transient,
+                // so keep listening without spinning on a persistent one
+                *last_error
+                    .lock()
+                    .unwrap_or_else(|poisoned| poisoned.into_inner()) = Some(e);
+                thread::sleep(POLL_INTERVAL);
+            }
+        }
+    }
+}
+
+/// Emulates a hardware thermometer by sending telemetry datagrams
+#[derive(Debug)]
+pub struct ThermometerEmulator {
+    socket: UdpSocket,
+    target: SocketAddr,
+}
+
+impl ThermometerEmulator {
+    /// Creates an emulator sending to the given thermometer address
+    pub fn new<A: ToSocketAddrs>(target: A) -> io::Result<Self> {
+        let target = target
+            .to_socket_addrs()?
+            .next()
+            .ok_or_else(|| io::Error::new(ErrorKind::InvalidInput, "no target address"))?;
+        let bind_addr: SocketAddr = if target.is_ipv4() {
+            ([0, 0, 0, 0], 0).into()
+        } else {
+            ([0u16; 8], 0).into()
+        };
+
+        Ok(Self {
+            socket: UdpSocket::bind(bind_addr)?,
+            target,
+        })
+    }
+
+    /// Sends a single temperature reading
+    pub fn send(&self, temperature: f32) -> io::Result<()> {
+        let datagram = TemperatureDatagram { temperature };
+        self.socket.send_to(&datagram.to_bytes(), self.target)?;
+        Ok(())
+    }
+
+    /// Sends a sequence of readings, pausing between them
+    pub fn send_all<I>(&self, readings: I, interval: Duration) -> io::Result<()>
+    where
+        I: IntoIterator<Item = f32>,
+    {
+        for (i, temperature) in readings.into_iter().enumerate() {
+            if i > 0 {
+                thread::sleep(interval);
+            }
+            self.send(temperature)?;
+        }
+        Ok(())
+    }
+}
+
+#[cfg(test)]
+pub(crate) mod tests {
+    use super::*;
+    use crate::device::SmartDevice;
+    use crate::report::{MetricValue, StructuredReport};
+    use std::time::Instant;
+
+    fn bind_thermometer() -> UdpThermometer {
+        UdpThermometer::bind("Udp Thermometer".to_string(), "127.0.0.1:0").unwrap()
+    }
+
+    /// Starts a thermometer whose socket reports a refused connection
+    ///
+    /// The socket is connected to a closed port and has sent to it, so the
+    /// ICMP port unreachable answer fails its next receive. Once the error
+    /// is reported, readings are only accepted from the returned address.
+    pub(crate) fn refused_thermometer() -> (UdpThermometer, SocketAddr) {
+        let peer_addr = UdpSocket::bind("127.0.0.1:0")
+            .unwrap()
+            .local_addr()
+            .unwrap();
+        let socket = UdpSocket::bind("127.0.0.1:0").unwrap();
+        socket.connect(peer_addr).unwrap();
+        socket.send(&[0]).unwrap();
+
+        let thermometer = UdpThermometer::spawn("Udp Thermometer".to_string(), socket).unwrap();
+        let deadline = Instant::now() + Duration::from_secs(2);
+        while thermometer.last_error().is_none() && Instant::now() < deadline {
+            thread::sleep(Duration::from_millis(5));
+        }
+        (thermometer, peer_addr)
+    }
+
+    /// Polls until the thermometer reports the expected temperature
+    fn wait_for(thermometer: &UdpThermometer, expected: f32) -> bool {
+        let deadline = Instant::now() + Duration::from_secs(2);
+        while Instant::now() < deadline {
+            if thermometer.temperature() == expected {
+                return true;
+            }
+            thread::sleep(Duration::from_millis(5));
+        }
+        false
+    }
+
+    #[test]
+    fn test_no_data_before_first_datagram() {
+        let thermometer = bind_thermometer();
+
+        assert!(thermometer.latest_reading().is_none());
+        assert!(thermometer.last_error().is_none());
+        assert!(thermometer.temperature().is_nan());
+        assert!(thermometer.is_stale());
+        assert_eq!(
+            thermometer.report(),
+            "Device: Udp Thermometer, Temperature: no data"
+        );
+    }
+
+    #[test]
+    fn test_receives_latest_reading() {
+        struct TestCase {
+            name: &'static str,
+            sent: f32,
+        }
+
+        let test_cases = vec![
+            TestCase {
+                name: "First reading",
+                sent: 21.5,
+            },
+            TestCase {
+                name: "Reading replaces previous one",
+                sent: 23.0,
+            },
+            TestCase {
+                name: "Negative reading",
+                sent: -4.5,
+            },
+        ];
+
+        let thermometer = bind_thermometer();
+        let emulator = ThermometerEmulator::new(thermometer.local_addr()).unwrap();
+
+        for tc in test_cases {
+            emulator.send(tc.sent).unwrap();
+            assert!(
+                wait_for(&thermometer, tc.sent),
+                "Test case '{}': expected temperature {} but got {}",
+                tc.name,
+                tc.sent,
+                thermometer.temperature()
+            );
+            assert!(!thermometer.is_stale(), "Failed test: {}", tc.name);
+        }
+
+        assert_eq!(
+            thermometer.report(),
+            "Device: Udp Thermometer, Temperature: -4.5°C"
+        );
+    }
+
+    #[test]
+    fn test_udp_thermometer_as_smart_device() {
+        let thermometer = bind_thermometer();
+        let emulator = ThermometerEmulator::new(thermometer.local_addr()).unwrap();
+        emulator.send(24.5).unwrap();
+        assert!(wait_for(&thermometer, 24.5));
+
+        let device = SmartDevice::custom(thermometer);
+        assert_eq!(device.kind(), "udp_thermometer");
+        assert_eq!(device.temperature(), Some(24.5));
+        assert!(!device.supports_power_control());
+
+        let report = device.structured_report();
+        assert_eq!(
+            report.metric("temperature").map(|m| &m.value),
+            Some(&MetricValue::Number(24.5))
+        );
+        assert_eq!(
+            report.metric("stale").map(|m| &m.value),
+            Some(&MetricValue::Bool(false))
+        );
+    }
+
+    #[test]
+    fn test_malformed_datagrams_are_ignored() {
+        let thermometer = bind_thermometer();
+        let emulator = ThermometerEmulator::new(thermometer.local_addr()).unwrap();
+        emulator.send(19.0).unwrap();
+        assert!(wait_for(&thermometer, 19.0));
+
+        let raw = UdpSocket::bind("127.0.0.1:0").unwrap();
+        raw.send_to(&[1, 2, 3], thermometer.local_addr()).unwrap();
+        raw.send_to(&[0; 16], thermometer.local_addr()).unwrap();
+        emulator.send(20.0).unwrap();
+
+        assert!(wait_for(&thermometer, 20.0));
+    }
+
+    #[test]
+    fn test_keeps_receiving_after_socket_error() {
+        let (thermometer, peer_addr) = refused_thermometer();
+
+        let error = thermometer.last_error().expect("socket error is kept");
+        assert_eq!(error.kind(), ErrorKind::ConnectionRefused);
+        assert!(thermometer.latest_reading().is_none());
+
+        let peer = UdpSocket::bind(peer_addr).unwrap();
+        peer.connect(thermometer.local_addr()).unwrap();
+        peer.send(&TemperatureDatagram { temperature: 18.5 }.to_bytes())
+            .unwrap();
+
+        assert!(wait_for(&thermometer, 18.5));
+        assert!(thermometer.last_error().is_none());
+    }
+
+    #[test]
+    fn test_reading_becomes_stale() {
+        let thermometer = bind_thermometer().with_stale_after(Duration::from_millis(30));
+        let emulator = ThermometerEmulator::new(thermometer.local_addr()).unwrap();
+
+        emulator.send(22.0).unwrap();
+        assert!(wait_for(&thermometer, 22.0));
+
+        thread::sleep(Duration::from_millis(60));
+        assert!(thermometer.is_stale());
+        assert_eq!(thermometer.temperature(), 22.0);
+        assert!(thermometer.report().ends_with("(stale)"));
+    }
+}
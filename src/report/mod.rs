@@ -0,0 +1,80 @@
+// Structured report model and its renderers
+mod model;
+mod render;
+
+pub use model::{DeviceReport, HouseReport, Metric, MetricValue, ReportOrder, RoomReport};
+pub use render::{CsvRenderer, JsonRenderer, MarkdownRenderer, ReportRenderer, TextRenderer};
+
+/// Trait for types that can generate a report
+pub trait Reporter {
+    /// Generates a text report about the state of the object
+    fn report(&self) -> String;
+}
+
+/// Trait for types that can build a structured report
+///
+/// Implemented by [`SmartHouse`](crate::SmartHouse), [`Room`](crate::Room) and
+/// [`SmartDevice`](crate::SmartDevice), so any of them can be rendered with any
+/// [`ReportRenderer`].
+pub trait StructuredReport {
+    /// The report model produced by the type
+    type Report: ReportModel;
+
+    /// Builds the structured report with rooms and devices in insertion order
+    fn structured_report(&self) -> Self::Report;
+
+    /// Builds the structured report with rooms and devices in the given order
+    fn structured_report_ordered(&self, order: ReportOrder) -> Self::Report {
+        let mut report = self.structured_report();
+        report.sort(order);
+        report
+    }
+
+    /// Builds the structured report and renders it
+    fn render(&self, renderer: &dyn ReportRenderer) -> String {
+        self.structured_report().render_with(renderer)
+    }
+
+    /// Builds the structured report in the given order and renders it
+    fn render_ordered(&self, renderer: &dyn ReportRenderer, order: ReportOrder) -> String {
+        self.structured_report_ordered(order).render_with(renderer)
+    }
+}
+
+/// Report models that can be reordered and passed to a [`ReportRenderer`]
+pub trait ReportModel {
+    /// Renders the report with the given renderer
+    fn render_with(&self, renderer: &dyn ReportRenderer) -> String;
+
+    /// Reorders the rooms and devices of the report
+    fn sort(&mut self, order: ReportOrder);
+}
+
+impl ReportModel for HouseReport {
+    fn render_with(&self, renderer: &dyn ReportRenderer) -> String {
+        renderer.render_house(self)
+    }
+
+    fn sort(&mut self, order: ReportOrder) {
+        HouseReport::sort(self, order);
+    }
+}
+
+impl ReportModel for RoomReport {
+    fn render_with(&self, renderer: &dyn ReportRenderer) -> String {
+        renderer.render_room(self)
+    }
+
+    fn sort(&mut self, order: ReportOrder) {
+        RoomReport::sort(self, order);
+    }
+}
+
+impl ReportModel for DeviceReport {
+    fn render_with(&self, renderer: &dyn ReportRenderer) -> String {
+        renderer.render_device(self)
+    }
+
+    /// A single device has nothing to reorder
+    fn sort(&mut self, _order: ReportOrder) {}
+}
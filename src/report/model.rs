@@ -0,0 +1,217 @@
+use crate::device::TemperatureStats;
+use crate::room::average;
+use serde::Serialize;
+use std::fmt;
+
+/// Structured report about a whole house
+#[derive(Debug, Clone, PartialEq, Serialize)]
+pub struct HouseReport {
+    pub name: String,
+    pub rooms: Vec<RoomReport>,
+    /// Aggregates over all devices of the house, such as the average temperature
+    #[serde(skip_serializing_if = "Vec::is_empty")]
+    pub summary: Vec<Metric>,
+}
+
+/// Structured report about a room and its devices
+#[derive(Debug, Clone, PartialEq, Serialize)]
+pub struct RoomReport {
+    pub name: String,
+    pub devices: Vec<DeviceReport>,
+    /// Aggregates over the devices of the room, such as the average temperature
+    #[serde(skip_serializing_if = "Vec::is_empty")]
+    pub summary: Vec<Metric>,
+}
+
+/// Structured report about a single device
+#[derive(Debug, Clone, PartialEq, Serialize)]
+pub struct DeviceReport {
+    pub name: String,
+    pub kind: String,
+    pub metrics: Vec<Metric>,
+}
+
+/// A named, typed value reported by a device
+#[derive(Debug, Clone, PartialEq, Serialize)]
+pub struct Metric {
+    pub name: String,
+    pub value: MetricValue,
+    #[serde(skip_serializing_if = "Option::is_none")]
+    pub unit: Option<String>,
+}
+
+impl Metric {
+    /// Creates a numeric metric with a unit, e.g. `Metric::number("power", 80.0, "W")`
+    pub fn number(name: &str, value: f32, unit: &str) -> Self {
+        Self {
+            name: name.to_string(),
+            value: MetricValue::Number(value),
+            unit: Some(unit.to_string()),
+        }
+    }
+
+    /// Creates a boolean metric
+    pub fn flag(name: &str, value: bool) -> Self {
+        Self {
+            name: name.to_string(),
+            value: MetricValue::Bool(value),
+            unit: None,
+        }
+    }
+
+    /// Creates a text metric
+    pub fn text(name: &str, value: &str) -> Self {
+        Self {
+            name: name.to_string(),
+            value: MetricValue::Text(value.to_string()),
+            unit: None,
+        }
+    }
+}
+
+impl fmt::Display for Metric {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        match &self.unit {
+            Some(unit) => write!(f, "{}: {}{}", self.name, self.value, unit),
+            None => write!(f, "{}: {}", self.name, self.value),
+        }
+    }
+}
+
+/// Value of a [`Metric`]
+#[derive(Debug, Clone, PartialEq, Serialize)]
+#[serde(untagged)]
+pub enum MetricValue {
+    Bool(bool),
+    Number(f32),
+    Text(String),
+}
+
+impl fmt::Display for MetricValue {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        match self {
+            MetricValue::Bool(value) => write!(f, "{}", value),
+            MetricValue::Number(value) => write!(f, "{}", value),
+            MetricValue::Text(value) => f.write_str(value),
+        }
+    }
+}
+
+/// Order of rooms and devices in a report
+#[derive(Debug, Clone, Copy, Default, PartialEq, Eq)]
+pub enum ReportOrder {
+    /// The order in which rooms and devices were added
+    #[default]
+    Insertion,
+    /// Alphabetical order by name
+    Name,
+    /// Highest power draw first; ties keep insertion order
+    PowerDraw,
+}
+
+impl ReportOrder {
+    /// Stably sorts entries given accessors for their name and power draw
+    pub(crate) fn sort<T>(
+        self,
+        entries: &mut [T],
+        name: impl Fn(&T) -> &str,
+        power: impl Fn(&T) -> f32,
+    ) {
+        match self {
+            ReportOrder::Insertion => {}
+            ReportOrder::Name => entries.sort_by(|a, b| name(a).cmp(name(b))),
+            ReportOrder::PowerDraw => entries.sort_by(|a, b| power(b).total_cmp(&power(a))),
+        }
+    }
+}
+
+impl HouseReport {
+    /// Creates a house report and computes its summary from the device metrics
+    pub fn new(name: String, rooms: Vec<RoomReport>) -> Self {
+        let summary = summarize(rooms.iter().flat_map(|room| &room.devices));
+        Self {
+            name,
+            rooms,
+            summary,
+        }
+    }
+
+    /// Reorders rooms, and devices within each room
+    pub fn sort(&mut self, order: ReportOrder) {
+        for room in &mut self.rooms {
+            room.sort(order);
+        }
+        order.sort(&mut self.rooms, |room| &room.name, RoomReport::power);
+    }
+}
+
+impl RoomReport {
+    /// Creates a room report and computes its summary from the device metrics
+    pub fn new(name: String, devices: Vec<DeviceReport>) -> Self {
+        let summary = summarize(&devices);
+        Self {
+            name,
+            devices,
+            summary,
+        }
+    }
+
+    /// Reorders the devices of the room
+    pub fn sort(&mut self, order: ReportOrder) {
+        order.sort(
+            &mut self.devices,
+            |device| &device.name,
+            DeviceReport::power,
+        );
+    }
+
+    /// Returns the total power drawn by the devices of the room in watts
+    pub fn power(&self) -> f32 {
+        self.devices.iter().map(DeviceReport::power).sum()
+    }
+}
+
+/// Builds the summary metrics of a set of devices
+fn summarize<'a>(devices: impl IntoIterator<Item = &'a DeviceReport>) -> Vec<Metric> {
+    let devices: Vec<&DeviceReport> = devices.into_iter().collect();
+    let numbers = |name: &'static str| {
+        devices
+            .iter()
+            .filter_map(move |device| match device.metric(name)?.value {
+                MetricValue::Number(value) => Some(value),
+                _ => None,
+            })
+    };
+
+    let mut summary = Vec::new();
+    if let Some(stats) = TemperatureStats::from_temperatures(numbers("temperature")) {
+        summary.push(Metric::number("average_temperature", stats.mean, "°C"));
+        summary.push(Metric::number("min_temperature", stats.min, "°C"));
+        summary.push(Metric::number("max_temperature", stats.max, "°C"));
+    }
+    if let Some(humidity) = average(numbers("humidity")) {
+        summary.push(Metric::number("average_humidity", humidity, "%"));
+    }
+    if let Some(co2) = average(numbers("co2")) {
+        summary.push(Metric::number("average_co2", co2, "ppm"));
+    }
+    if let Some(index) = numbers("air_quality_index").reduce(f32::max) {
+        summary.push(Metric::number("max_air_quality_index", index, "AQI"));
+    }
+    summary
+}
+
+impl DeviceReport {
+    /// Returns the metric with the given name, if the device reported it
+    pub fn metric(&self, name: &str) -> Option<&Metric> {
+        self.metrics.iter().find(|metric| metric.name == name)
+    }
+
+    /// Returns the power drawn by the device in watts, zero if it does not report power
+    pub fn power(&self) -> f32 {
+        match self.metric("power").map(|metric| &metric.value) {
+            Some(MetricValue::Number(watts)) => *watts,
+            _ => 0.0,
+        }
+    }
+}
@@ -0,0 +1,395 @@
+use super::model::{DeviceReport, HouseReport, Metric, RoomReport};
+use serde::Serialize;
+
+/// Turns a structured report into a string in some output format
+pub trait ReportRenderer {
+    /// Renders a report about a whole house
+    fn render_house(&self, report: &HouseReport) -> String;
+
+    /// Renders a report about a single room
+    fn render_room(&self, report: &RoomReport) -> String;
+
+    /// Renders a report about a single device
+    fn render_device(&self, report: &DeviceReport) -> String;
+}
+
+/// Renders reports as human-readable plain text
+#[derive(Debug, Clone, Copy, Default)]
+pub struct TextRenderer;
+
+impl TextRenderer {
+    fn device_line(report: &DeviceReport) -> String {
+        let mut line = format!("Device: {} ({})", report.name, report.kind);
+        for metric in &report.metrics {
+            line.push_str(", ");
+            line.push_str(&metric.to_string());
+        }
+        line
+    }
+
+    fn push_summary(output: &mut String, summary: &[Metric]) {
+        if !summary.is_empty() {
+            let metrics: Vec<String> = summary.iter().map(Metric::to_string).collect();
+            output.push_str(&format!("Summary: {}\n", metrics.join(", ")));
+        }
+    }
+}
+
+impl ReportRenderer for TextRenderer {
+    fn render_house(&self, report: &HouseReport) -> String {
+        let mut output = format!("=== Smart House: {} ===\n", report.name);
+        for room in &report.rooms {
+            output.push_str(&self.render_room(room));
+        }
+        if !report.summary.is_empty() {
+            output.push_str("=== House Summary ===\n");
+            Self::push_summary(&mut output, &report.summary);
+        }
+        output
+    }
+
+    fn render_room(&self, report: &RoomReport) -> String {
+        let mut output = format!("=== Room: {} ===\n", report.name);
+        for device in &report.devices {
+            output.push_str(&Self::device_line(device));
+            output.push('\n');
+        }
+        Self::push_summary(&mut output, &report.summary);
+        output
+    }
+
+    fn render_device(&self, report: &DeviceReport) -> String {
+        Self::device_line(report) + "\n"
+    }
+}
+
+/// Renders reports as pretty-printed JSON
+#[derive(Debug, Clone, Copy, Default)]
+pub struct JsonRenderer;
+
+impl JsonRenderer {
+    fn to_json<T: Serialize>(report: &T) -> String {
+        // The report model only holds strings, numbers and booleans
+        serde_json::to_string_pretty(report).expect("report model is always serializable")
+    }
+}
+
+impl ReportRenderer for JsonRenderer {
+    fn render_house(&self, report: &HouseReport) -> String {
+        Self::to_json(report)
+    }
+
+    fn render_room(&self, report: &RoomReport) -> String {
+        Self::to_json(report)
+    }
+
+    fn render_device(&self, report: &DeviceReport) -> String {
+        Self::to_json(report)
+    }
+}
+
+/// Renders reports as CSV with one row per device metric
+///
+/// Columns are `room,device,kind,metric,value,unit`. The `room` column is
+/// empty when a single device is rendered. Summary rows have empty `device`
+/// and `kind` columns, and house summary rows an empty `room` column too.
+#[derive(Debug, Clone, Copy, Default)]
+pub struct CsvRenderer;
+
+impl CsvRenderer {
+    const HEADER: &'static str = "room,device,kind,metric,value,unit\n";
+
+    fn push_rows(output: &mut String, room: &str, device: &DeviceReport) {
+        Self::push_metric_rows(output, room, &device.name, &device.kind, &device.metrics);
+    }
+
+    fn push_metric_rows(
+        output: &mut String,
+        room: &str,
+        device: &str,
+        kind: &str,
+        metrics: &[Metric],
+    ) {
+        for metric in metrics {
+            let fields = [
+                room,
+                device,
+                kind,
+                &metric.name,
+                &metric.value.to_string(),
+                metric.unit.as_deref().unwrap_or(""),
+            ];
+            let row: Vec<String> = fields.iter().map(|field| Self::escape(field)).collect();
+            output.push_str(&row.join(","));
+            output.push('\n');
+        }
+    }
+
+    /// Quotes a field if it contains a separator, a quote or a line break
+    fn escape(field: &str) -> String {
+        if field.contains([',', '"', '\n', '\r']) {
+            format!("\"{}\"", field.replace('"', "\"\""))
+        } else {
+            field.to_string()
+        }
+    }
+}
+
+impl ReportRenderer for CsvRenderer {
+    fn render_house(&self, report: &HouseReport) -> String {
+        let mut output = String::from(Self::HEADER);
+        for room in &report.rooms {
+            for device in &room.devices {
+                Self::push_rows(&mut output, &room.name, device);
+            }
+            Self::push_metric_rows(&mut output, &room.name, "", "", &room.summary);
+        }
+        Self::push_metric_rows(&mut output, "", "", "", &report.summary);
+        output
+    }
+
+    fn render_room(&self, report: &RoomReport) -> String {
+        let mut output = String::from(Self::HEADER);
+        for device in &report.devices {
+            Self::push_rows(&mut output, &report.name, device);
+        }
+        Self::push_metric_rows(&mut output, &report.name, "", "", &report.summary);
+        output
+    }
+
+    fn render_device(&self, report: &DeviceReport) -> String {
+        let mut output = String::from(Self::HEADER);
+        Self::push_rows(&mut output, "", report);
+        output
+    }
+}
+
+/// Renders reports as Markdown with a device table per room
+#[derive(Debug, Clone, Copy, Default)]
+pub struct MarkdownRenderer;
+
+impl MarkdownRenderer {
+    const TABLE_HEADER: &'static str = "| Device | Kind | Metrics |\n| --- | --- | --- |\n";
+
+    fn table<'a>(devices: impl IntoIterator<Item = &'a DeviceReport>) -> String {
+        let mut output = String::from(Self::TABLE_HEADER);
+        for device in devices {
+            let metrics: Vec<String> = device.metrics.iter().map(Metric::to_string).collect();
+            output.push_str(&format!(
+                "| {} | {} | {} |\n",
+                Self::escape(&device.name),
+                Self::escape(&device.kind),
+                Self::escape(&metrics.join(", "))
+            ));
+        }
+        output
+    }
+
+    fn summary(summary: &[Metric]) -> String {
+        if summary.is_empty() {
+            return String::new();
+        }
+        let metrics: Vec<String> = summary.iter().map(Metric::to_string).collect();
+        format!("\n**Summary:** {}\n", metrics.join(", "))
+    }
+
+    /// Keeps cell content from breaking the table layout
+    fn escape(cell: &str) -> String {
+        cell.replace('|', "\\|").replace('\n', " ")
+    }
+}
+
+impl ReportRenderer for MarkdownRenderer {
+    fn render_house(&self, report: &HouseReport) -> String {
+        let mut output = format!("# Smart House: {}\n", report.name);
+        for room in &report.rooms {
+            output.push('\n');
+            output.push_str(&format!("## Room: {}\n\n", room.name));
+            output.push_str(&Self::table(&room.devices));
+            output.push_str(&Self::summary(&room.summary));
+        }
+        if !report.summary.is_empty() {
+            output.push_str("\n## House Summary\n");
+            output.push_str(&Self::summary(&report.summary));
+        }
+        output
+    }
+
+    fn render_room(&self, report: &RoomReport) -> String {
+        format!(
+            "## Room: {}\n\n{}{}",
+            report.name,
+            Self::table(&report.devices),
+            Self::summary(&report.summary)
+        )
+    }
+
+    fn render_device(&self, report: &DeviceReport) -> String {
+        Self::table([report])
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    fn create_house_report() -> HouseReport {
+        HouseReport {
+            name: "Test House".to_string(),
+            rooms: vec![RoomReport {
+                name: "Kitchen".to_string(),
+                devices: vec![
+                    DeviceReport {
+                        name: "Kettle".to_string(),
+                        kind: "socket".to_string(),
+                        metrics: vec![
+                            Metric::flag("is_on", true),
+                            Metric::number("power", 80.0, "W"),
+                        ],
+                    },
+                    DeviceReport {
+                        name: "Thermo, wall".to_string(),
+                        kind: "thermometer".to_string(),
+                        metrics: vec![Metric::number("temperature", 21.5, "°C")],
+                    },
+                ],
+                summary: Vec::new(),
+            }],
+            summary: Vec::new(),
+        }
+    }
+
+    #[test]
+    fn test_render_house() {
+        struct TestCase {
+            name: &'static str,
+            renderer: &'static dyn ReportRenderer,
+            expected: &'static str,
+        }
+
+        let test_cases = vec![
+            TestCase {
+                name: "Plain text",
+                renderer: &TextRenderer,
+                expected: "=== Smart House: Test House ===\n\
+                           === Room: Kitchen ===\n\
+                           Device: Kettle (socket), is_on: true, power: 80W\n\
+                           Device: Thermo, wall (thermometer), temperature: 21.5°C\n",
+            },
+            TestCase {
+                name: "CSV",
+                renderer: &CsvRenderer,
+                expected: "room,device,kind,metric,value,unit\n\
+                           Kitchen,Kettle,socket,is_on,true,\n\
+                           Kitchen,Kettle,socket,power,80,W\n\
+                           Kitchen,\"Thermo, wall\",thermometer,temperature,21.5,°C\n",
+            },
+            TestCase {
+                name: "Markdown",
+                renderer: &MarkdownRenderer,
+                expected: "# Smart House: Test House\n\
+                           \n\
+                           ## Room: Kitchen\n\
+                           \n\
+                           | Device | Kind | Metrics |\n\
+                           | --- | --- | --- |\n\
+                           | Kettle | socket | is_on: true, power: 80W |\n\
+                           | Thermo, wall | thermometer | temperature: 21.5°C |\n",
+            },
+        ];
+
+        let report = create_house_report();
+        for tc in test_cases {
+            assert_eq!(
+                tc.renderer.render_house(&report),
+                tc.expected,
+                "Failed test: {}",
+                tc.name
+            );
+        }
+    }
+
+    #[test]
+    fn test_render_summary() {
+        let thermometer = |name: &str, temperature: f32| DeviceReport {
+            name: name.to_string(),
+            kind: "thermometer".to_string(),
+            metrics: vec![Metric::number("temperature", temperature, "°C")],
+        };
+        let report = HouseReport::new(
+            "Test House".to_string(),
+            vec![
+                RoomReport::new(
+                    "Kitchen".to_string(),
+                    vec![thermometer("Wall", 22.0), thermometer("Window", 20.0)],
+                ),
+                RoomReport::new("Hall".to_string(), Vec::new()),
+            ],
+        );
+
+        assert!(report.rooms[1].summary.is_empty());
+        assert_eq!(
+            TextRenderer.render_house(&report),
+            "=== Smart House: Test House ===\n\
+             === Room: Kitchen ===\n\
+             Device: Wall (thermometer), temperature: 22°C\n\
+             Device: Window (thermometer), temperature: 20°C\n\
+             Summary: average_temperature: 21°C, min_temperature: 20°C, max_temperature: 22°C\n\
+             === Room: Hall ===\n\
+             === House Summary ===\n\
+             Summary: average_temperature: 21°C, min_temperature: 20°C, max_temperature: 22°C\n"
+        );
+        assert!(
+            CsvRenderer
+                .render_house(&report)
+                .ends_with("Kitchen,,,max_temperature,22,°C\n,,,average_temperature,21,°C\n,,,min_temperature,20,°C\n,,,max_temperature,22,°C\n")
+        );
+        assert!(
+            MarkdownRenderer
+                .render_house(&report)
+                .ends_with("\n## House Summary\n\n**Summary:** average_temperature: 21°C, min_temperature: 20°C, max_temperature: 22°C\n")
+        );
+
+        let json: serde_json::Value =
+            serde_json::from_str(&JsonRenderer.render_house(&report)).unwrap();
+        assert_eq!(json["summary"][0]["name"], "average_temperature");
+        assert!(json["rooms"][1].get("summary").is_none());
+    }
+
+    #[test]
+    fn test_render_json() {
+        let report = create_house_report();
+        let json: serde_json::Value =
+            serde_json::from_str(&JsonRenderer.render_house(&report)).unwrap();
+
+        assert_eq!(json["name"], "Test House");
+        let kettle = &json["rooms"][0]["devices"][0];
+        assert_eq!(kettle["kind"], "socket");
+        assert_eq!(kettle["metrics"][0]["value"], true);
+        assert_eq!(kettle["metrics"][1]["value"], 80.0);
+        assert_eq!(kettle["metrics"][1]["unit"], "W");
+        assert!(kettle["metrics"][0].get("unit").is_none());
+    }
+
+    #[test]
+    fn test_render_single_device() {
+        let report = DeviceReport {
+            name: "Pipe | Valve".to_string(),
+            kind: "valve".to_string(),
+            metrics: vec![Metric::text("position", "half \"open\"")],
+        };
+
+        assert_eq!(
+            TextRenderer.render_device(&report),
+            "Device: Pipe | Valve (valve), position: half \"open\"\n"
+        );
+        assert_eq!(
+            CsvRenderer.render_device(&report),
+            "room,device,kind,metric,value,unit\n,Pipe | Valve,valve,position,\"half \"\"open\"\"\",\n"
+        );
+        assert_eq!(
+            MarkdownRenderer.render_device(&report),
+            "| Device | Kind | Metrics |\n| --- | --- | --- |\n| Pipe \\| Valve | valve | position: half \"open\" |\n"
+        );
+    }
+}
@@ -1,26 +1,43 @@
 use crate::Reporter;
-use crate::device::SmartDevice;
+use crate::budget::{self, BudgetPolicy, Candidate, PowerBudget};
+use crate::device::{Capability, SmartDevice, SmartDeviceTrait, TemperatureStats};
+use crate::error::{DeviceAccessError, SmartHomeError};
+use crate::report::{ReportOrder, RoomReport, StructuredReport};
+use indexmap::IndexMap;
 use std::collections::HashMap;
 
 /// Represents a room in a smart house with multiple devices
+///
+/// Devices are kept in insertion order.
 #[derive(Debug)]
 pub struct Room {
     name: String,
-    devices: HashMap<String, SmartDevice>,
+    devices: IndexMap<String, SmartDevice>,
+    budget: Option<PowerBudget>,
+    /// Shedding priorities of the devices that do not use the default of 0
+    priorities: HashMap<String, i32>,
 }
 
 impl Room {
     /// Creates a new room with the specified name and devices
-    pub fn new(name: String, devices: HashMap<String, SmartDevice>) -> Self {
-        Room { name, devices }
+    ///
+    /// Devices keep the order of the iterator, so pass a `Vec` or an
+    /// `IndexMap` rather than a `HashMap` when the order matters.
+    pub fn new<I>(name: String, devices: I) -> Self
+    where
+        I: IntoIterator<Item = (String, SmartDevice)>,
+    {
+        Room {
+            name,
+            devices: devices.into_iter().collect(),
+            budget: None,
+            priorities: HashMap::new(),
+        }
     }
 
     /// Creates an empty room with the specified name
     pub fn new_empty(name: String) -> Self {
-        Room {
-            name,
-            devices: HashMap::new(),
-        }
+        Room::new(name, IndexMap::new())
     }
 
     /// Returns the name of the room
@@ -28,76 +45,451 @@ impl Room {
         &self.name
     }
 
-    /// Returns all devices in the room
-    pub fn all_devices(&self) -> &HashMap<String, SmartDevice> {
+    /// Returns all devices in the room in insertion order
+    pub fn all_devices(&self) -> &IndexMap<String, SmartDevice> {
         &self.devices
     }
 
-    /// Returns a reference to a specific device by name
-    pub fn device(&self, name: &String) -> Option<&SmartDevice> {
-        self.devices.get(name)
+    /// Returns the devices of the room in the given order
+    pub fn devices_ordered(&self, order: ReportOrder) -> Vec<(&String, &SmartDevice)> {
+        let mut devices: Vec<_> = self.devices.iter().collect();
+        order.sort(
+            &mut devices,
+            |(_, device)| device.name(),
+            |(_, device)| device.power_consumption().unwrap_or(0.0),
+        );
+        devices
     }
 
-    /// Returns a mutable reference to a specific device by name
-    pub fn device_mut(&mut self, name: &String) -> Option<&mut SmartDevice> {
-        self.devices.get_mut(name)
+    /// Returns the total power drawn by the devices of the room in watts
+    pub fn total_power_consumption(&self) -> f32 {
+        self.devices
+            .values()
+            .filter_map(SmartDevice::power_consumption)
+            .sum()
     }
 
-    /// Adds a new device to the room
-    pub fn add_device(&mut self, name: String, device: SmartDevice) -> Option<SmartDevice> {
-        self.devices.insert(name, device)
+    /// Returns the power budget of the room
+    pub fn power_budget(&self) -> Option<&PowerBudget> {
+        self.budget.as_ref()
     }
 
-    /// Removes a device from the room by name
-    pub fn remove_device(&mut self, name: &String) -> Option<SmartDevice> {
-        self.devices.remove(name)
+    /// Sets or clears the power budget of the room
+    ///
+    /// The budget applies to devices turned on afterwards; devices already
+    /// on stay on even if they draw more than the new limit.
+    pub fn set_power_budget(&mut self, budget: Option<PowerBudget>) -> Result<(), SmartHomeError> {
+        if let Some(budget) = &budget {
+            budget.validate()?;
+        }
+        self.budget = budget;
+        Ok(())
     }
 
-    /// Turns on a device by name (if the device supports power control)
-    pub fn turn_on_device(&mut self, name: &String) -> Option<bool> {
-        self.device_mut(name).map(|device| device.turn_on())
+    /// Returns the shedding priority of a device, 0 unless set
+    pub fn device_priority(&self, name: &String) -> i32 {
+        self.priorities.get(name).copied().unwrap_or(0)
     }
 
-    /// Turns off a device by name (if the device supports power control)
-    pub fn turn_off_device(&mut self, name: &String) -> Option<bool> {
-        self.device_mut(name).map(|device| device.turn_off())
+    /// Sets the shedding priority of a device
+    ///
+    /// Under [`BudgetPolicy::Shed`], turning a device on may switch off
+    /// devices with a strictly lower priority.
+    pub fn set_device_priority(
+        &mut self,
+        name: &String,
+        priority: i32,
+    ) -> Result<(), SmartHomeError> {
+        self.find_device(name)?;
+        if priority == 0 {
+            self.priorities.remove(name);
+        } else {
+            self.priorities.insert(name.clone(), priority);
+        }
+        Ok(())
     }
 
-    /// Gets temperature from a device (if it's a thermometer)
-    pub fn get_temperature(&self, name: &String) -> Option<Option<f32>> {
-        self.device(name).map(|device| device.temperature())
+    /// Returns the current readings of the temperature sensors in the room
+    ///
+    /// Sensors without a reading yet are skipped.
+    pub(crate) fn temperatures(&self) -> impl Iterator<Item = f32> + '_ {
+        self.readings(SmartDevice::temperature)
     }
 
-    /// Gets power consumption from a device (if it's a socket)
-    pub fn get_power_consumption(&self, name: &String) -> Option<Option<f32>> {
-        self.device(name).map(|device| device.power_consumption())
+    /// Returns the finite readings taken by `read` from the devices in the room
+    pub(crate) fn readings(
+        &self,
+        read: fn(&SmartDevice) -> Option<f32>,
+    ) -> impl Iterator<Item = f32> + '_ {
+        self.devices
+            .values()
+            .filter_map(read)
+            .filter(|reading| reading.is_finite())
     }
-}
 
-impl Reporter for Room {
-    fn report(&self) -> String {
+    /// Returns statistics over the current readings of the temperature sensors in the room
+    pub fn temperature_stats(&self) -> Option<TemperatureStats> {
+        TemperatureStats::from_temperatures(self.temperatures())
+    }
+
+    /// Returns the average temperature across the temperature sensors in the room
+    ///
+    /// Returns `None` if the room has no sensor with a reading.
+    pub fn average_temperature(&self) -> Option<f32> {
+        self.temperature_stats().map(|stats| stats.mean)
+    }
+
+    /// Returns the average relative humidity in percent across the humidity sensors in the room
+    pub fn average_humidity(&self) -> Option<f32> {
+        average(self.readings(SmartDevice::humidity))
+    }
+
+    /// Returns the average CO2 concentration in ppm across the CO2 sensors in the room
+    pub fn average_co2_ppm(&self) -> Option<f32> {
+        average(self.readings(SmartDevice::co2_ppm))
+    }
+
+    /// Returns the worst air quality index reported in the room
+    pub fn max_air_quality_index(&self) -> Option<u16> {
+        self.devices
+            .values()
+            .filter_map(SmartDevice::air_quality_index)
+            .max()
+    }
+
+    /// Returns the names of the binary sensors in the room that are currently triggered
+    pub fn triggered_sensors(&self) -> Vec<&String> {
+        self.devices
+            .iter()
+            .filter(|(_, device)| device.is_triggered() == Some(true))
+            .map(|(name, _)| name)
+            .collect()
+    }
+
+    /// Generates the text report with devices in the given order
+    pub fn report_ordered(&self, order: ReportOrder) -> String {
+        let devices = self.devices_ordered(order);
         let header_format = format!("=== Room: {} ===\n", self.name);
-        let newlines = self.devices.len();
+        let newlines = devices.len();
 
         let estimated_capacity = header_format.len()
-            + self
-                .devices
-                .values()
-                .map(|d| d.report().len())
-                .sum::<usize>()
+            + devices.iter().map(|(_, d)| d.report().len()).sum::<usize>()
             + newlines;
 
         let mut report = String::with_capacity(estimated_capacity);
 
         report.push_str(&header_format);
 
-        for device in self.devices.values() {
+        for (_, device) in devices {
             report.push_str(&device.report());
             report.push('\n');
         }
 
         report
     }
+
+    /// Returns a reference to a specific device by name
+    pub fn device(&self, name: &String) -> Option<&SmartDevice> {
+        self.devices.get(name)
+    }
+
+    /// Returns a mutable reference to a specific device by name
+    pub fn device_mut(&mut self, name: &String) -> Option<&mut SmartDevice> {
+        self.devices.get_mut(name)
+    }
+
+    /// Adds a new device to the room
+    ///
+    /// Fails if the name is empty or already taken by another device in the room
+    pub fn add_device(&mut self, name: String, device: SmartDevice) -> Result<(), SmartHomeError> {
+        validate_name("device name", &name)?;
+        if self.devices.contains_key(&name) {
+            return Err(SmartHomeError::DuplicateDevice(name, self.name.clone()));
+        }
+
+        self.devices.insert(name, device);
+        Ok(())
+    }
+
+    /// Removes a device from the room by name and returns it
+    pub fn remove_device(&mut self, name: &String) -> Result<SmartDevice, SmartHomeError> {
+        let device = self
+            .devices
+            .shift_remove(name)
+            .ok_or_else(|| DeviceAccessError::DeviceNotFound(name.clone(), self.name.clone()))?;
+        self.priorities.remove(name);
+        Ok(device)
+    }
+
+    /// Returns a device by name or a `DeviceNotFound` error naming this room
+    fn find_device(&self, name: &String) -> Result<&SmartDevice, DeviceAccessError> {
+        self.devices
+            .get(name)
+            .ok_or_else(|| DeviceAccessError::DeviceNotFound(name.clone(), self.name.clone()))
+    }
+
+    /// Mutable counterpart of [`Room::find_device`]
+    fn find_device_mut(&mut self, name: &String) -> Result<&mut SmartDevice, DeviceAccessError> {
+        self.devices
+            .get_mut(name)
+            .ok_or_else(|| DeviceAccessError::DeviceNotFound(name.clone(), self.name.clone()))
+    }
+
+    /// Turns on a device by name
+    ///
+    /// Fails if the device does not exist, does not support power control or
+    /// would take the room over its power budget
+    pub fn turn_on_device(&mut self, name: &String) -> Result<(), SmartHomeError> {
+        self.switch_on(name).map(|_| ())
+    }
+
+    /// Turns on a device within the power budget of the room
+    ///
+    /// Returns the devices shed to make room for it. The budget is checked
+    /// before anything is switched. On failure the device stays off and the
+    /// shed devices are turned back on; the error lists any that stayed off.
+    pub(crate) fn switch_on(&mut self, name: &String) -> Result<Vec<String>, SmartHomeError> {
+        let plan = self.plan_switch_on(name)?;
+        if plan.was_on {
+            self.find_device_mut(name)?.turn_on()?;
+            return Ok(Vec::new());
+        }
+
+        self.shed_devices(&plan.shed)?;
+        if let Err(e) = self.turn_on_checked(name) {
+            return Err(self.restore_devices(&plan.shed, e));
+        }
+        Ok(plan.shed)
+    }
+
+    /// Works out what turning on a device takes without switching anything
+    ///
+    /// Fails if the device cannot be switched or would take the room over its
+    /// power budget even after shedding.
+    pub(crate) fn plan_switch_on(&self, name: &String) -> Result<SwitchPlan, SmartHomeError> {
+        let device = self.find_device(name)?;
+        let Some(was_on) = device.is_on() else {
+            return Err(device.unsupported(Capability::PowerControl).into());
+        };
+        let mut total = self.total_power_consumption();
+        if !was_on {
+            total +=
+                device.power_when_on().unwrap_or(0.0) - device.power_consumption().unwrap_or(0.0);
+        }
+
+        let Some(budget) = self
+            .budget
+            .filter(|budget| !was_on && total > budget.limit_watts)
+        else {
+            return Ok(SwitchPlan {
+                was_on,
+                shed: Vec::new(),
+                total,
+            });
+        };
+        let shed = match budget.policy {
+            BudgetPolicy::Reject => None,
+            BudgetPolicy::Shed => budget::plan_shedding(
+                &budget,
+                total,
+                self.device_priority(name),
+                self.shedding_candidates()
+                    .filter(|candidate| &candidate.key != name),
+            ),
+        }
+        .ok_or_else(|| self.over_budget(&budget, total))?;
+
+        let freed: f32 = shed
+            .iter()
+            .filter_map(|name| self.devices.get(name)?.power_consumption())
+            .sum();
+        Ok(SwitchPlan {
+            was_on,
+            shed,
+            total: total - freed,
+        })
+    }
+
+    /// Turns a device on, then switches it back off if the room went over budget
+    ///
+    /// Catches devices drawing more than they announced.
+    pub(crate) fn turn_on_checked(&mut self, name: &String) -> Result<(), SmartHomeError> {
+        self.find_device_mut(name)?.turn_on()?;
+
+        let total = self.total_power_consumption();
+        match self.budget.filter(|budget| total > budget.limit_watts) {
+            Some(budget) => {
+                let error = self.over_budget(&budget, total);
+                match self.set_power_state(name, false) {
+                    Ok(()) => Err(error),
+                    Err(_) => {
+                        Err(error.with_rollback_failures(vec![(self.name.clone(), name.clone())]))
+                    }
+                }
+            }
+            None => Ok(()),
+        }
+    }
+
+    fn over_budget(&self, budget: &PowerBudget, total: f32) -> SmartHomeError {
+        SmartHomeError::BudgetExceeded(format!("room '{}'", self.name), budget.limit_watts, total)
+    }
+
+    /// Lists the devices that are on and could be switched off to save power
+    pub(crate) fn shedding_candidates(&self) -> impl Iterator<Item = Candidate<String>> + '_ {
+        self.devices
+            .iter()
+            .filter(|(_, device)| device.is_on() == Some(true))
+            .map(|(name, device)| Candidate {
+                key: name.clone(),
+                priority: self.device_priority(name),
+                watts: device.power_consumption().unwrap_or(0.0),
+            })
+    }
+
+    /// Switches a device without checking the budget
+    pub(crate) fn set_power_state(
+        &mut self,
+        name: &String,
+        on: bool,
+    ) -> Result<(), SmartHomeError> {
+        let device = self.find_device_mut(name)?;
+        if on {
+            device.turn_on()
+        } else {
+            device.turn_off()
+        }
+    }
+
+    /// Switches devices off to make room, without checking the budget
+    ///
+    /// If one of them fails, the devices already switched off are turned
+    /// back on and its error is returned.
+    fn shed_devices(&mut self, names: &[String]) -> Result<(), SmartHomeError> {
+        for (index, name) in names.iter().enumerate() {
+            if let Err(e) = self.set_power_state(name, false) {
+                return Err(self.restore_devices(&names[..index], e));
+            }
+        }
+        Ok(())
+    }
+
+    /// Turns shed devices back on after a change failed with `cause`
+    ///
+    /// Returns `cause`, listing the devices that stayed off.
+    fn restore_devices(&mut self, names: &[String], cause: SmartHomeError) -> SmartHomeError {
+        let mut failed = Vec::new();
+        for name in names {
+            if self.set_power_state(name, true).is_err() {
+                failed.push((self.name.clone(), name.clone()));
+            }
+        }
+        cause.with_rollback_failures(failed)
+    }
+
+    /// Turns off a device by name
+    ///
+    /// Fails if the device does not exist or does not support power control
+    pub fn turn_off_device(&mut self, name: &String) -> Result<(), SmartHomeError> {
+        self.find_device_mut(name)?.turn_off()
+    }
+
+    /// Resets the energy counter of a device by name
+    ///
+    /// Fails if the device does not exist or does not meter energy
+    pub fn reset_device_energy(&mut self, name: &String) -> Result<(), SmartHomeError> {
+        Ok(self.find_device_mut(name)?.reset_energy()?)
+    }
+
+    /// Returns the energy metered by the devices of the room in watt-hours
+    pub fn energy_wh(&self) -> f64 {
+        self.devices
+            .values()
+            .filter_map(SmartDevice::energy_wh)
+            .sum()
+    }
+
+    /// Returns the energy metered by the devices of the room in kilowatt-hours
+    pub fn energy_kwh(&self) -> f64 {
+        self.energy_wh() / 1000.0
+    }
+
+    /// Resets the energy counters of all devices in the room
+    pub fn reset_energy(&mut self) {
+        for meter in self
+            .devices
+            .values_mut()
+            .filter_map(SmartDevice::as_energy_meter_mut)
+        {
+            meter.reset_energy();
+        }
+    }
+
+    /// Gets temperature from a device that measures temperature
+    pub fn get_temperature(&self, name: &String) -> Result<f32, DeviceAccessError> {
+        let device = self.find_device(name)?;
+        device
+            .as_temperature_sensor()
+            .map(|sensor| sensor.temperature())
+            .ok_or_else(|| device.unsupported(Capability::TemperatureSensor))
+    }
+
+    /// Gets power consumption from a device that consumes power
+    pub fn get_power_consumption(&self, name: &String) -> Result<f32, DeviceAccessError> {
+        let device = self.find_device(name)?;
+        device
+            .as_power_consumption()
+            .map(|consumer| consumer.power_consumption())
+            .ok_or_else(|| device.unsupported(Capability::PowerConsumption))
+    }
+}
+
+/// What turning on a device takes, worked out by [`Room::plan_switch_on`]
+pub(crate) struct SwitchPlan {
+    /// The device is already on, so nothing is checked or shed
+    pub was_on: bool,
+    /// Devices of the room to switch off first
+    pub shed: Vec<String>,
+    /// Expected draw of the room once the plan is carried out
+    pub total: f32,
+}
+
+/// Rejects empty or whitespace-only names for rooms and devices
+pub(crate) fn validate_name(what: &str, name: &str) -> Result<(), SmartHomeError> {
+    if name.trim().is_empty() {
+        return Err(SmartHomeError::invalid_value(what, "must not be empty"));
+    }
+    Ok(())
+}
+
+/// Returns the mean of the given readings, `None` if there are none
+pub(crate) fn average(readings: impl IntoIterator<Item = f32>) -> Option<f32> {
+    let (sum, count) = readings
+        .into_iter()
+        .fold((0.0, 0u32), |(sum, count), reading| {
+            (sum + f64::from(reading), count + 1)
+        });
+    (count > 0).then(|| (sum / f64::from(count)) as f32)
+}
+
+impl StructuredReport for Room {
+    type Report = RoomReport;
+
+    fn structured_report(&self) -> RoomReport {
+        RoomReport::new(
+            self.name.clone(),
+            self.devices
+                .values()
+                .map(SmartDevice::structured_report)
+                .collect(),
+        )
+    }
+}
+
+impl Reporter for Room {
+    fn report(&self) -> String {
+        self.report_ordered(ReportOrder::Insertion)
+    }
 }
 
 /// Macro to create a room with a name and devices
@@ -123,19 +515,18 @@ impl Reporter for Room {
 #[macro_export]
 macro_rules! create_room {
     () => {{
-        Room::new_empty("Default Room".to_string())
+        $crate::Room::new_empty("Default Room".to_string())
     }};
 
     ($room_name:expr) => {{
-        Room::new_empty($room_name.to_string())
+        $crate::Room::new_empty($room_name.to_string())
     }};
 
     ($room_name:expr, $(($device_key:expr, $device:expr)),* $(,)?) => {{
-        let mut devices = std::collections::HashMap::new();
-        $(
-            devices.insert($device_key.to_string(), $device.into());
-        )*
-        Room::new($room_name.to_string(), devices)
+        let devices: Vec<(String, $crate::SmartDevice)> = vec![
+            $(($device_key.to_string(), $device.into()),)*
+        ];
+        $crate::Room::new($room_name.to_string(), devices)
     }};
 }
 
@@ -241,7 +632,7 @@ mod tests {
         struct DeviceOperationTestCase {
             name: &'static str,
             use_empty_room: bool,
-            operation: fn(&mut Room) -> Result<usize, ()>,
+            operation: fn(&mut Room) -> Result<usize, SmartHomeError>,
             expected_devices_count: usize,
             expected_success: bool,
         }
@@ -255,18 +646,45 @@ mod tests {
                         "Added Thermometer".to_string(),
                         23.0,
                     ));
-                    room.add_device("Added Thermometer".to_string(), device);
+                    room.add_device("Added Thermometer".to_string(), device)?;
                     Ok(room.all_devices().len())
                 },
                 expected_devices_count: 1,
                 expected_success: true,
             },
+            DeviceOperationTestCase {
+                name: "Add device with a taken name",
+                use_empty_room: false,
+                operation: |room| {
+                    let device = SmartDevice::Socket(SmartSocket::new(
+                        "Another Socket".to_string(),
+                        false,
+                        10.0,
+                    ));
+                    room.add_device("Test Socket".to_string(), device)?;
+                    Ok(room.all_devices().len())
+                },
+                expected_devices_count: 2,
+                expected_success: false,
+            },
+            DeviceOperationTestCase {
+                name: "Add device with an empty name",
+                use_empty_room: true,
+                operation: |room| {
+                    let device =
+                        SmartDevice::Socket(SmartSocket::new("Nameless".to_string(), false, 10.0));
+                    room.add_device("  ".to_string(), device)?;
+                    Ok(room.all_devices().len())
+                },
+                expected_devices_count: 0,
+                expected_success: false,
+            },
             DeviceOperationTestCase {
                 name: "Remove device from room with devices",
                 use_empty_room: false,
-                operation: |room| match room.remove_device(&"Test Thermometer".to_string()) {
-                    Some(_) => Ok(room.all_devices().len()),
-                    None => Err(()),
+                operation: |room| {
+                    room.remove_device(&"Test Thermometer".to_string())?;
+                    Ok(room.all_devices().len())
                 },
                 expected_devices_count: 1,
                 expected_success: true,
@@ -274,9 +692,9 @@ mod tests {
             DeviceOperationTestCase {
                 name: "Try to remove non-existent device",
                 use_empty_room: false,
-                operation: |room| match room.remove_device(&"Non-existent Device".to_string()) {
-                    Some(_) => Ok(room.all_devices().len()),
-                    None => Err(()),
+                operation: |room| {
+                    room.remove_device(&"Non-existent Device".to_string())?;
+                    Ok(room.all_devices().len())
                 },
                 expected_devices_count: 2,
                 expected_success: false,
@@ -311,6 +729,12 @@ mod tests {
                         "Test case '{}': Expected success but got failure",
                         tc.name
                     );
+                    assert_eq!(
+                        room.all_devices().len(),
+                        tc.expected_devices_count,
+                        "Test case '{}': failed operation should not change the room",
+                        tc.name
+                    );
                 }
             }
         }
@@ -320,35 +744,35 @@ mod tests {
     fn test_device_operations() {
         struct DeviceOperationTestCase {
             name: &'static str,
-            operation: fn(&mut Room) -> Option<bool>,
-            expected_success: bool,
-            expected_operation_result: Option<bool>,
+            operation: fn(&mut Room) -> Result<(), SmartHomeError>,
+            expected_error: Option<&'static str>,
+            expected_socket_state: bool,
         }
 
         let test_cases = vec![
             DeviceOperationTestCase {
                 name: "Turn off socket",
                 operation: |room| room.turn_off_device(&"Test Socket".to_string()),
-                expected_success: true,
-                expected_operation_result: Some(true),
+                expected_error: None,
+                expected_socket_state: false,
             },
             DeviceOperationTestCase {
                 name: "Turn on socket",
                 operation: |room| room.turn_on_device(&"Test Socket".to_string()),
-                expected_success: true,
-                expected_operation_result: Some(true),
+                expected_error: None,
+                expected_socket_state: true,
             },
             DeviceOperationTestCase {
                 name: "Try to turn on thermometer (not supported)",
                 operation: |room| room.turn_on_device(&"Test Thermometer".to_string()),
-                expected_success: true,
-                expected_operation_result: Some(false),
+                expected_error: Some("Device 'Test Thermometer' does not support power control"),
+                expected_socket_state: true,
             },
             DeviceOperationTestCase {
                 name: "Try to operate non-existent device",
                 operation: |room| room.turn_on_device(&"Non-existent Device".to_string()),
-                expected_success: false,
-                expected_operation_result: None,
+                expected_error: Some("Device 'Non-existent Device' not found in room 'Test Room'"),
+                expected_socket_state: true,
             },
         ];
 
@@ -356,30 +780,19 @@ mod tests {
             let mut room = create_room_with_devices();
             let result = (tc.operation)(&mut room);
 
-            match result {
-                Some(operation_result) => {
-                    assert!(
-                        tc.expected_success,
-                        "Test case '{}': Expected failure but got success",
-                        tc.name
-                    );
-                    assert_eq!(
-                        Some(operation_result),
-                        tc.expected_operation_result,
-                        "Test case '{}': Expected operation result {:?} but got {:?}",
-                        tc.name,
-                        tc.expected_operation_result,
-                        Some(operation_result)
-                    );
-                }
-                None => {
-                    assert!(
-                        !tc.expected_success,
-                        "Test case '{}': Expected success but got failure",
-                        tc.name
-                    );
-                }
-            }
+            assert_eq!(
+                result.as_ref().err().map(|e| e.to_string()).as_deref(),
+                tc.expected_error,
+                "Test case '{}': unexpected result {:?}",
+                tc.name,
+                result
+            );
+            assert_eq!(
+                room.device(&"Test Socket".to_string()).unwrap().is_on(),
+                Some(tc.expected_socket_state),
+                "Test case '{}': unexpected socket state",
+                tc.name
+            );
         }
     }
 
@@ -387,10 +800,9 @@ mod tests {
     fn test_specialized_accessors() {
         struct SpecializedAccessTestCase {
             name: &'static str,
-            accessor: fn(&Room, &String) -> Option<Option<f32>>,
+            accessor: fn(&Room, &String) -> Result<f32, DeviceAccessError>,
             device_name: &'static str,
-            expected_success: bool,
-            expected_value: Option<f32>,
+            expected: Result<f32, &'static str>,
         }
 
         let test_cases = vec![
@@ -398,36 +810,31 @@ mod tests {
                 name: "Get temperature from thermometer",
                 accessor: |room, name| room.get_temperature(name),
                 device_name: "Test Thermometer",
-                expected_success: true,
-                expected_value: Some(22.0),
+                expected: Ok(22.0),
             },
             SpecializedAccessTestCase {
                 name: "Get temperature from socket (not supported)",
                 accessor: |room, name| room.get_temperature(name),
                 device_name: "Test Socket",
-                expected_success: true,
-                expected_value: None,
+                expected: Err("Device 'Test Socket' does not support temperature sensor"),
             },
             SpecializedAccessTestCase {
                 name: "Get power consumption from socket",
                 accessor: |room, name| room.get_power_consumption(name),
                 device_name: "Test Socket",
-                expected_success: true,
-                expected_value: Some(100.0),
+                expected: Ok(100.0),
             },
             SpecializedAccessTestCase {
                 name: "Get power consumption from thermometer (not supported)",
                 accessor: |room, name| room.get_power_consumption(name),
                 device_name: "Test Thermometer",
-                expected_success: true,
-                expected_value: None,
+                expected: Err("Device 'Test Thermometer' does not support power consumption"),
             },
             SpecializedAccessTestCase {
                 name: "Try to get temperature from non-existent device",
                 accessor: |room, name| room.get_temperature(name),
                 device_name: "Non-existent Device",
-                expected_success: false,
-                expected_value: None,
+                expected: Err("Device 'Non-existent Device' not found in room 'Test Room'"),
             },
         ];
 
@@ -435,27 +842,617 @@ mod tests {
             let room = create_room_with_devices();
             let result = (tc.accessor)(&room, &tc.device_name.to_string());
 
-            match result {
-                Some(value) => {
-                    assert!(
-                        tc.expected_success,
-                        "Test case '{}': Expected failure but got success",
-                        tc.name
-                    );
-                    assert_eq!(
-                        value, tc.expected_value,
-                        "Test case '{}': Expected value {:?} but got {:?}",
-                        tc.name, tc.expected_value, value
-                    );
-                }
-                None => {
-                    assert!(
-                        !tc.expected_success,
-                        "Test case '{}': Expected success but got failure",
-                        tc.name
-                    );
-                }
+            assert_eq!(
+                result.map_err(|e| e.to_string()),
+                tc.expected.map_err(str::to_string),
+                "Failed test case '{}'",
+                tc.name
+            );
+        }
+    }
+
+    #[test]
+    fn test_device_order() {
+        struct DeviceOrderTestCase {
+            name: &'static str,
+            order: ReportOrder,
+            expected: Vec<&'static str>,
+        }
+
+        let test_cases = vec![
+            DeviceOrderTestCase {
+                name: "Insertion order",
+                order: ReportOrder::Insertion,
+                expected: vec!["Kettle", "Thermometer", "Lamp", "Fridge"],
+            },
+            DeviceOrderTestCase {
+                name: "By name",
+                order: ReportOrder::Name,
+                expected: vec!["Fridge", "Kettle", "Lamp", "Thermometer"],
+            },
+            DeviceOrderTestCase {
+                name: "By power draw, ties keep insertion order",
+                order: ReportOrder::PowerDraw,
+                expected: vec!["Fridge", "Lamp", "Kettle", "Thermometer"],
+            },
+        ];
+
+        let room = create_room!(
+            "Kitchen",
+            (
+                "Kettle",
+                SmartSocket::new("Kettle".to_string(), false, 2000.0)
+            ),
+            (
+                "Thermometer",
+                SmartThermometer::new("Thermometer".to_string(), 21.0)
+            ),
+            ("Lamp", SmartSocket::new("Lamp".to_string(), true, 40.0)),
+            (
+                "Fridge",
+                SmartSocket::new("Fridge".to_string(), true, 150.0)
+            )
+        );
+
+        for tc in test_cases {
+            let names: Vec<&str> = room
+                .devices_ordered(tc.order)
+                .into_iter()
+                .map(|(key, _)| key.as_str())
+                .collect();
+            assert_eq!(names, tc.expected, "Failed test case '{}'", tc.name);
+
+            let report = room.report_ordered(tc.order);
+            let positions: Vec<usize> = tc
+                .expected
+                .iter()
+                .map(|name| report.find(&format!("Device: {}", name)).unwrap())
+                .collect();
+            assert!(
+                positions.windows(2).all(|pair| pair[0] < pair[1]),
+                "Test case '{}': text report out of order: {}",
+                tc.name,
+                report
+            );
+        }
+
+        assert_eq!(room.report(), room.report_ordered(ReportOrder::Insertion));
+    }
+
+    #[test]
+    fn test_removal_keeps_order() {
+        let mut room = create_room!(
+            "Hall",
+            ("A", SmartSocket::new("A".to_string(), false, 1.0)),
+            ("B", SmartSocket::new("B".to_string(), false, 1.0)),
+            ("C", SmartSocket::new("C".to_string(), false, 1.0))
+        );
+
+        room.remove_device(&"A".to_string()).unwrap();
+        room.add_device(
+            "D".to_string(),
+            SmartSocket::new("D".to_string(), false, 1.0).into(),
+        )
+        .unwrap();
+
+        let keys: Vec<&String> = room.all_devices().keys().collect();
+        assert_eq!(keys, ["B", "C", "D"]);
+    }
+
+    #[test]
+    fn test_locks() {
+        use crate::device::{PinCode, SmartLock};
+
+        let mut lock = SmartLock::new("Front Door".to_string(), true);
+        lock.set_pin("Alice".to_string(), PinCode::new("4321").unwrap());
+        let mut room = create_room!("Hall", ("Front Door", lock));
+
+        assert!(room.turn_on_device(&"Front Door".to_string()).is_err());
+        let Some(SmartDevice::Lock(lock)) = room.device_mut(&"Front Door".to_string()) else {
+            panic!("Front Door should be a lock");
+        };
+        assert_eq!(lock.unlock("4321"), Ok("Alice".to_string()));
+
+        let report = room.report();
+        assert!(report.contains("Device: Front Door, Lock: unlocked, Users: 1"));
+        assert!(!report.contains("4321"), "PIN leaked in {}", report);
+    }
+
+    #[test]
+    fn test_lights() {
+        use crate::device::SmartLight;
+
+        let mut room = create_room!(
+            "Bedroom",
+            (
+                "Ceiling",
+                SmartLight::new("Ceiling".to_string(), false, 12.0).with_brightness(50)
+            ),
+            ("Lamp", SmartSocket::new("Lamp".to_string(), true, 40.0))
+        );
+
+        room.turn_on_device(&"Ceiling".to_string()).unwrap();
+        assert_eq!(room.total_power_consumption(), 46.0);
+        assert_eq!(
+            room.get_power_consumption(&"Ceiling".to_string()).unwrap(),
+            6.0
+        );
+
+        room.turn_off_device(&"Ceiling".to_string()).unwrap();
+        assert_eq!(room.total_power_consumption(), 40.0);
+    }
+
+    #[test]
+    fn test_thermostats() {
+        use crate::device::{SmartThermostat, ThermostatMode};
+
+        let room = create_room!(
+            "Hall",
+            (
+                "Thermostat",
+                SmartThermostat::new("Thermostat".to_string(), 19.0, 21.0)
+                    .with_mode(ThermostatMode::Heat)
+                    .with_power(2000.0, 0.0)
+            ),
+            (
+                "Thermometer",
+                SmartThermometer::new("Thermometer".to_string(), 20.0)
+            )
+        );
+
+        assert_eq!(
+            room.get_temperature(&"Thermostat".to_string()).unwrap(),
+            19.0
+        );
+        assert_eq!(room.average_temperature(), Some(19.5));
+        assert_eq!(room.total_power_consumption(), 2000.0);
+        assert!(
+            room.device(&"Thermostat".to_string())
+                .unwrap()
+                .is_on()
+                .is_none()
+        );
+    }
+
+    #[test]
+    fn test_average_temperature() {
+        struct TestCase {
+            name: &'static str,
+            room: Room,
+            expected_average: Option<f32>,
+        }
+
+        let test_cases = vec![
+            TestCase {
+                name: "Room without thermometers",
+                room: create_room!(
+                    "Hall",
+                    ("Lamp", SmartSocket::new("Lamp".to_string(), true, 40.0))
+                ),
+                expected_average: None,
+            },
+            TestCase {
+                name: "Two thermometers",
+                room: create_room!(
+                    "Kitchen",
+                    ("Wall", SmartThermometer::new("Wall".to_string(), 22.0)),
+                    ("Lamp", SmartSocket::new("Lamp".to_string(), true, 40.0)),
+                    ("Window", SmartThermometer::new("Window".to_string(), 19.0))
+                ),
+                expected_average: Some(20.5),
+            },
+            TestCase {
+                name: "Thermometer without a reading is skipped",
+                room: create_room!(
+                    "Attic",
+                    ("Wall", SmartThermometer::new("Wall".to_string(), 15.0)),
+                    ("Roof", SmartThermometer::new("Roof".to_string(), f32::NAN))
+                ),
+                expected_average: Some(15.0),
+            },
+        ];
+
+        for tc in test_cases {
+            assert_eq!(
+                tc.room.average_temperature(),
+                tc.expected_average,
+                "Failed test: {}",
+                tc.name
+            );
+        }
+    }
+
+    #[test]
+    fn test_air_quality_aggregates() {
+        use crate::device::{SmartAirQualityMonitor, SmartHygrometer};
+
+        struct TestCase {
+            name: &'static str,
+            room: Room,
+            expected_humidity: Option<f32>,
+            expected_co2: Option<f32>,
+            expected_air_quality_index: Option<u16>,
+        }
+
+        let test_cases = vec![
+            TestCase {
+                name: "Room without air sensors",
+                room: create_room!(
+                    "Hall",
+                    ("Wall", SmartThermometer::new("Wall".to_string(), 21.0))
+                ),
+                expected_humidity: None,
+                expected_co2: None,
+                expected_air_quality_index: None,
+            },
+            TestCase {
+                name: "Readings are averaged, the index is the worst one",
+                room: create_room!(
+                    "Bathroom",
+                    ("Mirror", SmartHygrometer::new("Mirror".to_string(), 70.0)),
+                    ("Shower", SmartHygrometer::new("Shower".to_string(), 80.0)),
+                    (
+                        "Door",
+                        SmartAirQualityMonitor::new("Door".to_string(), 600.0, 20)
+                    ),
+                    (
+                        "Window",
+                        SmartAirQualityMonitor::new("Window".to_string(), 1000.0, 90)
+                    )
+                ),
+                expected_humidity: Some(75.0),
+                expected_co2: Some(800.0),
+                expected_air_quality_index: Some(90),
+            },
+            TestCase {
+                name: "Sensors without a reading are skipped",
+                room: create_room!(
+                    "Cellar",
+                    ("Floor", SmartHygrometer::new("Floor".to_string(), 60.0)),
+                    (
+                        "Ceiling",
+                        SmartHygrometer::new("Ceiling".to_string(), f32::NAN)
+                    )
+                ),
+                expected_humidity: Some(60.0),
+                expected_co2: None,
+                expected_air_quality_index: None,
+            },
+        ];
+
+        for tc in test_cases {
+            assert_eq!(
+                tc.room.average_humidity(),
+                tc.expected_humidity,
+                "Failed test: {}",
+                tc.name
+            );
+            assert_eq!(
+                tc.room.average_co2_ppm(),
+                tc.expected_co2,
+                "Failed test: {}",
+                tc.name
+            );
+            assert_eq!(
+                tc.room.max_air_quality_index(),
+                tc.expected_air_quality_index,
+                "Failed test: {}",
+                tc.name
+            );
+        }
+    }
+
+    #[test]
+    fn test_budget_is_checked_before_switching() {
+        use crate::device::custom::tests::Humidifier;
+        use crate::device::{CustomDevice, PowerConsumption, PowerControl};
+        use std::sync::Arc;
+        use std::sync::atomic::{AtomicUsize, Ordering};
+
+        /// Kettle announcing its draw and counting how often it was switched on
+        #[derive(Debug)]
+        struct Kettle {
+            is_on: bool,
+            switch_ons: Arc<AtomicUsize>,
+        }
+
+        impl SmartDeviceTrait for Kettle {
+            fn name(&self) -> &str {
+                "Kettle"
+            }
+
+            fn as_power_control(&self) -> Option<&dyn PowerControl> {
+                Some(self)
+            }
+
+            fn as_power_control_mut(&mut self) -> Option<&mut dyn PowerControl> {
+                Some(self)
+            }
+
+            fn as_power_consumption(&self) -> Option<&dyn PowerConsumption> {
+                Some(self)
+            }
+        }
+
+        impl PowerControl for Kettle {
+            fn is_on(&self) -> bool {
+                self.is_on
+            }
+
+            fn turn_on(&mut self) {
+                self.switch_ons.fetch_add(1, Ordering::SeqCst);
+                self.is_on = true;
+            }
+
+            fn turn_off(&mut self) {
+                self.is_on = false;
+            }
+        }
+
+        impl PowerConsumption for Kettle {
+            fn power_consumption(&self) -> f32 {
+                if self.is_on { 2000.0 } else { 0.0 }
+            }
+
+            fn power_when_on(&self) -> f32 {
+                2000.0
+            }
+        }
+
+        impl Reporter for Kettle {
+            fn report(&self) -> String {
+                "Device: Kettle".to_string()
+            }
+        }
+
+        impl CustomDevice for Kettle {
+            fn kind(&self) -> &str {
+                "kettle"
+            }
+        }
+
+        let switch_ons = Arc::new(AtomicUsize::new(0));
+        let mut room = create_room!(
+            "Kitchen",
+            (
+                "Kettle",
+                SmartDevice::custom(Kettle {
+                    is_on: false,
+                    switch_ons: Arc::clone(&switch_ons),
+                })
+            ),
+            (
+                "Humidifier",
+                SmartDevice::custom(Humidifier::new("Humidifier", false, 30.0))
+            )
+        );
+        room.set_power_budget(Some(PowerBudget::reject(1000.0)))
+            .unwrap();
+
+        assert!(matches!(
+            room.turn_on_device(&"Kettle".to_string()),
+            Err(SmartHomeError::BudgetExceeded(_, 1000.0, 2000.0))
+        ));
+        assert_eq!(switch_ons.load(Ordering::SeqCst), 0);
+
+        // The humidifier does not announce its draw, so it is switched back off
+        room.set_power_budget(Some(PowerBudget::reject(20.0)))
+            .unwrap();
+        assert!(matches!(
+            room.turn_on_device(&"Humidifier".to_string()),
+            Err(SmartHomeError::BudgetExceeded(_, 20.0, 30.0))
+        ));
+        assert_eq!(
+            room.device(&"Humidifier".to_string())
+                .and_then(SmartDevice::is_on),
+            Some(false)
+        );
+    }
+
+    #[test]
+    fn test_power_budget() {
+        struct TestCase {
+            name: &'static str,
+            budget: PowerBudget,
+            device: &'static str,
+            expected_shed: Result<Vec<&'static str>, &'static str>,
+            expected_states: Vec<bool>,
+        }
+
+        let create_room = || {
+            let mut room = create_room!(
+                "Kitchen",
+                (
+                    "Fridge",
+                    SmartSocket::new("Fridge".to_string(), true, 150.0)
+                ),
+                ("Radio", SmartSocket::new("Radio".to_string(), true, 20.0)),
+                (
+                    "Heater",
+                    SmartSocket::new("Heater".to_string(), true, 1000.0)
+                ),
+                (
+                    "Kettle",
+                    SmartSocket::new("Kettle".to_string(), false, 2000.0)
+                )
+            );
+            room.set_device_priority(&"Fridge".to_string(), 10).unwrap();
+            room.set_device_priority(&"Radio".to_string(), -2).unwrap();
+            room.set_device_priority(&"Heater".to_string(), -1).unwrap();
+            room
+        };
+
+        let test_cases = vec![
+            TestCase {
+                name: "Within budget",
+                budget: PowerBudget::reject(3500.0),
+                device: "Kettle",
+                expected_shed: Ok(vec![]),
+                expected_states: vec![true, true, true, true],
+            },
+            TestCase {
+                name: "Rejected",
+                budget: PowerBudget::reject(3000.0),
+                device: "Kettle",
+                expected_shed: Err(
+                    "Power budget of room 'Kitchen' exceeded: 3170.0 W requested, limit is 3000.0 W",
+                ),
+                expected_states: vec![true, true, true, false],
+            },
+            TestCase {
+                name: "Lowest priority devices are shed first",
+                budget: PowerBudget::shed(2200.0),
+                device: "Kettle",
+                expected_shed: Ok(vec!["Radio", "Heater"]),
+                expected_states: vec![true, false, false, true],
+            },
+            TestCase {
+                name: "Higher priority devices are never shed",
+                budget: PowerBudget::shed(2100.0),
+                device: "Kettle",
+                expected_shed: Err(
+                    "Power budget of room 'Kitchen' exceeded: 3170.0 W requested, limit is 2100.0 W",
+                ),
+                expected_states: vec![true, true, true, false],
+            },
+            TestCase {
+                name: "Device already on",
+                budget: PowerBudget::reject(100.0),
+                device: "Heater",
+                expected_shed: Ok(vec![]),
+                expected_states: vec![true, true, true, false],
+            },
+        ];
+
+        for tc in test_cases {
+            let mut room = create_room();
+            room.set_power_budget(Some(tc.budget)).unwrap();
+
+            let result = room.switch_on(&tc.device.to_string());
+            assert_eq!(
+                result.map_err(|e| e.to_string()),
+                tc.expected_shed
+                    .map(|shed| shed.into_iter().map(str::to_string).collect())
+                    .map_err(str::to_string),
+                "Failed test: {}",
+                tc.name
+            );
+            assert_eq!(
+                room.all_devices()
+                    .values()
+                    .map(|device| device.is_on().unwrap())
+                    .collect::<Vec<_>>(),
+                tc.expected_states,
+                "Failed test: {}",
+                tc.name
+            );
+        }
+
+        let mut room = create_room();
+        assert_eq!(room.total_power_consumption(), 1170.0);
+        assert!(
+            room.set_power_budget(Some(PowerBudget::reject(f32::NAN)))
+                .is_err()
+        );
+        assert!(room.set_device_priority(&"Lamp".to_string(), 1).is_err());
+
+        room.remove_device(&"Radio".to_string()).unwrap();
+        room.add_device(
+            "Radio".to_string(),
+            SmartSocket::new("Radio".to_string(), true, 20.0).into(),
+        )
+        .unwrap();
+        assert_eq!(room.device_priority(&"Radio".to_string()), 0);
+    }
+
+    #[test]
+    fn test_shedding_stops_at_a_failing_device() {
+        use crate::device::{CustomDevice, PowerConsumption, PowerControl};
+        use crate::error::ProtocolError;
+        use std::time::Duration;
+
+        /// Heater that is on and does not answer when told to switch
+        #[derive(Debug)]
+        struct StuckHeater;
+
+        impl SmartDeviceTrait for StuckHeater {
+            fn name(&self) -> &str {
+                "Heater"
+            }
+
+            fn as_power_control(&self) -> Option<&dyn PowerControl> {
+                Some(self)
+            }
+
+            fn as_power_control_mut(&mut self) -> Option<&mut dyn PowerControl> {
+                Some(self)
+            }
+
+            fn as_power_consumption(&self) -> Option<&dyn PowerConsumption> {
+                Some(self)
             }
         }
+
+        impl PowerControl for StuckHeater {
+            fn is_on(&self) -> bool {
+                true
+            }
+
+            fn turn_on(&mut self) {}
+
+            fn turn_off(&mut self) {}
+
+            fn try_turn_off(&mut self) -> Result<(), SmartHomeError> {
+                Err(ProtocolError::Timeout(Duration::from_millis(500)).into())
+            }
+        }
+
+        impl PowerConsumption for StuckHeater {
+            fn power_consumption(&self) -> f32 {
+                1000.0
+            }
+        }
+
+        impl Reporter for StuckHeater {
+            fn report(&self) -> String {
+                "Device: Heater".to_string()
+            }
+        }
+
+        impl CustomDevice for StuckHeater {
+            fn kind(&self) -> &str {
+                "heater"
+            }
+        }
+
+        let mut room = create_room!(
+            "Kitchen",
+            ("Radio", SmartSocket::new("Radio".to_string(), true, 20.0)),
+            ("Heater", SmartDevice::custom(StuckHeater)),
+            (
+                "Kettle",
+                SmartSocket::new("Kettle".to_string(), false, 2000.0)
+            )
+        );
+        room.set_device_priority(&"Radio".to_string(), -2).unwrap();
+        room.set_device_priority(&"Heater".to_string(), -1).unwrap();
+        room.set_power_budget(Some(PowerBudget::shed(2200.0)))
+            .unwrap();
+
+        let result = room.turn_on_device(&"Kettle".to_string());
+
+        assert_eq!(
+            result.map_err(|e| e.to_string()),
+            Err("No response within 500 ms".to_string())
+        );
+        assert_eq!(
+            room.all_devices()
+                .values()
+                .map(|device| device.is_on().unwrap())
+                .collect::<Vec<_>>(),
+            vec![true, true, false],
+            "The radio is turned back on and the kettle stays off"
+        );
+        assert_eq!(room.total_power_consumption(), 1020.0);
     }
 }
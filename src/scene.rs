@@ -0,0 +1,433 @@
+//! Named presets of device power states, such as "Movie night" or "Away"
+//!
+//! A [`Scene`] is applied with [`SmartHouse::apply_scene`], which checks every
+//! target before touching any device.
+
+use crate::SmartHouse;
+use crate::automation::DeviceRef;
+use crate::device::Capability;
+use crate::error::DeviceAccessError;
+use serde::{Deserialize, Serialize};
+
+/// Desired power state of one device in a scene
+#[derive(Debug, Clone, PartialEq, Eq, Serialize, Deserialize)]
+pub struct SceneTarget {
+    #[serde(flatten)]
+    pub target: DeviceRef,
+    pub is_on: bool,
+}
+
+/// A named set of target power states
+#[derive(Debug, Clone, PartialEq, Eq, Serialize, Deserialize)]
+pub struct Scene {
+    name: String,
+    #[serde(default)]
+    targets: Vec<SceneTarget>,
+}
+
+impl Scene {
+    /// Creates a scene without targets
+    pub fn new(name: impl Into<String>) -> Self {
+        Self {
+            name: name.into(),
+            targets: Vec::new(),
+        }
+    }
+
+    /// Captures the current power state of every switchable device in the house
+    pub fn capture(name: impl Into<String>, house: &SmartHouse) -> Self {
+        let targets = house
+            .all_rooms()
+            .iter()
+            .flat_map(|(room_name, room)| {
+                room.all_devices()
+                    .iter()
+                    .filter_map(move |(device_name, device)| {
+                        device.is_on().map(|is_on| SceneTarget {
+                            target: DeviceRef::new(room_name.as_str(), device_name.as_str()),
+                            is_on,
+                        })
+                    })
+            })
+            .collect();
+
+        Self {
+            name: name.into(),
+            targets,
+        }
+    }
+
+    /// Returns the name of the scene
+    pub fn name(&self) -> &str {
+        &self.name
+    }
+
+    /// Returns the targets in the order they are applied
+    pub fn targets(&self) -> &[SceneTarget] {
+        &self.targets
+    }
+
+    /// Sets the desired state of a device, replacing an earlier target for it
+    pub fn set(&mut self, target: DeviceRef, is_on: bool) {
+        match self.targets.iter_mut().find(|t| t.target == target) {
+            Some(existing) => existing.is_on = is_on,
+            None => self.targets.push(SceneTarget { target, is_on }),
+        }
+    }
+
+    /// Builder form of [`Scene::set`] turning a device on
+    pub fn with_on(mut self, target: DeviceRef) -> Self {
+        self.set(target, true);
+        self
+    }
+
+    /// Builder form of [`Scene::set`] turning a device off
+    pub fn with_off(mut self, target: DeviceRef) -> Self {
+        self.set(target, false);
+        self
+    }
+
+    /// Checks that a target exists and can be switched, returning its power state
+    ///
+    /// Both the read-only and the mutable power control accessors must be
+    /// available: a custom device may expose one without the other.
+    pub(crate) fn validate(
+        house: &mut SmartHouse,
+        target: &DeviceRef,
+    ) -> Result<bool, DeviceAccessError> {
+        let device = house.device_mut(&target.room, &target.device)?;
+        let switchable = device.as_power_control_mut().is_some();
+        match device.is_on() {
+            Some(is_on) if switchable => Ok(is_on),
+            _ => Err(device.unsupported(Capability::PowerControl)),
+        }
+    }
+}
+
+/// What happened to one target when a scene was applied
+#[derive(Debug, Clone, PartialEq)]
+pub enum TargetStatus {
+    /// The device was switched to the target state
+    Changed,
+    /// The device was already in the target state
+    Unchanged,
+    /// The target is valid but the scene was rejected because of another target
+    NotApplied,
+    /// The target is missing or cannot be switched
+    Rejected(DeviceAccessError),
+    /// Turning the device on would exceed a power budget; holds the error message
+    OverBudget(String),
+    /// Switching the device failed for another reason; holds the error message
+    Failed(String),
+}
+
+/// Outcome for one target of a scene
+#[derive(Debug, Clone, PartialEq)]
+pub struct TargetOutcome {
+    pub target: DeviceRef,
+    pub status: TargetStatus,
+}
+
+/// Result of [`SmartHouse::apply_scene`]
+#[derive(Debug, Clone, PartialEq)]
+#[must_use]
+pub struct SceneReport {
+    pub scene: String,
+    pub outcomes: Vec<TargetOutcome>,
+    /// Devices left switched after a failed scene, with the error that kept
+    /// them from being put back
+    pub not_restored: Vec<(DeviceRef, String)>,
+}
+
+impl SceneReport {
+    /// Checks whether the scene was applied; `false` means every device was
+    /// put back, except the ones listed in [`SceneReport::not_restored`]
+    pub fn applied(&self) -> bool {
+        !self.outcomes.iter().any(|outcome| {
+            matches!(
+                outcome.status,
+                TargetStatus::Rejected(_) | TargetStatus::OverBudget(_) | TargetStatus::Failed(_)
+            )
+        })
+    }
+
+    /// Returns the targets that prevented the scene from being applied
+    pub fn rejected(&self) -> Vec<(&DeviceRef, &DeviceAccessError)> {
+        self.outcomes
+            .iter()
+            .filter_map(|outcome| match &outcome.status {
+                TargetStatus::Rejected(error) => Some((&outcome.target, error)),
+                _ => None,
+            })
+            .collect()
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::{SmartSocket, SmartThermometer, create_room};
+
+    fn create_house() -> SmartHouse {
+        let living_room = create_room!(
+            "Living Room",
+            ("TV", SmartSocket::new("TV".to_string(), false, 120.0)),
+            ("Lamp", SmartSocket::new("Lamp".to_string(), true, 40.0)),
+            (
+                "Thermometer",
+                SmartThermometer::new("Thermometer".to_string(), 22.0)
+            )
+        );
+        let kitchen = create_room!(
+            "Kitchen",
+            (
+                "Kettle",
+                SmartSocket::new("Kettle".to_string(), true, 2000.0)
+            )
+        );
+        SmartHouse::new(
+            "Test House".to_string(),
+            vec![
+                ("Living Room".to_string(), living_room),
+                ("Kitchen".to_string(), kitchen),
+            ],
+        )
+    }
+
+    fn living(device: &str) -> DeviceRef {
+        DeviceRef::new("Living Room", device)
+    }
+
+    fn power_states(house: &SmartHouse) -> Vec<Option<bool>> {
+        house
+            .all_rooms()
+            .values()
+            .flat_map(|room| room.all_devices().values().map(|device| device.is_on()))
+            .collect()
+    }
+
+    #[test]
+    fn test_apply_scene() {
+        struct TestCase {
+            name: &'static str,
+            scene: Scene,
+            expected_applied: bool,
+            expected_statuses: Vec<TargetStatus>,
+            expected_states: Vec<Option<bool>>,
+        }
+
+        let test_cases = vec![
+            TestCase {
+                name: "Movie night",
+                scene: Scene::new("Movie night")
+                    .with_on(living("TV"))
+                    .with_off(living("Lamp"))
+                    .with_on(DeviceRef::new("Kitchen", "Kettle")),
+                expected_applied: true,
+                expected_statuses: vec![
+                    TargetStatus::Changed,
+                    TargetStatus::Changed,
+                    TargetStatus::Unchanged,
+                ],
+                expected_states: vec![Some(true), Some(false), None, Some(true)],
+            },
+            TestCase {
+                name: "Missing device rejects the whole scene",
+                scene: Scene::new("Away")
+                    .with_off(DeviceRef::new("Kitchen", "Kettle"))
+                    .with_off(living("Heater")),
+                expected_applied: false,
+                expected_statuses: vec![
+                    TargetStatus::NotApplied,
+                    TargetStatus::Rejected(DeviceAccessError::DeviceNotFound(
+                        "Heater".to_string(),
+                        "Living Room".to_string(),
+                    )),
+                ],
+                expected_states: vec![Some(false), Some(true), None, Some(true)],
+            },
+            TestCase {
+                name: "Device without power control rejects the scene",
+                scene: Scene::new("Broken")
+                    .with_on(living("TV"))
+                    .with_on(living("Thermometer")),
+                expected_applied: false,
+                expected_statuses: vec![
+                    TargetStatus::NotApplied,
+                    TargetStatus::Rejected(DeviceAccessError::UnsupportedCapability(
+                        "Thermometer".to_string(),
+                        Capability::PowerControl,
+                    )),
+                ],
+                expected_states: vec![Some(false), Some(true), None, Some(true)],
+            },
+            TestCase {
+                name: "Empty scene",
+                scene: Scene::new("Nothing"),
+                expected_applied: true,
+                expected_statuses: vec![],
+                expected_states: vec![Some(false), Some(true), None, Some(true)],
+            },
+        ];
+
+        for tc in test_cases {
+            let mut house = create_house();
+            let report = house.apply_scene(&tc.scene);
+
+            assert_eq!(report.scene, tc.scene.name(), "Failed test: {}", tc.name);
+            assert_eq!(
+                report.applied(),
+                tc.expected_applied,
+                "Failed test: {}",
+                tc.name
+            );
+            assert_eq!(
+                report
+                    .outcomes
+                    .iter()
+                    .map(|outcome| outcome.status.clone())
+                    .collect::<Vec<_>>(),
+                tc.expected_statuses,
+                "Failed test: {}",
+                tc.name
+            );
+            assert_eq!(
+                power_states(&house),
+                tc.expected_states,
+                "Failed test: {}",
+                tc.name
+            );
+            assert_eq!(
+                report.rejected().len(),
+                tc.expected_statuses
+                    .iter()
+                    .filter(|status| matches!(status, TargetStatus::Rejected(_)))
+                    .count(),
+                "Failed test: {}",
+                tc.name
+            );
+        }
+    }
+
+    #[test]
+    fn test_scene_with_read_only_power_control() {
+        use crate::device::{CustomDevice, PowerControl, SmartDeviceTrait};
+        use crate::{Reporter, SmartDevice};
+
+        /// Breaker whose state can be read but not switched
+        #[derive(Debug)]
+        struct Breaker;
+
+        impl SmartDeviceTrait for Breaker {
+            fn name(&self) -> &str {
+                "Breaker"
+            }
+
+            fn as_power_control(&self) -> Option<&dyn PowerControl> {
+                Some(self)
+            }
+        }
+
+        impl PowerControl for Breaker {
+            fn is_on(&self) -> bool {
+                false
+            }
+
+            fn turn_on(&mut self) {}
+
+            fn turn_off(&mut self) {}
+        }
+
+        impl Reporter for Breaker {
+            fn report(&self) -> String {
+                "Device: Breaker".to_string()
+            }
+        }
+
+        impl CustomDevice for Breaker {
+            fn kind(&self) -> &str {
+                "breaker"
+            }
+        }
+
+        let mut house = create_house();
+        house
+            .add_device(
+                &"Living Room".to_string(),
+                "Breaker".to_string(),
+                SmartDevice::custom(Breaker),
+            )
+            .unwrap();
+
+        let report = house.apply_scene(
+            &Scene::new("Everything on")
+                .with_on(living("TV"))
+                .with_on(living("Breaker")),
+        );
+        assert!(!report.applied());
+        assert_eq!(
+            report.rejected(),
+            [(
+                &living("Breaker"),
+                &DeviceAccessError::UnsupportedCapability(
+                    "Breaker".to_string(),
+                    Capability::PowerControl
+                )
+            )]
+        );
+        assert_eq!(
+            house
+                .device(&"Living Room".to_string(), &"TV".to_string())
+                .unwrap()
+                .is_on(),
+            Some(false)
+        );
+    }
+
+    #[test]
+    fn test_capture_and_restore() {
+        let mut house = create_house();
+        let scene = Scene::capture("Before", &house);
+
+        assert_eq!(scene.targets().len(), 3, "Thermometers are not captured");
+
+        let report = house.apply_scene(
+            &Scene::new("All off")
+                .with_off(living("Lamp"))
+                .with_off(DeviceRef::new("Kitchen", "Kettle")),
+        );
+        assert!(report.applied());
+
+        let report = house.apply_scene(&scene);
+        assert!(report.applied());
+        assert_eq!(power_states(&house), power_states(&create_house()));
+    }
+
+    #[test]
+    fn test_scene_targets_and_serialization() {
+        let mut scene = Scene::new("Away").with_on(living("Lamp"));
+        scene.set(living("Lamp"), false);
+        scene.set(living("TV"), false);
+
+        assert_eq!(
+            scene.targets(),
+            [
+                SceneTarget {
+                    target: living("Lamp"),
+                    is_on: false
+                },
+                SceneTarget {
+                    target: living("TV"),
+                    is_on: false
+                },
+            ]
+        );
+
+        let json = serde_json::to_string(&scene).unwrap();
+        assert!(json.contains(r#"{"room":"Living Room","device":"Lamp","is_on":false}"#));
+        assert_eq!(serde_json::from_str::<Scene>(&json).unwrap(), scene);
+
+        let toml = toml::to_string(&scene).unwrap();
+        assert_eq!(toml::from_str::<Scene>(&toml).unwrap(), scene);
+    }
+}
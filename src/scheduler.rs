@@ -0,0 +1,693 @@
+//! Time-based execution of [`Action`]s against a [`SmartHouse`]
+//!
+//! The scheduler does not run a thread of its own: call
+//! [`Scheduler::run_pending`] periodically, e.g. once a second. Occurrences
+//! that were due longer ago than the grace period are logged as missed
+//! instead of being run late. The run log keeps the most recent records
+//! only, [`Scheduler::DEFAULT_LOG_CAPACITY`] unless configured otherwise.
+
+use crate::SmartHouse;
+use crate::automation::Action;
+use crate::clock::{Clock, SystemClock};
+use crate::error::SmartHomeError;
+use chrono::{Datelike, NaiveDateTime, NaiveTime, TimeDelta, Weekday};
+use std::collections::VecDeque;
+use std::fmt;
+use std::time::Duration;
+
+/// When a scheduled action fires
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub enum Schedule {
+    /// A single point in time
+    Once(NaiveDateTime),
+    /// A time of day on the listed days of the week
+    Daily { time: NaiveTime, days: Vec<Weekday> },
+    /// A fixed interval, counted from the moment the entry was added
+    Every(Duration),
+}
+
+impl Schedule {
+    /// Fires at the given time of day on every day of the week
+    pub fn daily(time: NaiveTime) -> Self {
+        Self::on_days(
+            time,
+            &[
+                Weekday::Mon,
+                Weekday::Tue,
+                Weekday::Wed,
+                Weekday::Thu,
+                Weekday::Fri,
+                Weekday::Sat,
+                Weekday::Sun,
+            ],
+        )
+    }
+
+    /// Fires at the given time of day from Monday to Friday
+    pub fn weekdays(time: NaiveTime) -> Self {
+        Self::on_days(
+            time,
+            &[
+                Weekday::Mon,
+                Weekday::Tue,
+                Weekday::Wed,
+                Weekday::Thu,
+                Weekday::Fri,
+            ],
+        )
+    }
+
+    /// Fires at the given time of day on the listed days
+    pub fn on_days(time: NaiveTime, days: &[Weekday]) -> Self {
+        Schedule::Daily {
+            time,
+            days: days.to_vec(),
+        }
+    }
+
+    /// Fires repeatedly with the given interval of at least one second
+    pub fn every(interval: Duration) -> Self {
+        Schedule::Every(interval)
+    }
+
+    fn validate(&self) -> Result<(), SmartHomeError> {
+        match self {
+            Schedule::Once(_) => Ok(()),
+            Schedule::Daily { days, .. } if days.is_empty() => Err(SmartHomeError::invalid_value(
+                "schedule",
+                "at least one day of the week is required",
+            )),
+            Schedule::Daily { .. } => Ok(()),
+            Schedule::Every(interval) if *interval < Duration::from_secs(1) => Err(
+                SmartHomeError::invalid_value("schedule", "interval must be at least one second"),
+            ),
+            Schedule::Every(interval) => TimeDelta::from_std(*interval)
+                .map(|_| ())
+                .map_err(|_| SmartHomeError::invalid_value("schedule", "interval is too long")),
+        }
+    }
+
+    /// Returns the first occurrence strictly after `after`
+    ///
+    /// `anchor` is the start of the count for [`Schedule::Every`].
+    fn next_after(&self, after: NaiveDateTime, anchor: NaiveDateTime) -> Option<NaiveDateTime> {
+        match self {
+            Schedule::Once(at) => (*at > after).then_some(*at),
+            Schedule::Daily { time, days } => (0..=7)
+                .filter_map(|offset| after.date().checked_add_days(chrono::Days::new(offset)))
+                .filter(|date| days.contains(&date.weekday()))
+                .map(|date| date.and_time(*time))
+                .find(|candidate| *candidate > after),
+            Schedule::Every(interval) => {
+                let interval = i64::try_from(interval.as_millis()).ok()?;
+                let periods = if after < anchor {
+                    1
+                } else {
+                    (after - anchor).num_milliseconds() / interval + 1
+                };
+                let offset = TimeDelta::try_milliseconds(periods.checked_mul(interval)?)?;
+                anchor.checked_add_signed(offset)
+            }
+        }
+    }
+
+    /// Returns the latest occurrence up to `now` and the number of
+    /// occurrences from `first`, itself an occurrence, up to it
+    fn due_until(&self, first: NaiveDateTime, now: NaiveDateTime) -> (NaiveDateTime, usize) {
+        match self {
+            Schedule::Once(_) => (first, 1),
+            Schedule::Daily { time, days } => {
+                let is_due = |date: chrono::NaiveDate| days.contains(&date.weekday());
+                let latest = (0..=7)
+                    .filter_map(|offset| now.date().checked_sub_days(chrono::Days::new(offset)))
+                    .filter(|date| is_due(*date))
+                    .map(|date| date.and_time(*time))
+                    .find(|candidate| *candidate <= now)
+                    .map_or(first, |latest| latest.max(first));
+
+                // Every full week holds each listed day once
+                let span = (latest.date() - first.date()).num_days() + 1;
+                let weeks = span / 7;
+                let per_week = (0..7)
+                    .filter_map(|offset| first.date().checked_add_days(chrono::Days::new(offset)))
+                    .filter(|date| is_due(*date))
+                    .count();
+                let rest = (weeks * 7..span)
+                    .filter_map(|offset| {
+                        first
+                            .date()
+                            .checked_add_days(chrono::Days::new(offset as u64))
+                    })
+                    .filter(|date| is_due(*date))
+                    .count();
+                (latest, weeks as usize * per_week + rest)
+            }
+            Schedule::Every(interval) => {
+                let interval = i64::try_from(interval.as_millis()).unwrap_or(i64::MAX);
+                let periods = (now - first).num_milliseconds() / interval;
+                let latest = first + TimeDelta::milliseconds(periods * interval);
+                (latest, periods as usize + 1)
+            }
+        }
+    }
+}
+
+/// Identifier of a scheduled entry
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Hash, PartialOrd, Ord)]
+pub struct EntryId(u64);
+
+impl fmt::Display for EntryId {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        write!(f, "#{}", self.0)
+    }
+}
+
+/// An action with its schedule
+#[derive(Debug, Clone)]
+pub struct ScheduledEntry {
+    pub id: EntryId,
+    pub name: String,
+    pub schedule: Schedule,
+    pub action: Action,
+    anchor: NaiveDateTime,
+    next_fire: Option<NaiveDateTime>,
+}
+
+impl ScheduledEntry {
+    /// Returns the next time the entry fires, `None` once it is exhausted
+    pub fn next_fire(&self) -> Option<NaiveDateTime> {
+        self.next_fire
+    }
+}
+
+/// What happened to a due occurrence
+#[derive(Debug, Clone, PartialEq)]
+pub enum RunOutcome {
+    /// The action ran successfully
+    Executed,
+    /// The action ran and failed with the given message
+    Failed(String),
+    /// The occurrences were due outside the grace period and did not run
+    Missed { occurrences: usize },
+}
+
+/// Log record of a due occurrence
+#[derive(Debug, Clone, PartialEq)]
+pub struct RunRecord {
+    pub entry: EntryId,
+    pub name: String,
+    /// When the occurrence was due; the first one for missed runs
+    pub scheduled_for: NaiveDateTime,
+    /// When the scheduler handled it
+    pub handled_at: NaiveDateTime,
+    pub outcome: RunOutcome,
+}
+
+/// Holds scheduled actions and runs the ones that are due
+pub struct Scheduler {
+    clock: Box<dyn Clock>,
+    grace: Duration,
+    next_id: u64,
+    entries: Vec<ScheduledEntry>,
+    log_capacity: usize,
+    log: VecDeque<RunRecord>,
+}
+
+impl Scheduler {
+    /// Default window in which a due occurrence still runs
+    pub const DEFAULT_GRACE: Duration = Duration::from_secs(60);
+
+    /// Default number of records kept in the run log
+    pub const DEFAULT_LOG_CAPACITY: usize = 1000;
+
+    /// Creates a scheduler driven by the system clock
+    pub fn new() -> Self {
+        Self::with_clock(SystemClock)
+    }
+
+    /// Creates a scheduler driven by the given clock
+    pub fn with_clock<C: Clock + 'static>(clock: C) -> Self {
+        Self {
+            clock: Box::new(clock),
+            grace: Self::DEFAULT_GRACE,
+            next_id: 0,
+            entries: Vec::new(),
+            log_capacity: Self::DEFAULT_LOG_CAPACITY,
+            log: VecDeque::new(),
+        }
+    }
+
+    /// Sets how late a due occurrence may run before it counts as missed
+    pub fn with_grace(mut self, grace: Duration) -> Self {
+        self.grace = grace;
+        self
+    }
+
+    /// Keeps at most `capacity` records in the run log, dropping the oldest ones
+    ///
+    /// A capacity of zero is raised to one.
+    pub fn with_log_capacity(mut self, capacity: usize) -> Self {
+        self.log_capacity = capacity.max(1);
+        let excess = self.log.len().saturating_sub(self.log_capacity);
+        self.log.drain(..excess);
+        self
+    }
+
+    /// Returns the current time of the scheduler clock
+    pub fn now(&self) -> NaiveDateTime {
+        self.clock.now()
+    }
+
+    /// Adds an entry and returns its identifier
+    ///
+    /// The first occurrence is the first one after the current time.
+    pub fn add(
+        &mut self,
+        name: impl Into<String>,
+        schedule: Schedule,
+        action: Action,
+    ) -> Result<EntryId, SmartHomeError> {
+        schedule.validate()?;
+
+        let now = self.now();
+        let id = EntryId(self.next_id);
+        self.next_id += 1;
+        self.entries.push(ScheduledEntry {
+            id,
+            name: name.into(),
+            next_fire: schedule.next_after(now, now),
+            schedule,
+            action,
+            anchor: now,
+        });
+        Ok(id)
+    }
+
+    /// Removes an entry and returns it
+    pub fn remove(&mut self, id: EntryId) -> Option<ScheduledEntry> {
+        let index = self.entries.iter().position(|entry| entry.id == id)?;
+        Some(self.entries.remove(index))
+    }
+
+    /// Returns an entry by identifier
+    pub fn entry(&self, id: EntryId) -> Option<&ScheduledEntry> {
+        self.entries.iter().find(|entry| entry.id == id)
+    }
+
+    /// Returns all entries in the order they were added
+    pub fn entries(&self) -> &[ScheduledEntry] {
+        &self.entries
+    }
+
+    /// Returns the next time an entry fires
+    pub fn next_fire(&self, id: EntryId) -> Option<NaiveDateTime> {
+        self.entry(id).and_then(ScheduledEntry::next_fire)
+    }
+
+    /// Returns the entries that will still fire, soonest first
+    pub fn upcoming(&self) -> Vec<(NaiveDateTime, &ScheduledEntry)> {
+        let mut upcoming: Vec<_> = self
+            .entries
+            .iter()
+            .filter_map(|entry| entry.next_fire.map(|at| (at, entry)))
+            .collect();
+        upcoming.sort_by_key(|(at, entry)| (*at, entry.id));
+        upcoming
+    }
+
+    /// Returns the most recent records of executed and missed runs, oldest first
+    pub fn log(&self) -> impl ExactSizeIterator<Item = &RunRecord> {
+        self.log.iter()
+    }
+
+    /// Empties the run log
+    pub fn clear_log(&mut self) {
+        self.log.clear();
+    }
+
+    /// Runs every entry that is due and returns the records added to the log
+    ///
+    /// If several occurrences of an entry passed since the last call, only
+    /// the latest one may run; the earlier ones are logged as missed.
+    pub fn run_pending(&mut self, house: &mut SmartHouse) -> Vec<RunRecord> {
+        let now = self.now();
+        let grace = TimeDelta::from_std(self.grace).unwrap_or(TimeDelta::MAX);
+        let mut records = Vec::new();
+
+        for entry in &mut self.entries {
+            let Some(first) = entry.next_fire.filter(|at| *at <= now) else {
+                continue;
+            };
+
+            let (latest, occurrences) = entry.schedule.due_until(first, now);
+            entry.next_fire = entry.schedule.next_after(latest, entry.anchor);
+
+            let runs_latest = now - latest <= grace;
+            let missed = if runs_latest {
+                occurrences - 1
+            } else {
+                occurrences
+            };
+
+            if missed > 0 {
+                records.push(RunRecord {
+                    entry: entry.id,
+                    name: entry.name.clone(),
+                    scheduled_for: first,
+                    handled_at: now,
+                    outcome: RunOutcome::Missed {
+                        occurrences: missed,
+                    },
+                });
+            }
+            if runs_latest {
+                let outcome = match entry.action.execute(house) {
+                    Ok(()) => RunOutcome::Executed,
+                    Err(e) => RunOutcome::Failed(e.to_string()),
+                };
+                records.push(RunRecord {
+                    entry: entry.id,
+                    name: entry.name.clone(),
+                    scheduled_for: latest,
+                    handled_at: now,
+                    outcome,
+                });
+            }
+        }
+
+        for record in &records {
+            if self.log.len() == self.log_capacity {
+                self.log.pop_front();
+            }
+            self.log.push_back(record.clone());
+        }
+        records
+    }
+}
+
+impl Default for Scheduler {
+    fn default() -> Self {
+        Self::new()
+    }
+}
+
+impl fmt::Debug for Scheduler {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        f.debug_struct("Scheduler")
+            .field("now", &self.now())
+            .field("grace", &self.grace)
+            .field("entries", &self.entries)
+            .field("log", &self.log.len())
+            .finish()
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::automation::DeviceRef;
+    use crate::clock::ManualClock;
+    use crate::{SmartSocket, create_room};
+    use chrono::NaiveDate;
+    use std::sync::Arc;
+
+    /// Monday, 1 January 2024
+    fn at(day: u32, hour: u32, minute: u32) -> NaiveDateTime {
+        NaiveDate::from_ymd_opt(2024, 1, day)
+            .unwrap()
+            .and_hms_opt(hour, minute, 0)
+            .unwrap()
+    }
+
+    fn seven() -> NaiveTime {
+        NaiveTime::from_hms_opt(7, 0, 0).unwrap()
+    }
+
+    fn create_house() -> SmartHouse {
+        let bedroom = create_room!(
+            "Bedroom",
+            (
+                "Desk Lamp",
+                SmartSocket::new("Desk Lamp".to_string(), false, 10.0)
+            )
+        );
+        SmartHouse::new(
+            "Test House".to_string(),
+            vec![("Bedroom".to_string(), bedroom)],
+        )
+    }
+
+    fn lamp() -> DeviceRef {
+        DeviceRef::new("Bedroom", "Desk Lamp")
+    }
+
+    fn lamp_is_on(house: &SmartHouse) -> Option<bool> {
+        house
+            .device(&"Bedroom".to_string(), &"Desk Lamp".to_string())
+            .unwrap()
+            .is_on()
+    }
+
+    #[test]
+    fn test_next_fire() {
+        struct TestCase {
+            name: &'static str,
+            now: NaiveDateTime,
+            schedule: Schedule,
+            expected: Option<NaiveDateTime>,
+        }
+
+        let test_cases = vec![
+            TestCase {
+                name: "Weekday schedule later the same day",
+                now: at(1, 6, 0),
+                schedule: Schedule::weekdays(seven()),
+                expected: Some(at(1, 7, 0)),
+            },
+            TestCase {
+                name: "Weekday schedule at the exact time fires the next day",
+                now: at(1, 7, 0),
+                schedule: Schedule::weekdays(seven()),
+                expected: Some(at(2, 7, 0)),
+            },
+            TestCase {
+                name: "Weekday schedule skips the weekend",
+                now: at(5, 8, 0),
+                schedule: Schedule::weekdays(seven()),
+                expected: Some(at(8, 7, 0)),
+            },
+            TestCase {
+                name: "Single day of the week a week later",
+                now: at(1, 8, 0),
+                schedule: Schedule::on_days(seven(), &[Weekday::Mon]),
+                expected: Some(at(8, 7, 0)),
+            },
+            TestCase {
+                name: "Interval counted from the moment the entry is added",
+                now: at(1, 10, 5),
+                schedule: Schedule::every(Duration::from_secs(15 * 60)),
+                expected: Some(at(1, 10, 20)),
+            },
+            TestCase {
+                name: "One-off in the future",
+                now: at(1, 10, 0),
+                schedule: Schedule::Once(at(3, 9, 30)),
+                expected: Some(at(3, 9, 30)),
+            },
+            TestCase {
+                name: "One-off in the past never fires",
+                now: at(1, 10, 0),
+                schedule: Schedule::Once(at(1, 9, 0)),
+                expected: None,
+            },
+        ];
+
+        for tc in test_cases {
+            let mut scheduler = Scheduler::with_clock(ManualClock::new(tc.now));
+            let id = scheduler
+                .add(tc.name, tc.schedule, Action::TurnOn(lamp()))
+                .unwrap();
+            assert_eq!(
+                scheduler.next_fire(id),
+                tc.expected,
+                "Failed test: {}",
+                tc.name
+            );
+        }
+    }
+
+    #[test]
+    fn test_invalid_schedules() {
+        let mut scheduler = Scheduler::with_clock(ManualClock::new(at(1, 0, 0)));
+
+        for schedule in [
+            Schedule::on_days(seven(), &[]),
+            Schedule::every(Duration::from_millis(500)),
+        ] {
+            assert!(matches!(
+                scheduler.add("Broken", schedule, Action::TurnOn(lamp())),
+                Err(SmartHomeError::InvalidValue(..))
+            ));
+        }
+        assert!(scheduler.entries().is_empty());
+    }
+
+    #[test]
+    fn test_run_pending() {
+        let clock = Arc::new(ManualClock::new(at(1, 6, 0)));
+        let mut scheduler = Scheduler::with_clock(Arc::clone(&clock));
+        let mut house = create_house();
+
+        let morning = scheduler
+            .add(
+                "Morning lamp",
+                Schedule::weekdays(seven()),
+                Action::TurnOn(lamp()),
+            )
+            .unwrap();
+        let broken = scheduler
+            .add(
+                "Broken",
+                Schedule::Once(at(1, 7, 0)),
+                Action::TurnOn(DeviceRef::new("Attic", "Fan")),
+            )
+            .unwrap();
+
+        assert!(scheduler.run_pending(&mut house).is_empty());
+        assert_eq!(
+            scheduler
+                .upcoming()
+                .iter()
+                .map(|(at, entry)| (*at, entry.id))
+                .collect::<Vec<_>>(),
+            vec![(at(1, 7, 0), morning), (at(1, 7, 0), broken)]
+        );
+
+        clock.set(at(1, 7, 0) + TimeDelta::seconds(30));
+        let records = scheduler.run_pending(&mut house);
+        assert_eq!(
+            records
+                .iter()
+                .map(|record| (record.entry, record.outcome.clone()))
+                .collect::<Vec<_>>(),
+            vec![
+                (morning, RunOutcome::Executed),
+                (
+                    broken,
+                    RunOutcome::Failed("Room 'Attic' not found".to_string())
+                ),
+            ]
+        );
+        assert_eq!(lamp_is_on(&house), Some(true));
+        assert_eq!(scheduler.next_fire(morning), Some(at(2, 7, 0)));
+        assert_eq!(scheduler.next_fire(broken), None);
+
+        // Asleep until Thursday noon: Tuesday to Thursday were missed
+        house
+            .turn_off_device(&"Bedroom".to_string(), &"Desk Lamp".to_string())
+            .unwrap();
+        clock.set(at(4, 12, 0));
+        let records = scheduler.run_pending(&mut house);
+        assert_eq!(
+            records,
+            vec![RunRecord {
+                entry: morning,
+                name: "Morning lamp".to_string(),
+                scheduled_for: at(2, 7, 0),
+                handled_at: at(4, 12, 0),
+                outcome: RunOutcome::Missed { occurrences: 3 },
+            }]
+        );
+        assert_eq!(lamp_is_on(&house), Some(false));
+        assert_eq!(scheduler.next_fire(morning), Some(at(5, 7, 0)));
+        assert_eq!(scheduler.log().len(), 3);
+
+        scheduler.clear_log();
+        assert!(scheduler.remove(morning).is_some());
+        assert!(scheduler.upcoming().is_empty());
+    }
+
+    #[test]
+    fn test_interval_runs_latest_occurrence() {
+        let clock = Arc::new(ManualClock::new(at(1, 10, 0)));
+        let mut scheduler =
+            Scheduler::with_clock(Arc::clone(&clock)).with_grace(Duration::from_secs(5 * 60));
+        let mut house = create_house();
+        let id = scheduler
+            .add(
+                "Toggle",
+                Schedule::every(Duration::from_secs(15 * 60)),
+                Action::TurnOn(lamp()),
+            )
+            .unwrap();
+
+        // 10:15, 10:30 and 10:45 are due; 10:45 is within the grace period
+        clock.set(at(1, 10, 47));
+        let outcomes: Vec<_> = scheduler
+            .run_pending(&mut house)
+            .into_iter()
+            .map(|record| (record.scheduled_for, record.outcome))
+            .collect();
+        assert_eq!(
+            outcomes,
+            vec![
+                (at(1, 10, 15), RunOutcome::Missed { occurrences: 2 }),
+                (at(1, 10, 45), RunOutcome::Executed),
+            ]
+        );
+        assert_eq!(scheduler.next_fire(id), Some(at(1, 11, 0)));
+    }
+
+    #[test]
+    fn test_long_gaps_and_log_capacity() {
+        let clock = Arc::new(ManualClock::new(at(1, 6, 0)));
+        let mut scheduler = Scheduler::with_clock(Arc::clone(&clock)).with_log_capacity(2);
+        let mut house = create_house();
+        let morning = scheduler
+            .add(
+                "Morning lamp",
+                Schedule::weekdays(seven()),
+                Action::TurnOn(lamp()),
+            )
+            .unwrap();
+        let ticker = scheduler
+            .add(
+                "Ticker",
+                Schedule::every(Duration::from_secs(1)),
+                Action::TurnOn(lamp()),
+            )
+            .unwrap();
+
+        // Four full weeks and the Monday after, ticking every second
+        clock.set(at(29, 12, 0));
+        let records = scheduler.run_pending(&mut house);
+        assert_eq!(
+            records
+                .iter()
+                .map(|record| (record.entry, record.scheduled_for, record.outcome.clone()))
+                .collect::<Vec<_>>(),
+            vec![
+                (morning, at(1, 7, 0), RunOutcome::Missed { occurrences: 21 }),
+                (
+                    ticker,
+                    at(1, 6, 0) + TimeDelta::seconds(1),
+                    RunOutcome::Missed {
+                        occurrences: 28 * 24 * 60 * 60 + 6 * 60 * 60 - 1
+                    }
+                ),
+                (ticker, at(29, 12, 0), RunOutcome::Executed),
+            ]
+        );
+        assert_eq!(scheduler.next_fire(morning), Some(at(30, 7, 0)));
+        assert_eq!(
+            scheduler.next_fire(ticker),
+            Some(at(29, 12, 0) + TimeDelta::seconds(1))
+        );
+
+        // Only the two most recent records are kept
+        assert_eq!(
+            scheduler.log().cloned().collect::<Vec<_>>(),
+            records[1..].to_vec()
+        );
+    }
+}
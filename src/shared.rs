@@ -0,0 +1,812 @@
+//! Thread-safe handle to a house for multi-threaded services
+//!
+//! A [`SharedHouse`] locks every room separately, so threads working in
+//! different rooms never wait for each other and readers of one room share
+//! its lock. Adding or removing rooms takes a lock on the room list.
+//!
+//! Locks are always taken in the same order: power budget, room list, rooms
+//! in insertion order, event bus. Turning a device on while the house has a
+//! power budget locks every room, since any of them may have to shed load.
+//!
+//! Event callbacks run after every lock has been released, so they may call
+//! any method of the handle, including ones that publish further events.
+//! Callbacks of changes made concurrently from several threads may therefore
+//! interleave; channel receivers always get events in the order they were
+//! published.
+
+use crate::budget::{self, PowerBudget};
+use crate::error::{DeviceAccessError, SmartHomeError};
+use crate::events::{EventBus, HouseEvent, SubscriptionId};
+use crate::report::{HouseReport, ReportOrder, StructuredReport};
+use crate::room::{Room, validate_name};
+use crate::{Reporter, SmartDevice, SmartHouse};
+use indexmap::IndexMap;
+use std::fmt;
+use std::sync::mpsc::Receiver;
+use std::sync::{Arc, Mutex, MutexGuard, RwLock, RwLockReadGuard, RwLockWriteGuard};
+
+type RoomLock = Arc<RwLock<Room>>;
+
+/// Cloneable handle to a house shared between threads
+///
+/// Clones refer to the same house. Changes made through the handle are
+/// published on the event bus of the house, just like with [`SmartHouse`].
+#[derive(Clone)]
+pub struct SharedHouse {
+    inner: Arc<Inner>,
+}
+
+struct Inner {
+    name: String,
+    budget: RwLock<Option<PowerBudget>>,
+    rooms: RwLock<IndexMap<String, RoomLock>>,
+    events: Mutex<EventBus>,
+}
+
+/// A panicking thread cannot leave a room half-updated through this API, so
+/// poisoned locks are recovered rather than propagated
+fn read<T>(lock: &RwLock<T>) -> RwLockReadGuard<'_, T> {
+    lock.read().unwrap_or_else(|e| e.into_inner())
+}
+
+fn write<T>(lock: &RwLock<T>) -> RwLockWriteGuard<'_, T> {
+    lock.write().unwrap_or_else(|e| e.into_inner())
+}
+
+fn lock<T>(lock: &Mutex<T>) -> MutexGuard<'_, T> {
+    lock.lock().unwrap_or_else(|e| e.into_inner())
+}
+
+impl SharedHouse {
+    /// Wraps a house, keeping its rooms, devices and event subscribers
+    pub fn new(house: SmartHouse) -> Self {
+        let (name, rooms, events, budget) = house.into_parts();
+        let rooms = rooms
+            .into_iter()
+            .map(|(key, room)| (key, Arc::new(RwLock::new(room))))
+            .collect();
+
+        Self {
+            inner: Arc::new(Inner {
+                name,
+                budget: RwLock::new(budget),
+                rooms: RwLock::new(rooms),
+                events: Mutex::new(events),
+            }),
+        }
+    }
+
+    /// Turns the handle back into a plain house, e.g. to save it
+    ///
+    /// Fails and returns the handle if it has other clones.
+    pub fn try_into_house(self) -> Result<SmartHouse, SharedHouse> {
+        let inner = Arc::try_unwrap(self.inner).map_err(|inner| SharedHouse { inner })?;
+        let rooms = inner
+            .rooms
+            .into_inner()
+            .unwrap_or_else(|e| e.into_inner())
+            .into_iter()
+            .map(|(key, room)| {
+                let room = match Arc::try_unwrap(room) {
+                    Ok(room) => room.into_inner().unwrap_or_else(|e| e.into_inner()),
+                    // Room locks are only cloned for the duration of a call on a handle
+                    Err(_) => unreachable!("room locks are not shared outside the handle"),
+                };
+                (key, room)
+            })
+            .collect();
+        let events = inner.events.into_inner().unwrap_or_else(|e| e.into_inner());
+        let budget = inner.budget.into_inner().unwrap_or_else(|e| e.into_inner());
+
+        Ok(SmartHouse::from_parts(inner.name, rooms, events, budget))
+    }
+
+    /// Returns the name of the house
+    pub fn name(&self) -> &str {
+        &self.inner.name
+    }
+
+    /// Returns the power budget of the house
+    pub fn power_budget(&self) -> Option<PowerBudget> {
+        *read(&self.inner.budget)
+    }
+
+    /// Sets or clears the power budget of the whole house
+    ///
+    /// See [`SmartHouse::set_power_budget`].
+    pub fn set_power_budget(&self, budget: Option<PowerBudget>) -> Result<(), SmartHomeError> {
+        if let Some(budget) = &budget {
+            budget.validate()?;
+        }
+        *write(&self.inner.budget) = budget;
+        Ok(())
+    }
+
+    /// Returns the total power drawn by all devices in the house in watts
+    pub fn total_power_consumption(&self) -> f32 {
+        self.with_all_rooms(|rooms| {
+            rooms
+                .iter()
+                .map(|room| room.total_power_consumption())
+                .sum()
+        })
+    }
+
+    /// Returns the keys of all rooms in insertion order
+    pub fn room_names(&self) -> Vec<String> {
+        read(&self.inner.rooms).keys().cloned().collect()
+    }
+
+    /// Returns the lock of a room without keeping the room list locked
+    fn room_lock(&self, name: &String) -> Result<RoomLock, DeviceAccessError> {
+        read(&self.inner.rooms)
+            .get(name)
+            .cloned()
+            .ok_or_else(|| DeviceAccessError::RoomNotFound(name.clone()))
+    }
+
+    /// Publishes an event, running the callbacks once the bus is unlocked
+    pub(crate) fn publish(&self, event: HouseEvent) {
+        let callbacks = {
+            let mut events = lock(&self.inner.events);
+            events.send(&event);
+            events.callbacks()
+        };
+        for callback in callbacks {
+            callback(&event);
+        }
+    }
+
+    /// Registers a callback invoked for every event
+    pub fn subscribe<F>(&self, callback: F) -> SubscriptionId
+    where
+        F: Fn(&HouseEvent) + Send + Sync + 'static,
+    {
+        lock(&self.inner.events).subscribe(callback)
+    }
+
+    /// Removes a callback; returns `false` if the subscription was not found
+    pub fn unsubscribe(&self, id: SubscriptionId) -> bool {
+        lock(&self.inner.events).unsubscribe(id)
+    }
+
+    /// Returns a receiver that gets a copy of every event
+    pub fn channel(&self) -> Receiver<HouseEvent> {
+        lock(&self.inner.events).channel()
+    }
+
+    /// Adds a new room to the house
+    ///
+    /// Fails if the name is empty or already taken by another room
+    pub fn add_room(&self, name: String, room: Room) -> Result<(), SmartHomeError> {
+        validate_name("room name", &name)?;
+        {
+            let mut rooms = write(&self.inner.rooms);
+            if rooms.contains_key(&name) {
+                return Err(SmartHomeError::DuplicateRoom(name));
+            }
+            rooms.insert(name.clone(), Arc::new(RwLock::new(room)));
+        }
+
+        self.publish(HouseEvent::RoomAdded { room: name });
+        Ok(())
+    }
+
+    /// Removes a room from the house by name and returns it
+    ///
+    /// Waits for calls already working in the room to finish.
+    pub fn remove_room(&self, name: &String) -> Result<Room, SmartHomeError> {
+        let room = write(&self.inner.rooms)
+            .shift_remove(name)
+            .ok_or_else(|| DeviceAccessError::RoomNotFound(name.clone()))?;
+        let room = {
+            let mut room = write(&room);
+            let empty = Room::new_empty(room.name().to_string());
+            std::mem::replace(&mut *room, empty)
+        };
+
+        self.publish(HouseEvent::RoomRemoved { room: name.clone() });
+        Ok(room)
+    }
+
+    /// Runs a closure with shared access to a room
+    pub fn with_room<R>(
+        &self,
+        name: &String,
+        f: impl FnOnce(&Room) -> R,
+    ) -> Result<R, DeviceAccessError> {
+        let room = self.room_lock(name)?;
+        let room = read(&room);
+        Ok(f(&room))
+    }
+
+    /// Runs a closure with exclusive access to a room
+    ///
+    /// Changes made in the closure are not published as events.
+    pub fn with_room_mut<R>(
+        &self,
+        name: &String,
+        f: impl FnOnce(&mut Room) -> R,
+    ) -> Result<R, DeviceAccessError> {
+        let room = self.room_lock(name)?;
+        let mut room = write(&room);
+        Ok(f(&mut room))
+    }
+
+    /// Runs a closure with shared access to a device
+    pub fn with_device<R>(
+        &self,
+        room_name: &String,
+        device_name: &String,
+        f: impl FnOnce(&SmartDevice) -> R,
+    ) -> Result<R, DeviceAccessError> {
+        self.with_room(room_name, |room| {
+            room.device(device_name).map(f).ok_or_else(|| {
+                DeviceAccessError::DeviceNotFound(device_name.clone(), room_name.clone())
+            })
+        })?
+    }
+
+    /// Runs a closure with exclusive access to a device
+    ///
+    /// Only the room of the device is locked. Changes made in the closure are
+    /// not published as events.
+    pub fn with_device_mut<R>(
+        &self,
+        room_name: &String,
+        device_name: &String,
+        f: impl FnOnce(&mut SmartDevice) -> R,
+    ) -> Result<R, DeviceAccessError> {
+        self.with_room_mut(room_name, |room| {
+            room.device_mut(device_name).map(f).ok_or_else(|| {
+                DeviceAccessError::DeviceNotFound(device_name.clone(), room_name.clone())
+            })
+        })?
+    }
+
+    /// Adds a device to a room of the house
+    pub fn add_device(
+        &self,
+        room_name: &String,
+        device_name: String,
+        device: SmartDevice,
+    ) -> Result<(), SmartHomeError> {
+        self.with_room_mut(room_name, |room| {
+            room.add_device(device_name.clone(), device)
+        })??;
+
+        self.publish(HouseEvent::DeviceAdded {
+            room: room_name.clone(),
+            device: device_name,
+        });
+        Ok(())
+    }
+
+    /// Removes a device from a room of the house and returns it
+    pub fn remove_device(
+        &self,
+        room_name: &String,
+        device_name: &String,
+    ) -> Result<SmartDevice, SmartHomeError> {
+        let device = self.with_room_mut(room_name, |room| room.remove_device(device_name))??;
+
+        self.publish(HouseEvent::DeviceRemoved {
+            room: room_name.clone(),
+            device: device_name.clone(),
+        });
+        Ok(device)
+    }
+
+    /// Turns on a device in a room of the house
+    ///
+    /// Publishes [`HouseEvent::PowerStateChanged`] if the device was off, and
+    /// for every device shed to keep the room and the house within budget.
+    pub fn turn_on_device(
+        &self,
+        room_name: &String,
+        device_name: &String,
+    ) -> Result<(), SmartHomeError> {
+        self.switch_device(room_name, device_name, true)
+    }
+
+    /// Turns off a device in a room of the house
+    ///
+    /// Publishes [`HouseEvent::PowerStateChanged`] if the device was on.
+    pub fn turn_off_device(
+        &self,
+        room_name: &String,
+        device_name: &String,
+    ) -> Result<(), SmartHomeError> {
+        self.switch_device(room_name, device_name, false)
+    }
+
+    fn switch_device(
+        &self,
+        room_name: &String,
+        device_name: &String,
+        on: bool,
+    ) -> Result<(), SmartHomeError> {
+        if on {
+            return self.switch_on(room_name, device_name);
+        }
+
+        let changed = self.with_room_mut(room_name, |room| {
+            let was_on = room.device(device_name).and_then(SmartDevice::is_on);
+            room.turn_off_device(device_name)?;
+            let is_on = room.device(device_name).and_then(SmartDevice::is_on);
+            Ok::<_, SmartHomeError>(is_on.is_some() && is_on != was_on)
+        })??;
+
+        if changed {
+            self.publish(HouseEvent::PowerStateChanged {
+                room: room_name.clone(),
+                device: device_name.clone(),
+                is_on: false,
+            });
+        }
+        Ok(())
+    }
+
+    /// Turns a device on within the room and house budgets
+    fn switch_on(&self, room_name: &String, device_name: &String) -> Result<(), SmartHomeError> {
+        let house_budget = read(&self.inner.budget);
+        let events = match house_budget.as_ref() {
+            // Only the budget of the room applies, so the other rooms stay unlocked
+            None => self.with_room_mut(room_name, |room| {
+                budget::turn_on_in_house(
+                    &self.inner.name,
+                    None,
+                    &mut [(room_name, room)],
+                    room_name,
+                    device_name,
+                )
+            })??,
+            Some(house_budget) => self.with_all_rooms_mut(|rooms| {
+                budget::turn_on_in_house(
+                    &self.inner.name,
+                    Some(house_budget),
+                    rooms,
+                    room_name,
+                    device_name,
+                )
+            })?,
+        };
+
+        for event in events {
+            self.publish(event);
+        }
+        Ok(())
+    }
+
+    /// Runs a closure with every room read-locked at once
+    fn with_all_rooms<R>(&self, f: impl FnOnce(&[RwLockReadGuard<'_, Room>]) -> R) -> R {
+        let rooms = read(&self.inner.rooms);
+        let guards: Vec<_> = rooms.values().map(|room| read(room)).collect();
+        f(&guards)
+    }
+
+    /// Runs a closure with every room write-locked at once
+    fn with_all_rooms_mut<R>(&self, f: impl FnOnce(&mut [(&String, &mut Room)]) -> R) -> R {
+        let rooms = read(&self.inner.rooms);
+        let mut guards: Vec<_> = rooms.iter().map(|(key, room)| (key, write(room))).collect();
+        let mut rooms: Vec<(&String, &mut Room)> = guards
+            .iter_mut()
+            .map(|(key, room)| (*key, &mut **room))
+            .collect();
+        f(&mut rooms)
+    }
+
+    /// Generates the text report with rooms and devices in the given order
+    ///
+    /// The report is a consistent snapshot: no room changes while it is built.
+    pub fn report_ordered(&self, order: ReportOrder) -> String {
+        self.with_all_rooms(|rooms| {
+            let mut rooms: Vec<&Room> = rooms.iter().map(|room| &**room).collect();
+            order.sort(
+                &mut rooms,
+                |room| room.name(),
+                |room| room.total_power_consumption(),
+            );
+
+            let mut report = format!("=== Smart House: {} ===\n", self.inner.name);
+            for room in rooms {
+                report.push_str(&room.report_ordered(order));
+                report.push('\n');
+            }
+            report
+        })
+    }
+}
+
+impl From<SmartHouse> for SharedHouse {
+    fn from(house: SmartHouse) -> Self {
+        SharedHouse::new(house)
+    }
+}
+
+impl StructuredReport for SharedHouse {
+    type Report = HouseReport;
+
+    /// Builds a consistent snapshot: no room changes while it is built
+    fn structured_report(&self) -> HouseReport {
+        self.with_all_rooms(|rooms| {
+            HouseReport::new(
+                self.inner.name.clone(),
+                rooms.iter().map(|room| room.structured_report()).collect(),
+            )
+        })
+    }
+}
+
+impl Reporter for SharedHouse {
+    fn report(&self) -> String {
+        self.report_ordered(ReportOrder::Insertion)
+    }
+}
+
+impl fmt::Debug for SharedHouse {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        f.debug_struct("SharedHouse")
+            .field("name", &self.inner.name)
+            .field("rooms", &self.room_names())
+            .finish()
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::{SmartSocket, SmartThermometer, create_room};
+    use std::thread;
+
+    fn create_house() -> SmartHouse {
+        let living_room = create_room!(
+            "Living Room",
+            ("TV", SmartSocket::new("TV".to_string(), false, 120.0)),
+            (
+                "Thermometer",
+                SmartThermometer::new("Thermometer".to_string(), 22.0)
+            )
+        );
+        let kitchen = create_room!(
+            "Kitchen",
+            (
+                "Kettle",
+                SmartSocket::new("Kettle".to_string(), true, 2000.0)
+            )
+        );
+        SmartHouse::new(
+            "Test House".to_string(),
+            vec![
+                ("Living Room".to_string(), living_room),
+                ("Kitchen".to_string(), kitchen),
+            ],
+        )
+    }
+
+    fn key(name: &str) -> String {
+        name.to_string()
+    }
+
+    #[test]
+    fn test_shared_house_is_send_and_sync() {
+        fn assert_send_sync<T: Send + Sync + Clone>() {}
+        assert_send_sync::<SharedHouse>();
+    }
+
+    #[test]
+    fn test_device_access() {
+        struct TestCase {
+            name: &'static str,
+            room: &'static str,
+            device: &'static str,
+            expected: Result<Option<bool>, DeviceAccessError>,
+        }
+
+        let test_cases = vec![
+            TestCase {
+                name: "Socket",
+                room: "Kitchen",
+                device: "Kettle",
+                expected: Ok(Some(true)),
+            },
+            TestCase {
+                name: "Thermometer has no power state",
+                room: "Living Room",
+                device: "Thermometer",
+                expected: Ok(None),
+            },
+            TestCase {
+                name: "Missing room",
+                room: "Attic",
+                device: "Kettle",
+                expected: Err(DeviceAccessError::RoomNotFound(key("Attic"))),
+            },
+            TestCase {
+                name: "Missing device",
+                room: "Kitchen",
+                device: "Toaster",
+                expected: Err(DeviceAccessError::DeviceNotFound(
+                    key("Toaster"),
+                    key("Kitchen"),
+                )),
+            },
+        ];
+
+        let house = SharedHouse::new(create_house());
+        for tc in test_cases {
+            assert_eq!(
+                house.with_device(&key(tc.room), &key(tc.device), SmartDevice::is_on),
+                tc.expected,
+                "Failed test: {}",
+                tc.name
+            );
+        }
+    }
+
+    #[test]
+    fn test_mutations_publish_events() {
+        let house = SharedHouse::new(create_house());
+        let events = house.channel();
+
+        house
+            .turn_on_device(&key("Living Room"), &key("TV"))
+            .unwrap();
+        house
+            .turn_on_device(&key("Living Room"), &key("TV"))
+            .unwrap();
+        assert!(matches!(
+            house.turn_on_device(&key("Living Room"), &key("Thermometer")),
+            Err(SmartHomeError::Access(
+                DeviceAccessError::UnsupportedCapability(..)
+            ))
+        ));
+        house
+            .add_room(key("Garage"), Room::new_empty(key("Garage")))
+            .unwrap();
+        house
+            .add_device(
+                &key("Garage"),
+                key("Charger"),
+                SmartDevice::Socket(SmartSocket::new(key("Charger"), false, 7000.0)),
+            )
+            .unwrap();
+        house
+            .remove_device(&key("Garage"), &key("Charger"))
+            .unwrap();
+        let garage = house.remove_room(&key("Garage")).unwrap();
+
+        assert_eq!(garage.name(), "Garage");
+        assert_eq!(house.room_names(), vec![key("Living Room"), key("Kitchen")]);
+        assert_eq!(
+            events.try_iter().collect::<Vec<_>>(),
+            vec![
+                HouseEvent::PowerStateChanged {
+                    room: key("Living Room"),
+                    device: key("TV"),
+                    is_on: true,
+                },
+                HouseEvent::RoomAdded {
+                    room: key("Garage")
+                },
+                HouseEvent::DeviceAdded {
+                    room: key("Garage"),
+                    device: key("Charger"),
+                },
+                HouseEvent::DeviceRemoved {
+                    room: key("Garage"),
+                    device: key("Charger"),
+                },
+                HouseEvent::RoomRemoved {
+                    room: key("Garage")
+                },
+            ]
+        );
+    }
+
+    #[test]
+    fn test_callbacks_may_change_the_house() {
+        let house = SharedHouse::new(create_house());
+        let events = house.channel();
+
+        // Switches the TV straight back off whenever it is turned on
+        let id = {
+            let handle = house.clone();
+            house.subscribe(move |event| {
+                if let HouseEvent::PowerStateChanged {
+                    room, is_on: true, ..
+                } = event
+                {
+                    handle.turn_off_device(room, &key("TV")).unwrap();
+                }
+            })
+        };
+
+        let (done, finished) = std::sync::mpsc::channel();
+        {
+            let house = house.clone();
+            thread::spawn(move || {
+                house
+                    .turn_on_device(&key("Living Room"), &key("TV"))
+                    .unwrap();
+                done.send(()).unwrap();
+            });
+        }
+        finished
+            .recv_timeout(std::time::Duration::from_secs(5))
+            .expect("callback deadlocked the house");
+
+        assert!(house.unsubscribe(id));
+        assert_eq!(
+            house.with_device(&key("Living Room"), &key("TV"), SmartDevice::is_on),
+            Ok(Some(false))
+        );
+        assert_eq!(
+            events.try_iter().collect::<Vec<_>>(),
+            vec![
+                HouseEvent::PowerStateChanged {
+                    room: key("Living Room"),
+                    device: key("TV"),
+                    is_on: true,
+                },
+                HouseEvent::PowerStateChanged {
+                    room: key("Living Room"),
+                    device: key("TV"),
+                    is_on: false,
+                },
+            ]
+        );
+    }
+
+    #[test]
+    fn test_power_budget() {
+        use crate::budget::PowerBudget;
+
+        let mut house = create_house();
+        house
+            .set_power_budget(Some(PowerBudget::shed(2050.0)))
+            .unwrap();
+        let shared = SharedHouse::new(house);
+        let events = shared.channel();
+
+        shared
+            .with_room_mut(&key("Kitchen"), |room| {
+                room.set_device_priority(&key("Kettle"), -1)
+            })
+            .unwrap()
+            .unwrap();
+        shared
+            .turn_on_device(&key("Living Room"), &key("TV"))
+            .unwrap();
+        assert_eq!(shared.total_power_consumption(), 120.0);
+        assert_eq!(
+            events.try_iter().collect::<Vec<_>>(),
+            vec![
+                HouseEvent::PowerStateChanged {
+                    room: key("Living Room"),
+                    device: key("TV"),
+                    is_on: true,
+                },
+                HouseEvent::PowerStateChanged {
+                    room: key("Kitchen"),
+                    device: key("Kettle"),
+                    is_on: false,
+                },
+            ]
+        );
+
+        // The kettle has the lowest priority, so nothing can be shed for it
+        assert!(matches!(
+            shared.turn_on_device(&key("Kitchen"), &key("Kettle")),
+            Err(SmartHomeError::BudgetExceeded(..))
+        ));
+        assert_eq!(events.try_iter().count(), 0);
+
+        shared.set_power_budget(None).unwrap();
+        shared
+            .turn_on_device(&key("Kitchen"), &key("Kettle"))
+            .unwrap();
+        assert_eq!(shared.total_power_consumption(), 2120.0);
+
+        shared
+            .set_power_budget(Some(PowerBudget::reject(3000.0)))
+            .unwrap();
+        let house = shared.try_into_house().unwrap();
+        assert_eq!(house.power_budget(), Some(&PowerBudget::reject(3000.0)));
+    }
+
+    #[test]
+    fn test_round_trip_and_reports() {
+        let house = create_house();
+        let expected_report = house.report();
+        let expected_structured = house.structured_report();
+
+        let shared = SharedHouse::from(house);
+        assert_eq!(shared.report(), expected_report);
+        assert_eq!(shared.structured_report(), expected_structured);
+        assert_eq!(
+            shared.report_ordered(ReportOrder::PowerDraw),
+            create_house().report_ordered(ReportOrder::PowerDraw)
+        );
+
+        let clone = shared.clone();
+        let shared = shared.try_into_house().unwrap_err();
+        drop(clone);
+        let house = shared.try_into_house().unwrap();
+        assert_eq!(house.report(), expected_report);
+    }
+
+    #[test]
+    fn test_concurrent_readers_and_writers() {
+        const ROUNDS: usize = 200;
+
+        let house = SharedHouse::new(create_house());
+        let events = house.channel();
+
+        let writers: Vec<_> = [("Living Room", "TV"), ("Kitchen", "Kettle")]
+            .into_iter()
+            .map(|(room, device)| {
+                let house = house.clone();
+                thread::spawn(move || {
+                    for round in 0..ROUNDS {
+                        if round % 2 == 0 {
+                            house.turn_off_device(&key(room), &key(device)).unwrap();
+                        } else {
+                            house.turn_on_device(&key(room), &key(device)).unwrap();
+                        }
+                    }
+                })
+            })
+            .collect();
+
+        let thermometer_writer = {
+            let house = house.clone();
+            thread::spawn(move || {
+                for round in 0..ROUNDS {
+                    house
+                        .with_device_mut(&key("Living Room"), &key("Thermometer"), |device| {
+                            if let SmartDevice::Thermometer(thermometer) = device {
+                                thermometer.set_temperature(round as f32);
+                            }
+                        })
+                        .unwrap();
+                }
+            })
+        };
+
+        let readers: Vec<_> = (0..4)
+            .map(|_| {
+                let house = house.clone();
+                thread::spawn(move || {
+                    for _ in 0..ROUNDS {
+                        let report = house.structured_report();
+                        assert_eq!(report.rooms.len(), 2);
+                        assert_eq!(report.rooms[0].devices.len(), 2);
+                    }
+                })
+            })
+            .collect();
+
+        for handle in writers.into_iter().chain(readers) {
+            handle.join().unwrap();
+        }
+        thermometer_writer.join().unwrap();
+
+        // Every round flips the state, and the last round turns the devices on
+        assert_eq!(events.try_iter().count(), 2 * ROUNDS - 1);
+        for (room, device) in [("Living Room", "TV"), ("Kitchen", "Kettle")] {
+            assert_eq!(
+                house.with_device(&key(room), &key(device), SmartDevice::is_on),
+                Ok(Some(true))
+            );
+        }
+        assert_eq!(
+            house.with_device(
+                &key("Living Room"),
+                &key("Thermometer"),
+                SmartDevice::temperature
+            ),
+            Ok(Some((ROUNDS - 1) as f32))
+        );
+    }
+}
@@ -0,0 +1,798 @@
+//! Loading and saving a [`SmartHouse`] as JSON or TOML
+//!
+//! A house file lists rooms and their devices in order. Every device
+//! carries a `kind` tag next to its own fields:
+//!
+//! ```toml
+//! name = "My Home"
+//!
+//! [[rooms]]
+//! key = "Living Room"
+//! name = "Living Room"
+//!
+//! [[rooms.devices]]
+//! key = "TV Socket"
+//! kind = "socket"
+//! name = "TV Socket"
+//! is_on = true
+//! power_consumption = 50.0
+//! ```
+
+use crate::device::{
+    CustomDevice, SmartAirQualityMonitor, SmartBinarySensor, SmartDevice, SmartHygrometer,
+    SmartLight, SmartLock, SmartSocket, SmartThermometer, SmartThermostat,
+};
+use crate::error::PersistenceError;
+use crate::house::SmartHouse;
+use crate::room::Room;
+use indexmap::IndexMap;
+use serde::de::DeserializeOwned;
+use serde::{Deserialize, Deserializer, Serialize, Serializer};
+use serde_json::{Map, Value};
+use std::collections::HashMap;
+use std::fmt;
+use std::path::Path;
+
+/// File formats supported for house files
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub enum HouseFormat {
+    Json,
+    Toml,
+}
+
+impl HouseFormat {
+    /// Detects the format from the file extension
+    pub fn from_path(path: &Path) -> Result<Self, PersistenceError> {
+        let extension = path
+            .extension()
+            .and_then(|ext| ext.to_str())
+            .map(|ext| ext.to_ascii_lowercase());
+
+        match extension.as_deref() {
+            Some("json") => Ok(HouseFormat::Json),
+            Some("toml") => Ok(HouseFormat::Toml),
+            _ => Err(PersistenceError::UnsupportedFormat(path.to_path_buf())),
+        }
+    }
+}
+
+impl fmt::Display for HouseFormat {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        match self {
+            HouseFormat::Json => write!(f, "JSON"),
+            HouseFormat::Toml => write!(f, "TOML"),
+        }
+    }
+}
+
+/// Builds a custom device from the fields of its saved record
+pub type DeviceFactory = Box<dyn Fn(Value) -> Result<Box<dyn CustomDevice>, String> + Send + Sync>;
+
+/// Maps the kind tags of custom devices to the factories that load them
+///
+/// Built-in kinds (`socket`, `thermometer`, `light`, `thermostat`,
+/// `hygrometer`, `air_quality_monitor`, `binary_sensor`, `lock`) are always
+/// recognised and cannot be overridden.
+#[derive(Default)]
+pub struct DeviceRegistry {
+    factories: HashMap<String, DeviceFactory>,
+}
+
+impl DeviceRegistry {
+    /// Creates a registry that knows only the built-in device kinds
+    pub fn new() -> Self {
+        Self::default()
+    }
+
+    /// Registers a factory for the given kind tag, replacing any previous one
+    pub fn register<F>(&mut self, kind: impl Into<String>, factory: F) -> &mut Self
+    where
+        F: Fn(Value) -> Result<Box<dyn CustomDevice>, String> + Send + Sync + 'static,
+    {
+        self.factories.insert(kind.into(), Box::new(factory));
+        self
+    }
+
+    /// Registers a device type that is loaded by deserializing its fields
+    pub fn register_type<T>(&mut self, kind: impl Into<String>) -> &mut Self
+    where
+        T: CustomDevice + DeserializeOwned + 'static,
+    {
+        self.register(kind, |fields| {
+            serde_json::from_value::<T>(fields)
+                .map(|device| Box::new(device) as Box<dyn CustomDevice>)
+                .map_err(|e| e.to_string())
+        })
+    }
+
+    /// Checks whether a factory is registered for the given kind tag
+    pub fn contains(&self, kind: &str) -> bool {
+        self.factories.contains_key(kind)
+    }
+
+    fn create(&self, kind: &str, fields: Value) -> Option<Result<Box<dyn CustomDevice>, String>> {
+        self.factories.get(kind).map(|factory| factory(fields))
+    }
+}
+
+impl fmt::Debug for DeviceRegistry {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        let mut kinds: Vec<_> = self.factories.keys().collect();
+        kinds.sort();
+        f.debug_struct("DeviceRegistry")
+            .field("kinds", &kinds)
+            .finish()
+    }
+}
+
+/// Encodes a house in the given format
+pub fn to_string(house: &SmartHouse, format: HouseFormat) -> Result<String, PersistenceError> {
+    let encoded = match format {
+        HouseFormat::Json => serde_json::to_string_pretty(house).map_err(|e| e.to_string()),
+        HouseFormat::Toml => toml::to_string_pretty(house).map_err(|e| e.to_string()),
+    };
+
+    encoded.map_err(|message| PersistenceError::Serialize { format, message })
+}
+
+/// Decodes a house from a document in the given format
+pub fn from_str(input: &str, format: HouseFormat) -> Result<SmartHouse, PersistenceError> {
+    from_str_with(input, format, &DeviceRegistry::new())
+}
+
+/// Decodes a house, loading custom devices through the registry
+pub fn from_str_with(
+    input: &str,
+    format: HouseFormat,
+    registry: &DeviceRegistry,
+) -> Result<SmartHouse, PersistenceError> {
+    let record: HouseRecord = match format {
+        HouseFormat::Json => serde_json::from_str(input).map_err(|e| e.to_string()),
+        HouseFormat::Toml => toml::from_str(input).map_err(|e| e.to_string()),
+    }
+    .map_err(|message| PersistenceError::Parse { format, message })?;
+
+    record.into_house(registry)
+}
+
+/// Saves a house to a file, picking the format from the extension
+pub fn save(house: &SmartHouse, path: &Path) -> Result<(), PersistenceError> {
+    let format = HouseFormat::from_path(path)?;
+    let encoded = to_string(house, format)?;
+
+    std::fs::write(path, encoded).map_err(|source| PersistenceError::Io {
+        path: path.to_path_buf(),
+        source,
+    })
+}
+
+/// Loads a house from a file, picking the format from the extension
+pub fn load(path: &Path) -> Result<SmartHouse, PersistenceError> {
+    load_with(path, &DeviceRegistry::new())
+}
+
+/// Loads a house from a file, loading custom devices through the registry
+pub fn load_with(path: &Path, registry: &DeviceRegistry) -> Result<SmartHouse, PersistenceError> {
+    let format = HouseFormat::from_path(path)?;
+    let input = std::fs::read_to_string(path).map_err(|source| PersistenceError::Io {
+        path: path.to_path_buf(),
+        source,
+    })?;
+
+    from_str_with(&input, format, registry)
+}
+
+/// Borrowed view used to encode a house
+#[derive(Serialize)]
+struct HouseView<'a> {
+    name: &'a str,
+    rooms: Vec<RoomView<'a>>,
+}
+
+#[derive(Serialize)]
+struct RoomView<'a> {
+    key: &'a str,
+    name: &'a str,
+    devices: Vec<DeviceView<'a>>,
+}
+
+#[derive(Serialize)]
+struct DeviceView<'a> {
+    key: &'a str,
+    #[serde(flatten)]
+    device: &'a SmartDevice,
+}
+
+impl<'a> From<&'a SmartHouse> for HouseView<'a> {
+    fn from(house: &'a SmartHouse) -> Self {
+        let rooms = house
+            .all_rooms()
+            .iter()
+            .map(|(key, room)| RoomView {
+                key,
+                name: room.name(),
+                devices: room
+                    .all_devices()
+                    .iter()
+                    .map(|(key, device)| DeviceView { key, device })
+                    .collect(),
+            })
+            .collect();
+
+        HouseView {
+            name: house.name(),
+            rooms,
+        }
+    }
+}
+
+/// Owned record decoded from a house file
+#[derive(Deserialize)]
+struct HouseRecord {
+    name: String,
+    #[serde(default)]
+    rooms: Vec<RoomRecord>,
+}
+
+#[derive(Deserialize)]
+struct RoomRecord {
+    key: String,
+    name: Option<String>,
+    #[serde(default)]
+    devices: Vec<DeviceRecord>,
+}
+
+#[derive(Deserialize)]
+struct DeviceRecord {
+    key: String,
+    kind: String,
+    #[serde(flatten)]
+    fields: Map<String, Value>,
+}
+
+impl HouseRecord {
+    fn into_house(self, registry: &DeviceRegistry) -> Result<SmartHouse, PersistenceError> {
+        let mut rooms = IndexMap::with_capacity(self.rooms.len());
+
+        for record in self.rooms {
+            if rooms.contains_key(&record.key) {
+                return Err(PersistenceError::DuplicateRoom(record.key));
+            }
+            let (key, room) = record.into_room(registry)?;
+            rooms.insert(key, room);
+        }
+
+        Ok(SmartHouse::new(self.name, rooms))
+    }
+}
+
+impl RoomRecord {
+    fn into_room(self, registry: &DeviceRegistry) -> Result<(String, Room), PersistenceError> {
+        let mut devices = IndexMap::with_capacity(self.devices.len());
+
+        for record in self.devices {
+            if devices.contains_key(&record.key) {
+                return Err(PersistenceError::DuplicateDevice {
+                    room: self.key,
+                    device: record.key,
+                });
+            }
+            let key = record.key.clone();
+            devices.insert(key, record.into_device(&self.key, registry)?);
+        }
+
+        let name = self.name.unwrap_or_else(|| self.key.clone());
+        Ok((self.key, Room::new(name, devices)))
+    }
+}
+
+impl DeviceRecord {
+    fn into_device(
+        self,
+        room: &str,
+        registry: &DeviceRegistry,
+    ) -> Result<SmartDevice, PersistenceError> {
+        let mut fields = self.fields;
+        // The key doubles as the device name when the file omits it
+        fields
+            .entry("name")
+            .or_insert_with(|| Value::String(self.key.clone()));
+
+        let device = match self.kind.as_str() {
+            "socket" => decode::<SmartSocket>(fields).map(SmartDevice::from),
+            "thermometer" => decode::<SmartThermometer>(fields).map(SmartDevice::from),
+            "light" => decode::<SmartLight>(fields).map(SmartDevice::from),
+            "thermostat" => decode::<SmartThermostat>(fields).map(SmartDevice::from),
+            "hygrometer" => decode::<SmartHygrometer>(fields).map(SmartDevice::from),
+            "air_quality_monitor" => {
+                decode::<SmartAirQualityMonitor>(fields).map(SmartDevice::from)
+            }
+            "binary_sensor" => decode::<SmartBinarySensor>(fields).map(SmartDevice::from),
+            "lock" => decode::<SmartLock>(fields).map(SmartDevice::from),
+            kind => match registry.create(kind, Value::Object(fields)) {
+                Some(device) => device.map(SmartDevice::Custom),
+                None => {
+                    return Err(PersistenceError::UnknownDeviceKind {
+                        room: room.to_string(),
+                        device: self.key,
+                        kind: self.kind,
+                    });
+                }
+            },
+        };
+
+        device.map_err(|message| PersistenceError::InvalidDevice {
+            room: room.to_string(),
+            device: self.key,
+            message,
+        })
+    }
+}
+
+/// Decodes a single room record, e.g. `{"key": "Garage", "devices": []}`
+#[cfg(feature = "http")]
+pub(crate) fn room_from_value(
+    value: Value,
+    registry: &DeviceRegistry,
+) -> Result<(String, Room), PersistenceError> {
+    serde_json::from_value::<RoomRecord>(value)
+        .map_err(|e| PersistenceError::Parse {
+            format: HouseFormat::Json,
+            message: e.to_string(),
+        })?
+        .into_room(registry)
+}
+
+/// Decodes a single device record, e.g. `{"key": "Lamp", "kind": "socket", ...}`
+#[cfg(feature = "http")]
+pub(crate) fn device_from_value(
+    value: Value,
+    room: &str,
+    registry: &DeviceRegistry,
+) -> Result<(String, SmartDevice), PersistenceError> {
+    let record =
+        serde_json::from_value::<DeviceRecord>(value).map_err(|e| PersistenceError::Parse {
+            format: HouseFormat::Json,
+            message: e.to_string(),
+        })?;
+    let key = record.key.clone();
+    Ok((key, record.into_device(room, registry)?))
+}
+
+fn decode<T: DeserializeOwned>(fields: Map<String, Value>) -> Result<T, String> {
+    serde_json::from_value(Value::Object(fields)).map_err(|e| e.to_string())
+}
+
+impl Serialize for SmartHouse {
+    fn serialize<S: Serializer>(&self, serializer: S) -> Result<S::Ok, S::Error> {
+        HouseView::from(self).serialize(serializer)
+    }
+}
+
+impl<'de> Deserialize<'de> for SmartHouse {
+    fn deserialize<D: Deserializer<'de>>(deserializer: D) -> Result<Self, D::Error> {
+        HouseRecord::deserialize(deserializer)?
+            .into_house(&DeviceRegistry::new())
+            .map_err(serde::de::Error::custom)
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::Reporter;
+    use crate::device::custom::tests::Humidifier;
+    use crate::device::device_trait::PowerControl;
+    use std::path::PathBuf;
+
+    fn create_house() -> SmartHouse {
+        // Keys are deliberately out of alphabetical order
+        let living_room_devices = vec![
+            (
+                "TV Socket".to_string(),
+                SmartDevice::Socket(SmartSocket::new("TV Socket".to_string(), true, 50.0)),
+            ),
+            (
+                "Thermo".to_string(),
+                SmartDevice::Thermometer(SmartThermometer::new("Living Thermo".to_string(), 22.5)),
+            ),
+            (
+                "Humidity".to_string(),
+                SmartDevice::Hygrometer(SmartHygrometer::new("Humidity".to_string(), 41.0)),
+            ),
+            (
+                "Window".to_string(),
+                SmartDevice::BinarySensor(SmartBinarySensor::new(
+                    "Window".to_string(),
+                    crate::device::BinarySensorKind::Contact,
+                )),
+            ),
+            (
+                "Front Door".to_string(),
+                SmartDevice::Lock(SmartLock::new("Front Door".to_string(), true)),
+            ),
+            (
+                "Air".to_string(),
+                SmartDevice::AirQualityMonitor(SmartAirQualityMonitor::new(
+                    "Air".to_string(),
+                    720.0,
+                    38,
+                )),
+            ),
+        ];
+
+        let mut ceiling = SmartLight::new("Ceiling".to_string(), true, 12.0).with_brightness(40);
+        ceiling.set_color(Some(crate::device::Rgb::new(255, 136, 0)));
+        let bedroom_devices = vec![
+            (
+                "Desk Lamp".to_string(),
+                SmartDevice::Socket(SmartSocket::new("Desk Lamp".to_string(), false, 10.0)),
+            ),
+            ("Ceiling".to_string(), SmartDevice::Light(ceiling)),
+            (
+                "Thermostat".to_string(),
+                SmartDevice::Thermostat(
+                    SmartThermostat::new("Thermostat".to_string(), 18.0, 20.5)
+                        .with_mode(crate::device::ThermostatMode::Auto)
+                        .with_power(1200.0, 900.0),
+                ),
+            ),
+        ];
+
+        let rooms = vec![
+            (
+                "bedroom".to_string(),
+                Room::new("Bedroom".to_string(), bedroom_devices),
+            ),
+            (
+                "Living Room".to_string(),
+                Room::new("Living Room".to_string(), living_room_devices),
+            ),
+        ];
+
+        SmartHouse::new("Test House".to_string(), rooms)
+    }
+
+    fn temp_path(file_name: &str) -> PathBuf {
+        std::env::temp_dir().join(format!("smart_home_{}_{}", std::process::id(), file_name))
+    }
+
+    fn assert_same_house(actual: &SmartHouse, expected: &SmartHouse) {
+        assert_eq!(actual.name(), expected.name());
+        assert!(
+            actual.all_rooms().keys().eq(expected.all_rooms().keys()),
+            "Room order should be preserved"
+        );
+
+        for (room_key, expected_room) in expected.all_rooms() {
+            let room = actual.room(room_key).expect("room key preserved");
+            assert_eq!(room.name(), expected_room.name());
+            assert!(
+                room.all_devices()
+                    .keys()
+                    .eq(expected_room.all_devices().keys()),
+                "Device order should be preserved in room '{}'",
+                room_key
+            );
+
+            for (device_key, expected_device) in expected_room.all_devices() {
+                let device = room.device(device_key).expect("device key preserved");
+                assert_eq!(device.report(), expected_device.report());
+            }
+        }
+    }
+
+    #[test]
+    fn test_format_detection() {
+        struct TestCase {
+            name: &'static str,
+            path: &'static str,
+            expected: Option<HouseFormat>,
+        }
+
+        let test_cases = vec![
+            TestCase {
+                name: "JSON extension",
+                path: "house.json",
+                expected: Some(HouseFormat::Json),
+            },
+            TestCase {
+                name: "TOML extension in upper case",
+                path: "house.TOML",
+                expected: Some(HouseFormat::Toml),
+            },
+            TestCase {
+                name: "Unknown extension",
+                path: "house.yaml",
+                expected: None,
+            },
+            TestCase {
+                name: "No extension",
+                path: "house",
+                expected: None,
+            },
+        ];
+
+        for tc in test_cases {
+            assert_eq!(
+                HouseFormat::from_path(Path::new(tc.path)).ok(),
+                tc.expected,
+                "Failed test: {}",
+                tc.name
+            );
+        }
+    }
+
+    #[test]
+    fn test_round_trip_in_memory() {
+        let house = create_house();
+
+        for format in [HouseFormat::Json, HouseFormat::Toml] {
+            let encoded = to_string(&house, format).unwrap();
+            let decoded = from_str(&encoded, format).unwrap();
+            assert_same_house(&decoded, &house);
+        }
+    }
+
+    #[test]
+    fn test_round_trip_through_files() {
+        let mut house = create_house();
+        house
+            .device_mut(&"bedroom".to_string(), &"Desk Lamp".to_string())
+            .unwrap()
+            .turn_on()
+            .unwrap();
+
+        for file_name in ["house.json", "house.toml"] {
+            let path = temp_path(file_name);
+            house.save_to_path(&path).unwrap();
+            let loaded = SmartHouse::load_from_path(&path).unwrap();
+            std::fs::remove_file(&path).unwrap();
+
+            assert_same_house(&loaded, &house);
+            let SmartDevice::Socket(lamp) = loaded
+                .device(&"bedroom".to_string(), &"Desk Lamp".to_string())
+                .unwrap()
+            else {
+                panic!("Desk Lamp should load as a socket");
+            };
+            assert!(lamp.is_on(), "Device state should survive {}", file_name);
+        }
+    }
+
+    #[test]
+    fn test_toml_layout() {
+        let input = r#"
+            name = "Cottage"
+
+            [[rooms]]
+            key = "Kitchen"
+
+            [[rooms.devices]]
+            key = "Kettle"
+            kind = "socket"
+            is_on = false
+            power_consumption = 2000.0
+
+            [[rooms.devices]]
+            key = "Spots"
+            kind = "light"
+            is_on = true
+            brightness = 50
+            rated_power = 20.0
+            color_temperature = 2700
+        "#;
+
+        let house = from_str(input, HouseFormat::Toml).unwrap();
+        let room = house.room(&"Kitchen".to_string()).unwrap();
+        assert_eq!(room.name(), "Kitchen", "Room name defaults to its key");
+
+        let kettle = room.device(&"Kettle".to_string()).unwrap();
+        assert_eq!(
+            kettle.report(),
+            "Device: Kettle, Status: OFF, Power consumption: 0W"
+        );
+
+        let spots = room.device(&"Spots".to_string()).unwrap();
+        assert_eq!(
+            spots.report(),
+            "Device: Spots, Status: ON, Brightness: 50%, Color temperature: 2700K, Power consumption: 10W"
+        );
+    }
+
+    #[test]
+    fn test_load_errors() {
+        struct TestCase {
+            name: &'static str,
+            format: HouseFormat,
+            input: &'static str,
+            check: fn(&PersistenceError) -> bool,
+        }
+
+        let test_cases = vec![
+            TestCase {
+                name: "Malformed JSON",
+                format: HouseFormat::Json,
+                input: r#"{"name": "House", "rooms": [}"#,
+                check: |e| {
+                    matches!(
+                        e,
+                        PersistenceError::Parse {
+                            format: HouseFormat::Json,
+                            ..
+                        }
+                    )
+                },
+            },
+            TestCase {
+                name: "Malformed TOML",
+                format: HouseFormat::Toml,
+                input: "name = ",
+                check: |e| {
+                    matches!(
+                        e,
+                        PersistenceError::Parse {
+                            format: HouseFormat::Toml,
+                            ..
+                        }
+                    )
+                },
+            },
+            TestCase {
+                name: "Unknown device kind",
+                format: HouseFormat::Json,
+                input: r#"{"name": "House", "rooms": [{"key": "Kitchen", "devices": [
+                    {"key": "Fridge", "kind": "fridge"}
+                ]}]}"#,
+                check: |e| {
+                    matches!(e, PersistenceError::UnknownDeviceKind { room, device, kind }
+                        if room == "Kitchen" && device == "Fridge" && kind == "fridge")
+                },
+            },
+            TestCase {
+                name: "Device with missing fields",
+                format: HouseFormat::Json,
+                input: r#"{"name": "House", "rooms": [{"key": "Kitchen", "devices": [
+                    {"key": "Kettle", "kind": "socket", "is_on": true}
+                ]}]}"#,
+                check: |e| {
+                    matches!(e, PersistenceError::InvalidDevice { device, message, .. }
+                        if device == "Kettle" && message.contains("power_consumption"))
+                },
+            },
+            TestCase {
+                name: "Light brighter than 100%",
+                format: HouseFormat::Json,
+                input: r#"{"name": "House", "rooms": [{"key": "Hall", "devices": [
+                    {"key": "Spots", "kind": "light", "is_on": true, "brightness": 150, "rated_power": 20.0}
+                ]}]}"#,
+                check: |e| {
+                    matches!(e, PersistenceError::InvalidDevice { device, message, .. }
+                        if device == "Spots" && message.contains("between 0 and 100"))
+                },
+            },
+            TestCase {
+                name: "Duplicate room",
+                format: HouseFormat::Json,
+                input: r#"{"name": "House", "rooms": [{"key": "Kitchen"}, {"key": "Kitchen"}]}"#,
+                check: |e| matches!(e, PersistenceError::DuplicateRoom(room) if room == "Kitchen"),
+            },
+            TestCase {
+                name: "Duplicate device",
+                format: HouseFormat::Toml,
+                input: r#"
+                    name = "House"
+                    [[rooms]]
+                    key = "Hall"
+                    [[rooms.devices]]
+                    key = "Thermo"
+                    kind = "thermometer"
+                    temperature = 20.0
+                    [[rooms.devices]]
+                    key = "Thermo"
+                    kind = "thermometer"
+                    temperature = 21.0
+                "#,
+                check: |e| {
+                    matches!(e, PersistenceError::DuplicateDevice { room, device }
+                        if room == "Hall" && device == "Thermo")
+                },
+            },
+        ];
+
+        for tc in test_cases {
+            let error = from_str(tc.input, tc.format)
+                .err()
+                .unwrap_or_else(|| panic!("Test case '{}' should fail", tc.name));
+            assert!(
+                (tc.check)(&error),
+                "Test case '{}': got {:?}",
+                tc.name,
+                error
+            );
+        }
+    }
+
+    #[test]
+    fn test_path_errors() {
+        let unsupported = SmartHouse::load_from_path(temp_path("house.yaml")).unwrap_err();
+        assert!(matches!(
+            unsupported,
+            PersistenceError::UnsupportedFormat(_)
+        ));
+
+        let missing = SmartHouse::load_from_path(temp_path("missing.json")).unwrap_err();
+        assert!(matches!(missing, PersistenceError::Io { .. }));
+    }
+
+    #[test]
+    fn test_custom_devices_through_registry() {
+        let mut house = create_house();
+        house
+            .add_device(
+                &"bedroom".to_string(),
+                "Humidifier".to_string(),
+                SmartDevice::custom(Humidifier::new("Humidifier", true, 35.0)),
+            )
+            .unwrap();
+
+        let mut registry = DeviceRegistry::new();
+        registry.register_type::<Humidifier>("humidifier");
+        assert!(registry.contains("humidifier"));
+
+        for format in [HouseFormat::Json, HouseFormat::Toml] {
+            let encoded = to_string(&house, format).unwrap();
+
+            let loaded = from_str_with(&encoded, format, &registry).unwrap();
+            assert_same_house(&loaded, &house);
+            let humidifier = loaded
+                .device(&"bedroom".to_string(), &"Humidifier".to_string())
+                .unwrap();
+            assert_eq!(humidifier.kind(), "humidifier");
+            assert_eq!(humidifier.is_on(), Some(true));
+
+            // Without the registry the kind is unknown
+            let error = from_str(&encoded, format).unwrap_err();
+            assert!(
+                matches!(&error, PersistenceError::UnknownDeviceKind { kind, .. } if kind == "humidifier"),
+                "Unexpected error {:?}",
+                error
+            );
+        }
+    }
+
+    #[test]
+    fn test_custom_device_without_state_cannot_be_saved() {
+        #[derive(Debug)]
+        struct Opaque;
+
+        impl crate::SmartDeviceTrait for Opaque {
+            fn name(&self) -> &str {
+                "Opaque"
+            }
+        }
+
+        impl Reporter for Opaque {
+            fn report(&self) -> String {
+                "Device: Opaque".to_string()
+            }
+        }
+
+        impl CustomDevice for Opaque {
+            fn kind(&self) -> &str {
+                "opaque"
+            }
+        }
+
+        let mut house = create_house();
+        house
+            .room_mut(&"bedroom".to_string())
+            .unwrap()
+            .add_device("Opaque".to_string(), SmartDevice::custom(Opaque))
+            .unwrap();
+
+        let error = to_string(&house, HouseFormat::Json).unwrap_err();
+        assert!(matches!(error, PersistenceError::Serialize { .. }));
+        assert!(error.to_string().contains("does not support serialization"));
+    }
+}
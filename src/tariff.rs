@@ -0,0 +1,435 @@
+//! Electricity tariffs used to put a price on metered energy
+//!
+//! Prices are per kWh in whatever currency the tariff is quoted in. Use a
+//! [`CostTracker`](crate::cost::CostTracker) to apply a tariff to the energy
+//! metered in a house over a period.
+
+use crate::error::SmartHomeError;
+use chrono::{NaiveDateTime, NaiveTime, TimeDelta};
+
+/// A time-of-day band with its own price
+///
+/// A band whose end is not after its start wraps around midnight, e.g.
+/// 22:00 to 06:00 for a night rate.
+#[derive(Debug, Clone, PartialEq)]
+pub struct TariffBand {
+    pub start: NaiveTime,
+    pub end: NaiveTime,
+    pub price_per_kwh: f64,
+}
+
+impl TariffBand {
+    /// Creates a band from `start` (inclusive) to `end` (exclusive)
+    pub fn new(start: NaiveTime, end: NaiveTime, price_per_kwh: f64) -> Self {
+        Self {
+            start,
+            end,
+            price_per_kwh,
+        }
+    }
+
+    fn contains(&self, time: NaiveTime) -> bool {
+        if self.start < self.end {
+            self.start <= time && time < self.end
+        } else {
+            time >= self.start || time < self.end
+        }
+    }
+}
+
+/// A consumption tier of a tiered tariff
+#[derive(Debug, Clone, PartialEq)]
+pub struct Tier {
+    /// Energy used in the period up to which the tier applies; `None` for the last tier
+    pub up_to_kwh: Option<f64>,
+    pub price_per_kwh: f64,
+}
+
+/// How energy is priced
+#[derive(Debug, Clone, PartialEq)]
+pub enum Tariff {
+    /// The same price at any time
+    Flat { price_per_kwh: f64 },
+    /// Prices depending on the time of day
+    ///
+    /// The first band containing a time wins; times outside every band use
+    /// the default price.
+    TimeOfUse {
+        bands: Vec<TariffBand>,
+        default_price_per_kwh: f64,
+    },
+    /// Prices rising with the energy used in the period
+    Tiered { tiers: Vec<Tier> },
+}
+
+impl Tariff {
+    /// Charges the same price at any time
+    pub fn flat(price_per_kwh: f64) -> Self {
+        Tariff::Flat { price_per_kwh }
+    }
+
+    /// Charges the price of the band containing the time of use
+    pub fn time_of_use(default_price_per_kwh: f64, bands: Vec<TariffBand>) -> Self {
+        Tariff::TimeOfUse {
+            bands,
+            default_price_per_kwh,
+        }
+    }
+
+    /// Charges by tier, given as `(up to kWh, price)` pairs in increasing
+    /// order, with `above_price_per_kwh` for everything past the last one
+    pub fn tiered(tiers: &[(f64, f64)], above_price_per_kwh: f64) -> Self {
+        let mut tiers: Vec<Tier> = tiers
+            .iter()
+            .map(|&(up_to_kwh, price_per_kwh)| Tier {
+                up_to_kwh: Some(up_to_kwh),
+                price_per_kwh,
+            })
+            .collect();
+        tiers.push(Tier {
+            up_to_kwh: None,
+            price_per_kwh: above_price_per_kwh,
+        });
+        Tariff::Tiered { tiers }
+    }
+
+    /// Checks that prices are valid and tiers are in increasing order
+    pub fn validate(&self) -> Result<(), SmartHomeError> {
+        let prices: Vec<f64> = match self {
+            Tariff::Flat { price_per_kwh } => vec![*price_per_kwh],
+            Tariff::TimeOfUse {
+                bands,
+                default_price_per_kwh,
+            } => {
+                if bands.iter().any(|band| band.start == band.end) {
+                    return Err(SmartHomeError::invalid_value(
+                        "tariff",
+                        "a band must not start and end at the same time",
+                    ));
+                }
+                bands
+                    .iter()
+                    .map(|band| band.price_per_kwh)
+                    .chain([*default_price_per_kwh])
+                    .collect()
+            }
+            Tariff::Tiered { tiers } => {
+                Self::validate_tiers(tiers)?;
+                tiers.iter().map(|tier| tier.price_per_kwh).collect()
+            }
+        };
+
+        if prices
+            .iter()
+            .any(|price| !price.is_finite() || *price < 0.0)
+        {
+            return Err(SmartHomeError::invalid_value(
+                "tariff",
+                "prices must be finite and not negative",
+            ));
+        }
+        Ok(())
+    }
+
+    fn validate_tiers(tiers: &[Tier]) -> Result<(), SmartHomeError> {
+        let Some((last, bounded)) = tiers.split_last() else {
+            return Err(SmartHomeError::invalid_value(
+                "tariff",
+                "at least one tier is required",
+            ));
+        };
+        if last.up_to_kwh.is_some() || bounded.iter().any(|tier| tier.up_to_kwh.is_none()) {
+            return Err(SmartHomeError::invalid_value(
+                "tariff",
+                "only the last tier must be unbounded",
+            ));
+        }
+
+        let mut previous = 0.0;
+        for limit in bounded.iter().filter_map(|tier| tier.up_to_kwh) {
+            if !limit.is_finite() || limit <= previous {
+                return Err(SmartHomeError::invalid_value(
+                    "tariff",
+                    "tier limits must be positive and increasing",
+                ));
+            }
+            previous = limit;
+        }
+        Ok(())
+    }
+
+    /// Returns the cost of `energy_wh` used evenly between `from` and `to`
+    ///
+    /// `period_used_wh` is the energy already used in the billing period
+    /// before `from`, which decides the tier of a tiered tariff.
+    pub fn cost(
+        &self,
+        from: NaiveDateTime,
+        to: NaiveDateTime,
+        energy_wh: f64,
+        period_used_wh: f64,
+    ) -> f64 {
+        match self {
+            Tariff::Flat { price_per_kwh } => energy_wh / 1000.0 * price_per_kwh,
+            Tariff::TimeOfUse {
+                bands,
+                default_price_per_kwh,
+            } => Self::time_of_use_cost(bands, *default_price_per_kwh, from, to, energy_wh),
+            Tariff::Tiered { tiers } => Self::tiered_cost(tiers, energy_wh, period_used_wh),
+        }
+    }
+
+    fn time_of_use_cost(
+        bands: &[TariffBand],
+        default_price: f64,
+        from: NaiveDateTime,
+        to: NaiveDateTime,
+        energy_wh: f64,
+    ) -> f64 {
+        let price_at = |at: NaiveDateTime| {
+            bands
+                .iter()
+                .find(|band| band.contains(at.time()))
+                .map_or(default_price, |band| band.price_per_kwh)
+        };
+
+        let total_ms = (to - from).num_milliseconds();
+        if total_ms <= 0 {
+            return energy_wh / 1000.0 * price_at(to);
+        }
+
+        // Split the interval where the price may change and spread the energy
+        // over the pieces by duration
+        let mut cost = 0.0;
+        let mut start = from;
+        while start < to {
+            let end = Self::next_boundary(bands, start).min(to);
+            let share = (end - start).num_milliseconds() as f64 / total_ms as f64;
+            cost += energy_wh * share / 1000.0 * price_at(start);
+            start = end;
+        }
+        cost
+    }
+
+    /// Returns the first band edge or midnight strictly after `after`
+    fn next_boundary(bands: &[TariffBand], after: NaiveDateTime) -> NaiveDateTime {
+        let next_day = after
+            .date()
+            .succ_opt()
+            .map(|date| date.and_time(NaiveTime::MIN));
+        bands
+            .iter()
+            .flat_map(|band| [band.start, band.end])
+            .map(|time| after.date().and_time(time))
+            .filter(|candidate| *candidate > after)
+            .chain(next_day)
+            .min()
+            .unwrap_or_else(|| after + TimeDelta::days(1))
+    }
+
+    fn tiered_cost(tiers: &[Tier], energy_wh: f64, period_used_wh: f64) -> f64 {
+        let mut cost = 0.0;
+        let mut used_kwh = period_used_wh / 1000.0;
+        let mut remaining_kwh = energy_wh / 1000.0;
+
+        for tier in tiers {
+            if remaining_kwh <= 0.0 {
+                break;
+            }
+            let in_tier = match tier.up_to_kwh {
+                Some(limit) => (limit - used_kwh).clamp(0.0, remaining_kwh),
+                None => remaining_kwh,
+            };
+            cost += in_tier * tier.price_per_kwh;
+            used_kwh += in_tier;
+            remaining_kwh -= in_tier;
+        }
+        cost
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use chrono::NaiveDate;
+
+    fn at(day: u32, hour: u32, minute: u32) -> NaiveDateTime {
+        NaiveDate::from_ymd_opt(2024, 1, day)
+            .unwrap()
+            .and_hms_opt(hour, minute, 0)
+            .unwrap()
+    }
+
+    fn time(hour: u32) -> NaiveTime {
+        NaiveTime::from_hms_opt(hour, 0, 0).unwrap()
+    }
+
+    fn night_and_peak() -> Tariff {
+        Tariff::time_of_use(
+            0.30,
+            vec![
+                TariffBand::new(time(22), time(6), 0.10),
+                TariffBand::new(time(17), time(20), 0.50),
+            ],
+        )
+    }
+
+    #[test]
+    fn test_cost() {
+        struct TestCase {
+            name: &'static str,
+            tariff: Tariff,
+            from: NaiveDateTime,
+            to: NaiveDateTime,
+            energy_wh: f64,
+            period_used_wh: f64,
+            expected: f64,
+        }
+
+        let test_cases = vec![
+            TestCase {
+                name: "Flat rate",
+                tariff: Tariff::flat(0.25),
+                from: at(1, 10, 0),
+                to: at(1, 12, 0),
+                energy_wh: 2000.0,
+                period_used_wh: 0.0,
+                expected: 0.5,
+            },
+            TestCase {
+                name: "Inside a single band",
+                tariff: night_and_peak(),
+                from: at(1, 18, 0),
+                to: at(1, 19, 0),
+                energy_wh: 1000.0,
+                period_used_wh: 0.0,
+                expected: 0.5,
+            },
+            TestCase {
+                name: "Default price between bands",
+                tariff: night_and_peak(),
+                from: at(1, 9, 0),
+                to: at(1, 10, 0),
+                energy_wh: 1000.0,
+                period_used_wh: 0.0,
+                expected: 0.3,
+            },
+            TestCase {
+                name: "Across a band edge",
+                tariff: night_and_peak(),
+                from: at(1, 16, 0),
+                to: at(1, 18, 0),
+                energy_wh: 2000.0,
+                period_used_wh: 0.0,
+                expected: 0.8,
+            },
+            TestCase {
+                name: "Night band across midnight",
+                tariff: night_and_peak(),
+                from: at(1, 21, 0),
+                to: at(2, 7, 0),
+                energy_wh: 10000.0,
+                period_used_wh: 0.0,
+                // 1 h at 0.30, 8 h at 0.10, 1 h at 0.30
+                expected: 1.4,
+            },
+            TestCase {
+                name: "Within the first tier",
+                tariff: Tariff::tiered(&[(100.0, 0.10), (200.0, 0.20)], 0.40),
+                from: at(1, 0, 0),
+                to: at(1, 1, 0),
+                energy_wh: 50_000.0,
+                period_used_wh: 0.0,
+                expected: 5.0,
+            },
+            TestCase {
+                name: "Crossing two tiers",
+                tariff: Tariff::tiered(&[(100.0, 0.10), (200.0, 0.20)], 0.40),
+                from: at(1, 0, 0),
+                to: at(1, 1, 0),
+                energy_wh: 150_000.0,
+                period_used_wh: 90_000.0,
+                // 10 kWh at 0.10, 100 kWh at 0.20, 40 kWh at 0.40
+                expected: 37.0,
+            },
+        ];
+
+        for tc in test_cases {
+            tc.tariff.validate().unwrap();
+            let cost = tc
+                .tariff
+                .cost(tc.from, tc.to, tc.energy_wh, tc.period_used_wh);
+            assert!(
+                (cost - tc.expected).abs() < 1e-9,
+                "Failed test: {}: {} != {}",
+                tc.name,
+                cost,
+                tc.expected
+            );
+        }
+    }
+
+    #[test]
+    fn test_validate() {
+        struct TestCase {
+            name: &'static str,
+            tariff: Tariff,
+            expected_error: Option<&'static str>,
+        }
+
+        let test_cases = vec![
+            TestCase {
+                name: "Negative price",
+                tariff: Tariff::flat(-0.1),
+                expected_error: Some("Invalid tariff: prices must be finite and not negative"),
+            },
+            TestCase {
+                name: "Empty band",
+                tariff: Tariff::time_of_use(0.3, vec![TariffBand::new(time(6), time(6), 0.1)]),
+                expected_error: Some(
+                    "Invalid tariff: a band must not start and end at the same time",
+                ),
+            },
+            TestCase {
+                name: "Decreasing tiers",
+                tariff: Tariff::tiered(&[(200.0, 0.1), (100.0, 0.2)], 0.3),
+                expected_error: Some("Invalid tariff: tier limits must be positive and increasing"),
+            },
+            TestCase {
+                name: "No tiers",
+                tariff: Tariff::Tiered { tiers: Vec::new() },
+                expected_error: Some("Invalid tariff: at least one tier is required"),
+            },
+            TestCase {
+                name: "Unbounded tier in the middle",
+                tariff: Tariff::Tiered {
+                    tiers: vec![
+                        Tier {
+                            up_to_kwh: None,
+                            price_per_kwh: 0.1,
+                        },
+                        Tier {
+                            up_to_kwh: None,
+                            price_per_kwh: 0.2,
+                        },
+                    ],
+                },
+                expected_error: Some("Invalid tariff: only the last tier must be unbounded"),
+            },
+            TestCase {
+                name: "Valid time of use",
+                tariff: night_and_peak(),
+                expected_error: None,
+            },
+        ];
+
+        for tc in test_cases {
+            assert_eq!(
+                tc.tariff.validate().err().map(|e| e.to_string()).as_deref(),
+                tc.expected_error,
+                "Failed test: {}",
+                tc.name
+            );
+        }
+    }
+}